@@ -0,0 +1,261 @@
+use std::env;
+use std::io;
+use std::io::BufRead;
+use std::sync::mpsc;
+use std::sync::mpsc::TryRecvError;
+use std::thread;
+use std::thread::sleep;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use wavetrx::control::ModemBackend;
+use wavetrx::error::WavetrxError;
+use wavetrx::protocol::arq::ArqConfig;
+use wavetrx::protocol::arq::ReliableLink;
+use wavetrx::protocol::profile::Profile;
+use wavetrx::protocol::transceiver::Transceiver;
+use wavetrx::utils::get_profile_by_name;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+struct ChatArgs {
+    profile_name: String,
+    name: Option<String>,
+    reliable: bool,
+}
+
+fn parse_args() -> Result<ChatArgs, Box<dyn std::error::Error>> {
+    let mut profile_name: String = "fast".to_string();
+    let mut name: Option<String> = None;
+    let mut reliable: bool = false;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--profile" => profile_name = args.next().ok_or("Missing value for --profile")?,
+            "--name" => name = Some(args.next().ok_or("Missing value for --name")?),
+            "--arq" => reliable = true,
+            _ => return Err(format!("Unknown argument: {}", arg).into()),
+        }
+    }
+
+    Ok(ChatArgs {
+        profile_name,
+        name,
+        reliable,
+    })
+}
+
+// One end of a conversation. With ARQ each line is resent until the far end
+// acknowledges it; without, lines go out once and may be lost
+pub struct Chat<B: ModemBackend> {
+    link: ReliableLink<B>,
+    reliable: bool,
+}
+
+impl<B: ModemBackend> Chat<B> {
+    pub fn new(backend: B) -> Self {
+        let link: ReliableLink<B> = ReliableLink::new(backend, ArqConfig::default());
+        let reliable: bool = false;
+        Chat { link, reliable }
+    }
+
+    pub fn with_arq(mut self, config: ArqConfig) -> Self {
+        self.link = ReliableLink::new(self.link.into_inner(), config);
+        self.reliable = true;
+        self
+    }
+
+    // Blocks until every frame is acknowledged when ARQ is on; lines from
+    // the far end that arrive meanwhile are acknowledged and kept for `poll`
+    pub fn send(&mut self, line: &str) -> Result<(), WavetrxError> {
+        match self.reliable {
+            true => self.link.send(line.as_bytes()),
+            false => self.link.backend().send(line.as_bytes()),
+        }
+    }
+
+    pub fn poll(&mut self) -> Result<Vec<String>, WavetrxError> {
+        let lines: Vec<Vec<u8>> = match self.reliable {
+            true => self.link.receive(POLL_INTERVAL)?.into_iter().collect(),
+            false => self
+                .link
+                .backend()
+                .poll()
+                .into_iter()
+                .map(|message| message.into_data())
+                .collect(),
+        };
+        let lines: Vec<String> = lines
+            .iter()
+            .map(|line| String::from_utf8_lossy(line).into_owned())
+            .collect();
+        Ok(lines)
+    }
+}
+
+// UTC wall-clock time as HH:MM:SS
+fn timestamp() -> String {
+    let seconds: u64 = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or(0);
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600 % 24,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+fn print_line(line: &str) {
+    println!("[{}] {}", timestamp(), line);
+}
+
+// Lines come from a separate thread so typing never blocks decoding
+fn spawn_stdin() -> mpsc::Receiver<String> {
+    let (sender, receiver) = mpsc::channel::<String>();
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines().map_while(Result::ok) {
+            if sender.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    receiver
+}
+
+pub fn terminal_chat() -> Result<(), Box<dyn std::error::Error>> {
+    let args: ChatArgs = parse_args()?;
+    let profile: Profile = get_profile_by_name(&args.profile_name)?;
+
+    let mut transceiver: Transceiver = Transceiver::from_default_devices(profile)?;
+    transceiver.start()?;
+    let mut chat: Chat<Transceiver> = Chat::new(transceiver);
+    if args.reliable {
+        chat = chat.with_arq(ArqConfig::default());
+    }
+
+    eprintln!("[Chat] Type a line and press enter to send; Ctrl-D quits");
+    let input: mpsc::Receiver<String> = spawn_stdin();
+    loop {
+        match input.try_recv() {
+            Ok(line) if line.trim().is_empty() => {}
+            Ok(line) => {
+                let line: String = match &args.name {
+                    Some(name) => format!("{}: {}", name, line),
+                    None => line,
+                };
+                match chat.send(&line) {
+                    Ok(()) => print_line(&line),
+                    Err(err) => eprintln!("[Chat] Not delivered: {}", err),
+                }
+            }
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => return Ok(()),
+        }
+
+        for line in chat.poll()? {
+            print_line(&line);
+        }
+        sleep(POLL_INTERVAL);
+    }
+}
+
+#[test]
+fn test_chat_over_audio() {
+    use std::sync::Arc;
+    use std::sync::Barrier;
+
+    use wavetrx::audio::types::AudioSpec;
+    use wavetrx::audio::types::NormSamples;
+    use wavetrx::audio::types::SampleEncoding;
+    use wavetrx::protocol::rx::DecodedMessage;
+    use wavetrx::protocol::rx::Receiver;
+    use wavetrx::protocol::tx::Transmitter;
+    use wavetrx::utils::get_fast_profile;
+
+    // Modulates every frame and hands the audio to the other end's receiver
+    struct AirBackend {
+        transmitter: Transmitter,
+        receiver: Receiver,
+        outgoing: mpsc::Sender<Vec<f32>>,
+        incoming: mpsc::Receiver<Vec<f32>>,
+    }
+
+    impl AirBackend {
+        fn pair() -> (AirBackend, AirBackend) {
+            let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+            let profile: Profile = get_fast_profile();
+            let (a_sender, b_receiver) = mpsc::channel::<Vec<f32>>();
+            let (b_sender, a_receiver) = mpsc::channel::<Vec<f32>>();
+            let end = |outgoing, incoming| AirBackend {
+                transmitter: Transmitter::new(&profile, &spec),
+                receiver: Receiver::new(profile, spec),
+                outgoing,
+                incoming,
+            };
+            (end(a_sender, a_receiver), end(b_sender, b_receiver))
+        }
+    }
+
+    impl ModemBackend for AirBackend {
+        fn set_profile(&mut self, _: Profile) -> Result<(), WavetrxError> {
+            Ok(())
+        }
+
+        fn send(&mut self, data: &[u8]) -> Result<(), WavetrxError> {
+            let _ = self.outgoing.send(self.transmitter.create(data)?);
+            Ok(())
+        }
+
+        fn poll(&mut self) -> Vec<DecodedMessage> {
+            for samples in self.incoming.try_iter() {
+                self.receiver
+                    .add_samples(&mut NormSamples::from_vec(samples));
+            }
+            self.receiver.analyze_full_buffer();
+            self.receiver.take_messages()
+        }
+    }
+
+    let (a, b): (AirBackend, AirBackend) = AirBackend::pair();
+    let config: ArqConfig = ArqConfig::new(8, 3, Duration::from_millis(500));
+    let mut alice: Chat<AirBackend> = Chat::new(a).with_arq(config);
+    let mut bob: Chat<AirBackend> = Chat::new(b).with_arq(config);
+
+    let wait_for_line = |chat: &mut Chat<AirBackend>| -> Vec<String> {
+        let mut lines: Vec<String> = Vec::new();
+        while lines.is_empty() {
+            lines = chat.poll().unwrap();
+        }
+        lines
+    };
+
+    let handle = thread::spawn(move || {
+        let lines: Vec<String> = wait_for_line(&mut bob);
+        bob.send("bob: hi alice").unwrap();
+        (bob, lines)
+    });
+
+    // Alice has to keep polling to acknowledge Bob's reply
+    alice.send("alice: hello over the air").unwrap();
+    assert_eq!(wait_for_line(&mut alice), vec!["bob: hi alice"]);
+    let (mut bob, lines): (Chat<AirBackend>, Vec<String>) = handle.join().unwrap();
+    assert_eq!(lines, vec!["alice: hello over the air"]);
+
+    // Both typing at once: each end acknowledges the other's line while it
+    // waits for the ACK of its own
+    let barrier: Arc<Barrier> = Arc::new(Barrier::new(2));
+    let bob_barrier: Arc<Barrier> = barrier.clone();
+    let handle = thread::spawn(move || {
+        bob_barrier.wait();
+        bob.send("bob: at the same time").unwrap();
+        wait_for_line(&mut bob)
+    });
+    barrier.wait();
+    alice.send("alice: at the same time").unwrap();
+    assert_eq!(wait_for_line(&mut alice), vec!["bob: at the same time"]);
+    assert_eq!(handle.join().unwrap(), vec!["alice: at the same time"]);
+}
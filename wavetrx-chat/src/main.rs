@@ -0,0 +1,6 @@
+mod chat;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    chat::terminal_chat()?;
+    Ok(())
+}
@@ -0,0 +1,236 @@
+use std::env;
+
+use wavetrx::audio::raw::RawFormat;
+
+pub const USAGE: &str = "\
+Usage:
+  wavetrx send (--text <TEXT> | --hex <HEX>) (--out <FILE> [--bits <8|16|24|32>] | --play) [--repeat <N> [--repeat-gap <MS>]] [--device <N|NAME>] [--profile <NAME>] [RAW]
+  wavetrx recv --in <FILE> [--channels <N>] [--profile <NAME>] [RAW]
+  wavetrx listen [--device <N|NAME>] [--profile <NAME>] [--low-power] [--archive <DIR>]
+  wavetrx analyze --in <FILE> [--csv <FILE>] [--window <SAMPLES>] [--hop <SAMPLES>] [--profile <NAME>]
+  wavetrx calibrate [--profile <NAME>] [--out <FILE>]
+  wavetrx devices
+
+Calibration:
+  --calibration <FILE>    threshold and gain saved by `wavetrx calibrate`, for send, recv and listen
+
+Raw PCM:
+  --raw <u8|s16le|s24le|s32le|f32le> [--rate <HZ>]    headerless PCM instead of WAV; `-` for stdin/stdout
+
+Profiles: default, fast, ultrasonic";
+
+const DEFAULT_RAW_RATE: u32 = 48_000;
+
+pub enum SendTarget {
+    File(String),
+    Play,
+}
+
+// Set by `--raw`; files and `-` then carry headerless PCM
+#[derive(Copy, Clone)]
+pub struct RawArgs {
+    pub format: RawFormat,
+    pub rate: u32,
+    pub channels: u16,
+}
+
+pub struct SendArgs {
+    pub profile_name: String,
+    pub data: Vec<u8>,
+    pub target: SendTarget,
+    pub device: Option<String>,
+    // Integer PCM at this depth for WAV output; 32-bit float otherwise
+    pub bits: Option<u16>,
+    pub raw: Option<RawArgs>,
+    pub calibration: Option<String>,
+    // Copies of the message sent, `repeat_gap` milliseconds apart
+    pub repeat: usize,
+    pub repeat_gap: u64,
+}
+
+pub struct RecvArgs {
+    pub profile_name: String,
+    pub input: String,
+    pub raw: Option<RawArgs>,
+    pub calibration: Option<String>,
+}
+
+pub struct ListenArgs {
+    pub profile_name: String,
+    pub device: Option<String>,
+    pub low_power: bool,
+    pub calibration: Option<String>,
+    // Raw capture kept in rotating WAV files
+    pub archive: Option<String>,
+}
+
+pub struct AnalyzeArgs {
+    pub profile_name: String,
+    pub input: String,
+    pub csv: Option<String>,
+    pub window: Option<usize>,
+    pub hop: Option<usize>,
+}
+
+// Plays a tone staircase on the default output and records it on the default
+// input; `out` saves the recommendation for `--calibration`
+pub struct CalibrateArgs {
+    pub profile_name: String,
+    pub out: Option<String>,
+}
+
+pub enum Command {
+    Send(SendArgs),
+    Recv(RecvArgs),
+    Listen(ListenArgs),
+    Analyze(AnalyzeArgs),
+    Calibrate(CalibrateArgs),
+    Devices,
+    Help,
+}
+
+pub fn parse_args() -> Result<Command, String> {
+    let mut args = env::args().skip(1);
+    let subcommand: String = args.next().unwrap_or_else(|| "help".to_string());
+
+    let mut profile_name: String = "fast".to_string();
+    let mut data: Option<Vec<u8>> = None;
+    let mut target: Option<SendTarget> = None;
+    let mut input: Option<String> = None;
+    let mut device: Option<String> = None;
+    let mut csv: Option<String> = None;
+    let mut window: Option<usize> = None;
+    let mut hop: Option<usize> = None;
+    let mut raw_format: Option<RawFormat> = None;
+    let mut rate: u32 = DEFAULT_RAW_RATE;
+    let mut channels: u16 = 1;
+    let mut low_power: bool = false;
+    let mut bits: Option<u16> = None;
+    let mut calibration: Option<String> = None;
+    let mut archive: Option<String> = None;
+    let mut repeat: usize = 1;
+    let mut repeat_gap: u64 = 0;
+
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| args.next().ok_or(format!("Missing value for {}", flag));
+        match arg.as_str() {
+            "--profile" => profile_name = value("--profile")?,
+            "--text" => data = Some(value("--text")?.into_bytes()),
+            "--hex" => data = Some(parse_hex(&value("--hex")?)?),
+            "--out" => target = Some(SendTarget::File(value("--out")?)),
+            "--play" => target = Some(SendTarget::Play),
+            "--in" => input = Some(value("--in")?),
+            "--device" => device = Some(value("--device")?),
+            "--low-power" => low_power = true,
+            "--archive" => archive = Some(value("--archive")?),
+            "--csv" => csv = Some(value("--csv")?),
+            "--window" => window = Some(parse_count("--window", &value("--window")?)?),
+            "--hop" => hop = Some(parse_count("--hop", &value("--hop")?)?),
+            "--raw" => {
+                raw_format =
+                    Some(RawFormat::from_name(&value("--raw")?).map_err(|err| err.to_string())?)
+            }
+            "--rate" => rate = parse_count("--rate", &value("--rate")?)? as u32,
+            "--bits" => bits = Some(parse_bits(&value("--bits")?)?),
+            "--calibration" => calibration = Some(value("--calibration")?),
+            "--channels" => channels = parse_count("--channels", &value("--channels")?)? as u16,
+            "--repeat" => repeat = parse_count("--repeat", &value("--repeat")?)?,
+            "--repeat-gap" => {
+                let gap: String = value("--repeat-gap")?;
+                repeat_gap = gap
+                    .parse::<u64>()
+                    .map_err(|_| format!("Invalid value for --repeat-gap: {}", gap))?
+            }
+            _ => return Err(format!("Unknown argument: {}", arg)),
+        }
+    }
+
+    let raw: Option<RawArgs> = raw_format.map(|format| RawArgs {
+        format,
+        rate,
+        channels,
+    });
+    let uses_pipe: bool = input.as_deref() == Some("-")
+        || matches!(target, Some(SendTarget::File(ref path)) if path == "-");
+    if uses_pipe && raw.is_none() {
+        return Err("stdin and stdout require --raw".to_string());
+    }
+    if raw.is_some() && matches!(target, Some(SendTarget::Play)) {
+        return Err("--raw applies to files and pipes, not --play".to_string());
+    }
+
+    match subcommand.as_str() {
+        "send" => Ok(Command::Send(SendArgs {
+            profile_name,
+            data: data.ok_or("send requires --text or --hex")?,
+            target: target.ok_or("send requires --out or --play")?,
+            device,
+            bits,
+            raw,
+            calibration,
+            repeat,
+            repeat_gap,
+        })),
+        "recv" => Ok(Command::Recv(RecvArgs {
+            profile_name,
+            input: input.ok_or("recv requires --in")?,
+            raw,
+            calibration,
+        })),
+        "listen" => Ok(Command::Listen(ListenArgs {
+            profile_name,
+            device,
+            low_power,
+            calibration,
+            archive,
+        })),
+        "analyze" => Ok(Command::Analyze(AnalyzeArgs {
+            profile_name,
+            input: input.ok_or("analyze requires --in")?,
+            csv,
+            window,
+            hop,
+        })),
+        "calibrate" => Ok(Command::Calibrate(CalibrateArgs {
+            profile_name,
+            out: match target {
+                Some(SendTarget::File(path)) => Some(path),
+                _ => None,
+            },
+        })),
+        "devices" => Ok(Command::Devices),
+        "help" | "--help" | "-h" => Ok(Command::Help),
+        _ => Err(format!("Unknown command: {}", subcommand)),
+    }
+}
+
+fn parse_count(flag: &str, value: &str) -> Result<usize, String> {
+    match value.parse::<usize>() {
+        Ok(count) if count > 0 => Ok(count),
+        _ => Err(format!("Invalid value for {}: {}", flag, value)),
+    }
+}
+
+fn parse_bits(value: &str) -> Result<u16, String> {
+    match value.parse::<u16>() {
+        Ok(bits @ (8 | 16 | 24 | 32)) => Ok(bits),
+        _ => Err(format!("Invalid value for --bits: {}", value)),
+    }
+}
+
+// Non-ASCII input is rejected up front, as slicing it could split a character
+fn parse_hex(hex: &str) -> Result<Vec<u8>, String> {
+    if !hex.is_ascii() {
+        return Err(format!("Invalid hex: {}", hex));
+    }
+    if !hex.len().is_multiple_of(2) {
+        return Err(format!("Odd number of hex digits: {}", hex));
+    }
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|idx| {
+            u8::from_str_radix(&hex[idx..idx + 2], 16).map_err(|_| format!("Invalid hex: {}", hex))
+        })
+        .collect()
+}
@@ -0,0 +1,341 @@
+use std::fs::File;
+use std::io;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Read;
+use std::io::Write;
+use std::thread::sleep;
+use std::time::Duration;
+
+use cpal::traits::DeviceTrait;
+use cpal::traits::HostTrait;
+use cpal::Device;
+use cpal::Host;
+use cpal::StreamConfig;
+
+use wavetrx::audio::archive::ArchiveConfig;
+use wavetrx::audio::devices::find_device;
+use wavetrx::audio::devices::list_devices;
+use wavetrx::audio::devices::DeviceDirection;
+use wavetrx::audio::devices::DeviceInfo;
+
+use wavetrx::audio::io::SampleSource;
+use wavetrx::audio::raw::RawReader;
+use wavetrx::audio::raw::RawWriter;
+use wavetrx::audio::spectrogram::Spectrogram;
+use wavetrx::audio::types::AudioSpec;
+use wavetrx::audio::types::ChannelMode;
+use wavetrx::audio::types::NormSamples;
+use wavetrx::audio::types::SampleEncoding;
+use wavetrx::calibrate::calibrate;
+use wavetrx::calibrate::Calibration;
+use wavetrx::calibrate::CalibrationReport;
+use wavetrx::consts::SPECTROGRAM_HOP;
+use wavetrx::consts::SPECTROGRAM_WINDOW;
+use wavetrx::error::WavetrxError;
+use wavetrx::protocol::profile::Profile;
+use wavetrx::protocol::rx::DecodedMessage;
+use wavetrx::protocol::rx::LiveReceiver;
+use wavetrx::protocol::rx::Receiver;
+use wavetrx::protocol::tx::LiveTransmitter;
+use wavetrx::protocol::tx::Transmitter;
+use wavetrx::utils::get_profile_by_name;
+use wavetrx::utils::read_wav_file;
+
+use crate::args::AnalyzeArgs;
+use crate::args::CalibrateArgs;
+use crate::args::Command;
+use crate::args::ListenArgs;
+use crate::args::RawArgs;
+use crate::args::RecvArgs;
+use crate::args::SendArgs;
+use crate::args::SendTarget;
+use crate::args::USAGE;
+
+const FILE_SAMPLE_RATE: u32 = 48_000;
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+const RAW_BLOCK: usize = 4_096;
+const CALIBRATION_TIMEOUT: Duration = Duration::from_secs(1);
+
+pub fn run(command: Command) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        Command::Send(args) => send(args)?,
+        Command::Recv(args) => recv(args)?,
+        Command::Listen(args) => listen(args)?,
+        Command::Analyze(args) => analyze(args)?,
+        Command::Calibrate(args) => calibrate_devices(args)?,
+        Command::Devices => devices()?,
+        Command::Help => println!("{}", USAGE),
+    }
+    Ok(())
+}
+
+fn print_message(message: &DecodedMessage) {
+    println!("{}", message.as_utf8_lossy());
+}
+
+// The named profile with a saved calibration laid over it, and the
+// transmitter gain that goes with it
+fn load_profile(
+    name: &str,
+    calibration: Option<&str>,
+) -> Result<(Profile, f32), Box<dyn std::error::Error>> {
+    let profile: Profile = get_profile_by_name(name)?;
+    match calibration {
+        Some(path) => {
+            let calibration: Calibration = Calibration::from_file(path)?;
+            Ok((calibration.apply(profile), calibration.gain))
+        }
+        None => Ok((profile, 1.0)),
+    }
+}
+
+fn send(args: SendArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let (profile, gain): (Profile, f32) =
+        load_profile(&args.profile_name, args.calibration.as_deref())?;
+    let repeat_gap: Duration = Duration::from_millis(args.repeat_gap);
+
+    match (args.target, args.raw) {
+        (SendTarget::File(path), Some(raw)) => {
+            let spec: AudioSpec = raw.format.spec(raw.rate, 1);
+            let transmitter: Transmitter = Transmitter::new(&profile, &spec)
+                .with_gain(gain)
+                .with_repeats(args.repeat, repeat_gap);
+            let stream: Box<dyn Write> = match path.as_str() {
+                "-" => Box::new(io::stdout().lock()),
+                path => Box::new(BufWriter::new(File::create(path)?)),
+            };
+            let mut writer: RawWriter<Box<dyn Write>> = RawWriter::new(stream, raw.format);
+            transmitter.write_to(&args.data, &mut writer)?;
+            // Stdout may be carrying the audio
+            eprintln!("Wrote {} bytes to {}", args.data.len(), path);
+        }
+        (SendTarget::File(path), None) => {
+            let spec: AudioSpec = match args.bits {
+                Some(bits) => AudioSpec::new(FILE_SAMPLE_RATE, bits, 1, SampleEncoding::I32),
+                None => AudioSpec::new(FILE_SAMPLE_RATE, 32, 1, SampleEncoding::F32),
+            };
+            let transmitter: Transmitter = Transmitter::new(&profile, &spec)
+                .with_gain(gain)
+                .with_repeats(args.repeat, repeat_gap);
+            transmitter.create_file(&path, &args.data)?;
+            println!("Wrote {} bytes to {}", args.data.len(), path);
+        }
+        (SendTarget::Play, _) => {
+            let device: Device = select_device(args.device.as_deref(), DeviceDirection::Output)?;
+            let config: StreamConfig = device.default_output_config()?.into();
+
+            let mut transmitter: LiveTransmitter = LiveTransmitter::new(profile, device, config);
+            transmitter.set_gain(gain);
+            transmitter.set_repeats(args.repeat, repeat_gap);
+            transmitter.start()?;
+            transmitter.send_blocking(&args.data)?;
+            println!("Sent {} bytes", args.data.len());
+        }
+    }
+    Ok(())
+}
+
+fn recv(args: RecvArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let (profile, _): (Profile, f32) =
+        load_profile(&args.profile_name, args.calibration.as_deref())?;
+    if let Some(raw) = args.raw {
+        return recv_raw(profile, &args.input, raw);
+    }
+    let mut receiver: Receiver = Receiver::from_file(profile, &args.input)?;
+    receiver.analyze_full_buffer();
+
+    for message in receiver.take_messages() {
+        print_message(&message);
+    }
+    for err in receiver.take_frame_errors() {
+        eprintln!("Frame error: {}", err);
+    }
+    Ok(())
+}
+
+// Decodes as the PCM arrives, so messages print while the pipe stays open
+fn recv_raw(profile: Profile, input: &str, raw: RawArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let stream: Box<dyn Read> = match input {
+        "-" => Box::new(io::stdin().lock()),
+        path => Box::new(BufReader::new(File::open(path)?)),
+    };
+    let spec: AudioSpec = raw.format.spec(raw.rate, raw.channels);
+    let mut receiver: Receiver = Receiver::new(profile, spec);
+    let mut reader: RawReader<Box<dyn Read>> = RawReader::new(stream, raw.format);
+
+    while !reader.is_finished() {
+        receiver.read_from(&mut reader, RAW_BLOCK * raw.channels as usize);
+        for message in receiver.take_messages() {
+            print_message(&message);
+        }
+        for err in receiver.take_frame_errors() {
+            eprintln!("Frame error: {}", err);
+        }
+    }
+    Ok(())
+}
+
+fn listen(args: ListenArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let (profile, _): (Profile, f32) =
+        load_profile(&args.profile_name, args.calibration.as_deref())?;
+    let device: Device = select_device(args.device.as_deref(), DeviceDirection::Input)?;
+    let config: StreamConfig = device.default_input_config()?.into();
+
+    eprintln!("[Listening on {}]", device.name()?);
+    let mut receiver: LiveReceiver = LiveReceiver::new(profile, device, config);
+    receiver.set_low_power(args.low_power);
+    if let Some(dir) = args.archive {
+        receiver.start_archive(&dir, ArchiveConfig::default())?;
+        eprintln!("[Archiving capture to {}]", dir);
+    }
+    receiver.start()?;
+
+    loop {
+        for message in receiver.poll() {
+            print_message(&message);
+        }
+        sleep(POLL_INTERVAL);
+    }
+}
+
+fn analyze(args: AnalyzeArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let profile: Profile = get_profile_by_name(&args.profile_name)?;
+    let (samples, spec): (NormSamples, AudioSpec) = read_wav_file(&args.input)?;
+    let channels: usize = spec.channels() as usize;
+    let samples: NormSamples = samples.into_mono(channels, ChannelMode::default());
+    let spec: AudioSpec = spec.with_channels(1);
+
+    let window: usize = args.window.unwrap_or(SPECTROGRAM_WINDOW);
+    let hop: usize = args.hop.unwrap_or(SPECTROGRAM_HOP);
+    let spectrogram: Spectrogram = Spectrogram::new(&samples, &spec, window, hop);
+
+    let duration: Duration = spec.sample_timestamp(samples.0.len());
+    println!(
+        "{}: {:.3}s at {} Hz, {} frames of {} samples (hop {})",
+        args.input,
+        duration.as_secs_f64(),
+        spec.sample_rate(),
+        spectrogram.frame_count(),
+        window,
+        hop
+    );
+
+    let mut tones: Vec<(String, f32)> = vec![
+        ("Start".to_string(), profile.markers.start.hz()),
+        ("End".to_string(), profile.markers.end.hz()),
+        ("Next".to_string(), profile.markers.next.hz()),
+    ];
+    for (idx, tone) in profile.bits.tones().iter().enumerate() {
+        tones.push((format!("Symbol {}", idx), tone.hz()));
+    }
+
+    for (name, frequency) in tones {
+        match spectrogram.peak_at(frequency) {
+            Some((frame, magnitude)) => println!(
+                "  {:<10} {:>8.1} Hz  peak {:>7.2} dB at {:.3}s",
+                name,
+                frequency,
+                magnitude,
+                spectrogram.frame_time(frame).as_secs_f64()
+            ),
+            None => println!("  {:<10} {:>8.1} Hz  no frames", name, frequency),
+        }
+    }
+
+    if let Some(path) = args.csv {
+        spectrogram.save_csv(&path)?;
+        println!("Wrote spectrogram to {}", path);
+    }
+    Ok(())
+}
+
+fn calibrate_devices(args: CalibrateArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let profile: Profile = get_profile_by_name(&args.profile_name)?;
+    eprintln!("[Calibrating on the default output and input]");
+    let report: CalibrationReport = calibrate(profile, CALIBRATION_TIMEOUT)?;
+
+    for measurement in report.measurements.iter() {
+        let note: &str = match measurement.is_clean() {
+            true => "",
+            false => "  (too close to call)",
+        };
+        println!(
+            "  {:>8.1} Hz  level {:<6} {:>7.1} dB  margin {:>6.1} dB{}",
+            measurement.step.frequency,
+            measurement.step.level,
+            measurement.magnitude,
+            measurement.margin(),
+            note
+        );
+    }
+
+    let calibration: Calibration = match report.calibration {
+        Some(calibration) => calibration,
+        None => return Err("No step was heard cleanly; check the volume and devices".into()),
+    };
+    println!(
+        "Threshold {:.1} dB, gain {:.3}",
+        calibration.threshold, calibration.gain
+    );
+    if let Some(path) = args.out {
+        calibration.to_file(&path)?;
+        println!("Wrote calibration to {}", path);
+    }
+    Ok(())
+}
+
+fn devices() -> Result<(), Box<dyn std::error::Error>> {
+    for direction in [DeviceDirection::Input, DeviceDirection::Output] {
+        println!("[{:?} Devices]", direction);
+        for (index, info) in list_devices(direction)?.iter().enumerate() {
+            print_device(index, info);
+        }
+        println!();
+    }
+    Ok(())
+}
+
+fn print_device(index: usize, info: &DeviceInfo) {
+    let marker: &str = if info.is_default { "*" } else { " " };
+    println!("{}{:>3}: {} ({})", marker, index, info.name, info.host);
+    for config in info.configs.iter() {
+        println!(
+            "        {} ch, {}-{} Hz, {:?}",
+            config.channels, config.min_sample_rate, config.max_sample_rate, config.sample_format
+        );
+    }
+}
+
+// `selector` is an index from `wavetrx devices` or a device name
+fn select_device(
+    selector: Option<&str>,
+    direction: DeviceDirection,
+) -> Result<Device, Box<dyn std::error::Error>> {
+    let selector: &str = match selector {
+        Some(selector) => selector,
+        None => return default_device(direction),
+    };
+
+    let name: String = match selector.parse::<usize>() {
+        Ok(index) => list_devices(direction)?
+            .into_iter()
+            .nth(index)
+            .map(|info| info.name)
+            .ok_or_else(|| WavetrxError::DeviceError(format!("No device at index {}", index)))?,
+        Err(_) => selector.to_string(),
+    };
+    Ok(find_device(&name, direction)?)
+}
+
+fn default_device(direction: DeviceDirection) -> Result<Device, Box<dyn std::error::Error>> {
+    let host: Host = cpal::default_host();
+    let device: Option<Device> = match direction {
+        DeviceDirection::Input => host.default_input_device(),
+        DeviceDirection::Output => host.default_output_device(),
+    };
+
+    let reason: String = format!("No {:?} device available", direction);
+    let device: Device = device.ok_or(WavetrxError::DeviceError(reason))?;
+    Ok(device)
+}
@@ -0,0 +1,18 @@
+mod args;
+mod commands;
+
+use args::Command;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let command: Command = match args::parse_args() {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("Error: {}\n", err);
+            eprintln!("{}", args::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    commands::run(command)?;
+    Ok(())
+}
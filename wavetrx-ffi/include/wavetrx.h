@@ -0,0 +1,105 @@
+#ifndef WAVETRX_H
+#define WAVETRX_H
+
+/* Generated by cbindgen from wavetrx-ffi; do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum WavetrxStatus {
+  WAVETRX_STATUS_OK = 0,
+  WAVETRX_STATUS_NULL_POINTER = 1,
+  WAVETRX_STATUS_ENCODE_FAILED = 2,
+  WAVETRX_STATUS_EMPTY = 3,
+  WAVETRX_STATUS_PANICKED = 4,
+} WavetrxStatus;
+
+typedef struct WavetrxRx WavetrxRx;
+
+typedef struct WavetrxTx WavetrxTx;
+
+/**
+ * Mono f32 samples owned by the library; release with `wavetrx_samples_free`.
+ */
+typedef struct WavetrxSamples {
+  float *data;
+  size_t len;
+} WavetrxSamples;
+
+/**
+ * Payload owned by the library; release with `wavetrx_message_free`.
+ */
+typedef struct WavetrxMessage {
+  uint8_t *data;
+  size_t len;
+  uint64_t start_sample;
+} WavetrxMessage;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates a transmitter for a named profile ("default", "fast", "ultrasonic")
+ * producing mono f32 at `sample_rate`. Returns NULL for an unknown profile.
+ * `profile` must be a NUL-terminated string.
+ */
+WavetrxTx *wavetrx_tx_create(const char *profile, uint32_t sample_rate);
+
+/**
+ * Encodes `len` bytes at `data` into `out`, which the caller frees with
+ * `wavetrx_samples_free`. `out` is only written on success.
+ */
+WavetrxStatus wavetrx_tx_encode(const WavetrxTx *tx,
+                                const uint8_t *data,
+                                size_t len,
+                                WavetrxSamples *out);
+
+/**
+ * Releases samples returned by `wavetrx_tx_encode`; each buffer exactly once.
+ */
+void wavetrx_samples_free(WavetrxSamples samples);
+
+/**
+ * Destroys a transmitter from `wavetrx_tx_create`; NULL is ignored.
+ */
+void wavetrx_tx_destroy(WavetrxTx *tx);
+
+/**
+ * Creates a receiver for a named profile expecting mono f32 at
+ * `sample_rate`. Returns NULL for an unknown profile. `profile` must be a
+ * NUL-terminated string.
+ */
+WavetrxRx *wavetrx_rx_create(const char *profile, uint32_t sample_rate);
+
+/**
+ * Buffers `len` samples and decodes as much as possible; finished messages
+ * are queued for `wavetrx_rx_poll_message`. Call from one thread at a time.
+ */
+WavetrxStatus wavetrx_rx_push_samples(WavetrxRx *rx, const float *samples, size_t len);
+
+/**
+ * Moves the oldest decoded message into `out`, which the caller frees with
+ * `wavetrx_message_free`. Returns `WAVETRX_STATUS_EMPTY` and leaves `out`
+ * untouched when nothing is queued.
+ */
+WavetrxStatus wavetrx_rx_poll_message(WavetrxRx *rx, WavetrxMessage *out);
+
+/**
+ * Releases a message from `wavetrx_rx_poll_message`; each message exactly once.
+ */
+void wavetrx_message_free(WavetrxMessage message);
+
+/**
+ * Destroys a receiver from `wavetrx_rx_create`; NULL is ignored.
+ */
+void wavetrx_rx_destroy(WavetrxRx *rx);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* WAVETRX_H */
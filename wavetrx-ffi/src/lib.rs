@@ -0,0 +1,292 @@
+// Every entry point takes raw pointers from C; the contracts live in the doc
+// comments, which cbindgen carries into include/wavetrx.h
+#![allow(clippy::missing_safety_doc)]
+
+use std::collections::VecDeque;
+use std::ffi::c_char;
+use std::ffi::CStr;
+use std::panic;
+use std::panic::AssertUnwindSafe;
+use std::ptr;
+use std::slice;
+
+use wavetrx::audio::types::AudioSpec;
+use wavetrx::audio::types::NormSamples;
+use wavetrx::audio::types::SampleEncoding;
+use wavetrx::protocol::profile::Profile;
+use wavetrx::protocol::rx::DecodedMessage;
+use wavetrx::protocol::rx::Receiver;
+use wavetrx::protocol::tx::Transmitter;
+use wavetrx::utils::get_profile_by_name;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WavetrxStatus {
+    Ok = 0,
+    NullPointer = 1,
+    EncodeFailed = 2,
+    Empty = 3,
+    Panicked = 4,
+}
+
+/// Mono f32 samples owned by the library; release with `wavetrx_samples_free`.
+#[repr(C)]
+pub struct WavetrxSamples {
+    pub data: *mut f32,
+    pub len: usize,
+}
+
+/// Payload owned by the library; release with `wavetrx_message_free`.
+#[repr(C)]
+pub struct WavetrxMessage {
+    pub data: *mut u8,
+    pub len: usize,
+    pub start_sample: u64,
+}
+
+pub struct WavetrxTx {
+    transmitter: Transmitter,
+}
+
+pub struct WavetrxRx {
+    receiver: Receiver,
+    messages: VecDeque<DecodedMessage>,
+}
+
+/// Creates a transmitter for a named profile ("default", "fast", "ultrasonic")
+/// producing mono f32 at `sample_rate`. Returns NULL for an unknown profile.
+/// `profile` must be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn wavetrx_tx_create(
+    profile: *const c_char,
+    sample_rate: u32,
+) -> *mut WavetrxTx {
+    guard(ptr::null_mut(), || {
+        let (profile, spec): (Profile, AudioSpec) = match stream_setup(profile, sample_rate) {
+            Some(setup) => setup,
+            None => return ptr::null_mut(),
+        };
+        let transmitter: Transmitter = Transmitter::new(&profile, &spec);
+        Box::into_raw(Box::new(WavetrxTx { transmitter }))
+    })
+}
+
+/// Encodes `len` bytes at `data` into `out`, which the caller frees with
+/// `wavetrx_samples_free`. `out` is only written on success.
+#[no_mangle]
+pub unsafe extern "C" fn wavetrx_tx_encode(
+    tx: *const WavetrxTx,
+    data: *const u8,
+    len: usize,
+    out: *mut WavetrxSamples,
+) -> WavetrxStatus {
+    guard(WavetrxStatus::Panicked, || {
+        if tx.is_null() || out.is_null() || (data.is_null() && len != 0) {
+            return WavetrxStatus::NullPointer;
+        }
+        let data: &[u8] = if len == 0 {
+            &[]
+        } else {
+            slice::from_raw_parts(data, len)
+        };
+        let samples: Vec<f32> = match (*tx).transmitter.create(data) {
+            Ok(samples) => samples,
+            Err(_) => return WavetrxStatus::EncodeFailed,
+        };
+        let samples: Box<[f32]> = samples.into_boxed_slice();
+        let len: usize = samples.len();
+        let data: *mut f32 = Box::into_raw(samples) as *mut f32;
+        out.write(WavetrxSamples { data, len });
+        WavetrxStatus::Ok
+    })
+}
+
+/// Releases samples returned by `wavetrx_tx_encode`; each buffer exactly once.
+#[no_mangle]
+pub unsafe extern "C" fn wavetrx_samples_free(samples: WavetrxSamples) {
+    guard((), || {
+        if !samples.data.is_null() {
+            let slice: *mut [f32] = ptr::slice_from_raw_parts_mut(samples.data, samples.len);
+            drop(Box::from_raw(slice));
+        }
+    })
+}
+
+/// Destroys a transmitter from `wavetrx_tx_create`; NULL is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn wavetrx_tx_destroy(tx: *mut WavetrxTx) {
+    guard((), || {
+        if !tx.is_null() {
+            drop(Box::from_raw(tx));
+        }
+    })
+}
+
+/// Creates a receiver for a named profile expecting mono f32 at
+/// `sample_rate`. Returns NULL for an unknown profile. `profile` must be a
+/// NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn wavetrx_rx_create(
+    profile: *const c_char,
+    sample_rate: u32,
+) -> *mut WavetrxRx {
+    guard(ptr::null_mut(), || {
+        let (profile, spec): (Profile, AudioSpec) = match stream_setup(profile, sample_rate) {
+            Some(setup) => setup,
+            None => return ptr::null_mut(),
+        };
+        let receiver: Receiver = Receiver::new(profile, spec);
+        let messages: VecDeque<DecodedMessage> = VecDeque::new();
+        Box::into_raw(Box::new(WavetrxRx { receiver, messages }))
+    })
+}
+
+/// Buffers `len` samples and decodes as much as possible; finished messages
+/// are queued for `wavetrx_rx_poll_message`. Call from one thread at a time.
+#[no_mangle]
+pub unsafe extern "C" fn wavetrx_rx_push_samples(
+    rx: *mut WavetrxRx,
+    samples: *const f32,
+    len: usize,
+) -> WavetrxStatus {
+    guard(WavetrxStatus::Panicked, || {
+        if rx.is_null() || (samples.is_null() && len != 0) {
+            return WavetrxStatus::NullPointer;
+        }
+        let rx: &mut WavetrxRx = &mut *rx;
+        if len != 0 {
+            let samples: &[f32] = slice::from_raw_parts(samples, len);
+            rx.receiver
+                .add_samples(&mut NormSamples::from_slice(samples));
+        }
+        rx.receiver.analyze_full_buffer();
+        rx.messages.extend(rx.receiver.take_messages());
+        WavetrxStatus::Ok
+    })
+}
+
+/// Moves the oldest decoded message into `out`, which the caller frees with
+/// `wavetrx_message_free`. Returns `WAVETRX_STATUS_EMPTY` and leaves `out`
+/// untouched when nothing is queued.
+#[no_mangle]
+pub unsafe extern "C" fn wavetrx_rx_poll_message(
+    rx: *mut WavetrxRx,
+    out: *mut WavetrxMessage,
+) -> WavetrxStatus {
+    guard(WavetrxStatus::Panicked, || {
+        if rx.is_null() || out.is_null() {
+            return WavetrxStatus::NullPointer;
+        }
+        let message: DecodedMessage = match (*rx).messages.pop_front() {
+            Some(message) => message,
+            None => return WavetrxStatus::Empty,
+        };
+        let start_sample: u64 = message.start_sample() as u64;
+        let data: Box<[u8]> = message.into_data().into_boxed_slice();
+        let len: usize = data.len();
+        let data: *mut u8 = Box::into_raw(data) as *mut u8;
+        out.write(WavetrxMessage {
+            data,
+            len,
+            start_sample,
+        });
+        WavetrxStatus::Ok
+    })
+}
+
+/// Releases a message from `wavetrx_rx_poll_message`; each message exactly once.
+#[no_mangle]
+pub unsafe extern "C" fn wavetrx_message_free(message: WavetrxMessage) {
+    guard((), || {
+        if !message.data.is_null() {
+            let slice: *mut [u8] = ptr::slice_from_raw_parts_mut(message.data, message.len);
+            drop(Box::from_raw(slice));
+        }
+    })
+}
+
+/// Destroys a receiver from `wavetrx_rx_create`; NULL is ignored.
+#[no_mangle]
+pub unsafe extern "C" fn wavetrx_rx_destroy(rx: *mut WavetrxRx) {
+    guard((), || {
+        if !rx.is_null() {
+            drop(Box::from_raw(rx));
+        }
+    })
+}
+
+// A panic must not unwind into C, so every entry point runs its body here
+// and hands back `fallback` instead
+fn guard<T, F>(fallback: T, body: F) -> T
+where
+    F: FnOnce() -> T,
+{
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(fallback)
+}
+
+unsafe fn stream_setup(profile: *const c_char, sample_rate: u32) -> Option<(Profile, AudioSpec)> {
+    if profile.is_null() || sample_rate == 0 {
+        return None;
+    }
+    let name: &str = CStr::from_ptr(profile).to_str().ok()?;
+    let profile: Profile = get_profile_by_name(name).ok()?;
+    let spec: AudioSpec = AudioSpec::new(sample_rate, 32, 1, SampleEncoding::F32);
+    Some((profile, spec))
+}
+
+#[test]
+fn test_ffi_roundtrip() {
+    let profile: &CStr = c"fast";
+    let payload: &[u8] = b"Hello from C";
+
+    unsafe {
+        let tx: *mut WavetrxTx = wavetrx_tx_create(profile.as_ptr(), 48_000);
+        let rx: *mut WavetrxRx = wavetrx_rx_create(profile.as_ptr(), 48_000);
+        assert!(!tx.is_null() && !rx.is_null());
+        assert!(wavetrx_rx_create(c"unknown".as_ptr(), 48_000).is_null());
+
+        let mut samples: WavetrxSamples = WavetrxSamples {
+            data: ptr::null_mut(),
+            len: 0,
+        };
+        let status: WavetrxStatus =
+            wavetrx_tx_encode(tx, payload.as_ptr(), payload.len(), &mut samples);
+        assert_eq!(status, WavetrxStatus::Ok);
+
+        // One push holding the whole message must still decode it
+        let status: WavetrxStatus = wavetrx_rx_push_samples(rx, samples.data, samples.len);
+        assert_eq!(status, WavetrxStatus::Ok);
+        wavetrx_samples_free(samples);
+
+        let mut message: WavetrxMessage = WavetrxMessage {
+            data: ptr::null_mut(),
+            len: 0,
+            start_sample: 0,
+        };
+        assert_eq!(wavetrx_rx_poll_message(rx, &mut message), WavetrxStatus::Ok);
+        assert_eq!(slice::from_raw_parts(message.data, message.len), payload);
+        wavetrx_message_free(message);
+
+        let mut empty: WavetrxMessage = WavetrxMessage {
+            data: ptr::null_mut(),
+            len: 0,
+            start_sample: 0,
+        };
+        let status: WavetrxStatus = wavetrx_rx_poll_message(rx, &mut empty);
+        assert_eq!(status, WavetrxStatus::Empty);
+        assert!(empty.data.is_null());
+
+        wavetrx_tx_destroy(tx);
+        wavetrx_rx_destroy(rx);
+    }
+}
+
+#[test]
+fn test_guard_catches_panic() {
+    let status: WavetrxStatus = guard(WavetrxStatus::Panicked, || panic!("decoder bug"));
+    assert_eq!(status, WavetrxStatus::Panicked);
+    assert_eq!(
+        guard(WavetrxStatus::Panicked, || WavetrxStatus::Ok),
+        WavetrxStatus::Ok
+    );
+}
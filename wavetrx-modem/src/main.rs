@@ -0,0 +1,6 @@
+mod modem;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    modem::control_modem()?;
+    Ok(())
+}
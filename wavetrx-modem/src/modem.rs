@@ -0,0 +1,51 @@
+use std::env;
+
+use wavetrx::control::serve_stdio;
+use wavetrx::control::serve_tcp;
+use wavetrx::control::AudioBackend;
+use wavetrx::control::ControlServer;
+
+use wavetrx::protocol::profile::Profile;
+use wavetrx::utils::get_profile_by_name;
+
+struct ModemArgs {
+    profile_name: String,
+    tcp_addr: Option<String>,
+}
+
+fn parse_args() -> Result<ModemArgs, Box<dyn std::error::Error>> {
+    let mut profile_name: String = "fast".to_string();
+    let mut tcp_addr: Option<String> = None;
+
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--profile" => profile_name = args.next().ok_or("Missing value for --profile")?,
+            "--tcp" => tcp_addr = Some(args.next().ok_or("Missing value for --tcp")?),
+            _ => return Err(format!("Unknown argument: {}", arg).into()),
+        }
+    }
+
+    Ok(ModemArgs {
+        profile_name,
+        tcp_addr,
+    })
+}
+
+pub fn control_modem() -> Result<(), Box<dyn std::error::Error>> {
+    let args: ModemArgs = parse_args()?;
+    let profile: Profile = get_profile_by_name(&args.profile_name)?;
+
+    let mut backend: AudioBackend = AudioBackend::from_default_devices(profile)?;
+    backend.start()?;
+
+    let mut server: ControlServer<AudioBackend> = ControlServer::new(backend, &args.profile_name);
+    match args.tcp_addr {
+        Some(addr) => {
+            eprintln!("[Modem] Listening on {}", addr);
+            serve_tcp(&mut server, addr)?;
+        }
+        None => serve_stdio(&mut server)?,
+    }
+    Ok(())
+}
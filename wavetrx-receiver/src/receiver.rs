@@ -11,7 +11,6 @@ use cpal::traits::HostTrait;
 
 use wavetrx::audio::recorder::InputRecorder;
 use wavetrx::audio::types::AudioSpec;
-use wavetrx::audio::types::NormSamples;
 use wavetrx::audio::types::SampleEncoding;
 
 use wavetrx::protocol::profile::Profile;
@@ -45,11 +44,11 @@ pub fn get_default_output_device(
     Ok((device, config))
 }
 
-pub fn get_mono_audio_spec_i32(config: &SupportedStreamConfig) -> AudioSpec {
+pub fn get_audio_spec_i32(config: &SupportedStreamConfig) -> AudioSpec {
     let sample_rate: u32 = config.sample_rate().0;
     let sample_format: SampleFormat = config.sample_format();
     let bps: u16 = (sample_format.sample_size() * 8) as u16;
-    let channels: u16 = 1;
+    let channels: u16 = config.channels();
     let encoding: SampleEncoding = SampleEncoding::I32;
     let spec: AudioSpec = AudioSpec::new(sample_rate, bps, channels, encoding);
     spec
@@ -68,7 +67,7 @@ pub fn live_output_receiver() -> Result<(), Box<dyn std::error::Error>> {
     let (device, config): (Device, SupportedStreamConfig) = get_default_output_device()?;
     print_config(&device, &config);
 
-    let spec: AudioSpec = get_mono_audio_spec_i32(&config);
+    let spec: AudioSpec = get_audio_spec_i32(&config);
     let profile: Profile = get_fast_profile();
     display_profile(&profile, &spec);
 
@@ -79,16 +78,12 @@ pub fn live_output_receiver() -> Result<(), Box<dyn std::error::Error>> {
     println!("\n[Messages]");
 
     loop {
-        if let Some(samples) = recorder.take_frame() {
-            let mut sc_samples: NormSamples = NormSamples::new();
-            for (idx, sample) in samples.0.iter().enumerate() {
-                if idx % 2 == 0 {
-                    sc_samples.0.push(*sample);
-                }
-            }
-
-            receiver.add_samples(&mut sc_samples);
+        if let Some(mut samples) = recorder.take_frame() {
+            receiver.add_samples(&mut samples);
             receiver.analyze_buffer();
+            for message in receiver.take_messages() {
+                println!("{}", message.as_utf8_lossy());
+            }
             continue;
         }
         sleep(Duration::from_millis(50));
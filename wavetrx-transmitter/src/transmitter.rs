@@ -13,6 +13,7 @@ use wavetrx::audio::player::OutputPlayer;
 use wavetrx::audio::types::AudioSpec;
 use wavetrx::audio::types::NormSamples;
 use wavetrx::audio::types::SampleEncoding;
+use wavetrx::error::WavetrxError;
 
 use wavetrx::protocol::profile::Profile;
 use wavetrx::protocol::tx::StreamTransmitter;
@@ -33,12 +34,9 @@ fn input(prompt: &str) -> String {
     input.trim().to_string()
 }
 
-fn transmit_string(
-    string: &str,
-    transmitter: &Transmitter,
-) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
+fn transmit_string(string: &str, transmitter: &Transmitter) -> Result<Vec<f32>, WavetrxError> {
     let data: &[u8] = string.as_bytes();
-    let result: Result<Vec<f32>, Box<dyn std::error::Error>> = transmitter.create(data);
+    let result: Result<Vec<f32>, WavetrxError> = transmitter.create(data);
 
     if let Err(err) = result {
         panic!("Error: Failed to generate data: {:?}", err);
@@ -98,7 +96,7 @@ pub fn transmitter_player() -> Result<(), Box<dyn std::error::Error>> {
             println!("Length: {:?}s", timestamp.as_millis() as f32 / 1e3);
             player.add_samples(samples);
 
-            player.wait();
+            player.wait()?;
             println!();
         }
     }
@@ -121,12 +119,12 @@ pub fn stream_transmitter_player() -> Result<(), Box<dyn std::error::Error>> {
         let string: String = input("Input: ");
         let data: &[u8] = string.as_bytes();
         let stream_transmitter: StreamTransmitter<'_, TX_BUFFER> =
-            StreamTransmitter::new(&profile, &spec, data);
+            StreamTransmitter::new(&profile, &spec, data)?;
 
         for stream_samples in stream_transmitter {
-            let stream_samples: NormSamples = NormSamples::from_vec(stream_samples);
+            let stream_samples: NormSamples = NormSamples::from_vec(stream_samples?);
             player.add_samples(stream_samples);
-            player.wait_until(4096);
+            player.wait_until(4096)?;
         }
     }
 }
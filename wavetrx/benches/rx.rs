@@ -0,0 +1,128 @@
+use std::path::PathBuf;
+
+use criterion::black_box;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::BatchSize;
+use criterion::Criterion;
+
+use wavetrx::audio::spectrum::FourierMagnitude;
+use wavetrx::audio::spectrum::GoertzelMagnitude;
+use wavetrx::audio::spectrum::MagnitudeBackend;
+use wavetrx::audio::spectrum::Normalizer;
+use wavetrx::audio::types::AudioSpec;
+use wavetrx::audio::types::NormSamples;
+use wavetrx::audio::types::SampleEncoding;
+use wavetrx::protocol::profile::Profile;
+use wavetrx::protocol::profile::SizedPulses;
+use wavetrx::protocol::rx::Receiver;
+use wavetrx::protocol::tx::Transmitter;
+use wavetrx::sim::NoiseSource;
+use wavetrx::utils::get_fast_profile;
+
+const PAYLOAD: &[u8] = b"The quick brown fox jumps over the lazy dog";
+
+fn spec() -> AudioSpec {
+    AudioSpec::new(48_000, 16, 1, SampleEncoding::I32)
+}
+
+// Deterministic low-level noise so every run sees the same input
+fn noise(len: usize, seed: u64) -> Vec<f32> {
+    let mut noise: NoiseSource = NoiseSource::new(seed);
+    (0..len).map(|_| (noise.uniform() - 0.5) * 0.02).collect()
+}
+
+fn message(profile: &Profile, spec: &AudioSpec) -> Vec<f32> {
+    let transmitter: Transmitter = Transmitter::new(profile, spec);
+    transmitter.create(PAYLOAD).unwrap()
+}
+
+fn decode(profile: Profile, spec: AudioSpec, samples: Vec<f32>) -> usize {
+    let mut receiver: Receiver = Receiver::new(profile, spec);
+    receiver.add_samples(&mut NormSamples::from_vec(samples));
+    receiver.analyze_full_buffer();
+    receiver.take_messages().len()
+}
+
+fn bench_start_search(c: &mut Criterion) {
+    let profile: Profile = get_fast_profile();
+    let spec: AudioSpec = spec();
+    // No Start marker anywhere, so only the start-index search runs
+    let samples: Vec<f32> = noise(spec.sample_rate() as usize, 7);
+
+    c.bench_function("start_search_1s_noise", |b| {
+        b.iter_batched(
+            || samples.clone(),
+            |samples| decode(profile, spec, samples),
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+fn bench_symbol_magnitudes(c: &mut Criterion) {
+    let profile: Profile = get_fast_profile();
+    let spec: AudioSpec = spec();
+    let pulses: SizedPulses = profile.pulses.into_sized(&spec);
+    let chunk: Vec<f32> = message(&profile, &spec)[..pulses.tone_size()].to_vec();
+    let probes: [f32; 5] = [
+        profile.markers.start.hz(),
+        profile.markers.end.hz(),
+        profile.markers.next.hz(),
+        profile.bits.high.hz(),
+        profile.bits.low.hz(),
+    ];
+
+    let fft: FourierMagnitude = FourierMagnitude::new(&pulses, &spec);
+    let goertzel: GoertzelMagnitude = GoertzelMagnitude::new(&pulses, &spec);
+    c.bench_function("symbol_magnitudes_fft", |b| {
+        b.iter(|| fft.get_magnitudes(black_box(&chunk), &probes))
+    });
+    c.bench_function("symbol_magnitudes_goertzel", |b| {
+        b.iter(|| MagnitudeBackend::get_magnitudes(&goertzel, black_box(&chunk), &probes))
+    });
+}
+
+fn bench_normalization(c: &mut Criterion) {
+    let spec: AudioSpec = spec();
+    let samples: Vec<f32> = noise(spec.sample_rate() as usize, 5);
+
+    c.bench_function("normalize_1s", |b| {
+        b.iter_batched(
+            || samples.clone(),
+            |mut samples| {
+                let mut normalizer: Normalizer<'_> = Normalizer::new(&mut samples);
+                normalizer.normalize_floor(1.0, 0.1);
+                samples
+            },
+            BatchSize::LargeInput,
+        )
+    });
+}
+
+fn bench_file_decode(c: &mut Criterion) {
+    let profile: Profile = get_fast_profile();
+    let spec: AudioSpec = spec();
+    let path: PathBuf = std::env::temp_dir().join("wavetrx_bench_rx.wav");
+    NormSamples::from_vec(message(&profile, &spec))
+        .save_file(&path, &spec)
+        .unwrap();
+
+    c.bench_function("file_decode", |b| {
+        b.iter(|| {
+            let mut receiver: Receiver = Receiver::from_file(profile, &path).unwrap();
+            receiver.analyze_full_buffer();
+            let messages: usize = receiver.take_messages().len();
+            assert_eq!(messages, 1);
+            messages
+        })
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_start_search,
+    bench_symbol_magnitudes,
+    bench_normalization,
+    bench_file_decode
+);
+criterion_main!(benches);
@@ -0,0 +1,389 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use log::warn;
+
+use super::io::SampleSink;
+use super::io::WavSink;
+use super::types::AudioSpec;
+use super::types::Backpressure;
+use super::types::FrameBuffer;
+use super::types::NormSamples;
+
+use crate::consts::ARCHIVE_QUEUE;
+use crate::consts::ARCHIVE_ROTATION;
+use crate::error::WavetrxError;
+
+const IDLE_INTERVAL: Duration = Duration::from_millis(50);
+
+// When an archive file is closed and the next one begun; sizes count the
+// sample data, not the WAV header
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Rotation {
+    Size(u64),
+    Duration(Duration),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ArchiveConfig {
+    rotation: Rotation,
+    quota: Option<u64>,
+    prefix: String,
+}
+
+impl ArchiveConfig {
+    pub fn new() -> Self {
+        ArchiveConfig {
+            rotation: Rotation::Duration(ARCHIVE_ROTATION),
+            quota: None,
+            prefix: "capture".to_string(),
+        }
+    }
+
+    pub fn with_rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    // Bytes on disk the archive may use; the oldest closed files are deleted
+    // to stay under it, the file being written never is
+    pub fn with_quota(mut self, quota: Option<u64>) -> Self {
+        self.quota = quota;
+        self
+    }
+
+    // Files are named `<prefix>_<n>.wav`, numbered on from the highest
+    // already in the directory, so a restart never overwrites a capture
+    pub fn with_prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    pub fn rotation(&self) -> Rotation {
+        self.rotation
+    }
+
+    pub fn quota(&self) -> Option<u64> {
+        self.quota
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+}
+
+impl Default for ArchiveConfig {
+    fn default() -> Self {
+        ArchiveConfig::new()
+    }
+}
+
+// Tees captured audio into rotating WAV files on its own thread, so disk
+// writes never hold up decoding. Frames that arrive while the queue is full
+// are dropped from the archive only
+pub struct WavArchive {
+    frames: Arc<FrameBuffer>,
+    running: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl WavArchive {
+    // `spec` is that of the audio written, interleaved channels included
+    pub fn start<P>(dir: P, spec: &AudioSpec, config: ArchiveConfig) -> Result<Self, WavetrxError>
+    where
+        P: AsRef<Path>,
+    {
+        spec.check_wav_support()?;
+        fs::create_dir_all(&dir)?;
+        let files: ArchiveFiles = ArchiveFiles::new(dir.as_ref(), spec, config);
+        let frames: Arc<FrameBuffer> =
+            FrameBuffer::bounded(ARCHIVE_QUEUE, Backpressure::DropNewest);
+        let running: Arc<AtomicBool> = Arc::new(AtomicBool::new(true));
+
+        let handle: JoinHandle<()> = Self::spawn_thread(files, frames.clone(), running.clone());
+        Ok(WavArchive {
+            frames,
+            running,
+            handle: Some(handle),
+        })
+    }
+
+    pub fn write(&self, samples: &[f32]) {
+        self.frames.add_frame(NormSamples::from_slice(samples));
+    }
+
+    pub fn dropped_frames(&self) -> usize {
+        self.frames.dropped()
+    }
+
+    // Frames already queued are written and the last file completed
+    pub fn stop(mut self) {
+        self.join();
+    }
+}
+
+impl WavArchive {
+    fn spawn_thread(
+        mut files: ArchiveFiles,
+        frames: Arc<FrameBuffer>,
+        running: Arc<AtomicBool>,
+    ) -> JoinHandle<()> {
+        thread::spawn(move || {
+            loop {
+                match frames.take_timeout(IDLE_INTERVAL) {
+                    Some(frame) => files.write(&frame.0),
+                    None if !running.load(Ordering::Relaxed) => break,
+                    None => {}
+                }
+            }
+            files.close();
+        })
+    }
+
+    fn join(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for WavArchive {
+    fn drop(&mut self) {
+        self.join();
+    }
+}
+
+// The archive thread's side: the open file and those closed before it
+struct ArchiveFiles {
+    dir: PathBuf,
+    spec: AudioSpec,
+    config: ArchiveConfig,
+    sink: Option<(WavSink, PathBuf)>,
+    written: usize,
+    closed: VecDeque<(PathBuf, u64)>,
+    count: usize,
+}
+
+impl ArchiveFiles {
+    // Files left by earlier runs count towards the quota, oldest first
+    fn new(dir: &Path, spec: &AudioSpec, config: ArchiveConfig) -> Self {
+        let mut files: ArchiveFiles = ArchiveFiles {
+            dir: dir.to_path_buf(),
+            spec: *spec,
+            config,
+            sink: None,
+            written: 0,
+            closed: VecDeque::new(),
+            count: 0,
+        };
+        files.scan();
+        files
+    }
+
+    // Chunks are split at rotation points, so every closed file holds
+    // exactly one rotation's worth of whole frames
+    fn write(&mut self, mut samples: &[f32]) {
+        let channels: usize = (self.spec.channels() as usize).max(1);
+        while samples.len() >= channels {
+            if self.sink.is_none() && !self.open() {
+                return;
+            }
+
+            let frames: usize = (samples.len() / channels).min(self.file_frames() - self.written);
+            let (chunk, rest): (&[f32], &[f32]) = samples.split_at(frames * channels);
+            if let Some((sink, _)) = self.sink.as_mut() {
+                sink.write(chunk);
+            }
+            self.written += frames;
+            samples = rest;
+
+            if self.written >= self.file_frames() {
+                self.close();
+            }
+        }
+    }
+
+    // A name another writer has taken since the scan is skipped
+    fn open(&mut self) -> bool {
+        loop {
+            let path: PathBuf = self.path(self.count);
+            self.count += 1;
+            match WavSink::create_new(&path, &self.spec) {
+                Ok(sink) => {
+                    self.sink = Some((sink, path));
+                    self.written = 0;
+                    return true;
+                }
+                Err(WavetrxError::Io(err)) if err.kind() == io::ErrorKind::AlreadyExists => {}
+                Err(err) => {
+                    warn!("Could not create {}: {}", path.display(), err);
+                    return false;
+                }
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        let (mut sink, path): (WavSink, PathBuf) = match self.sink.take() {
+            Some(open) => open,
+            None => return,
+        };
+        if let Err(err) = sink.flush() {
+            warn!("Could not complete archive file: {}", err);
+        }
+        drop(sink);
+
+        let size: u64 = fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0);
+        self.closed.push_back((path, size));
+        self.enforce_quota();
+    }
+
+    fn enforce_quota(&mut self) {
+        let quota: u64 = match self.config.quota {
+            Some(quota) => quota,
+            None => return,
+        };
+        let mut used: u64 = self.closed.iter().map(|(_, size)| size).sum();
+        while used > quota {
+            let (path, size): (PathBuf, u64) = match self.closed.pop_front() {
+                Some(file) => file,
+                None => break,
+            };
+            if let Err(err) = fs::remove_file(&path) {
+                warn!("Could not remove {}: {}", path.display(), err);
+            }
+            used -= size;
+        }
+    }
+
+    fn scan(&mut self) {
+        let entries: fs::ReadDir = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(err) => {
+                warn!("Could not list {}: {}", self.dir.display(), err);
+                return;
+            }
+        };
+        let mut found: Vec<(usize, PathBuf, u64)> = Vec::new();
+        for entry in entries.flatten() {
+            let name: String = entry.file_name().to_string_lossy().into_owned();
+            let index: Option<usize> = name
+                .strip_prefix(self.config.prefix.as_str())
+                .and_then(|rest| rest.strip_prefix('_'))
+                .and_then(|rest| rest.strip_suffix(".wav"))
+                .filter(|digits| digits.bytes().all(|byte| byte.is_ascii_digit()))
+                .and_then(|digits| digits.parse().ok());
+            if let Some(index) = index {
+                let size: u64 = entry.metadata().map(|meta| meta.len()).unwrap_or(0);
+                found.push((index, entry.path(), size));
+            }
+        }
+        found.sort_by_key(|(index, _, _)| *index);
+
+        if let Some((index, _, _)) = found.last() {
+            self.count = index + 1;
+        }
+        self.closed = found
+            .into_iter()
+            .map(|(_, path, size)| (path, size))
+            .collect();
+    }
+
+    fn path(&self, index: usize) -> PathBuf {
+        let filename: String = format!("{}_{:06}.wav", self.config.prefix, index);
+        self.dir.join(filename)
+    }
+
+    // At least one frame, so a rotation below a frame's size still progresses
+    fn file_frames(&self) -> usize {
+        let frames: u64 = match self.config.rotation {
+            Rotation::Size(bytes) => {
+                let channels: u64 = self.spec.channels().max(1) as u64;
+                let block: u64 = channels * (self.spec.bits_per_sample() as u64).div_ceil(8);
+                bytes / block
+            }
+            Rotation::Duration(duration) => {
+                (duration.as_secs_f64() * self.spec.sample_rate() as f64) as u64
+            }
+        };
+        (frames as usize).max(1)
+    }
+}
+
+#[test]
+fn test_wav_archive() {
+    use crate::audio::types::SampleEncoding;
+    use crate::utils::read_wav_file;
+
+    let dir: PathBuf = std::env::temp_dir().join(format!("wavetrx_archive_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let spec: AudioSpec = AudioSpec::new(8_000, 16, 2, SampleEncoding::I32);
+
+    // A quarter second per file, 1.1 seconds written in uneven chunks
+    let config: ArchiveConfig = ArchiveConfig::new()
+        .with_rotation(Rotation::Duration(Duration::from_millis(250)))
+        .with_prefix("test");
+    let archive: WavArchive = WavArchive::start(&dir, &spec, config).unwrap();
+    let samples: Vec<f32> = (0..17_600).map(|idx| (idx % 200) as f32 / 400.0).collect();
+    for chunk in samples.chunks(1_234) {
+        archive.write(chunk);
+    }
+    archive.stop();
+
+    let mut names: Vec<String> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    assert_eq!(names.len(), 5);
+    assert_eq!(names[0], "test_000000.wav");
+    let (first, first_spec): (NormSamples, AudioSpec) = read_wav_file(dir.join(&names[0])).unwrap();
+    assert_eq!(first.0.len(), 4_000);
+    assert_eq!(first_spec.channels(), 2);
+    let (last, _): (NormSamples, AudioSpec) = read_wav_file(dir.join(&names[4])).unwrap();
+    assert_eq!(last.0.len(), 1_600);
+
+    // Closed files beyond the quota are removed oldest first
+    let _ = fs::remove_dir_all(&dir);
+    let config: ArchiveConfig = ArchiveConfig::new()
+        .with_rotation(Rotation::Size(4_000))
+        .with_quota(Some(9_000))
+        .with_prefix("quota");
+    let archive: WavArchive = WavArchive::start(&dir, &spec, config).unwrap();
+    archive.write(&samples[..10_000]);
+    archive.stop();
+    let mut names: Vec<String> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["quota_000003.wav", "quota_000004.wav"]);
+
+    // A restart numbers on from the last file and counts it against the quota
+    let config: ArchiveConfig = ArchiveConfig::new()
+        .with_rotation(Rotation::Size(4_000))
+        .with_quota(Some(9_000))
+        .with_prefix("quota");
+    let archive: WavArchive = WavArchive::start(&dir, &spec, config).unwrap();
+    archive.write(&samples[..2_000]);
+    archive.stop();
+    let mut names: Vec<String> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["quota_000004.wav", "quota_000005.wav"]);
+    let _ = fs::remove_dir_all(&dir);
+}
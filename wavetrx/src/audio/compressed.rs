@@ -0,0 +1,88 @@
+use std::fs::File;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use symphonia::core::audio::AudioBufferRef;
+use symphonia::core::audio::SampleBuffer as DecodedBuffer;
+use symphonia::core::audio::SignalSpec;
+use symphonia::core::codecs::Decoder;
+use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::codecs::CODEC_TYPE_NULL;
+use symphonia::core::errors::Error;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::formats::FormatReader;
+use symphonia::core::formats::Track;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use super::types::AudioSpec;
+use super::types::NormSamples;
+use super::types::SampleEncoding;
+
+use crate::error::WavetrxError;
+
+// Decodes the first audio track of an MP3, AAC/M4A, Ogg Vorbis, FLAC, MKV or
+// WAV file to interleaved samples. The extension is only a hint; the
+// container is probed from its contents
+pub fn decode_file<P>(filename: P) -> Result<(NormSamples, AudioSpec), WavetrxError>
+where
+    P: AsRef<Path>,
+{
+    let path: &Path = filename.as_ref();
+    let file: File = File::open(path)?;
+    let stream: MediaSourceStream = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint: Hint = Hint::new();
+    if let Some(extension) = path.extension().and_then(|extension| extension.to_str()) {
+        hint.with_extension(extension);
+    }
+    let mut reader: Box<dyn FormatReader> = symphonia::default::get_probe()
+        .format(
+            &hint,
+            stream,
+            &FormatOptions::default(),
+            &MetadataOptions::default(),
+        )?
+        .format;
+
+    let track: &Track = reader
+        .tracks()
+        .iter()
+        .find(|track| track.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| WavetrxError::UnsupportedAudio("No audio track".to_string()))?;
+    let track_id: u32 = track.id;
+    let mut decoder: Box<dyn Decoder> =
+        symphonia::default::get_codecs().make(&track.codec_params, &DecoderOptions::default())?;
+
+    let mut samples: Vec<f32> = Vec::new();
+    let mut signal: Option<SignalSpec> = None;
+    loop {
+        let packet = match reader.next_packet() {
+            Ok(packet) => packet,
+            Err(Error::IoError(err)) if err.kind() == ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err.into()),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        // A corrupt packet drops its own samples, not the whole recording
+        let decoded: AudioBufferRef = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            Err(Error::DecodeError(_)) => continue,
+            Err(err) => return Err(err.into()),
+        };
+        let spec: SignalSpec = *decoded.spec();
+        let mut buffer: DecodedBuffer<f32> = DecodedBuffer::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        samples.extend_from_slice(buffer.samples());
+        signal = Some(spec);
+    }
+
+    let signal: SignalSpec =
+        signal.ok_or_else(|| WavetrxError::UnsupportedAudio("No decodable audio".to_string()))?;
+    let channels: u16 = signal.channels.count() as u16;
+    let spec: AudioSpec = AudioSpec::new(signal.rate, 32, channels, SampleEncoding::F32);
+    Ok((NormSamples::from_vec(samples), spec))
+}
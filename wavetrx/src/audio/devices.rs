@@ -0,0 +1,143 @@
+use cpal::traits::DeviceTrait;
+use cpal::traits::HostTrait;
+use cpal::Device;
+use cpal::Host;
+use cpal::SampleFormat;
+use cpal::SupportedStreamConfigRange;
+
+use crate::error::WavetrxError;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DeviceDirection {
+    Input,
+    Output,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ConfigRange {
+    pub channels: u16,
+    pub min_sample_rate: u32,
+    pub max_sample_rate: u32,
+    pub sample_format: SampleFormat,
+}
+
+impl ConfigRange {
+    pub fn supports_rate(&self, sample_rate: u32) -> bool {
+        (self.min_sample_rate..=self.max_sample_rate).contains(&sample_rate)
+    }
+}
+
+impl From<SupportedStreamConfigRange> for ConfigRange {
+    fn from(range: SupportedStreamConfigRange) -> Self {
+        ConfigRange {
+            channels: range.channels(),
+            min_sample_rate: range.min_sample_rate().0,
+            max_sample_rate: range.max_sample_rate().0,
+            sample_format: range.sample_format(),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct DeviceInfo {
+    pub host: String,
+    pub name: String,
+    pub direction: DeviceDirection,
+    pub is_default: bool,
+    pub configs: Vec<ConfigRange>,
+}
+
+// Devices from every available host, in host order
+pub fn list_devices(direction: DeviceDirection) -> Result<Vec<DeviceInfo>, WavetrxError> {
+    let mut infos: Vec<DeviceInfo> = Vec::new();
+    for host_id in cpal::available_hosts() {
+        let host: Host = cpal::host_from_id(host_id)?;
+        let default_name: Option<String> = default_device(&host, direction)
+            .as_ref()
+            .and_then(|device| device.name().ok());
+
+        for device in host_devices(&host, direction)? {
+            let name: String = device.name()?;
+            let configs: Vec<ConfigRange> = device_configs(&device, direction)?;
+            infos.push(DeviceInfo {
+                host: host_id.name().to_string(),
+                is_default: default_name.as_deref() == Some(name.as_str()),
+                name,
+                direction,
+                configs,
+            });
+        }
+    }
+    Ok(infos)
+}
+
+pub fn input_devices() -> Result<Vec<DeviceInfo>, WavetrxError> {
+    list_devices(DeviceDirection::Input)
+}
+
+pub fn output_devices() -> Result<Vec<DeviceInfo>, WavetrxError> {
+    list_devices(DeviceDirection::Output)
+}
+
+// Exact names win; otherwise the first case-insensitive partial match is used
+pub fn find_device(name: &str, direction: DeviceDirection) -> Result<Device, WavetrxError> {
+    let needle: String = name.to_lowercase();
+    let mut partial: Option<Device> = None;
+
+    for host_id in cpal::available_hosts() {
+        let host: Host = cpal::host_from_id(host_id)?;
+        for device in host_devices(&host, direction)? {
+            let device_name: String = device.name()?;
+            if device_name == name {
+                return Ok(device);
+            }
+            if partial.is_none() && device_name.to_lowercase().contains(&needle) {
+                partial = Some(device);
+            }
+        }
+    }
+
+    partial.ok_or_else(|| {
+        WavetrxError::DeviceError(format!("No {:?} device named {}", direction, name))
+    })
+}
+
+pub fn find_input_device(name: &str) -> Result<Device, WavetrxError> {
+    find_device(name, DeviceDirection::Input)
+}
+
+pub fn find_output_device(name: &str) -> Result<Device, WavetrxError> {
+    find_device(name, DeviceDirection::Output)
+}
+
+fn default_device(host: &Host, direction: DeviceDirection) -> Option<Device> {
+    match direction {
+        DeviceDirection::Input => host.default_input_device(),
+        DeviceDirection::Output => host.default_output_device(),
+    }
+}
+
+fn host_devices(host: &Host, direction: DeviceDirection) -> Result<Vec<Device>, WavetrxError> {
+    let devices: Vec<Device> = match direction {
+        DeviceDirection::Input => host.input_devices()?.collect(),
+        DeviceDirection::Output => host.output_devices()?.collect(),
+    };
+    Ok(devices)
+}
+
+fn device_configs(
+    device: &Device,
+    direction: DeviceDirection,
+) -> Result<Vec<ConfigRange>, WavetrxError> {
+    let configs: Vec<ConfigRange> = match direction {
+        DeviceDirection::Input => device
+            .supported_input_configs()?
+            .map(ConfigRange::from)
+            .collect(),
+        DeviceDirection::Output => device
+            .supported_output_configs()?
+            .map(ConfigRange::from)
+            .collect(),
+    };
+    Ok(configs)
+}
@@ -74,6 +74,7 @@ impl<'a> FrequencyPass<'a> {
 }
 
 #[test]
+#[cfg(feature = "wav")]
 fn test_filter() {
     use super::types::NormSamples;
     use super::types::SampleEncoding;
@@ -101,5 +102,5 @@ fn test_filter() {
     filters.apply_lowpass(lowpass_frequency, 0.707);
     // filters.apply_bandpass(5000.0, 10_000.0, 2.0);
 
-    samples.save_file("test_filters2.wav", &spec);
+    samples.save_file("test_filters2.wav", &spec).unwrap();
 }
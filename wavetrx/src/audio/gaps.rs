@@ -0,0 +1,159 @@
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::SystemTime;
+
+// Shared between the capture callback and the consumer; positions count the
+// samples accepted into the ring since recording started
+pub struct GapLog {
+    accepted: AtomicUsize,
+    count: AtomicUsize,
+    position: AtomicUsize,
+}
+
+impl GapLog {
+    pub fn new() -> Arc<Self> {
+        let accepted: AtomicUsize = AtomicUsize::new(0);
+        let count: AtomicUsize = AtomicUsize::new(0);
+        let position: AtomicUsize = AtomicUsize::new(0);
+        Arc::new(Self {
+            accepted,
+            count,
+            position,
+        })
+    }
+
+    pub fn accept(&self, samples: usize) {
+        self.accepted.fetch_add(samples, Ordering::Relaxed);
+    }
+
+    // Marks a hole in the timeline just after the samples accepted so far
+    pub fn mark(&self) {
+        let position: usize = self.accepted.load(Ordering::Relaxed);
+        self.position.store(position, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Release);
+    }
+
+    pub fn accepted(&self) -> usize {
+        self.accepted.load(Ordering::Relaxed)
+    }
+
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Acquire)
+    }
+
+    pub fn last_position(&self) -> usize {
+        self.position.load(Ordering::Relaxed)
+    }
+}
+
+// Wall-clock time of the first sample of the latest capture callback, at
+// its position among the accepted samples. The callback only tries the
+// lock, so a stamp is skipped rather than waited for
+pub struct CaptureStamp {
+    stamp: Mutex<Option<(usize, SystemTime)>>,
+    sample_rate: u32,
+    channels: usize,
+}
+
+impl CaptureStamp {
+    pub fn new(sample_rate: u32, channels: usize) -> Arc<Self> {
+        let stamp: Mutex<Option<(usize, SystemTime)>> = Mutex::new(None);
+        let channels: usize = channels.max(1);
+        Arc::new(Self {
+            stamp,
+            sample_rate,
+            channels,
+        })
+    }
+
+    pub fn stamp(&self, position: usize, time: SystemTime) {
+        if let Ok(mut stamp) = self.stamp.try_lock() {
+            *stamp = Some((position, time));
+        }
+    }
+
+    // Extrapolated from the latest stamp at the capture rate; None until the
+    // first callback
+    pub fn time_at(&self, position: usize) -> Option<SystemTime> {
+        let (stamped, time): (usize, SystemTime) = (*self.stamp.lock().ok()?)?;
+        let offset = |samples: usize| -> Duration {
+            let frames: u64 = (samples / self.channels) as u64;
+            Duration::from_nanos(frames * 1_000_000_000 / self.sample_rate.max(1) as u64)
+        };
+        match position >= stamped {
+            true => time.checked_add(offset(position - stamped)),
+            false => time.checked_sub(offset(stamped - position)),
+        }
+    }
+}
+
+// Flags callbacks whose capture time lands well past the end of the previous block
+pub struct CaptureClock {
+    sample_rate: u32,
+    channels: usize,
+    last_frames: Option<usize>,
+}
+
+impl CaptureClock {
+    pub fn new(sample_rate: u32, channels: usize) -> Self {
+        let channels: usize = channels.max(1);
+        let last_frames: Option<usize> = None;
+        CaptureClock {
+            sample_rate,
+            channels,
+            last_frames,
+        }
+    }
+
+    // `elapsed` is the capture time since the previous callback, when known
+    pub fn is_gap(&mut self, elapsed: Option<Duration>, samples: usize) -> bool {
+        let gap: bool = match (elapsed, self.last_frames) {
+            (Some(elapsed), Some(frames)) => {
+                let period: Duration =
+                    Duration::from_secs_f64(frames as f64 / self.sample_rate as f64);
+                elapsed > period + period / 2
+            }
+            _ => false,
+        };
+        self.last_frames = Some(samples / self.channels);
+        gap
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    pub fn reset(&mut self) {
+        self.last_frames = None;
+    }
+}
+
+#[test]
+fn test_capture_clock() {
+    let mut clock: CaptureClock = CaptureClock::new(48_000, 2);
+    let period: Duration = Duration::from_millis(10);
+
+    assert!(!clock.is_gap(None, 960));
+    assert!(!clock.is_gap(Some(period), 960));
+    assert!(!clock.is_gap(Some(period + Duration::from_millis(2)), 960));
+    assert!(clock.is_gap(Some(period * 3), 960));
+
+    let gaps: Arc<GapLog> = GapLog::new();
+    gaps.accept(960);
+    gaps.mark();
+    gaps.accept(960);
+    assert_eq!(gaps.count(), 1);
+    assert_eq!(gaps.last_position(), 960);
+
+    // Stamps are extrapolated either side at the frame rate
+    let stamp: Arc<CaptureStamp> = CaptureStamp::new(48_000, 2);
+    let time: SystemTime = SystemTime::UNIX_EPOCH + Duration::from_secs(60);
+    assert_eq!(stamp.time_at(0), None);
+    stamp.stamp(960, time);
+    assert_eq!(stamp.time_at(960), Some(time));
+    assert_eq!(stamp.time_at(1_920), Some(time + period));
+    assert_eq!(stamp.time_at(0), Some(time - period));
+}
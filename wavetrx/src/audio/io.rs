@@ -0,0 +1,307 @@
+#[cfg(feature = "wav")]
+use std::fs::File;
+#[cfg(feature = "wav")]
+use std::fs::OpenOptions;
+#[cfg(feature = "wav")]
+use std::io::BufReader;
+#[cfg(feature = "wav")]
+use std::io::BufWriter;
+use std::io::Read;
+use std::io::Write;
+#[cfg(feature = "wav")]
+use std::path::Path;
+
+#[cfg(feature = "wav")]
+use hound::WavReader;
+#[cfg(feature = "wav")]
+use hound::WavSpec;
+#[cfg(feature = "wav")]
+use hound::WavWriter;
+#[cfg(feature = "wav")]
+use log::warn;
+
+use super::raw::RawFormat;
+use super::raw::RawReader;
+use super::raw::RawWriter;
+#[cfg(feature = "wav")]
+use super::types::AudioSpec;
+#[cfg(feature = "wav")]
+use super::types::NormSamples;
+#[cfg(feature = "wav")]
+use super::types::SampleEncoding;
+
+use crate::error::WavetrxError;
+
+// Interleaved f32 input in whatever layout the consumer was configured for.
+// `read` returns how many samples it wrote into `out`, 0 when none are ready
+pub trait SampleSource {
+    fn read(&mut self, out: &mut [f32]) -> usize;
+
+    // Files and buffers run dry; devices and sockets stay open
+    fn is_finished(&self) -> bool {
+        false
+    }
+}
+
+// Returns how many samples were accepted; fewer than given means the sink
+// is full or closed
+pub trait SampleSink {
+    fn write(&mut self, samples: &[f32]) -> usize;
+
+    fn flush(&mut self) -> Result<(), WavetrxError> {
+        Ok(())
+    }
+}
+
+pub struct MemorySource {
+    samples: Vec<f32>,
+    position: usize,
+}
+
+impl MemorySource {
+    pub fn new(samples: Vec<f32>) -> Self {
+        let position: usize = 0;
+        MemorySource { samples, position }
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.samples.len() - self.position
+    }
+}
+
+impl SampleSource for MemorySource {
+    fn read(&mut self, out: &mut [f32]) -> usize {
+        let count: usize = out.len().min(self.remaining());
+        out[..count].copy_from_slice(&self.samples[self.position..self.position + count]);
+        self.position += count;
+        count
+    }
+
+    fn is_finished(&self) -> bool {
+        self.remaining() == 0
+    }
+}
+
+impl SampleSink for Vec<f32> {
+    fn write(&mut self, samples: &[f32]) -> usize {
+        self.extend_from_slice(samples);
+        samples.len()
+    }
+}
+
+// Raw little-endian f32 over any byte stream, e.g. a TcpStream or UnixStream
+pub struct SocketSource<R> {
+    reader: RawReader<R>,
+}
+
+impl<R> SocketSource<R>
+where
+    R: Read,
+{
+    pub fn new(stream: R) -> Self {
+        let reader: RawReader<R> = RawReader::new(stream, RawFormat::F32Le);
+        SocketSource { reader }
+    }
+
+    pub fn into_inner(self) -> R {
+        self.reader.into_inner()
+    }
+}
+
+impl<R> SampleSource for SocketSource<R>
+where
+    R: Read,
+{
+    fn read(&mut self, out: &mut [f32]) -> usize {
+        self.reader.read(out)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.reader.is_finished()
+    }
+}
+
+pub struct SocketSink<W> {
+    writer: RawWriter<W>,
+}
+
+impl<W> SocketSink<W>
+where
+    W: Write,
+{
+    pub fn new(stream: W) -> Self {
+        let writer: RawWriter<W> = RawWriter::new(stream, RawFormat::F32Le);
+        SocketSink { writer }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer.into_inner()
+    }
+}
+
+impl<W> SampleSink for SocketSink<W>
+where
+    W: Write,
+{
+    fn write(&mut self, samples: &[f32]) -> usize {
+        self.writer.write(samples)
+    }
+
+    fn flush(&mut self) -> Result<(), WavetrxError> {
+        self.writer.flush()
+    }
+}
+
+// Streams a WAV file from disk instead of loading it whole
+#[cfg(feature = "wav")]
+pub struct WavSource {
+    reader: WavReader<BufReader<File>>,
+    spec: AudioSpec,
+    finished: bool,
+}
+
+#[cfg(feature = "wav")]
+impl WavSource {
+    pub fn open<P>(filename: P) -> Result<Self, WavetrxError>
+    where
+        P: AsRef<Path>,
+    {
+        let reader: WavReader<BufReader<File>> = WavReader::open(filename)?;
+        let spec: AudioSpec = reader.spec().into();
+        spec.check_wav_support()?;
+        let finished: bool = false;
+        Ok(WavSource {
+            reader,
+            spec,
+            finished,
+        })
+    }
+
+    pub fn spec(&self) -> AudioSpec {
+        self.spec
+    }
+}
+
+#[cfg(feature = "wav")]
+impl SampleSource for WavSource {
+    fn read(&mut self, out: &mut [f32]) -> usize {
+        let read: Result<Vec<f32>, hound::Error> = match self.spec.encoding() {
+            SampleEncoding::F32 => self.reader.samples::<f32>().take(out.len()).collect(),
+            SampleEncoding::I32 => self
+                .reader
+                .samples::<i32>()
+                .take(out.len())
+                .collect::<Result<Vec<i32>, _>>()
+                .map(|samples| NormSamples::from_i32(&samples, &self.spec).0),
+        };
+
+        let samples: Vec<f32> = match read {
+            Ok(samples) => samples,
+            Err(err) => {
+                warn!("WAV source stopped: {}", err);
+                Vec::new()
+            }
+        };
+        if samples.len() < out.len() {
+            self.finished = true;
+        }
+        out[..samples.len()].copy_from_slice(&samples);
+        samples.len()
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+// Samples are written in the spec's encoding and bit depth; the header is
+// completed on `flush` and again when the sink is dropped
+#[cfg(feature = "wav")]
+pub struct WavSink {
+    writer: WavWriter<BufWriter<File>>,
+    spec: AudioSpec,
+}
+
+#[cfg(feature = "wav")]
+impl WavSink {
+    pub fn create<P>(filename: P, spec: &AudioSpec) -> Result<Self, WavetrxError>
+    where
+        P: AsRef<Path>,
+    {
+        spec.check_wav_support()?;
+        let wav_spec: WavSpec = (*spec).into();
+        let writer: WavWriter<BufWriter<File>> = WavWriter::create(filename, wav_spec)?;
+        let spec: AudioSpec = *spec;
+        Ok(WavSink { writer, spec })
+    }
+
+    // Like `create`, but fails with `AlreadyExists` instead of truncating
+    pub fn create_new<P>(filename: P, spec: &AudioSpec) -> Result<Self, WavetrxError>
+    where
+        P: AsRef<Path>,
+    {
+        spec.check_wav_support()?;
+        let wav_spec: WavSpec = (*spec).into();
+        let file: File = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(filename)?;
+        let writer: WavWriter<BufWriter<File>> = WavWriter::new(BufWriter::new(file), wav_spec)?;
+        let spec: AudioSpec = *spec;
+        Ok(WavSink { writer, spec })
+    }
+}
+
+#[cfg(feature = "wav")]
+impl SampleSink for WavSink {
+    fn write(&mut self, samples: &[f32]) -> usize {
+        for (idx, sample) in samples.iter().enumerate() {
+            let written: Result<(), hound::Error> = match self.spec.encoding() {
+                SampleEncoding::F32 => self.writer.write_sample(*sample),
+                SampleEncoding::I32 => self
+                    .writer
+                    .write_sample(NormSamples::f32_to_i32(*sample, &self.spec)),
+            };
+            if let Err(err) = written {
+                warn!("WAV sink stopped: {}", err);
+                return idx;
+            }
+        }
+        samples.len()
+    }
+
+    fn flush(&mut self) -> Result<(), WavetrxError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_socket_source_split_samples() {
+    use std::io::Cursor;
+
+    // Cursor reads hand out at most what the buffer asks for, so a short
+    // `out` splits samples across reads
+    let samples: Vec<f32> = vec![0.5, -0.25, 1.0];
+    let mut sink: SocketSink<Vec<u8>> = SocketSink::new(Vec::new());
+    assert_eq!(sink.write(&samples), 3);
+    let mut bytes: Vec<u8> = sink.into_inner();
+    bytes.pop();
+
+    let mut source: SocketSource<Cursor<Vec<u8>>> = SocketSource::new(Cursor::new(bytes));
+    let mut out: [f32; 2] = [0.0; 2];
+    assert_eq!(source.read(&mut out), 2);
+    assert_eq!(out, [0.5, -0.25]);
+    assert_eq!(source.read(&mut out), 0);
+    assert!(!source.is_finished());
+    assert_eq!(source.read(&mut out), 0);
+    assert!(source.is_finished());
+
+    let mut memory: MemorySource = MemorySource::new(samples);
+    let mut collected: Vec<f32> = Vec::new();
+    while !memory.is_finished() {
+        let count: usize = memory.read(&mut out);
+        collected.write(&out[..count]);
+    }
+    assert_eq!(collected, vec![0.5, -0.25, 1.0]);
+}
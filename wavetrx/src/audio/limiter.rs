@@ -0,0 +1,58 @@
+use crate::consts::LIMITER_THRESHOLD;
+
+// Leaves samples below `threshold` untouched and bends anything louder
+// smoothly towards full scale, so peaks never clip
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SoftLimiter {
+    threshold: f32,
+}
+
+impl SoftLimiter {
+    pub fn new(threshold: f32) -> Self {
+        let threshold: f32 = threshold.clamp(0.0, 1.0);
+        SoftLimiter { threshold }
+    }
+
+    pub fn threshold(&self) -> f32 {
+        self.threshold
+    }
+
+    pub fn apply(&self, sample: f32) -> f32 {
+        let level: f32 = sample.abs();
+        if level <= self.threshold {
+            return sample;
+        }
+        let headroom: f32 = 1.0 - self.threshold;
+        if headroom <= 0.0 {
+            return sample.signum() * self.threshold;
+        }
+        let over: f32 = (level - self.threshold) / headroom;
+        sample.signum() * (self.threshold + headroom * over.tanh())
+    }
+
+    pub fn process(&self, samples: &mut [f32]) {
+        for sample in samples.iter_mut() {
+            *sample = self.apply(*sample);
+        }
+    }
+}
+
+impl Default for SoftLimiter {
+    fn default() -> Self {
+        SoftLimiter::new(LIMITER_THRESHOLD)
+    }
+}
+
+#[test]
+fn test_soft_limiter() {
+    let limiter: SoftLimiter = SoftLimiter::default();
+    assert_eq!(limiter.apply(0.5), 0.5);
+    assert_eq!(limiter.apply(-LIMITER_THRESHOLD), -LIMITER_THRESHOLD);
+
+    let mut samples: Vec<f32> = vec![0.95, 1.0, 1.5, 4.0, -4.0];
+    limiter.process(&mut samples);
+    assert!(samples.iter().all(|sample| sample.abs() <= 1.0));
+    assert!(samples.windows(2).take(3).all(|pair| pair[0] < pair[1]));
+    assert_eq!(samples[3], -samples[4]);
+}
@@ -0,0 +1,167 @@
+use std::fs::File;
+use std::io::Cursor;
+use std::path::Path;
+
+use hound::SampleFormat;
+use hound::WavReader;
+use hound::WavSpec;
+use memmap2::Mmap;
+
+use super::types::AudioSpec;
+use super::types::NormSamples;
+
+use crate::error::WavetrxError;
+use crate::protocol::profile::Profile;
+use crate::protocol::rx::DecodedMessage;
+use crate::protocol::rx::Receiver;
+
+pub struct MappedWav {
+    mmap: Mmap,
+    spec: WavSpec,
+    width: usize,
+    data_offset: usize,
+    len: usize,
+}
+
+impl MappedWav {
+    pub fn open<P>(filename: P) -> Result<Self, WavetrxError>
+    where
+        P: AsRef<Path>,
+    {
+        let file: File = File::open(filename)?;
+        // The mapping is read-only; the file must not be truncated while mapped
+        let mmap: Mmap = unsafe { Mmap::map(&file)? };
+
+        let reader: WavReader<Cursor<&[u8]>> = WavReader::new(Cursor::new(&mmap[..]))?;
+        let spec: WavSpec = reader.spec();
+        let len: usize = reader.len() as usize;
+        let data_offset: usize = reader.into_inner().position() as usize;
+
+        match (spec.sample_format, spec.bits_per_sample) {
+            (SampleFormat::Int, 8 | 16 | 24 | 32) | (SampleFormat::Float, 32) => {}
+            _ => {
+                let reason: String = "Unsupported WAV sample format for mapped access".to_string();
+                return Err(WavetrxError::UnsupportedWav(reason));
+            }
+        }
+
+        // Hound doesn't expose the container width, which differs from the
+        // bit depth for e.g. 24-bit samples padded to four bytes
+        let width: usize = match container_width(&mmap, spec.channels) {
+            Some(width) if width * 8 >= spec.bits_per_sample as usize => width,
+            _ => (spec.bits_per_sample as usize).div_ceil(8),
+        };
+
+        Ok(MappedWav {
+            mmap,
+            spec,
+            width,
+            data_offset,
+            len,
+        })
+    }
+
+    pub fn spec(&self) -> AudioSpec {
+        self.spec.into()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn window(&self, start: usize, size: usize) -> NormSamples {
+        let start: usize = start.min(self.len);
+        let end: usize = start.saturating_add(size).min(self.len);
+        let mut samples: Vec<f32> = Vec::with_capacity(end - start);
+
+        for idx in start..end {
+            samples.push(self.sample(idx));
+        }
+        NormSamples::from_vec(samples)
+    }
+
+    pub fn windows(&self, size: usize) -> MappedWindows<'_> {
+        MappedWindows {
+            wav: self,
+            idx: 0,
+            size: size.max(1),
+        }
+    }
+
+    pub fn decode(&self, profile: Profile, window_size: usize) -> Vec<DecodedMessage> {
+        let mut receiver: Receiver = Receiver::new(profile, self.spec());
+        for mut window in self.windows(window_size) {
+            receiver.add_samples(&mut window);
+            receiver.analyze_full_buffer();
+        }
+        receiver.take_messages()
+    }
+}
+
+impl MappedWav {
+    // Only the low bytes of a padded container carry the sample, as hound reads it
+    fn sample(&self, idx: usize) -> f32 {
+        let offset: usize = self.data_offset + (idx * self.width);
+        let used: usize = (self.spec.bits_per_sample as usize).div_ceil(8);
+        let bytes: &[u8] = &self.mmap[offset..offset + used];
+
+        match (self.spec.sample_format, used) {
+            (SampleFormat::Float, _) => {
+                f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+            }
+            (SampleFormat::Int, 1) => (bytes[0] as i32 - 128) as f32 / i8::MAX as f32,
+            (SampleFormat::Int, 2) => {
+                i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / i16::MAX as f32
+            }
+            (SampleFormat::Int, 3) => {
+                let sample: i32 = i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8;
+                sample as f32 / 8_388_607.0
+            }
+            (SampleFormat::Int, _) => {
+                let sample: i32 = i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                sample as f32 / i32::MAX as f32
+            }
+        }
+    }
+}
+
+// Bytes per sample from the block alignment in the `fmt ` chunk
+fn container_width(bytes: &[u8], channels: u16) -> Option<usize> {
+    let mut offset: usize = 12;
+    while offset + 8 <= bytes.len() {
+        let id: &[u8] = &bytes[offset..offset + 4];
+        let size: usize =
+            u32::from_le_bytes(bytes[offset + 4..offset + 8].try_into().ok()?) as usize;
+        if id == b"fmt " {
+            let align: &[u8] = bytes.get(offset + 20..offset + 22)?;
+            let block_align: usize = u16::from_le_bytes([align[0], align[1]]) as usize;
+            return Some(block_align / (channels.max(1) as usize));
+        }
+        offset += 8 + size + (size % 2);
+    }
+    None
+}
+
+pub struct MappedWindows<'a> {
+    wav: &'a MappedWav,
+    idx: usize,
+    size: usize,
+}
+
+impl<'a> Iterator for MappedWindows<'a> {
+    type Item = NormSamples;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.idx >= self.wav.len() {
+            return None;
+        }
+
+        let window: NormSamples = self.wav.window(self.idx, self.size);
+        self.idx += self.size;
+        Some(window)
+    }
+}
@@ -1,6 +1,26 @@
+#[cfg(feature = "wav")]
+pub mod archive;
+#[cfg(feature = "symphonia")]
+pub mod compressed;
+#[cfg(feature = "wav")]
 pub mod conversions;
+#[cfg(feature = "device")]
+pub mod devices;
 pub mod filters;
+pub mod gaps;
+pub mod io;
+pub mod limiter;
+#[cfg(feature = "mmap")]
+pub mod mapped;
+#[cfg(feature = "device")]
 pub mod player;
+pub mod raw;
+#[cfg(feature = "device")]
 pub mod recorder;
+pub mod resampler;
+pub mod ring;
+pub mod spectrogram;
 pub mod spectrum;
+pub mod squelch;
 pub mod types;
+pub mod watchdog;
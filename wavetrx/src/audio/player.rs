@@ -1,6 +1,6 @@
-use std::error;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::thread::sleep;
 use std::time::Duration;
 
 use cpal::traits::DeviceTrait;
@@ -8,95 +8,195 @@ use cpal::traits::StreamTrait;
 use cpal::BuildStreamError;
 use cpal::Device;
 use cpal::OutputCallbackInfo;
+use cpal::OutputStreamTimestamp;
 use cpal::Stream;
 use cpal::StreamConfig;
 use cpal::StreamError;
+use log::error;
 
+use super::devices::find_output_device;
+use super::io::SampleSink;
 use super::types::AudioSpec;
 use super::types::NormSamples;
 use super::types::SampleBuffer;
+use super::types::SampleEncoding;
+use super::watchdog::Heartbeat;
+use super::watchdog::StreamState;
+use super::watchdog::Supervised;
+
+use crate::consts::PLAYER_STALL;
+use crate::error::WavetrxError;
+
+const BUFFER_SECONDS: usize = 60;
 
 pub struct OutputPlayer {
     device: Device,
     config: StreamConfig,
     spec: Arc<AudioSpec>,
     buffer: Arc<SampleBuffer>,
+    heartbeat: Arc<Heartbeat>,
+    latency: Arc<AtomicU64>,
     stream: Option<Stream>,
+    state: StreamState,
 }
 
 impl OutputPlayer {
     pub fn new(device: Device, config: StreamConfig, spec: AudioSpec) -> Self {
-        let buffer: Arc<SampleBuffer> = SampleBuffer::new();
+        let capacity: usize =
+            spec.sample_rate() as usize * spec.channels() as usize * BUFFER_SECONDS;
+        let buffer: Arc<SampleBuffer> = SampleBuffer::new(capacity);
         let spec: Arc<AudioSpec> = Arc::new(spec);
+        let heartbeat: Arc<Heartbeat> = Heartbeat::new();
+        let latency: Arc<AtomicU64> = Arc::new(AtomicU64::new(0));
         let stream: Option<Stream> = None;
+        let state: StreamState = StreamState::Stopped;
         Self {
             device,
             config,
             spec,
             buffer,
+            heartbeat,
+            latency,
             stream,
+            state,
         }
     }
 
-    pub fn play(&mut self) -> Result<(), Box<dyn error::Error>> {
+    // Plays mono F32 at the device's default rate, like the modem backend
+    pub fn from_device_name(name: &str) -> Result<Self, WavetrxError> {
+        let device: Device = find_output_device(name)?;
+        let config: StreamConfig = device.default_output_config()?.into();
+        let spec: AudioSpec = AudioSpec::new(config.sample_rate.0, 32, 1, SampleEncoding::F32);
+        Ok(OutputPlayer::new(device, config, spec))
+    }
+
+    // Only takes effect before `play`, as the stream holds the old buffer
+    pub fn with_buffer_duration(mut self, duration: Duration) -> Self {
+        let frames: f64 = duration.as_secs_f64() * self.spec.sample_rate() as f64;
+        let capacity: usize = frames as usize * self.spec.channels() as usize;
+        self.buffer = SampleBuffer::new(capacity);
+        self
+    }
+
+    pub fn play(&mut self) -> Result<(), WavetrxError> {
         let stream: Stream = self.build_output_stream()?;
         stream.play()?;
         self.stream = Some(stream);
+        self.state = StreamState::Running;
+        Ok(())
+    }
+
+    // Releases the device without dropping the stream; queued samples wait for `resume`
+    pub fn pause(&mut self) -> Result<(), WavetrxError> {
+        self.started_stream()?.pause()?;
+        self.state = StreamState::Paused;
         Ok(())
     }
 
-    pub fn add_sample(&self, sample: f32) {
-        self.buffer.add_sample(sample);
+    // The heartbeat restarts with the stream, so a watchdog sees no stall
+    pub fn resume(&mut self) -> Result<(), WavetrxError> {
+        self.started_stream()?.play()?;
+        self.heartbeat.beat();
+        self.state = StreamState::Running;
+        Ok(())
     }
 
-    pub fn add_samples(&self, samples: NormSamples) {
-        self.buffer.add_samples(samples);
+    pub fn state(&self) -> StreamState {
+        self.state
     }
 
-    pub fn wait(&self) {
-        let buffer_len: usize = self.buffer.buffer_len();
-        let timestamp: Duration = self.spec.sample_timestamp(buffer_len);
-        sleep(timestamp);
+    pub fn is_paused(&self) -> bool {
+        self.state == StreamState::Paused
     }
 
-    pub fn wait_until(&self, remaining_size: usize) {
+    pub fn add_sample(&self, sample: f32) -> bool {
+        self.buffer.add_sample(sample)
+    }
+
+    // Returns how many samples were queued; any beyond capacity are dropped
+    pub fn add_samples(&self, samples: NormSamples) -> usize {
+        self.buffer.add_samples(samples)
+    }
+
+    // Stops playback of everything queued; audio already handed to the
+    // device still plays out. Returns how many samples were dropped
+    pub fn clear(&self) -> usize {
+        self.buffer.clear()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buffer.capacity()
+    }
+
+    pub fn dropped_samples(&self) -> usize {
+        self.buffer.dropped()
+    }
+
+    pub fn output_latency(&self) -> Duration {
+        Duration::from_nanos(self.latency.load(Ordering::Relaxed))
+    }
+
+    pub fn queued(&self) -> Duration {
         let buffer_len: usize = self.buffer.buffer_len();
-        let timestamp: Duration = self.spec.sample_timestamp(buffer_len - remaining_size);
-        sleep(timestamp);
+        self.spec.sample_timestamp(buffer_len)
+    }
+
+    // Blocks until the output callback has drained the queue. Fails at once
+    // unless the stream is running, and once the callback has been silent
+    // for `PLAYER_STALL`, as nothing would drain the queue then
+    pub fn wait(&self) -> Result<(), WavetrxError> {
+        self.wait_until(0)
+    }
+
+    // Returns false if samples were still queued when the timeout elapsed
+    pub fn wait_timeout(&self, timeout: Duration) -> bool {
+        self.buffer.wait_until(0, Some(timeout))
+    }
+
+    pub fn wait_until(&self, remaining_size: usize) -> Result<(), WavetrxError> {
+        if self.state != StreamState::Running {
+            let reason: String = format!("Output stream is {:?}", self.state);
+            return Err(WavetrxError::DeviceError(reason));
+        }
+        while !self.buffer.wait_until(remaining_size, Some(PLAYER_STALL)) {
+            if self.heartbeat.idle() >= PLAYER_STALL {
+                let reason: String = format!("Output stream stalled for {:?}", PLAYER_STALL);
+                return Err(WavetrxError::DeviceError(reason));
+            }
+        }
+        Ok(())
     }
 }
 
 impl OutputPlayer {
-    fn append_mono(data: &mut [f32], buffer: &Arc<SampleBuffer>) {
-        let mut count: usize = 0;
-        while count < data.len() {
-            if let Some(sample) = buffer.take() {
-                data[count] = sample;
-                data[count + 1] = sample;
-                count += 2;
-                continue;
-            }
-            break;
+    fn append_mono(data: &mut [f32], buffer: &Arc<SampleBuffer>, scratch: &mut Vec<f32>) {
+        scratch.resize(data.len() / 2, 0.0);
+        let count: usize = buffer.pop_slice(scratch);
+        for (frame, sample) in data.chunks_exact_mut(2).zip(scratch[..count].iter()) {
+            frame[0] = *sample;
+            frame[1] = *sample;
         }
     }
 
     fn append_stereo(data: &mut [f32], buffer: &Arc<SampleBuffer>) {
-        let mut count: usize = 0;
-        while count < data.len() {
-            if let Some(sample) = buffer.take() {
-                data[count] = sample;
-                count += 1;
-                continue;
-            }
-            break;
-        }
+        buffer.pop_slice(data);
     }
 
     fn data_callback(
         buffer: Arc<SampleBuffer>,
         spec: Arc<AudioSpec>,
+        heartbeat: Arc<Heartbeat>,
+        latency: Arc<AtomicU64>,
     ) -> impl FnMut(&mut [f32], &OutputCallbackInfo) {
-        let callback = move |data: &mut [f32], _: &OutputCallbackInfo| {
+        let mut scratch: Vec<f32> = Vec::new();
+        let callback = move |data: &mut [f32], info: &OutputCallbackInfo| {
+            heartbeat.beat();
+
+            let timestamp: OutputStreamTimestamp = info.timestamp();
+            if let Some(delay) = timestamp.playback.duration_since(&timestamp.callback) {
+                latency.store(delay.as_nanos() as u64, Ordering::Relaxed);
+            }
+
             // Sometimes the data buffer remains filled from previous frame
             if data.iter().any(|&value| value > 0.0) {
                 for data in data.iter_mut() {
@@ -106,7 +206,7 @@ impl OutputPlayer {
 
             if !buffer.buffer_empty() {
                 match spec.channels() {
-                    1 => Self::append_mono(data, &buffer),
+                    1 => Self::append_mono(data, &buffer, &mut scratch),
                     2 => Self::append_stereo(data, &buffer),
                     _ => {}
                 }
@@ -116,17 +216,54 @@ impl OutputPlayer {
         callback
     }
 
+    fn started_stream(&self) -> Result<&Stream, WavetrxError> {
+        self.stream
+            .as_ref()
+            .ok_or_else(|| WavetrxError::DeviceError("Stream not started".to_string()))
+    }
+
     fn error_callback(err: StreamError) {
-        println!("Error: {:?}", err);
+        error!("Stream error: {}", err);
     }
 
     fn build_output_stream(&mut self) -> Result<Stream, BuildStreamError> {
         let stream: Stream = self.device.build_output_stream(
             &self.config,
-            Self::data_callback(self.buffer.clone(), self.spec.clone()),
+            Self::data_callback(
+                self.buffer.clone(),
+                self.spec.clone(),
+                self.heartbeat.clone(),
+                self.latency.clone(),
+            ),
             Self::error_callback,
             None,
         )?;
         Ok(stream)
     }
 }
+
+impl SampleSink for OutputPlayer {
+    fn write(&mut self, samples: &[f32]) -> usize {
+        self.buffer.push_slice(samples)
+    }
+
+    // Playback is done once the output callback has drained the queue
+    fn flush(&mut self) -> Result<(), WavetrxError> {
+        self.wait()
+    }
+}
+
+impl Supervised for OutputPlayer {
+    fn heartbeat(&self) -> Arc<Heartbeat> {
+        self.heartbeat.clone()
+    }
+
+    fn restart(&mut self) -> Result<(), WavetrxError> {
+        self.stream = None;
+        self.play()
+    }
+
+    fn is_paused(&self) -> bool {
+        self.state == StreamState::Paused
+    }
+}
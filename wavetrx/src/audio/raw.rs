@@ -0,0 +1,271 @@
+use std::io::ErrorKind;
+use std::io::Read;
+use std::io::Write;
+
+use log::warn;
+
+use super::io::SampleSink;
+use super::io::SampleSource;
+use super::types::AudioSpec;
+use super::types::SampleEncoding;
+
+use crate::error::WavetrxError;
+
+const I24_MAX: i32 = (1 << 23) - 1;
+
+// Headerless interleaved PCM as produced by `arecord -t raw`, `sox -t raw`
+// and `ffmpeg -f u8|s16le|s24le|s32le|f32le`; 24-bit samples are packed
+// into three bytes
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum RawFormat {
+    U8,
+    S16Le,
+    S24Le,
+    S32Le,
+    F32Le,
+}
+
+impl RawFormat {
+    pub fn from_name(name: &str) -> Result<Self, WavetrxError> {
+        match name.to_ascii_lowercase().as_str() {
+            "u8" => Ok(RawFormat::U8),
+            "s16le" => Ok(RawFormat::S16Le),
+            "s24le" => Ok(RawFormat::S24Le),
+            "s32le" => Ok(RawFormat::S32Le),
+            "f32le" => Ok(RawFormat::F32Le),
+            _ => Err(WavetrxError::InvalidInput(format!(
+                "Unknown raw format: {}",
+                name
+            ))),
+        }
+    }
+
+    pub fn bytes_per_sample(&self) -> usize {
+        match self {
+            RawFormat::U8 => 1,
+            RawFormat::S16Le => 2,
+            RawFormat::S24Le => 3,
+            RawFormat::S32Le | RawFormat::F32Le => 4,
+        }
+    }
+
+    pub fn spec(&self, sample_rate: u32, channels: u16) -> AudioSpec {
+        let bits_per_sample: u16 = (self.bytes_per_sample() * 8) as u16;
+        match self {
+            RawFormat::F32Le => AudioSpec::new(sample_rate, 32, channels, SampleEncoding::F32),
+            _ => AudioSpec::new(sample_rate, bits_per_sample, channels, SampleEncoding::I32),
+        }
+    }
+
+    pub fn decode(&self, bytes: &[u8]) -> f32 {
+        match self {
+            RawFormat::U8 => (bytes[0] as i32 - 128) as f32 / i8::MAX as f32,
+            RawFormat::S16Le => i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / i16::MAX as f32,
+            RawFormat::S24Le => {
+                let sample: i32 = i32::from_le_bytes([0, bytes[0], bytes[1], bytes[2]]) >> 8;
+                sample as f32 / I24_MAX as f32
+            }
+            RawFormat::S32Le => {
+                let sample: i32 = i32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                sample as f32 / i32::MAX as f32
+            }
+            RawFormat::F32Le => f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        }
+    }
+
+    pub fn encode(&self, sample: f32, bytes: &mut Vec<u8>) {
+        let clamped: f64 = sample.clamp(-1.0, 1.0) as f64;
+        match self {
+            RawFormat::U8 => {
+                let sample: i32 = (clamped * i8::MAX as f64) as i32;
+                bytes.push((sample + 128) as u8);
+            }
+            RawFormat::S16Le => {
+                let sample: i16 = (clamped * i16::MAX as f64) as i16;
+                bytes.extend_from_slice(&sample.to_le_bytes());
+            }
+            RawFormat::S24Le => {
+                let sample: i32 = (clamped * I24_MAX as f64) as i32;
+                bytes.extend_from_slice(&sample.to_le_bytes()[..3]);
+            }
+            RawFormat::S32Le => {
+                let sample: i32 = (clamped * i32::MAX as f64) as i32;
+                bytes.extend_from_slice(&sample.to_le_bytes());
+            }
+            RawFormat::F32Le => bytes.extend_from_slice(&sample.to_le_bytes()),
+        }
+    }
+}
+
+// Reads raw PCM from any byte stream, e.g. stdin, a pipe or a socket.
+// Non-blocking streams read 0 samples instead of waiting
+pub struct RawReader<R> {
+    stream: R,
+    format: RawFormat,
+    pending: Vec<u8>,
+    finished: bool,
+}
+
+impl<R> RawReader<R>
+where
+    R: Read,
+{
+    pub fn new(stream: R, format: RawFormat) -> Self {
+        let pending: Vec<u8> = Vec::new();
+        let finished: bool = false;
+        RawReader {
+            stream,
+            format,
+            pending,
+            finished,
+        }
+    }
+
+    pub fn format(&self) -> RawFormat {
+        self.format
+    }
+
+    pub fn into_inner(self) -> R {
+        self.stream
+    }
+}
+
+impl<R> SampleSource for RawReader<R>
+where
+    R: Read,
+{
+    // A sample split across reads is kept until its last byte arrives.
+    // An empty `out` reads nothing, so it can't mistake that for the end
+    fn read(&mut self, out: &mut [f32]) -> usize {
+        if out.is_empty() {
+            return 0;
+        }
+        let width: usize = self.format.bytes_per_sample();
+        let mut bytes: Vec<u8> = vec![0; out.len() * width - self.pending.len()];
+        match self.stream.read(&mut bytes) {
+            Ok(0) => self.finished = true,
+            Ok(count) => self.pending.extend_from_slice(&bytes[..count]),
+            Err(err) if err.kind() == ErrorKind::WouldBlock => {}
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) => {
+                warn!("Raw source closed: {}", err);
+                self.finished = true;
+            }
+        }
+
+        let count: usize = self.pending.len() / width;
+        for (sample, chunk) in out.iter_mut().zip(self.pending.chunks_exact(width)) {
+            *sample = self.format.decode(chunk);
+        }
+        self.pending.drain(..count * width);
+        count
+    }
+
+    fn is_finished(&self) -> bool {
+        self.finished
+    }
+}
+
+pub struct RawWriter<W> {
+    stream: W,
+    format: RawFormat,
+}
+
+impl<W> RawWriter<W>
+where
+    W: Write,
+{
+    pub fn new(stream: W, format: RawFormat) -> Self {
+        RawWriter { stream, format }
+    }
+
+    pub fn format(&self) -> RawFormat {
+        self.format
+    }
+
+    pub fn into_inner(self) -> W {
+        self.stream
+    }
+}
+
+impl<W> SampleSink for RawWriter<W>
+where
+    W: Write,
+{
+    fn write(&mut self, samples: &[f32]) -> usize {
+        let mut bytes: Vec<u8> = Vec::with_capacity(samples.len() * self.format.bytes_per_sample());
+        for sample in samples.iter() {
+            self.format.encode(*sample, &mut bytes);
+        }
+        match self.stream.write_all(&bytes) {
+            Ok(()) => samples.len(),
+            Err(err) => {
+                warn!("Raw sink closed: {}", err);
+                0
+            }
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), WavetrxError> {
+        self.stream.flush()?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_raw_s16le_roundtrip() {
+    let samples: Vec<f32> = vec![0.0, 0.5, -1.0, 2.0];
+    let mut writer: RawWriter<Vec<u8>> = RawWriter::new(Vec::new(), RawFormat::S16Le);
+    assert_eq!(writer.write(&samples), 4);
+    let bytes: Vec<u8> = writer.into_inner();
+    assert_eq!(bytes.len(), 8);
+    assert_eq!(&bytes[4..6], &(-i16::MAX).to_le_bytes());
+
+    let format: RawFormat = RawFormat::from_name("S16LE").unwrap();
+    let mut reader: RawReader<&[u8]> = RawReader::new(bytes.as_slice(), format);
+    assert_eq!(reader.read(&mut []), 0);
+    assert!(!reader.is_finished());
+    let mut out: [f32; 8] = [0.0; 8];
+    assert_eq!(reader.read(&mut out), 4);
+    assert!((out[1] - 0.5).abs() < 1e-4);
+    assert_eq!(&out[2..4], &[-1.0, 1.0]);
+    assert_eq!(reader.read(&mut out), 0);
+    assert!(reader.is_finished());
+    assert!(RawFormat::from_name("s8").is_err());
+}
+
+#[test]
+fn test_raw_bit_depths() {
+    let samples: Vec<f32> = vec![0.0, 0.25, -0.5, 1.0, -1.0];
+    for name in ["u8", "s16le", "s24le", "s32le", "f32le"] {
+        let format: RawFormat = RawFormat::from_name(name).unwrap();
+        let mut writer: RawWriter<Vec<u8>> = RawWriter::new(Vec::new(), format);
+        assert_eq!(writer.write(&samples), samples.len());
+        let bytes: Vec<u8> = writer.into_inner();
+        assert_eq!(bytes.len(), samples.len() * format.bytes_per_sample());
+
+        let spec: AudioSpec = format.spec(48_000, 1);
+        assert_eq!(
+            spec.bits_per_sample() as usize,
+            format.bytes_per_sample() * 8
+        );
+        let mut reader: RawReader<&[u8]> = RawReader::new(bytes.as_slice(), format);
+        let mut out: [f32; 5] = [0.0; 5];
+        assert_eq!(reader.read(&mut out), samples.len());
+        let tolerance: f32 = (1.0 / spec.get_magnitudes().0 as f32).max(f32::EPSILON);
+        for (sent, received) in samples.iter().zip(out.iter()) {
+            assert!(
+                (sent - received).abs() <= tolerance,
+                "{}: {}",
+                name,
+                received
+            );
+        }
+    }
+
+    // Silence is the unsigned midpoint, and 24-bit samples are packed
+    let mut bytes: Vec<u8> = Vec::new();
+    RawFormat::U8.encode(0.0, &mut bytes);
+    RawFormat::S24Le.encode(-1.0, &mut bytes);
+    assert_eq!(bytes, vec![0x80, 0x01, 0x00, 0x80]);
+}
@@ -1,69 +1,287 @@
-use std::error;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::time::Duration;
+use std::time::SystemTime;
 
 use cpal::traits::DeviceTrait;
 use cpal::traits::StreamTrait;
 use cpal::BuildStreamError;
 use cpal::Device;
 use cpal::InputCallbackInfo;
+use cpal::InputStreamTimestamp;
 use cpal::Stream;
 use cpal::StreamConfig;
 use cpal::StreamError;
+use cpal::StreamInstant;
+use log::error;
 
-use super::types::FrameBuffer;
+use super::devices::find_input_device;
+use super::gaps::CaptureClock;
+use super::gaps::CaptureStamp;
+use super::gaps::GapLog;
+use super::io::SampleSource;
+use super::ring::SampleRing;
 use super::types::NormSamples;
+use super::watchdog::Heartbeat;
+use super::watchdog::StreamState;
+use super::watchdog::Supervised;
+
+use crate::error::WavetrxError;
+
+const RING_SECONDS: usize = 2;
 
 pub struct InputRecorder {
     device: Device,
     config: StreamConfig,
-    buffer: Arc<FrameBuffer>,
+    buffer: Arc<SampleRing>,
+    heartbeat: Arc<Heartbeat>,
+    gaps: Arc<GapLog>,
+    stamp: Arc<CaptureStamp>,
+    lost: Arc<AtomicBool>,
+    taken: usize,
+    seen_gaps: usize,
     stream: Option<Stream>,
+    state: StreamState,
 }
 
 impl InputRecorder {
     pub fn new(device: Device, config: StreamConfig) -> Self {
-        let buffer: Arc<FrameBuffer> = FrameBuffer::new();
+        let sample_rate: usize = config.sample_rate.0 as usize;
+        let channels: usize = config.channels as usize;
+        let capacity: usize = sample_rate * channels * RING_SECONDS;
+
+        let buffer: Arc<SampleRing> = SampleRing::new(capacity);
+        let heartbeat: Arc<Heartbeat> = Heartbeat::new();
+        let gaps: Arc<GapLog> = GapLog::new();
+        let stamp: Arc<CaptureStamp> = CaptureStamp::new(config.sample_rate.0, channels);
+        let lost: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+        let taken: usize = 0;
+        let seen_gaps: usize = 0;
         let stream: Option<Stream> = None;
+        let state: StreamState = StreamState::Stopped;
         Self {
             device,
             config,
             buffer,
+            heartbeat,
+            gaps,
+            stamp,
+            lost,
+            taken,
+            seen_gaps,
             stream,
+            state,
         }
     }
 
-    pub fn record(&mut self) -> Result<(), Box<dyn error::Error>> {
+    pub fn from_device_name(name: &str) -> Result<Self, WavetrxError> {
+        let device: Device = find_input_device(name)?;
+        let config: StreamConfig = device.default_input_config()?.into();
+        Ok(InputRecorder::new(device, config))
+    }
+
+    pub fn record(&mut self) -> Result<(), WavetrxError> {
+        self.lost.store(false, Ordering::Relaxed);
         let stream: Stream = self.build_input_stream()?;
         stream.play()?;
         self.stream = Some(stream);
+        self.state = StreamState::Running;
+        Ok(())
+    }
+
+    // Releases the device without dropping the stream; nothing is captured until `resume`, which is seen as a gap
+    pub fn pause(&mut self) -> Result<(), WavetrxError> {
+        self.started_stream()?.pause()?;
+        self.state = StreamState::Paused;
+        Ok(())
+    }
+
+    // The heartbeat restarts with the stream, so a watchdog sees no stall
+    pub fn resume(&mut self) -> Result<(), WavetrxError> {
+        self.started_stream()?.play()?;
+        self.heartbeat.beat();
+        self.state = StreamState::Running;
         Ok(())
     }
 
+    pub fn state(&self) -> StreamState {
+        self.state
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.state == StreamState::Paused
+    }
+
+    // Set once the stream reports that its device has gone, e.g. unplugged;
+    // nothing more is captured until a new recorder is started
+    pub fn is_device_lost(&self) -> bool {
+        self.lost.load(Ordering::Relaxed)
+    }
+
+    pub fn device_name(&self) -> Option<String> {
+        self.device.name().ok()
+    }
+
+    pub fn channels(&self) -> usize {
+        (self.config.channels as usize).max(1)
+    }
+
     pub fn take_frame(&mut self) -> Option<NormSamples> {
-        self.buffer.take()
+        if self.buffer.is_empty() {
+            return None;
+        }
+        let samples: Vec<f32> = self.buffer.take_all();
+        self.taken += samples.len();
+        Some(NormSamples::from_vec(samples))
+    }
+
+    // Also reports where in the frame the most recent unseen gap starts;
+    // earlier gaps in the same frame are folded into it
+    pub fn take_frame_with_gap(&mut self) -> Option<(NormSamples, Option<usize>)> {
+        let start: usize = self.taken;
+        let frame: NormSamples = self.take_frame()?;
+
+        let count: usize = self.gaps.count();
+        if count == self.seen_gaps {
+            return Some((frame, None));
+        }
+        self.seen_gaps = count;
+
+        let offset: usize = self.gaps.last_position().saturating_sub(start);
+        let offset: usize = offset.min(frame.0.len());
+        Some((frame, Some(offset - offset % self.channels())))
+    }
+
+    // Wall-clock capture time of the first sample the next take returns, from
+    // the stream timestamps of the callbacks; None before the first callback
+    pub fn next_sample_time(&self) -> Option<SystemTime> {
+        self.stamp.time_at(self.taken)
+    }
+
+    // Callback timing jumps, ring overflows and stream restarts
+    pub fn gaps(&self) -> usize {
+        self.gaps.count()
+    }
+
+    pub fn dropped_samples(&self) -> usize {
+        self.buffer.dropped()
     }
 }
 
 impl InputRecorder {
-    fn data_callback(buffer: Arc<FrameBuffer>) -> impl Fn(&[f32], &InputCallbackInfo) {
-        let callback = move |data: &[f32], _: &InputCallbackInfo| {
-            let frame: NormSamples = NormSamples::from_slice(data);
-            buffer.add_frame(frame);
+    fn data_callback(
+        buffer: Arc<SampleRing>,
+        heartbeat: Arc<Heartbeat>,
+        gaps: Arc<GapLog>,
+        stamp: Arc<CaptureStamp>,
+        mut clock: CaptureClock,
+    ) -> impl FnMut(&[f32], &InputCallbackInfo) {
+        let channels: usize = clock.channels();
+        let mut last_capture: Option<StreamInstant> = None;
+        let mut epoch: Option<(StreamInstant, SystemTime)> = None;
+        let callback = move |data: &[f32], info: &InputCallbackInfo| {
+            heartbeat.beat();
+
+            let timestamp: InputStreamTimestamp = info.timestamp();
+            if let Some(time) = Self::capture_time(&mut epoch, timestamp) {
+                stamp.stamp(gaps.accepted(), time);
+            }
+
+            let capture: StreamInstant = timestamp.capture;
+            let elapsed: Option<Duration> =
+                last_capture.and_then(|last| capture.duration_since(&last));
+            last_capture = Some(capture);
+            if clock.is_gap(elapsed, data.len()) {
+                gaps.mark();
+            }
+
+            let count: usize = buffer.push_frames(data, channels);
+            gaps.accept(count);
+            if count < data.len() {
+                gaps.mark();
+            }
         };
         callback
     }
 
-    fn error_callback(err: StreamError) {
-        println!("Error: {:?}", err);
+    // The stream clock is tied to the wall clock at the first callback, so
+    // later stamps follow the device clock rather than callback jitter
+    fn capture_time(
+        epoch: &mut Option<(StreamInstant, SystemTime)>,
+        timestamp: InputStreamTimestamp,
+    ) -> Option<SystemTime> {
+        let (callback, wall): (StreamInstant, SystemTime) =
+            *epoch.get_or_insert((timestamp.callback, SystemTime::now()));
+        match timestamp.capture.duration_since(&callback) {
+            Some(after) => wall.checked_add(after),
+            None => callback
+                .duration_since(&timestamp.capture)
+                .and_then(|before| wall.checked_sub(before)),
+        }
+    }
+
+    fn started_stream(&self) -> Result<&Stream, WavetrxError> {
+        self.stream
+            .as_ref()
+            .ok_or_else(|| WavetrxError::DeviceError("Stream not started".to_string()))
+    }
+
+    fn error_callback(lost: Arc<AtomicBool>) -> impl FnMut(StreamError) {
+        move |err: StreamError| {
+            error!("Stream error: {}", err);
+            if matches!(err, StreamError::DeviceNotAvailable) {
+                lost.store(true, Ordering::Relaxed);
+            }
+        }
     }
 
     fn build_input_stream(&mut self) -> Result<Stream, BuildStreamError> {
         let stream: Stream = self.device.build_input_stream(
             &self.config,
-            Self::data_callback(self.buffer.clone()),
-            Self::error_callback,
+            Self::data_callback(
+                self.buffer.clone(),
+                self.heartbeat.clone(),
+                self.gaps.clone(),
+                self.stamp.clone(),
+                CaptureClock::new(self.config.sample_rate.0, self.channels()),
+            ),
+            Self::error_callback(self.lost.clone()),
             None,
         )?;
         Ok(stream)
     }
 }
+
+// Drains captured samples without the allocation of `take_frame`
+impl SampleSource for InputRecorder {
+    fn read(&mut self, out: &mut [f32]) -> usize {
+        let count: usize = self.buffer.pop_slice(out);
+        self.taken += count;
+        count
+    }
+}
+
+impl Supervised for InputRecorder {
+    fn heartbeat(&self) -> Arc<Heartbeat> {
+        self.heartbeat.clone()
+    }
+
+    fn restart(&mut self) -> Result<(), WavetrxError> {
+        self.stream = None;
+        self.gaps.mark();
+        self.record()
+    }
+
+    fn is_paused(&self) -> bool {
+        self.state == StreamState::Paused
+    }
+}
+
+#[test]
+fn test_device_lost() {
+    let lost: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+    let mut callback = InputRecorder::error_callback(lost.clone());
+    callback(StreamError::DeviceNotAvailable);
+    assert!(lost.load(Ordering::Relaxed));
+}
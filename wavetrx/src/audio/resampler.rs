@@ -0,0 +1,107 @@
+// Streaming linear interpolation; state carries across chunks so that
+// consecutive calls produce the same output as one call over the whole signal
+pub struct LinearResampler {
+    from_rate: u32,
+    to_rate: u32,
+    position: f64,
+    previous: Option<f32>,
+}
+
+impl LinearResampler {
+    pub fn new(from_rate: u32, to_rate: u32) -> Self {
+        let position: f64 = 0.0;
+        let previous: Option<f32> = None;
+        LinearResampler {
+            from_rate,
+            to_rate,
+            position,
+            previous,
+        }
+    }
+
+    pub fn from_rate(&self) -> u32 {
+        self.from_rate
+    }
+
+    pub fn to_rate(&self) -> u32 {
+        self.to_rate
+    }
+
+    pub fn is_passthrough(&self) -> bool {
+        self.from_rate == self.to_rate
+    }
+
+    // How many samples `process` would return for `input_len` more input
+    pub fn output_len(&self, input_len: usize) -> usize {
+        if self.is_passthrough() || input_len == 0 {
+            return input_len;
+        }
+
+        let step: f64 = self.from_rate as f64 / self.to_rate as f64;
+        let last: f64 = (input_len + self.previous.is_some() as usize - 1) as f64;
+        let mut position: f64 = self.position;
+        let mut count: usize = 0;
+        while position < last {
+            count += 1;
+            position += step;
+        }
+        count
+    }
+
+    pub fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        if self.is_passthrough() || input.is_empty() {
+            return input.to_vec();
+        }
+
+        let samples: Vec<f32> = self
+            .previous
+            .into_iter()
+            .chain(input.iter().copied())
+            .collect();
+        let step: f64 = self.from_rate as f64 / self.to_rate as f64;
+        let last: f64 = (samples.len() - 1) as f64;
+
+        let mut output: Vec<f32> = Vec::with_capacity((input.len() as f64 / step) as usize + 1);
+        while self.position < last {
+            let idx: usize = self.position as usize;
+            let fraction: f32 = (self.position - idx as f64) as f32;
+            let sample: f32 = samples[idx] + (samples[idx + 1] - samples[idx]) * fraction;
+            output.push(sample);
+            self.position += step;
+        }
+
+        self.position -= last;
+        self.previous = samples.last().copied();
+        output
+    }
+}
+
+pub fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    let mut resampler: LinearResampler = LinearResampler::new(from_rate, to_rate);
+    resampler.process(samples)
+}
+
+#[test]
+fn test_linear_resampler() {
+    let input: Vec<f32> = (0..100).map(|idx| idx as f32).collect();
+
+    let mut whole: LinearResampler = LinearResampler::new(44_100, 48_000);
+    let expected: Vec<f32> = whole.process(&input);
+
+    let mut chunked: LinearResampler = LinearResampler::new(44_100, 48_000);
+    let mut output: Vec<f32> = Vec::new();
+    for chunk in input.chunks(7) {
+        let predicted: usize = chunked.output_len(chunk.len());
+        let resampled: Vec<f32> = chunked.process(chunk);
+        assert_eq!(resampled.len(), predicted);
+        output.extend(resampled);
+    }
+
+    assert_eq!(output.len(), expected.len());
+    for (sample, expected) in output.iter().zip(expected.iter()) {
+        assert!((sample - expected).abs() < 1e-3);
+    }
+    assert_eq!(expected[0], 0.0);
+    assert!((expected[1] - 0.91875).abs() < 1e-4);
+    assert!(expected.len() >= 108);
+}
@@ -0,0 +1,147 @@
+use std::sync::atomic::AtomicU32;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+// Single-producer/single-consumer: exactly one thread may push and one may pop;
+// the producer may also `clear`
+pub struct SampleRing {
+    slots: Box<[AtomicU32]>,
+    head: AtomicUsize,
+    tail: AtomicUsize,
+    dropped: AtomicUsize,
+}
+
+impl SampleRing {
+    pub fn new(capacity: usize) -> Arc<Self> {
+        let capacity: usize = capacity.max(1);
+        let slots: Box<[AtomicU32]> = (0..capacity).map(|_| AtomicU32::new(0)).collect();
+        let head: AtomicUsize = AtomicUsize::new(0);
+        let tail: AtomicUsize = AtomicUsize::new(0);
+        let dropped: AtomicUsize = AtomicUsize::new(0);
+
+        Arc::new(Self {
+            slots,
+            head,
+            tail,
+            dropped,
+        })
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    pub fn len(&self) -> usize {
+        let tail: usize = self.tail.load(Ordering::Acquire);
+        let head: usize = self.head.load(Ordering::Acquire);
+        head.wrapping_sub(tail)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn dropped(&self) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    pub fn push_slice(&self, samples: &[f32]) -> usize {
+        self.push_frames(samples, 1)
+    }
+
+    // Only whole frames of `channels` samples are accepted, so an overflow
+    // never leaves the interleaving shifted
+    pub fn push_frames(&self, samples: &[f32], channels: usize) -> usize {
+        let head: usize = self.head.load(Ordering::Relaxed);
+        let tail: usize = self.tail.load(Ordering::Acquire);
+        let free: usize = self.capacity() - head.wrapping_sub(tail);
+        let count: usize = samples.len().min(free);
+        let count: usize = count - count % channels.max(1);
+
+        for (offset, sample) in samples[..count].iter().enumerate() {
+            let slot: usize = head.wrapping_add(offset) % self.capacity();
+            self.slots[slot].store(sample.to_bits(), Ordering::Relaxed);
+        }
+        self.head.store(head.wrapping_add(count), Ordering::Release);
+
+        if count < samples.len() {
+            self.dropped
+                .fetch_add(samples.len() - count, Ordering::Relaxed);
+        }
+        count
+    }
+
+    // A `clear` that lands while samples are being copied makes them stale,
+    // so the copy is redone from the new tail
+    pub fn pop_slice(&self, out: &mut [f32]) -> usize {
+        loop {
+            let tail: usize = self.tail.load(Ordering::Acquire);
+            let head: usize = self.head.load(Ordering::Acquire);
+            let count: usize = out.len().min(head.wrapping_sub(tail));
+
+            for (offset, sample) in out[..count].iter_mut().enumerate() {
+                let slot: usize = tail.wrapping_add(offset) % self.capacity();
+                *sample = f32::from_bits(self.slots[slot].load(Ordering::Relaxed));
+            }
+            let next: usize = tail.wrapping_add(count);
+            if self
+                .tail
+                .compare_exchange(tail, next, Ordering::AcqRel, Ordering::Relaxed)
+                .is_ok()
+            {
+                return count;
+            }
+        }
+    }
+
+    // Producer side: discards everything queued and returns how much that was
+    pub fn clear(&self) -> usize {
+        let head: usize = self.head.load(Ordering::Relaxed);
+        let mut tail: usize = self.tail.load(Ordering::Acquire);
+        loop {
+            match self
+                .tail
+                .compare_exchange(tail, head, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => return head.wrapping_sub(tail),
+                Err(current) => tail = current,
+            }
+        }
+    }
+
+    pub fn take_all(&self) -> Vec<f32> {
+        let mut samples: Vec<f32> = vec![0.0; self.len()];
+        let count: usize = self.pop_slice(&mut samples);
+        samples.truncate(count);
+        samples
+    }
+}
+
+#[test]
+fn test_sample_ring() {
+    let ring: Arc<SampleRing> = SampleRing::new(4);
+
+    assert_eq!(ring.push_slice(&[1.0, 2.0, 3.0]), 3);
+    assert_eq!(ring.take_all(), vec![1.0, 2.0, 3.0]);
+
+    assert_eq!(ring.push_slice(&[4.0, 5.0, 6.0, 7.0, 8.0]), 4);
+    assert_eq!(ring.dropped(), 1);
+    assert_eq!(ring.len(), 4);
+
+    let mut out: [f32; 2] = [0.0; 2];
+    assert_eq!(ring.pop_slice(&mut out), 2);
+    assert_eq!(out, [4.0, 5.0]);
+    assert_eq!(ring.take_all(), vec![6.0, 7.0]);
+    assert!(ring.is_empty());
+
+    assert_eq!(ring.push_frames(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 2), 4);
+    assert_eq!(ring.dropped(), 3);
+    assert_eq!(ring.take_all(), vec![1.0, 2.0, 3.0, 4.0]);
+
+    assert_eq!(ring.push_slice(&[1.0, 2.0, 3.0]), 3);
+    assert_eq!(ring.clear(), 3);
+    assert!(ring.is_empty());
+    assert_eq!(ring.push_slice(&[9.0]), 1);
+    assert_eq!(ring.take_all(), vec![9.0]);
+}
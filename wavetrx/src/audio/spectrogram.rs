@@ -0,0 +1,192 @@
+use std::f32::consts;
+use std::fs::File;
+use std::io::BufWriter;
+use std::io::Write;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rustfft::num_complex::Complex;
+use rustfft::Fft;
+use rustfft::FftPlanner;
+
+use crate::audio::types::AudioSpec;
+use crate::audio::types::NormSamples;
+use crate::consts::SPECTROGRAM_HOP;
+use crate::consts::SPECTROGRAM_WINDOW;
+use crate::error::WavetrxError;
+
+// Short-time Fourier transform of a capture; each frame holds the Hann-windowed
+// magnitude in dB of bins 0 through Nyquist
+pub struct Spectrogram {
+    frames: Vec<Vec<f32>>,
+    window_size: usize,
+    hop: usize,
+    sample_rate: u32,
+}
+
+impl Spectrogram {
+    pub fn new(samples: &NormSamples, spec: &AudioSpec, window_size: usize, hop: usize) -> Self {
+        let window_size: usize = window_size.max(2);
+        let hop: usize = hop.max(1);
+        let window: Vec<f32> = Self::hann_window(window_size);
+        let gain: f32 = 2.0 / window.iter().sum::<f32>();
+
+        let mut planner: FftPlanner<f32> = FftPlanner::<f32>::new();
+        let fft: Arc<dyn Fft<f32>> = planner.plan_fft_forward(window_size);
+        let bins: usize = window_size / 2 + 1;
+
+        let mut frames: Vec<Vec<f32>> = Vec::new();
+        let mut st_idx: usize = 0;
+        while st_idx + window_size <= samples.0.len() {
+            let mut buffer: Vec<Complex<f32>> = samples.0[st_idx..st_idx + window_size]
+                .iter()
+                .zip(window.iter())
+                .map(|(sample, weight)| Complex::new(sample * weight, 0.0))
+                .collect();
+            fft.process(&mut buffer);
+
+            let frame: Vec<f32> = buffer[..bins]
+                .iter()
+                .map(|bin| 20.0 * (bin.norm() * gain).log10())
+                .collect();
+            frames.push(frame);
+            st_idx += hop;
+        }
+
+        Spectrogram {
+            frames,
+            window_size,
+            hop,
+            sample_rate: spec.sample_rate(),
+        }
+    }
+
+    pub fn from_samples(samples: &NormSamples, spec: &AudioSpec) -> Self {
+        Spectrogram::new(samples, spec, SPECTROGRAM_WINDOW, SPECTROGRAM_HOP)
+    }
+
+    pub fn frames(&self) -> &[Vec<f32>] {
+        &self.frames
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn bin_count(&self) -> usize {
+        self.window_size / 2 + 1
+    }
+
+    pub fn window_size(&self) -> usize {
+        self.window_size
+    }
+
+    pub fn hop(&self) -> usize {
+        self.hop
+    }
+
+    pub fn bin_frequency(&self, bin: usize) -> f32 {
+        bin as f32 * self.sample_rate as f32 / self.window_size as f32
+    }
+
+    pub fn frequency_bin(&self, frequency: f32) -> usize {
+        let bin: f32 = frequency * self.window_size as f32 / self.sample_rate as f32;
+        (bin.round() as usize).min(self.bin_count() - 1)
+    }
+
+    // Time of the centre of the frame's window
+    pub fn frame_time(&self, frame: usize) -> Duration {
+        let sample: usize = frame * self.hop + self.window_size / 2;
+        Duration::from_secs_f64(sample as f64 / self.sample_rate as f64)
+    }
+
+    pub fn magnitude(&self, frame: usize, frequency: f32) -> Option<f32> {
+        let bin: usize = self.frequency_bin(frequency);
+        self.frames.get(frame).map(|magnitudes| magnitudes[bin])
+    }
+
+    pub fn peak_frequency(&self, frame: usize) -> Option<f32> {
+        let magnitudes: &Vec<f32> = self.frames.get(frame)?;
+        let (bin, _): (usize, &f32) = magnitudes
+            .iter()
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(b.1))?;
+        Some(self.bin_frequency(bin))
+    }
+
+    // Loudest frame for `frequency` as (frame, dB)
+    pub fn peak_at(&self, frequency: f32) -> Option<(usize, f32)> {
+        let bin: usize = self.frequency_bin(frequency);
+        self.frames
+            .iter()
+            .map(|magnitudes| magnitudes[bin])
+            .enumerate()
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+    }
+
+    // One row per frame: the frame time in seconds, then every bin in dB
+    pub fn write_csv<W: Write>(&self, writer: &mut W) -> Result<(), WavetrxError> {
+        write!(writer, "time_s")?;
+        for bin in 0..self.bin_count() {
+            write!(writer, ",{:.1}", self.bin_frequency(bin))?;
+        }
+        writeln!(writer)?;
+
+        for (idx, magnitudes) in self.frames.iter().enumerate() {
+            write!(writer, "{:.6}", self.frame_time(idx).as_secs_f64())?;
+            for magnitude in magnitudes.iter() {
+                write!(writer, ",{:.2}", magnitude)?;
+            }
+            writeln!(writer)?;
+        }
+        Ok(())
+    }
+
+    pub fn save_csv<P>(&self, filename: P) -> Result<(), WavetrxError>
+    where
+        P: AsRef<Path>,
+    {
+        let mut writer: BufWriter<File> = BufWriter::new(File::create(filename)?);
+        self.write_csv(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl Spectrogram {
+    fn hann_window(size: usize) -> Vec<f32> {
+        (0..size)
+            .map(|idx| 0.5 - 0.5 * (2.0 * consts::PI * idx as f32 / (size - 1) as f32).cos())
+            .collect()
+    }
+}
+
+#[test]
+fn test_spectrogram_peaks() {
+    use super::types::SampleEncoding;
+
+    let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+    let mut samples: Vec<f32> = (0..4_800)
+        .map(|idx| (2.0 * consts::PI * 3_000.0 * idx as f32 / 48_000.0).sin())
+        .collect();
+    samples
+        .extend((0..4_800).map(|idx| (2.0 * consts::PI * 9_000.0 * idx as f32 / 48_000.0).sin()));
+
+    let spectrogram: Spectrogram =
+        Spectrogram::new(&NormSamples::from_vec(samples), &spec, 480, 480);
+    assert_eq!(spectrogram.frame_count(), 20);
+    assert_eq!(spectrogram.bin_count(), 241);
+    assert_eq!(spectrogram.peak_frequency(0), Some(3_000.0));
+    assert_eq!(spectrogram.peak_frequency(19), Some(9_000.0));
+    assert!(spectrogram.magnitude(0, 3_000.0).unwrap().abs() < 1.0);
+
+    let (frame, _): (usize, f32) = spectrogram.peak_at(9_000.0).unwrap();
+    assert!(frame >= 10);
+
+    let mut csv: Vec<u8> = Vec::new();
+    spectrogram.write_csv(&mut csv).unwrap();
+    let csv: String = String::from_utf8(csv).unwrap();
+    assert_eq!(csv.lines().count(), 21);
+    assert!(csv.starts_with("time_s,0.0,100.0,"));
+}
@@ -1,4 +1,5 @@
-use std::cmp::Ordering;
+use std::cell::RefCell;
+use std::cell::RefMut;
 use std::f32::consts;
 use std::sync::Arc;
 
@@ -7,12 +8,155 @@ use rustfft::Fft;
 use rustfft::FftPlanner;
 
 use crate::audio::types::AudioSpec;
+use crate::consts::ADAPTIVE_THRESHOLD_MAX;
+use crate::consts::ADAPTIVE_THRESHOLD_MIN;
+use crate::consts::NOISE_SMOOTHING;
+use crate::embedded::frequency_bin;
+use crate::embedded::goertzel_db_iter;
 use crate::protocol::profile::SizedPulses;
 
+pub trait MagnitudeBackend {
+    fn new(pulses: &SizedPulses, spec: &AudioSpec) -> Self
+    where
+        Self: Sized;
+
+    // Backends that do not taper their input keep ignoring the window
+    fn with_window(self, _window: WindowFunction) -> Self
+    where
+        Self: Sized,
+    {
+        self
+    }
+
+    fn get_magnitude(&self, samples: &[f32], target_frequency: f32) -> f32;
+
+    fn get_magnitudes(&self, samples: &[f32], target_frequencies: &[f32]) -> Vec<f32> {
+        target_frequencies
+            .iter()
+            .map(|frequency| self.get_magnitude(samples, *frequency))
+            .collect()
+    }
+
+    // `scale` is applied to each sample as it is read, so a shared buffer is
+    // analyzed as normalized without being rewritten; the built-in backends
+    // do this without copying the chunk
+    fn get_scaled_magnitude(
+        &self,
+        samples: &[f32],
+        scale: &SampleScale,
+        target_frequency: f32,
+    ) -> f32 {
+        let scaled: Vec<f32> = samples.iter().map(|sample| scale.apply(*sample)).collect();
+        self.get_magnitude(&scaled, target_frequency)
+    }
+
+    fn get_scaled_magnitudes(
+        &self,
+        samples: &[f32],
+        scale: &SampleScale,
+        target_frequencies: &[f32],
+    ) -> Vec<f32> {
+        let scaled: Vec<f32> = samples.iter().map(|sample| scale.apply(*sample)).collect();
+        self.get_magnitudes(&scaled, target_frequencies)
+    }
+}
+
+// Tapers each chunk before analysis to keep leakage from neighbouring tones
+// out of the probed bin; coefficients are scaled to unit coherent gain so
+// dB thresholds keep their meaning
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum WindowFunction {
+    #[default]
+    None,
+    Hann,
+    Hamming,
+    Blackman,
+}
+
+impl WindowFunction {
+    pub fn coefficients(&self, size: usize) -> Vec<f32> {
+        if size < 2 || *self == WindowFunction::None {
+            return vec![1.0; size];
+        }
+        let span: f32 = (size - 1) as f32;
+        let coefficients: Vec<f32> = (0..size)
+            .map(|idx| {
+                let phase: f32 = 2.0 * consts::PI * idx as f32 / span;
+                match self {
+                    WindowFunction::None => 1.0,
+                    WindowFunction::Hann => 0.5 - 0.5 * phase.cos(),
+                    WindowFunction::Hamming => 0.54 - 0.46 * phase.cos(),
+                    WindowFunction::Blackman => {
+                        0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos()
+                    }
+                }
+            })
+            .collect();
+
+        let gain: f32 = coefficients.iter().sum::<f32>() / size as f32;
+        coefficients.iter().map(|value| value / gain).collect()
+    }
+}
+
+// Coefficients are cached for the tone size; the last other chunk length
+// seen keeps its own, so a run of short tail chunks computes them once
+#[derive(Clone)]
+struct Taper {
+    window: WindowFunction,
+    coefficients: Vec<f32>,
+    resized: RefCell<Vec<f32>>,
+}
+
+impl Taper {
+    fn new(window: WindowFunction, size: usize) -> Self {
+        let coefficients: Vec<f32> = window.coefficients(size);
+        let resized: RefCell<Vec<f32>> = RefCell::new(Vec::new());
+        Taper {
+            window,
+            coefficients,
+            resized,
+        }
+    }
+
+    fn apply_complex(&self, buffer: &mut [Complex<f32>]) {
+        if self.window == WindowFunction::None {
+            return;
+        }
+        self.with_coefficients(buffer.len(), |coefficients| {
+            for (value, coefficient) in buffer.iter_mut().zip(coefficients.iter()) {
+                value.re *= coefficient;
+            }
+        })
+    }
+
+    fn with_coefficients<F, R>(&self, size: usize, read: F) -> R
+    where
+        F: FnOnce(&[f32]) -> R,
+    {
+        if size == self.coefficients.len() {
+            return read(&self.coefficients);
+        }
+        let mut resized: RefMut<'_, Vec<f32>> = self.resized.borrow_mut();
+        if resized.len() != size {
+            *resized = self.window.coefficients(size);
+        }
+        read(&resized)
+    }
+}
+
+// Buffers kept between calls so a chunk costs no allocation
+struct FftScratch {
+    spectrum: Vec<Complex<f32>>,
+    scratch: Vec<Complex<f32>>,
+}
+
 pub struct FourierMagnitude {
     fft: Arc<dyn Fft<f32>>,
     pulses: SizedPulses,
     spec: AudioSpec,
+    taper: Taper,
+    scratch: RefCell<FftScratch>,
 }
 
 impl FourierMagnitude {
@@ -22,19 +166,62 @@ impl FourierMagnitude {
 
         let mut planner: FftPlanner<f32> = FftPlanner::<f32>::new();
         let fft: Arc<dyn Fft<f32>> = planner.plan_fft_forward(pulses.tone_size());
+        let taper: Taper = Taper::new(WindowFunction::None, pulses.tone_size());
+        let scratch: RefCell<FftScratch> = RefCell::new(FftScratch {
+            spectrum: Vec::with_capacity(fft.len()),
+            scratch: vec![Complex::new(0.0, 0.0); fft.get_inplace_scratch_len()],
+        });
+
+        FourierMagnitude {
+            fft,
+            pulses,
+            spec,
+            taper,
+            scratch,
+        }
+    }
+
+    pub fn with_window(mut self, window: WindowFunction) -> Self {
+        self.taper = Taper::new(window, self.pulses.tone_size());
+        self
+    }
 
-        FourierMagnitude { fft, pulses, spec }
+    pub fn window(&self) -> WindowFunction {
+        self.taper.window
     }
 
     pub fn get_magnitude(&self, samples: &[f32], target_frequency: f32) -> f32 {
-        let mut buffer: Vec<Complex<f32>> = samples.iter().map(|&s| Complex::new(s, 0.0)).collect();
-        self.fft.process(&mut buffer);
+        self.get_scaled_magnitude(samples, &SampleScale::unit(), target_frequency)
+    }
 
-        let k: usize = self.get_frequency_bin(target_frequency);
-        let normalization_factor: f32 = 2.0 / self.pulses.tone_size() as f32;
-        let magnitude: f32 = (buffer[k].norm_sqr()).sqrt() * normalization_factor;
-        let magnitude_db: f32 = 20.0 * magnitude.log10();
-        magnitude_db
+    // One transform of the chunk serves every probed frequency
+    pub fn get_magnitudes(&self, samples: &[f32], target_frequencies: &[f32]) -> Vec<f32> {
+        self.get_scaled_magnitudes(samples, &SampleScale::unit(), target_frequencies)
+    }
+
+    pub fn get_scaled_magnitude(
+        &self,
+        samples: &[f32],
+        scale: &SampleScale,
+        target_frequency: f32,
+    ) -> f32 {
+        self.with_spectrum(samples, scale, |spectrum| {
+            self.get_bin_magnitude(spectrum, target_frequency)
+        })
+    }
+
+    pub fn get_scaled_magnitudes(
+        &self,
+        samples: &[f32],
+        scale: &SampleScale,
+        target_frequencies: &[f32],
+    ) -> Vec<f32> {
+        self.with_spectrum(samples, scale, |spectrum| {
+            target_frequencies
+                .iter()
+                .map(|frequency| self.get_bin_magnitude(spectrum, *frequency))
+                .collect()
+        })
     }
 
     pub fn get_frequency_bin(&self, target_frequency: f32) -> usize {
@@ -48,9 +235,78 @@ impl FourierMagnitude {
     }
 }
 
+impl FourierMagnitude {
+    // Chunks are truncated or zero-padded to the planned transform size
+    fn with_spectrum<F, R>(&self, samples: &[f32], scale: &SampleScale, read: F) -> R
+    where
+        F: FnOnce(&[Complex<f32>]) -> R,
+    {
+        let mut scratch: RefMut<'_, FftScratch> = self.scratch.borrow_mut();
+        let FftScratch { spectrum, scratch } = &mut *scratch;
+        let size: usize = self.fft.len();
+
+        spectrum.clear();
+        spectrum.extend(
+            samples
+                .iter()
+                .take(size)
+                .map(|&s| Complex::new(scale.apply(s), 0.0)),
+        );
+        spectrum.resize(size, Complex::new(0.0, 0.0));
+        self.taper.apply_complex(spectrum);
+        self.fft.process_with_scratch(spectrum, scratch);
+        read(spectrum)
+    }
+
+    fn get_bin_magnitude(&self, spectrum: &[Complex<f32>], target_frequency: f32) -> f32 {
+        let k: usize = self.get_frequency_bin(target_frequency);
+        let normalization_factor: f32 = 2.0 / self.pulses.tone_size() as f32;
+        let magnitude: f32 = (spectrum[k].norm_sqr()).sqrt() * normalization_factor;
+        let magnitude_db: f32 = 20.0 * magnitude.log10();
+        magnitude_db
+    }
+}
+
+impl MagnitudeBackend for FourierMagnitude {
+    fn new(pulses: &SizedPulses, spec: &AudioSpec) -> Self {
+        FourierMagnitude::new(pulses, spec)
+    }
+
+    fn with_window(self, window: WindowFunction) -> Self {
+        FourierMagnitude::with_window(self, window)
+    }
+
+    fn get_magnitude(&self, samples: &[f32], target_frequency: f32) -> f32 {
+        FourierMagnitude::get_magnitude(self, samples, target_frequency)
+    }
+
+    fn get_magnitudes(&self, samples: &[f32], target_frequencies: &[f32]) -> Vec<f32> {
+        FourierMagnitude::get_magnitudes(self, samples, target_frequencies)
+    }
+
+    fn get_scaled_magnitude(
+        &self,
+        samples: &[f32],
+        scale: &SampleScale,
+        target_frequency: f32,
+    ) -> f32 {
+        FourierMagnitude::get_scaled_magnitude(self, samples, scale, target_frequency)
+    }
+
+    fn get_scaled_magnitudes(
+        &self,
+        samples: &[f32],
+        scale: &SampleScale,
+        target_frequencies: &[f32],
+    ) -> Vec<f32> {
+        FourierMagnitude::get_scaled_magnitudes(self, samples, scale, target_frequencies)
+    }
+}
+
 pub struct GoertzelMagnitude {
     pulses: SizedPulses,
     spec: AudioSpec,
+    taper: Taper,
 }
 
 impl GoertzelMagnitude {
@@ -58,128 +314,229 @@ impl GoertzelMagnitude {
         let pulses: SizedPulses = pulses.clone();
         let spec: AudioSpec = spec.clone();
 
-        GoertzelMagnitude { pulses, spec }
+        let taper: Taper = Taper::new(WindowFunction::None, pulses.tone_size());
+
+        GoertzelMagnitude {
+            pulses,
+            spec,
+            taper,
+        }
+    }
+
+    pub fn with_window(mut self, window: WindowFunction) -> Self {
+        self.taper = Taper::new(window, self.pulses.tone_size());
+        self
+    }
+
+    pub fn window(&self) -> WindowFunction {
+        self.taper.window
     }
 
     pub fn get_magnitude(&self, samples: &[f32], target_frequency: f32) -> f32 {
-        let mut q1: f32 = 0.0;
-        let mut q2: f32 = 0.0;
+        self.get_scaled_magnitude(samples, &SampleScale::unit(), target_frequency)
+    }
 
-        let sample_size: f32 = samples.len() as f32;
+    // The window and the scale are applied as the recurrence reads each sample
+    pub fn get_scaled_magnitude(
+        &self,
+        samples: &[f32],
+        scale: &SampleScale,
+        target_frequency: f32,
+    ) -> f32 {
         let k: usize = self.get_frequency_bin(target_frequency);
-        let w: f32 = 2.0 * consts::PI * k as f32 / sample_size;
-        let cosine: f32 = f32::cos(w);
-        let coeff: f32 = 2.0 * cosine;
-
-        for &sample in samples.iter() {
-            let q0: f32 = coeff * q1 - q2 + sample as f32;
-            q2 = q1;
-            q1 = q0;
+        if self.taper.window == WindowFunction::None {
+            let scaled = samples.iter().map(|sample| scale.apply(*sample));
+            return goertzel_db_iter(scaled, samples.len(), k);
         }
-
-        let magnitude: f32 = ((q1 * q1) + (q2 * q2) - (q1 * q2 * coeff)).sqrt();
-        let normalization_factor: f32 = 2.0 / sample_size;
-        let magnitude: f32 = magnitude * normalization_factor;
-        let magnitude_db: f32 = 20.0 * magnitude.log10();
-        magnitude_db
+        self.taper.with_coefficients(samples.len(), |coefficients| {
+            let tapered = samples
+                .iter()
+                .zip(coefficients.iter())
+                .map(|(sample, coefficient)| scale.apply(*sample) * coefficient);
+            goertzel_db_iter(tapered, samples.len(), k)
+        })
     }
 
     pub fn get_frequency_bin(&self, target_frequency: f32) -> usize {
-        let sample_rate: f32 = self.spec.sample_rate() as f32;
-        let sample_size: f32 = self.pulses.tone_size() as f32;
-        let normalized_frequency: f32 = target_frequency / sample_rate;
-        let scaled_frequency: f32 = sample_size * normalized_frequency;
-        let biased_frequency: f32 = 0.5 + scaled_frequency;
-        let k: usize = biased_frequency as usize;
-        k
+        frequency_bin(
+            target_frequency,
+            self.spec.sample_rate(),
+            self.pulses.tone_size(),
+        )
     }
 }
 
-pub struct Normalizer<'a> {
-    samples: &'a mut [f32],
-}
+// Goertzel evaluates only the probed bins instead of the whole spectrum
+impl MagnitudeBackend for GoertzelMagnitude {
+    fn new(pulses: &SizedPulses, spec: &AudioSpec) -> Self {
+        GoertzelMagnitude::new(pulses, spec)
+    }
 
-impl<'a> Normalizer<'a> {
-    pub fn new(samples: &'a mut [f32]) -> Self {
-        Normalizer { samples }
+    fn with_window(self, window: WindowFunction) -> Self {
+        GoertzelMagnitude::with_window(self, window)
     }
 
-    pub fn normalize(&mut self, ceiling: f32) {
-        let (mut p_max, mut n_max): (f32, f32) = self.find_max_magnitudes();
-        let (p_min, n_min): (f32, f32) = (0.0, 0.0);
+    fn get_magnitude(&self, samples: &[f32], target_frequency: f32) -> f32 {
+        GoertzelMagnitude::get_magnitude(self, samples, target_frequency)
+    }
 
-        p_max /= ceiling;
-        n_max /= ceiling;
+    fn get_scaled_magnitude(
+        &self,
+        samples: &[f32],
+        scale: &SampleScale,
+        target_frequency: f32,
+    ) -> f32 {
+        GoertzelMagnitude::get_scaled_magnitude(self, samples, scale, target_frequency)
+    }
 
-        self.normalize_samples(p_max, n_max, p_min, n_min);
+    fn get_scaled_magnitudes(
+        &self,
+        samples: &[f32],
+        scale: &SampleScale,
+        target_frequencies: &[f32],
+    ) -> Vec<f32> {
+        target_frequencies
+            .iter()
+            .map(|frequency| self.get_scaled_magnitude(samples, scale, *frequency))
+            .collect()
     }
+}
 
-    pub fn normalize_floor(&mut self, ceiling: f32, floor: f32) {
-        let (mut p_max, mut n_max): (f32, f32) = self.find_max_magnitudes();
-        let (p_min, n_min): (f32, f32) = (floor, -floor);
+// Tracks the tone-bin level of chunks that carried no signal, in dB
+#[derive(Clone, Debug)]
+pub struct NoiseEstimator {
+    floor: Option<f32>,
+    margin: f32,
+}
+
+impl NoiseEstimator {
+    pub fn new(margin: f32) -> Self {
+        let floor: Option<f32> = None;
+        NoiseEstimator { floor, margin }
+    }
 
-        p_max /= ceiling;
-        n_max /= ceiling;
+    pub fn update(&mut self, magnitude_db: f32) {
+        if !magnitude_db.is_finite() {
+            return;
+        }
+        self.floor = match self.floor {
+            Some(floor) => Some(floor + NOISE_SMOOTHING * (magnitude_db - floor)),
+            None => Some(magnitude_db),
+        };
+    }
 
-        self.normalize_samples(p_max, n_max, p_min, n_min);
+    pub fn floor(&self) -> Option<f32> {
+        self.floor
     }
-}
 
-impl<'a> Normalizer<'a> {
-    fn normalize_samples(&mut self, p_max: f32, n_max: f32, p_min: f32, n_min: f32) {
-        for sample in self.samples.iter_mut() {
-            if sample.is_normal() {
-                if sample.is_sign_positive() {
-                    Self::normalize_positive(sample, p_max, p_min);
-                } else if sample.is_sign_negative() {
-                    Self::normalize_negative(sample, n_max, n_min);
-                };
+    pub fn margin(&self) -> f32 {
+        self.margin
+    }
+
+    // Tones must clear the floor by the margin; `fallback` applies until a floor is known
+    pub fn threshold(&self, fallback: f32) -> f32 {
+        match self.floor {
+            Some(floor) => {
+                let threshold: f32 = -(floor + self.margin);
+                threshold.clamp(ADAPTIVE_THRESHOLD_MIN, ADAPTIVE_THRESHOLD_MAX)
             }
+            None => fallback,
         }
     }
 
-    fn compare_positive(a: &&f32, b: &&f32) -> Ordering {
-        match (a.is_sign_positive(), b.is_sign_positive()) {
-            (true, false) => Ordering::Greater,
-            (false, true) => Ordering::Less,
-            (true, true) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
-            (false, false) => Ordering::Equal,
-        }
+    pub fn reset(&mut self) {
+        self.floor = None;
     }
+}
+
+// Normalization of one chunk as a per-sample mapping: positive and negative
+// samples are scaled by their own peaks, and samples within `floor` of zero
+// are silenced. Analysis reads through it instead of rewriting the buffer
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct SampleScale {
+    positive: f32,
+    negative: f32,
+    floor: f32,
+}
 
-    fn compare_negative(a: &&f32, b: &&f32) -> Ordering {
-        match (a.is_sign_negative(), b.is_sign_negative()) {
-            (true, false) => Ordering::Greater,
-            (false, true) => Ordering::Less,
-            (true, true) => b.partial_cmp(a).unwrap_or(Ordering::Equal),
-            (false, false) => Ordering::Equal,
+impl SampleScale {
+    pub fn unit() -> Self {
+        SampleScale {
+            positive: 1.0,
+            negative: 1.0,
+            floor: 0.0,
         }
     }
 
-    fn normalize_positive(sample: &mut f32, p_max: f32, p_min: f32) {
-        if *sample < p_min {
-            *sample = 0.0;
-        } else {
-            *sample /= p_max
+    pub fn normalize(samples: &[f32], ceiling: f32) -> Self {
+        SampleScale::normalize_floor(samples, ceiling, 0.0)
+    }
+
+    pub fn normalize_floor(samples: &[f32], ceiling: f32, floor: f32) -> Self {
+        let (p_max, n_max): (f32, f32) =
+            samples
+                .iter()
+                .fold((0.0_f32, 0.0_f32), |(p_max, n_max), sample| {
+                    match sample.is_sign_positive() {
+                        true => (p_max.max(*sample), n_max),
+                        false => (p_max, n_max.min(*sample)),
+                    }
+                });
+        SampleScale {
+            positive: p_max / ceiling,
+            negative: n_max.abs() / ceiling,
+            floor,
         }
     }
 
-    fn normalize_negative(sample: &mut f32, n_max: f32, n_min: f32) {
-        if *sample > n_min {
-            *sample = 0.0;
+    pub fn apply(&self, sample: f32) -> f32 {
+        if !sample.is_normal() {
+            return sample;
+        }
+        if sample.is_sign_positive() {
+            match sample < self.floor {
+                true => 0.0,
+                false => sample / self.positive,
+            }
         } else {
-            *sample /= n_max.abs();
+            match sample > -self.floor {
+                true => 0.0,
+                false => sample / self.negative,
+            }
         }
     }
+}
+
+pub struct Normalizer<'a> {
+    samples: &'a mut [f32],
+}
+
+impl<'a> Normalizer<'a> {
+    pub fn new(samples: &'a mut [f32]) -> Self {
+        Normalizer { samples }
+    }
+
+    pub fn normalize(&mut self, ceiling: f32) {
+        let scale: SampleScale = SampleScale::normalize(self.samples, ceiling);
+        self.apply(&scale);
+    }
 
-    fn find_max_magnitudes(&self) -> (f32, f32) {
-        let p_max: &f32 = self.samples.iter().max_by(Self::compare_positive).unwrap();
-        let n_max: &f32 = self.samples.iter().max_by(Self::compare_negative).unwrap();
-        (*p_max, *n_max)
+    pub fn normalize_floor(&mut self, ceiling: f32, floor: f32) {
+        let scale: SampleScale = SampleScale::normalize_floor(self.samples, ceiling, floor);
+        self.apply(&scale);
+    }
+}
+
+impl<'a> Normalizer<'a> {
+    fn apply(&mut self, scale: &SampleScale) {
+        for sample in self.samples.iter_mut() {
+            *sample = scale.apply(*sample);
+        }
     }
 }
 
 #[test]
+#[cfg(feature = "wav")]
 fn test_normalizer() {
     use super::types::NormSamples;
     use super::types::SampleEncoding;
@@ -201,5 +558,153 @@ fn test_normalizer() {
 
     let spec: AudioSpec =
         AudioSpec::new(spec.sample_rate(), 32, spec.channels(), SampleEncoding::F32);
-    samples.save_file("test_normalizer.wav", &spec);
+    samples.save_file("test_normalizer.wav", &spec).unwrap();
+}
+
+#[test]
+fn test_goertzel_matches_fft() {
+    use super::types::SampleEncoding;
+    use crate::utils::get_fast_profile;
+
+    let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+    let pulses: SizedPulses = get_fast_profile().pulses.into_sized(&spec);
+    let samples: Vec<f32> = (0..pulses.tone_size())
+        .map(|idx| (2.0 * consts::PI * 5_000.0 * idx as f32 / 48_000.0).sin())
+        .collect();
+
+    let fft: FourierMagnitude = MagnitudeBackend::new(&pulses, &spec);
+    let goertzel: GoertzelMagnitude = MagnitudeBackend::new(&pulses, &spec);
+    let frequencies: [f32; 5] = [1_000.0, 3_000.0, 5_000.0, 7_000.0, 9_000.0];
+    let batch: Vec<f32> = fft.get_magnitudes(&samples, &frequencies);
+    for (frequency, batched) in frequencies.iter().zip(batch.iter()) {
+        let expected: f32 = fft.get_magnitude(&samples, *frequency);
+        let magnitude: f32 = goertzel.get_magnitude(&samples, *frequency);
+        assert!((expected - magnitude).abs() < 0.5 || expected < -60.0);
+        assert_eq!(expected.to_bits(), batched.to_bits());
+    }
+}
+
+#[test]
+fn test_batched_magnitudes() {
+    use super::types::SampleEncoding;
+    use crate::utils::get_fast_profile;
+
+    let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+    let pulses: SizedPulses = get_fast_profile().pulses.into_sized(&spec);
+    let full: Vec<f32> = (0..pulses.tone_size())
+        .map(|idx| (2.0 * consts::PI * 3_000.0 * idx as f32 / 48_000.0).sin())
+        .collect();
+    let short: Vec<f32> = (0..pulses.tone_size() / 3)
+        .map(|idx| 0.5 * (2.0 * consts::PI * 7_000.0 * idx as f32 / 48_000.0).sin())
+        .collect();
+
+    // The scratch buffers are reused across calls, so a short chunk between
+    // two full ones must not leave anything behind
+    let frequencies: [f32; 4] = [1_000.0, 3_000.0, 7_000.0, 9_000.0];
+    for window in [WindowFunction::None, WindowFunction::Hann] {
+        let fft: FourierMagnitude = FourierMagnitude::new(&pulses, &spec).with_window(window);
+        for chunk in [&full, &short, &full, &short] {
+            let batch: Vec<f32> = fft.get_magnitudes(chunk, &frequencies);
+            assert_eq!(batch.len(), frequencies.len());
+            for (frequency, batched) in frequencies.iter().zip(batch.iter()) {
+                let single: f32 = fft.get_magnitude(chunk, *frequency);
+                assert_eq!(single.to_bits(), batched.to_bits());
+            }
+        }
+    }
+}
+
+#[test]
+fn test_window_leakage() {
+    use super::types::SampleEncoding;
+    use crate::utils::get_fast_profile;
+
+    let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+    let pulses: SizedPulses = get_fast_profile().pulses.into_sized(&spec);
+    let bin_width: f32 = 48_000.0 / pulses.tone_size() as f32;
+    let tone = |frequency: f32| -> Vec<f32> {
+        (0..pulses.tone_size())
+            .map(|idx| (2.0 * consts::PI * frequency * idx as f32 / 48_000.0).sin())
+            .collect()
+    };
+
+    let plain: GoertzelMagnitude = GoertzelMagnitude::new(&pulses, &spec);
+    for window in [
+        WindowFunction::Hann,
+        WindowFunction::Hamming,
+        WindowFunction::Blackman,
+    ] {
+        let goertzel: GoertzelMagnitude =
+            GoertzelMagnitude::new(&pulses, &spec).with_window(window);
+        let fft: FourierMagnitude = FourierMagnitude::new(&pulses, &spec).with_window(window);
+
+        // Unit coherent gain keeps an on-bin tone at its rectangular level
+        let on_bin: Vec<f32> = tone(bin_width * 8.0);
+        let expected: f32 = plain.get_magnitude(&on_bin, bin_width * 8.0);
+        assert!((goertzel.get_magnitude(&on_bin, bin_width * 8.0) - expected).abs() < 0.5);
+        assert!((fft.get_magnitude(&on_bin, bin_width * 8.0) - expected).abs() < 0.5);
+
+        let off_bin: Vec<f32> = tone(bin_width * 8.5);
+        let leaked: f32 = plain.get_magnitude(&off_bin, bin_width * 14.0);
+        assert!(goertzel.get_magnitude(&off_bin, bin_width * 14.0) < leaked - 15.0);
+
+        // A shorter tail chunk gets its own window, computed once and reused
+        let tail: &[f32] = &on_bin[..pulses.tone_size() / 2];
+        let first: f32 = goertzel.get_magnitude(tail, bin_width * 8.0);
+        assert_eq!(goertzel.taper.resized.borrow().len(), tail.len());
+        assert_eq!(goertzel.get_magnitude(tail, bin_width * 8.0), first);
+    }
+}
+
+#[test]
+fn test_scaled_magnitudes() {
+    use super::types::SampleEncoding;
+    use crate::utils::get_fast_profile;
+
+    let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+    let pulses: SizedPulses = get_fast_profile().pulses.into_sized(&spec);
+    let samples: Vec<f32> = (0..pulses.tone_size())
+        .map(|idx| 0.3 * (2.0 * consts::PI * 7_000.0 * idx as f32 / 48_000.0).sin() + 0.05)
+        .collect();
+
+    // Reading through the scale matches normalizing a copy, and leaves the input alone
+    let scale: SampleScale = SampleScale::normalize_floor(&samples, 1.0, 0.1);
+    let mut normalized: Vec<f32> = samples.clone();
+    Normalizer::new(&mut normalized).normalize_floor(1.0, 0.1);
+    let frequencies: [f32; 3] = [3_000.0, 7_000.0, 9_000.0];
+    for window in [WindowFunction::None, WindowFunction::Hann] {
+        let fft: FourierMagnitude = FourierMagnitude::new(&pulses, &spec).with_window(window);
+        let goertzel: GoertzelMagnitude =
+            GoertzelMagnitude::new(&pulses, &spec).with_window(window);
+        let scaled: Vec<f32> = fft.get_scaled_magnitudes(&samples, &scale, &frequencies);
+        assert_eq!(scaled, fft.get_magnitudes(&normalized, &frequencies));
+        for frequency in frequencies {
+            let expected: f32 = goertzel.get_magnitude(&normalized, frequency);
+            let magnitude: f32 = goertzel.get_scaled_magnitude(&samples, &scale, frequency);
+            assert_eq!(expected.to_bits(), magnitude.to_bits());
+        }
+    }
+    assert!((samples[0] - 0.05).abs() < 1e-6);
+}
+
+#[test]
+fn test_noise_estimator() {
+    let mut estimator: NoiseEstimator = NoiseEstimator::new(6.0);
+    assert_eq!(estimator.threshold(8.0), 8.0);
+
+    estimator.update(f32::NEG_INFINITY);
+    assert!(estimator.floor().is_none());
+
+    estimator.update(-20.0);
+    assert_eq!(estimator.floor(), Some(-20.0));
+    assert_eq!(estimator.threshold(8.0), 14.0);
+
+    for _ in 0..100 {
+        estimator.update(-8.0);
+    }
+    assert!((estimator.floor().unwrap() + 8.0).abs() < 0.01);
+    assert_eq!(estimator.threshold(8.0), ADAPTIVE_THRESHOLD_MIN);
+
+    estimator.reset();
+    assert!(estimator.floor().is_none());
 }
@@ -0,0 +1,79 @@
+use crate::consts::SQUELCH_CLOSE_DB;
+use crate::consts::SQUELCH_OPEN_DB;
+
+// Energy gate in front of the start search; it opens above `open_db` and only
+// closes again once the level falls below the lower `close_db`
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Squelch {
+    open_db: f32,
+    close_db: f32,
+    open: bool,
+}
+
+impl Squelch {
+    pub fn new(open_db: f32, close_db: f32) -> Self {
+        let close_db: f32 = close_db.min(open_db);
+        let open: bool = false;
+        Squelch {
+            open_db,
+            close_db,
+            open,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    pub fn open_db(&self) -> f32 {
+        self.open_db
+    }
+
+    pub fn close_db(&self) -> f32 {
+        self.close_db
+    }
+
+    pub fn update(&mut self, samples: &[f32]) -> bool {
+        let level: f32 = rms_db(samples);
+        if self.open {
+            if level < self.close_db {
+                self.open = false;
+            }
+        } else if level >= self.open_db {
+            self.open = true;
+        }
+        self.open
+    }
+
+    pub fn reset(&mut self) {
+        self.open = false;
+    }
+}
+
+impl Default for Squelch {
+    fn default() -> Self {
+        Squelch::new(SQUELCH_OPEN_DB, SQUELCH_CLOSE_DB)
+    }
+}
+
+pub fn rms_db(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return f32::NEG_INFINITY;
+    }
+    let power: f32 = samples.iter().map(|sample| sample * sample).sum::<f32>();
+    let rms: f32 = (power / samples.len() as f32).sqrt();
+    20.0 * rms.log10()
+}
+
+#[test]
+fn test_squelch_hysteresis() {
+    let mut squelch: Squelch = Squelch::new(-40.0, -50.0);
+    assert!(!squelch.update(&[0.0; 64]));
+    assert!(!squelch.update(&[0.005; 64]));
+
+    // -34 dBFS opens, -46 dBFS sits between the levels and holds it open
+    assert!(squelch.update(&[0.02; 64]));
+    assert!(squelch.update(&[0.005; 64]));
+    assert!(!squelch.update(&[0.001; 64]));
+}
@@ -1,30 +1,53 @@
-use std::collections::LinkedList;
+use std::collections::VecDeque;
+#[cfg(feature = "wav")]
 use std::path::Path;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::sync::RwLock;
+use std::sync::Condvar;
+use std::sync::Mutex;
+use std::sync::MutexGuard;
+use std::thread;
 use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
 
+#[cfg(feature = "wav")]
 use std::fs::File;
+#[cfg(feature = "wav")]
 use std::io::BufWriter;
 
+#[cfg(feature = "wav")]
 use hound::WavSpec;
+#[cfg(feature = "wav")]
 use hound::WavWriter;
 
 use super::filters::FrequencyPass;
+use super::ring::SampleRing;
 use super::spectrum::Normalizer;
 
+use crate::error::WavetrxError;
+
 use crate::consts::HP_FILTER;
 use crate::consts::LP_FILTER;
+use crate::consts::MAX_CHANNELS;
+use crate::consts::SAMPLE_BUFFER_POLL;
 
 pub struct NormSamples(pub Vec<f32>);
 
 impl NormSamples {
+    // Integer samples span the spec's bit depth, as hound reads and writes
+    // them; 8-bit PCM is already shifted to signed and 24-bit is sign-extended
     fn i32_to_f32(sample: i32, spec: &AudioSpec) -> f32 {
-        match spec.bits_per_sample() {
-            16 => (sample as f32) / (i16::MAX as f32),
-            32 => (sample as f32) / (i32::MAX as f32),
-            _ => panic!("Unsupported Bits-Per-Sample while normalizing"),
-        }
+        let (positive, _): (i32, i32) = spec.get_magnitudes();
+        sample as f32 / positive as f32
+    }
+
+    #[cfg(feature = "wav")]
+    pub fn f32_to_i32(sample: f32, spec: &AudioSpec) -> i32 {
+        let (positive, _): (i32, i32) = spec.get_magnitudes();
+        let sample: f64 = sample.clamp(-1.0, 1.0) as f64;
+        (sample * positive as f64) as i32
     }
 }
 
@@ -64,17 +87,42 @@ impl NormSamples {
         }
     }
 
-    pub fn save_file<P>(&self, filename: P, spec: &AudioSpec)
+    // Collapses interleaved frames into a single channel; a trailing partial frame is dropped
+    pub fn into_mono(self, channels: usize, mode: ChannelMode) -> NormSamples {
+        let channels: usize = channels.clamp(1, MAX_CHANNELS);
+        if channels == 1 {
+            return self;
+        }
+
+        let mut samples: Vec<f32> = Vec::with_capacity(self.0.len() / channels);
+        for frame in self.0.chunks_exact(channels) {
+            let sample: f32 = match mode {
+                ChannelMode::Downmix => frame.iter().sum::<f32>() / channels as f32,
+                ChannelMode::Select(channel) => frame[channel.min(channels - 1)],
+            };
+            samples.push(sample);
+        }
+        NormSamples::from_vec(samples)
+    }
+
+    #[cfg(feature = "wav")]
+    pub fn save_file<P>(&self, filename: P, spec: &AudioSpec) -> Result<(), WavetrxError>
     where
         P: AsRef<Path>,
     {
+        spec.check_wav_support()?;
+
         let wav_spec: WavSpec = (*spec).into();
-        let mut writer: WavWriter<BufWriter<File>> =
-            WavWriter::create(filename, wav_spec).expect("Error creating WAV writer");
+        let mut writer: WavWriter<BufWriter<File>> = WavWriter::create(filename, wav_spec)?;
 
         for sample in self.0.iter() {
-            writer.write_sample(*sample).expect("Error writing sample");
+            match spec.encoding() {
+                SampleEncoding::F32 => writer.write_sample(*sample)?,
+                SampleEncoding::I32 => writer.write_sample(Self::f32_to_i32(*sample, spec))?,
+            }
         }
+        writer.finalize()?;
+        Ok(())
     }
 }
 
@@ -99,6 +147,19 @@ impl NormSamples {
     }
 }
 
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ChannelMode {
+    Downmix,
+    Select(usize),
+}
+
+impl Default for ChannelMode {
+    fn default() -> Self {
+        ChannelMode::Select(0)
+    }
+}
+
 #[derive(Clone, Copy)]
 pub enum SampleEncoding {
     F32,
@@ -127,6 +188,14 @@ impl AudioSpec {
         self.channels
     }
 
+    pub fn with_channels(&self, channels: u16) -> Self {
+        Self { channels, ..*self }
+    }
+
+    pub fn with_sample_rate(&self, sr: u32) -> Self {
+        Self { sr, ..*self }
+    }
+
     pub fn sample_rate(&self) -> u32 {
         self.sr
     }
@@ -139,8 +208,23 @@ impl AudioSpec {
         self.encoding
     }
 
+    // Hound reads and writes 8/16/24/32-bit PCM and 32-bit float
+    pub fn check_wav_support(&self) -> Result<(), WavetrxError> {
+        let supported: bool = match self.encoding {
+            SampleEncoding::F32 => self.bps == 32,
+            SampleEncoding::I32 => matches!(self.bps, 8 | 16 | 24 | 32),
+        };
+        if !supported {
+            let reason: String = format!("{} bits per sample ({:?})", self.bps, self.encoding);
+            return Err(WavetrxError::UnsupportedWav(reason));
+        }
+        Ok(())
+    }
+
+    // Integer range of the bit depth, clamped to 2..=32 bits
     pub fn get_magnitudes(&self) -> (i32, i32) {
-        let positive_magnitude: i32 = (2i32.pow((self.bps - 1) as u32)) - 1;
+        let bps: u32 = self.bps.clamp(2, 32) as u32;
+        let positive_magnitude: i32 = ((1i64 << (bps - 1)) - 1) as i32;
         let negative_magnitude: i32 = -positive_magnitude - 1;
         (positive_magnitude, negative_magnitude)
     }
@@ -173,73 +257,222 @@ impl std::fmt::Debug for AudioSpec {
     }
 }
 
+// What a full FrameBuffer does with the next frame; `Block` stalls the
+// producer, so it suits file feeders rather than audio callbacks
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Backpressure {
+    Block,
+    DropOldest,
+    DropNewest,
+}
+
+// Frames carry the wall-clock capture time of their first sample when the
+// producer knows it
+pub type TimedFrame = (NormSamples, Option<SystemTime>);
+
 pub struct FrameBuffer {
-    buffer: RwLock<LinkedList<NormSamples>>,
+    buffer: Mutex<VecDeque<TimedFrame>>,
+    ready: Condvar,
+    space: Condvar,
+    capacity: usize,
+    policy: Backpressure,
+    dropped: AtomicUsize,
 }
 
 impl FrameBuffer {
     pub fn new() -> Arc<Self> {
-        let buffer: RwLock<LinkedList<NormSamples>> = RwLock::new(LinkedList::new());
-        Arc::new(Self { buffer })
+        FrameBuffer::bounded(usize::MAX, Backpressure::DropNewest)
+    }
+
+    pub fn bounded(capacity: usize, policy: Backpressure) -> Arc<Self> {
+        let buffer: Mutex<VecDeque<TimedFrame>> = Mutex::new(VecDeque::new());
+        let ready: Condvar = Condvar::new();
+        let space: Condvar = Condvar::new();
+        let capacity: usize = capacity.max(1);
+        let dropped: AtomicUsize = AtomicUsize::new(0);
+        Arc::new(Self {
+            buffer,
+            ready,
+            space,
+            capacity,
+            policy,
+            dropped,
+        })
     }
 
     pub fn add_frame(self: &Arc<Self>, frame: NormSamples) {
-        if let Ok(mut buffer_guard) = self.buffer.write() {
-            buffer_guard.push_back(frame);
-        }
+        self.push((frame, None));
+    }
+
+    pub fn add_timed_frame(self: &Arc<Self>, frame: NormSamples, time: SystemTime) {
+        self.push((frame, Some(time)));
     }
 
     pub fn take(self: &Arc<Self>) -> Option<NormSamples> {
-        if let Ok(mut buffer_guard) = self.buffer.write() {
-            return buffer_guard.pop_front();
+        self.take_timed().map(|(frame, _)| frame)
+    }
+
+    pub fn take_timed(self: &Arc<Self>) -> Option<TimedFrame> {
+        if let Ok(mut buffer_guard) = self.buffer.lock() {
+            let frame: Option<TimedFrame> = buffer_guard.pop_front();
+            self.space.notify_one();
+            return frame;
         }
         None
     }
+
+    pub fn take_timeout(self: &Arc<Self>, timeout: Duration) -> Option<NormSamples> {
+        self.take_timed_timeout(timeout).map(|(frame, _)| frame)
+    }
+
+    pub fn take_timed_timeout(self: &Arc<Self>, timeout: Duration) -> Option<TimedFrame> {
+        let deadline: Instant = Instant::now() + timeout;
+        let mut buffer_guard: MutexGuard<'_, VecDeque<TimedFrame>> = self.buffer.lock().ok()?;
+        while buffer_guard.is_empty() {
+            let remaining: Duration = deadline.checked_duration_since(Instant::now())?;
+            buffer_guard = self.ready.wait_timeout(buffer_guard, remaining).ok()?.0;
+        }
+        let frame: Option<TimedFrame> = buffer_guard.pop_front();
+        self.space.notify_one();
+        frame
+    }
+
+    pub fn len(self: &Arc<Self>) -> usize {
+        match self.buffer.lock() {
+            Ok(buffer_guard) => buffer_guard.len(),
+            Err(_) => 0,
+        }
+    }
+
+    pub fn is_empty(self: &Arc<Self>) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(self: &Arc<Self>) -> usize {
+        self.capacity
+    }
+
+    pub fn dropped(self: &Arc<Self>) -> usize {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+impl FrameBuffer {
+    fn push(self: &Arc<Self>, frame: TimedFrame) {
+        let mut buffer_guard: MutexGuard<'_, VecDeque<TimedFrame>> = match self.buffer.lock() {
+            Ok(buffer_guard) => buffer_guard,
+            Err(_) => return,
+        };
+
+        while buffer_guard.len() >= self.capacity {
+            match self.policy {
+                Backpressure::Block => match self.space.wait(buffer_guard) {
+                    Ok(guard) => buffer_guard = guard,
+                    Err(_) => return,
+                },
+                Backpressure::DropOldest => {
+                    buffer_guard.pop_front();
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                }
+                Backpressure::DropNewest => {
+                    self.dropped.fetch_add(1, Ordering::Relaxed);
+                    return;
+                }
+            }
+        }
+        buffer_guard.push_back(frame);
+        self.ready.notify_one();
+    }
 }
 
+// Fixed-capacity playback queue; pushes are serialised so any thread may
+// queue samples while the output callback pops without taking a lock. The
+// callback wakes nobody either; waiters poll the queue length instead
 pub struct SampleBuffer {
-    buffer: RwLock<LinkedList<f32>>,
+    ring: Arc<SampleRing>,
+    producer: Mutex<()>,
 }
 
 impl SampleBuffer {
-    pub fn new() -> Arc<Self> {
-        let buffer: RwLock<LinkedList<f32>> = RwLock::new(LinkedList::new());
-        Arc::new(Self { buffer })
+    pub fn new(capacity: usize) -> Arc<Self> {
+        let ring: Arc<SampleRing> = SampleRing::new(capacity);
+        let producer: Mutex<()> = Mutex::new(());
+        Arc::new(Self { ring, producer })
+    }
+
+    pub fn add_sample(&self, sample: f32) -> bool {
+        self.push_slice(&[sample]) == 1
+    }
+
+    pub fn add_samples(&self, samples: NormSamples) -> usize {
+        self.push_slice(&samples.0)
     }
 
-    pub fn add_sample(self: &Arc<Self>, sample: f32) {
-        if let Ok(mut buffer_guard) = self.buffer.write() {
-            buffer_guard.push_back(sample);
+    // Returns how many samples fit; the rest are counted as dropped
+    pub fn push_slice(&self, samples: &[f32]) -> usize {
+        let _guard: MutexGuard<()> = match self.producer.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        self.ring.push_slice(samples)
+    }
+
+    // Drops everything queued, which ends any wait for it to drain
+    pub fn clear(&self) -> usize {
+        let _guard: MutexGuard<()> = match self.producer.lock() {
+            Ok(guard) => guard,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        self.ring.clear()
+    }
+
+    // Lock-free, for the output callback
+    pub fn pop_slice(&self, out: &mut [f32]) -> usize {
+        self.ring.pop_slice(out)
+    }
+
+    pub fn take(&self) -> Option<f32> {
+        let mut sample: [f32; 1] = [0.0];
+        match self.pop_slice(&mut sample) {
+            0 => None,
+            _ => Some(sample[0]),
         }
     }
 
-    pub fn add_samples(self: &Arc<Self>, samples: NormSamples) {
-        if let Ok(mut buffer_guard) = self.buffer.write() {
-            for sample in samples.0 {
-                buffer_guard.push_back(sample);
+    // Blocks until at most `remaining` samples are queued, checking every
+    // `SAMPLE_BUFFER_POLL`; gives up and returns false once the timeout elapses
+    pub fn wait_until(&self, remaining: usize, timeout: Option<Duration>) -> bool {
+        let deadline: Option<Instant> = timeout.map(|timeout| Instant::now() + timeout);
+
+        while self.ring.len() > remaining {
+            let mut poll: Duration = SAMPLE_BUFFER_POLL;
+            if let Some(deadline) = deadline {
+                let now: Instant = Instant::now();
+                if now >= deadline {
+                    return false;
+                }
+                poll = poll.min(deadline - now);
             }
+            thread::sleep(poll);
         }
+        true
     }
 
-    pub fn take(self: &Arc<Self>) -> Option<f32> {
-        if let Ok(mut buffer_guard) = self.buffer.write() {
-            return buffer_guard.pop_front();
-        }
-        None
+    pub fn buffer_empty(&self) -> bool {
+        self.ring.is_empty()
     }
 
-    pub fn buffer_empty(self: &Arc<Self>) -> bool {
-        if let Ok(buffer_guard) = self.buffer.read() {
-            return buffer_guard.is_empty();
-        }
-        false
+    pub fn buffer_len(&self) -> usize {
+        self.ring.len()
     }
 
-    pub fn buffer_len(self: &Arc<Self>) -> usize {
-        if let Ok(buffer_guard) = self.buffer.read() {
-            return buffer_guard.len();
-        }
-        0
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity()
+    }
+
+    pub fn dropped(&self) -> usize {
+        self.ring.dropped()
     }
 }
 
@@ -266,3 +499,44 @@ impl Scalar for f32 {
         *self
     }
 }
+
+#[test]
+fn test_sample_buffer_overflow() {
+    let buffer: Arc<SampleBuffer> = SampleBuffer::new(4);
+    assert_eq!(buffer.capacity(), 4);
+
+    assert_eq!(
+        buffer.add_samples(NormSamples::from_vec(vec![0.1, 0.2, 0.3])),
+        3
+    );
+    assert!(buffer.add_sample(0.4));
+    assert!(!buffer.add_sample(0.5));
+    assert_eq!(buffer.dropped(), 1);
+    assert_eq!(buffer.buffer_len(), 4);
+
+    let mut out: [f32; 3] = [0.0; 3];
+    assert_eq!(buffer.pop_slice(&mut out), 3);
+    assert_eq!(out, [0.1, 0.2, 0.3]);
+    assert_eq!(buffer.take(), Some(0.4));
+    assert_eq!(buffer.take(), None);
+    assert!(buffer.buffer_empty());
+}
+
+#[test]
+fn test_sample_buffer_wait() {
+    let buffer: Arc<SampleBuffer> = SampleBuffer::new(64);
+    buffer.add_samples(NormSamples::from_vec(vec![0.5; 32]));
+    assert!(!buffer.wait_until(0, Some(Duration::from_millis(20))));
+
+    let consumer: Arc<SampleBuffer> = buffer.clone();
+    let handle: std::thread::JoinHandle<()> = std::thread::spawn(move || {
+        let mut out: [f32; 8] = [0.0; 8];
+        while consumer.pop_slice(&mut out) > 0 {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    });
+
+    assert!(buffer.wait_until(0, Some(Duration::from_secs(5))));
+    assert!(buffer.buffer_empty());
+    handle.join().unwrap();
+}
@@ -0,0 +1,204 @@
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::error::WavetrxError;
+
+pub struct Heartbeat {
+    origin: Instant,
+    last: AtomicU64,
+    beats: AtomicU64,
+}
+
+impl Heartbeat {
+    pub fn new() -> Arc<Self> {
+        let origin: Instant = Instant::now();
+        let last: AtomicU64 = AtomicU64::new(0);
+        let beats: AtomicU64 = AtomicU64::new(0);
+        Arc::new(Self {
+            origin,
+            last,
+            beats,
+        })
+    }
+
+    pub fn beat(&self) {
+        let nanos: u64 = self.origin.elapsed().as_nanos() as u64;
+        self.last.store(nanos, Ordering::Relaxed);
+        self.beats.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn beats(&self) -> u64 {
+        self.beats.load(Ordering::Relaxed)
+    }
+
+    pub fn idle(&self) -> Duration {
+        let now: u64 = self.origin.elapsed().as_nanos() as u64;
+        let last: u64 = self.last.load(Ordering::Relaxed);
+        Duration::from_nanos(now.saturating_sub(last))
+    }
+}
+
+// Where an audio endpoint's stream is; `Stopped` until it is first started
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StreamState {
+    #[default]
+    Stopped,
+    Running,
+    Paused,
+}
+
+pub trait Supervised {
+    fn heartbeat(&self) -> Arc<Heartbeat>;
+    fn restart(&mut self) -> Result<(), WavetrxError>;
+
+    // A paused endpoint is silent on purpose and is not watched
+    fn is_paused(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum HealthEvent {
+    Stalled(Duration),
+    Restarted,
+    RestartFailed(String),
+    Recovered,
+}
+
+pub struct Watchdog {
+    timeout: Duration,
+    auto_restart: bool,
+    stalled: bool,
+    stall_beats: u64,
+    last_restart: Option<Instant>,
+}
+
+impl Watchdog {
+    pub fn new(timeout: Duration) -> Self {
+        let auto_restart: bool = false;
+        let stalled: bool = false;
+        let stall_beats: u64 = 0;
+        let last_restart: Option<Instant> = None;
+        Self {
+            timeout,
+            auto_restart,
+            stalled,
+            stall_beats,
+            last_restart,
+        }
+    }
+
+    pub fn set_auto_restart(&mut self, auto_restart: bool) {
+        self.auto_restart = auto_restart;
+    }
+
+    pub fn is_stalled(&self) -> bool {
+        self.stalled
+    }
+
+    pub fn poll<S>(&mut self, endpoint: &mut S) -> Option<HealthEvent>
+    where
+        S: Supervised,
+    {
+        if endpoint.is_paused() {
+            self.stalled = false;
+            self.last_restart = None;
+            return None;
+        }
+
+        let heartbeat: Arc<Heartbeat> = endpoint.heartbeat();
+        let beats: u64 = heartbeat.beats();
+
+        if self.stalled {
+            if beats != self.stall_beats {
+                self.stalled = false;
+                self.last_restart = None;
+                return Some(HealthEvent::Recovered);
+            }
+            if self.auto_restart && self.restart_due() {
+                return Some(self.restart(endpoint));
+            }
+            return None;
+        }
+
+        let idle: Duration = heartbeat.idle();
+        if idle >= self.timeout {
+            self.stalled = true;
+            self.stall_beats = beats;
+            return Some(HealthEvent::Stalled(idle));
+        }
+        None
+    }
+}
+
+impl Watchdog {
+    fn restart_due(&self) -> bool {
+        match self.last_restart {
+            Some(last_restart) => last_restart.elapsed() >= self.timeout,
+            None => true,
+        }
+    }
+
+    fn restart<S>(&mut self, endpoint: &mut S) -> HealthEvent
+    where
+        S: Supervised,
+    {
+        self.last_restart = Some(Instant::now());
+        match endpoint.restart() {
+            Ok(()) => HealthEvent::Restarted,
+            Err(err) => HealthEvent::RestartFailed(err.to_string()),
+        }
+    }
+}
+
+#[test]
+fn test_watchdog() {
+    struct Endpoint {
+        heartbeat: Arc<Heartbeat>,
+        restarts: usize,
+        paused: bool,
+    }
+
+    impl Supervised for Endpoint {
+        fn heartbeat(&self) -> Arc<Heartbeat> {
+            self.heartbeat.clone()
+        }
+
+        fn restart(&mut self) -> Result<(), WavetrxError> {
+            self.restarts += 1;
+            Ok(())
+        }
+
+        fn is_paused(&self) -> bool {
+            self.paused
+        }
+    }
+
+    let mut endpoint: Endpoint = Endpoint {
+        heartbeat: Heartbeat::new(),
+        restarts: 0,
+        paused: false,
+    };
+    let mut watchdog: Watchdog = Watchdog::new(Duration::ZERO);
+    watchdog.set_auto_restart(true);
+
+    let event: Option<HealthEvent> = watchdog.poll(&mut endpoint);
+    assert!(matches!(event, Some(HealthEvent::Stalled(_))));
+    assert_eq!(watchdog.poll(&mut endpoint), Some(HealthEvent::Restarted));
+    assert_eq!(endpoint.restarts, 1);
+
+    endpoint.heartbeat.beat();
+    assert_eq!(watchdog.poll(&mut endpoint), Some(HealthEvent::Recovered));
+    assert!(!watchdog.is_stalled());
+
+    // Silence while paused is neither a stall nor a reason to restart
+    endpoint.paused = true;
+    assert_eq!(watchdog.poll(&mut endpoint), None);
+    assert_eq!(watchdog.poll(&mut endpoint), None);
+    assert_eq!(endpoint.restarts, 1);
+}
@@ -0,0 +1,357 @@
+#[cfg(feature = "serde")]
+use std::fs;
+#[cfg(feature = "serde")]
+use std::path::Path;
+#[cfg(feature = "device")]
+use std::thread::sleep;
+use std::time::Duration;
+#[cfg(feature = "device")]
+use std::time::Instant;
+
+#[cfg(feature = "device")]
+use cpal::traits::DeviceTrait;
+#[cfg(feature = "device")]
+use cpal::traits::HostTrait;
+#[cfg(feature = "device")]
+use cpal::Device;
+#[cfg(feature = "device")]
+use cpal::Host;
+#[cfg(feature = "device")]
+use cpal::StreamConfig;
+
+#[cfg(feature = "device")]
+use crate::audio::player::OutputPlayer;
+#[cfg(feature = "device")]
+use crate::audio::recorder::InputRecorder;
+use crate::audio::spectrum::FourierMagnitude;
+use crate::audio::spectrum::SampleScale;
+use crate::audio::types::AudioSpec;
+#[cfg(feature = "device")]
+use crate::audio::types::NormSamples;
+#[cfg(feature = "device")]
+use crate::audio::types::SampleEncoding;
+use crate::consts::ADAPTIVE_THRESHOLD_MAX;
+use crate::consts::ADAPTIVE_THRESHOLD_MIN;
+use crate::consts::CALIBRATION_BURST_TONES;
+use crate::consts::CALIBRATION_HEADROOM;
+use crate::consts::CALIBRATION_LEVELS;
+use crate::consts::CALIBRATION_MARGIN_DB;
+use crate::embedded::sample_size;
+use crate::error::WavetrxError;
+use crate::protocol::profile::Profile;
+use crate::protocol::profile::SizedPulses;
+use crate::protocol::tx::ToneGenerator;
+
+const LEAD_SILENCE: Duration = Duration::from_millis(250);
+const ONSET_FLOOR: f32 = 0.01;
+#[cfg(feature = "device")]
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CalibrationStep {
+    pub frequency: f32,
+    pub level: f32,
+}
+
+// How the receiver's detector saw one step: the raw peak that arrived, the
+// step's tone and the strongest other profile tone in dB, and the strongest
+// profile tone in the silence that followed
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StepMeasurement {
+    pub step: CalibrationStep,
+    pub peak: f32,
+    pub magnitude: f32,
+    pub competitor: f32,
+    pub noise: f32,
+}
+
+impl StepMeasurement {
+    // Lead of the tone over its nearest rival, tone or room noise
+    pub fn margin(&self) -> f32 {
+        self.magnitude - self.competitor.max(self.noise)
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.magnitude.is_finite() && self.margin() >= CALIBRATION_MARGIN_DB
+    }
+}
+
+// Receiver threshold and transmitter gain found by calibration, saved apart
+// from the profile they tune and laid over it when loaded
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Calibration {
+    pub threshold: f32,
+    pub gain: f32,
+}
+
+impl Calibration {
+    // The gain is not part of the profile; pass it to `Transmitter::with_gain`
+    pub fn apply(&self, profile: Profile) -> Profile {
+        profile.with_threshold(self.threshold)
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn from_file<P>(path: P) -> Result<Self, WavetrxError>
+    where
+        P: AsRef<Path>,
+    {
+        let contents: String = fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(|err| WavetrxError::ProfileInvalid(err.to_string()))
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn to_file<P>(&self, path: P) -> Result<(), WavetrxError>
+    where
+        P: AsRef<Path>,
+    {
+        let contents: String = serde_json::to_string_pretty(self)
+            .map_err(|err| WavetrxError::ProfileInvalid(err.to_string()))?;
+        fs::write(path, contents)?;
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CalibrationReport {
+    pub measurements: Vec<StepMeasurement>,
+    pub calibration: Option<Calibration>,
+}
+
+// Every marker and symbol tone of a profile at each calibration level, loudest
+// level first, played as bursts of a few tone lengths with equal gaps between
+pub struct Staircase {
+    profile: Profile,
+    steps: Vec<CalibrationStep>,
+}
+
+impl Staircase {
+    pub fn new(profile: Profile) -> Self {
+        let steps: Vec<CalibrationStep> = CALIBRATION_LEVELS
+            .iter()
+            .flat_map(|level| {
+                profile
+                    .frequencies()
+                    .into_iter()
+                    .map(|frequency| CalibrationStep {
+                        frequency,
+                        level: *level,
+                    })
+            })
+            .collect();
+        Staircase { profile, steps }
+    }
+
+    pub fn steps(&self) -> &[CalibrationStep] {
+        &self.steps
+    }
+
+    pub fn render(&self, spec: &AudioSpec) -> Result<Vec<f32>, WavetrxError> {
+        let burst: usize = self.burst_duration();
+        let mut tone: ToneGenerator = ToneGenerator::new(spec)?;
+        tone.append_silence(LEAD_SILENCE.as_micros() as usize)?;
+        for step in self.steps.iter() {
+            tone.set_gain(step.level);
+            tone.append_sine_faded_tone(step.frequency, burst, 0.1)?;
+            tone.append_silence(burst)?;
+        }
+        Ok(tone.samples())
+    }
+
+    // `samples` is a mono recording at `spec` that starts before the
+    // staircase was played; the first burst, at full level, is taken as the
+    // start of the steps. None if it can't be found or the recording ends early
+    pub fn measure(&self, samples: &[f32], spec: &AudioSpec) -> Option<Vec<StepMeasurement>> {
+        let sample_rate: u32 = spec.sample_rate();
+        let pulses: SizedPulses = self.profile.pulses.into_sized(spec);
+        let magnitude: FourierMagnitude =
+            FourierMagnitude::new(&pulses, spec).with_window(self.profile.window);
+        let probes: Vec<f32> = self.profile.frequencies();
+        let tone_size: usize = pulses.tone_size().max(1);
+        let burst: usize = self.burst_duration();
+        let burst_size: usize = sample_size(sample_rate, burst);
+
+        let lead: usize = sample_size(sample_rate, LEAD_SILENCE.as_micros() as usize);
+        let noise_peak: f32 = peak(&samples[..lead.min(samples.len())]);
+        let onset_level: f32 = (2.0 * noise_peak).max(ONSET_FLOOR);
+        let onset: usize = lead.min(samples.len())
+            + samples[lead.min(samples.len())..]
+                .iter()
+                .position(|sample| sample.abs() > onset_level)?;
+
+        // The receiver normalizes each chunk and drops what sits under its floor
+        let analyze = |start: usize| -> Option<Vec<f32>> {
+            let chunk: &[f32] = samples.get(start..start + tone_size)?;
+            let scale: SampleScale = SampleScale::normalize_floor(chunk, 1.0, 0.1);
+            Some(magnitude.get_scaled_magnitudes(chunk, &scale, &probes))
+        };
+
+        let mut measurements: Vec<StepMeasurement> = Vec::with_capacity(self.steps.len());
+        for (idx, step) in self.steps.iter().enumerate() {
+            let start: usize = onset + sample_size(sample_rate, 2 * idx * burst);
+            let centre: usize = (start + burst_size / 2).saturating_sub(tone_size / 2);
+            let magnitudes: Vec<f32> = analyze(centre)?;
+            let noise: Vec<f32> = analyze(centre + burst_size)?;
+
+            let probe: usize = idx % probes.len();
+            let competitor: f32 = magnitudes
+                .iter()
+                .enumerate()
+                .filter(|(other, _)| *other != probe)
+                .fold(f32::NEG_INFINITY, |max, (_, magnitude)| max.max(*magnitude));
+            measurements.push(StepMeasurement {
+                step: *step,
+                peak: peak(samples.get(start..start + burst_size)?),
+                magnitude: magnitudes[probe],
+                competitor,
+                noise: noise.iter().fold(f32::NEG_INFINITY, |max, m| max.max(*m)),
+            });
+        }
+        Some(measurements)
+    }
+
+    // The gain sits `CALIBRATION_HEADROOM` above the quietest level at which
+    // every tone was clean. Tones caught off their peak read lower than the
+    // steady bursts, so the threshold is as wide as it can be while the
+    // strongest rival heard at that gain or louder stays a margin outside it
+    pub fn recommend(&self, measurements: &[StepMeasurement]) -> Option<Calibration> {
+        let clean_levels: Vec<f32> = CALIBRATION_LEVELS
+            .iter()
+            .copied()
+            .filter(|level| {
+                let mut steps = measurements.iter().filter(|m| m.step.level == *level);
+                steps.clone().count() > 0 && steps.all(|m| m.is_clean())
+            })
+            .collect();
+        let quietest: f32 = clean_levels.iter().copied().reduce(f32::min)?;
+        let loudest: f32 = clean_levels.iter().copied().reduce(f32::max)?;
+        let gain: f32 = (quietest * CALIBRATION_HEADROOM).min(loudest);
+
+        let rival: f32 = measurements
+            .iter()
+            .filter(|m| m.step.level >= gain && m.is_clean())
+            .fold(-ADAPTIVE_THRESHOLD_MAX, |max, m| {
+                max.max(m.competitor).max(m.noise)
+            });
+        let threshold: f32 = -rival - CALIBRATION_MARGIN_DB;
+        Some(Calibration {
+            threshold: threshold.clamp(ADAPTIVE_THRESHOLD_MIN, ADAPTIVE_THRESHOLD_MAX),
+            gain,
+        })
+    }
+}
+
+impl Staircase {
+    fn burst_duration(&self) -> usize {
+        self.profile.pulses.tone.as_micros::<usize>() * CALIBRATION_BURST_TONES
+    }
+}
+
+// Plays the staircase on the default output while recording the default
+// input; `timeout` is how long past the staircase to keep recording for it
+// to arrive
+#[cfg(feature = "device")]
+pub fn calibrate(profile: Profile, timeout: Duration) -> Result<CalibrationReport, WavetrxError> {
+    let host: Host = cpal::default_host();
+    let output_device: Device = host
+        .default_output_device()
+        .ok_or_else(|| WavetrxError::DeviceError("No output device available".to_string()))?;
+    let input_device: Device = host
+        .default_input_device()
+        .ok_or_else(|| WavetrxError::DeviceError("No input device available".to_string()))?;
+    let output_config: StreamConfig = output_device.default_output_config()?.into();
+    let input_config: StreamConfig = input_device.default_input_config()?.into();
+
+    let tx_spec: AudioSpec =
+        AudioSpec::new(output_config.sample_rate.0, 32, 1, SampleEncoding::F32);
+    let rx_spec: AudioSpec = AudioSpec::new(input_config.sample_rate.0, 32, 1, SampleEncoding::F32);
+    let staircase: Staircase = Staircase::new(profile);
+    let samples: Vec<f32> = staircase.render(&tx_spec)?;
+    let duration: Duration = tx_spec.sample_timestamp(samples.len()) + timeout;
+
+    let channels: usize = (input_config.channels as usize).max(1);
+    let mut player: OutputPlayer = OutputPlayer::new(output_device, output_config, tx_spec);
+    let mut recorder: InputRecorder = InputRecorder::new(input_device, input_config);
+    player.play()?;
+    recorder.record()?;
+    player.add_samples(NormSamples::from_vec(samples));
+
+    let started: Instant = Instant::now();
+    let mut recorded: Vec<f32> = Vec::new();
+    while started.elapsed() < duration {
+        match recorder.take_frame() {
+            Some(frame) => recorded.extend(frame.0.into_iter().step_by(channels)),
+            None => sleep(POLL_INTERVAL),
+        }
+    }
+
+    let measurements: Vec<StepMeasurement> =
+        staircase.measure(&recorded, &rx_spec).unwrap_or_default();
+    let calibration: Option<Calibration> = staircase.recommend(&measurements);
+    Ok(CalibrationReport {
+        measurements,
+        calibration,
+    })
+}
+
+fn peak(samples: &[f32]) -> f32 {
+    samples
+        .iter()
+        .fold(0.0, |peak: f32, sample| peak.max(sample.abs()))
+}
+
+#[test]
+fn test_calibration() {
+    use crate::audio::types::NormSamples;
+    use crate::audio::types::SampleEncoding;
+    use crate::protocol::rx::Receiver;
+    use crate::protocol::tx::Transmitter;
+    use crate::sim::NoiseSource;
+    use crate::utils::get_fast_profile;
+
+    let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+    let profile: Profile = get_fast_profile();
+    let staircase: Staircase = Staircase::new(profile);
+    let played: Vec<f32> = staircase.render(&spec).unwrap();
+
+    // A quiet room 30 ms away that loses 40% of the level on the way
+    let mut noise: NoiseSource = NoiseSource::new(5);
+    let mut channel = |samples: &[f32]| -> Vec<f32> {
+        let mut recorded: Vec<f32> = (0..1_440).map(|_| 0.002 * noise.gaussian()).collect();
+        recorded.extend(
+            samples
+                .iter()
+                .map(|sample| 0.6 * sample + 0.002 * noise.gaussian()),
+        );
+        recorded
+    };
+    let measurements: Vec<StepMeasurement> = staircase.measure(&channel(&played), &spec).unwrap();
+    assert_eq!(measurements.len(), staircase.steps().len());
+    assert!(measurements
+        .iter()
+        .all(|m| (m.peak - 0.6 * m.step.level).abs() < 0.01));
+
+    // Arrivals under the receiver's floor are lost, so the quietest levels fail
+    let quiet: &StepMeasurement = measurements.last().unwrap();
+    assert!(quiet.step.level * 0.6 < 0.1 && !quiet.is_clean());
+    let calibration: Calibration = staircase.recommend(&measurements).unwrap();
+    // A frame sent at the recommended gain decodes with the tuned threshold
+    let tuned: Profile = calibration.apply(profile);
+    assert_eq!(tuned.threshold, calibration.threshold);
+    let frame: Vec<f32> = Transmitter::new(&tuned, &spec)
+        .with_gain(calibration.gain)
+        .create(b"tuned")
+        .unwrap();
+    let mut receiver: Receiver = Receiver::new(tuned, spec);
+    receiver.add_samples(&mut NormSamples::from_vec(channel(&frame)));
+    receiver.analyze_full_buffer();
+    let messages: Vec<Vec<u8>> = receiver
+        .take_messages()
+        .into_iter()
+        .map(|message| message.into_data())
+        .collect();
+    assert_eq!(messages, vec![b"tuned".to_vec()]);
+}
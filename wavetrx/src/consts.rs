@@ -24,10 +24,127 @@ impl FastProfile {
     pub const BIT_TONE_HIGH: f32 = 5_000.0;
     pub const BIT_TONE_LOW: f32 = 1_000.0;
 
+    pub const SYMBOL_TONES_4: [f32; 4] = [1_000.0, 2_000.0, 4_000.0, 5_000.0];
+    pub const SYMBOL_TONES_8: [f32; 8] = [
+        1_000.0, 2_000.0, 4_000.0, 5_000.0, 6_000.0, 8_000.0, 10_000.0, 11_000.0,
+    ];
+
     pub const PULSE_LENGTH_US: Duration = Duration::from_micros(1000);
     pub const PULSE_GAP_US: Duration = Duration::from_micros(100);
 }
 
+// Keeps every tone inside 17-20 kHz, which stays below Nyquist on 44.1 kHz
+// hardware; the longer pulses make up for speakers rolling off up there
+pub struct UltrasonicProfile;
+
+impl UltrasonicProfile {
+    pub const MARKER_TONE_START: f32 = 19_000.0;
+    pub const MARKER_TONE_END: f32 = 20_000.0;
+    pub const MARKER_TONE_NEXT: f32 = 17_000.0;
+
+    pub const BIT_TONE_HIGH: f32 = 18_500.0;
+    pub const BIT_TONE_LOW: f32 = 17_500.0;
+
+    pub const PULSE_LENGTH_US: Duration = Duration::from_micros(4_000);
+    pub const PULSE_GAP_US: Duration = Duration::from_micros(1_000);
+
+    // Recommended detection window; attenuated highs need more headroom than DB_THRESHOLD
+    pub const DB_THRESHOLD: f32 = 12.0;
+}
+
 pub const LP_FILTER: f32 = 18_000.0;
 pub const HP_FILTER: f32 = 200.0;
 pub const DB_THRESHOLD: f32 = 8.0;
+pub const MAX_CHANNELS: usize = 8;
+// Timing recovery searches up to 1/8th of a tone either side of the stride
+pub const TIMING_RECOVERY_DIVISOR: usize = 8;
+// Start marker search: steps past the best match before it is taken, Start
+// tone cycles skipped while nothing matches, and tones buffered beforehand
+pub const START_MAX_FAILS: usize = 5;
+pub const START_SKIP_CYCLES: usize = 8;
+pub const START_WARMUP_TONES: usize = 8;
+// Recently seen (source, sequence) pairs kept for duplicate suppression
+pub const SEQUENCE_HISTORY: usize = 16;
+// Audio time after which a remembered pair no longer marks a duplicate
+pub const SEQUENCE_WINDOW: Duration = Duration::from_secs(30);
+// Adaptive thresholds sit this far above the estimated noise floor
+pub const NOISE_MARGIN_DB: f32 = 6.0;
+pub const NOISE_SMOOTHING: f32 = 0.1;
+pub const ADAPTIVE_THRESHOLD_MIN: f32 = 3.0;
+pub const ADAPTIVE_THRESHOLD_MAX: f32 = 30.0;
+// Calibration staircase output levels, loudest first, and the lead a tone
+// needs over every other profile tone and the room noise to count as clean
+pub const CALIBRATION_LEVELS: [f32; 5] = [1.0, 0.5, 0.25, 0.125, 0.0625];
+pub const CALIBRATION_MARGIN_DB: f32 = 6.0;
+// Tone lengths per staircase burst, and the gain kept above the quietest clean level
+pub const CALIBRATION_BURST_TONES: usize = 4;
+pub const CALIBRATION_HEADROOM: f32 = 2.0;
+// Input levels in dBFS that open and close the live receiver squelch
+pub const SQUELCH_OPEN_DB: f32 = -50.0;
+pub const SQUELCH_CLOSE_DB: f32 = -55.0;
+pub const LIMITER_THRESHOLD: f32 = 0.9;
+// Capture archive files are closed after this long, and captured frames
+// queued for the archive thread beyond this many are dropped
+pub const ARCHIVE_ROTATION: Duration = Duration::from_secs(600);
+pub const ARCHIVE_QUEUE: usize = 256;
+// Wait between attempts to reopen a lost capture device
+pub const DEVICE_RECONNECT_INTERVAL: Duration = Duration::from_secs(1);
+pub const SPECTROGRAM_WINDOW: usize = 1024;
+pub const SPECTROGRAM_HOP: usize = 256;
+pub const VALIDATION_SAMPLE_RATE: u32 = 48_000;
+// ITU-T Q.23 row (low group) and column (high group) frequencies
+pub const DTMF_LOW_HZ: [f32; 4] = [697.0, 770.0, 852.0, 941.0];
+pub const DTMF_HIGH_HZ: [f32; 4] = [1_209.0, 1_336.0, 1_477.0, 1_633.0];
+pub const DTMF_TONE: Duration = Duration::from_millis(50);
+pub const DTMF_GAP: Duration = Duration::from_millis(50);
+// Detection windows must fit twice into a tone so one is always fully covered
+pub const DTMF_WINDOW: Duration = Duration::from_millis(20);
+pub const DTMF_MIN_DB: f32 = -30.0;
+pub const DTMF_MARGIN_DB: f32 = 6.0;
+pub const MORSE_WPM: f32 = 20.0;
+pub const MORSE_FREQUENCY: f32 = 700.0;
+// Envelope resolution of the decoder, in windows per dot
+pub const MORSE_WINDOWS_PER_UNIT: usize = 4;
+pub const MORSE_MIN_DB: f32 = -30.0;
+// Subcarriers sit on adjacent FFT bins of a symbol, from the bin nearest the base
+pub const OFDM_BASE_HZ: f32 = 2_000.0;
+pub const OFDM_SUBCARRIERS: usize = 16;
+pub const OFDM_MAX_SUBCARRIERS: usize = 16;
+pub const OFDM_SYMBOL: Duration = Duration::from_millis(5);
+// Cyclic prefix ahead of each symbol; absorbs sync error and short echoes
+pub const OFDM_GUARD: Duration = Duration::from_millis(1);
+pub const OFDM_CHIRP: Duration = Duration::from_millis(20);
+// A subcarrier is on when it reaches this close to its reference level
+pub const OFDM_THRESHOLD_DB: f32 = 6.0;
+// Carrier for FSK profiles handed to the DBPSK backend
+pub const DBPSK_CARRIER_HZ: f32 = 3_000.0;
+// Sync chirp sweeps this far either side of the carrier
+pub const DBPSK_CHIRP_SPAN_HZ: f32 = 1_000.0;
+pub const DBPSK_CHIRP: Duration = Duration::from_millis(20);
+// File bytes per transfer fragment; each fragment is sent as its own message
+pub const TRANSFER_CHUNK_SIZE: usize = 64;
+// A profile tone at this level in dBFS marks the channel busy for carrier sense
+pub const CARRIER_SENSE_DB: f32 = -40.0;
+// Most recent audio checked before each transmission
+pub const CARRIER_LISTEN: Duration = Duration::from_millis(50);
+pub const CARRIER_BACKOFF_MIN: Duration = Duration::from_millis(20);
+pub const CARRIER_BACKOFF_MAX: Duration = Duration::from_millis(200);
+pub const CARRIER_MAX_WAIT: Duration = Duration::from_secs(5);
+// How often a waiter rechecks a playback queue it is waiting to drain
+pub const SAMPLE_BUFFER_POLL: Duration = Duration::from_millis(1);
+// A wait on the player fails once its output callback has been silent this long
+pub const PLAYER_STALL: Duration = Duration::from_secs(2);
+// Metres per second in air at 20 °C
+pub const SPEED_OF_SOUND: f32 = 343.0;
+// Sample clock mismatch assumed between two stations when ranging
+pub const RANGE_CLOCK_PPM: f32 = 100.0;
+// How long each step of a range measurement waits for the far end
+pub const RANGE_TIMEOUT: Duration = Duration::from_secs(3);
+// Share of a window's energy the watched marker tone needs to wake the receiver
+pub const WAKE_SHARE: f32 = 0.25;
+// How far the watched marker has to read above the frequencies beside it
+pub const WAKE_CONTRAST_DB: f32 = 10.0;
+// Windows quieter than this in dBFS never wake the receiver
+pub const WAKE_FLOOR_DB: f32 = -70.0;
+// Quiet time after which a woken receiver goes back to sleep
+pub const WAKE_HOLD: Duration = Duration::from_secs(1);
@@ -0,0 +1,132 @@
+#[cfg(feature = "device")]
+use cpal::traits::DeviceTrait;
+#[cfg(feature = "device")]
+use cpal::traits::HostTrait;
+#[cfg(feature = "device")]
+use cpal::Device;
+#[cfg(feature = "device")]
+use cpal::Host;
+#[cfg(feature = "device")]
+use cpal::StreamConfig;
+
+use crate::error::WavetrxError;
+use crate::protocol::profile::Profile;
+use crate::protocol::rx::DecodedMessage;
+#[cfg(feature = "device")]
+use crate::protocol::rx::LiveReceiver;
+#[cfg(feature = "device")]
+use crate::protocol::transceiver::Transceiver;
+#[cfg(feature = "device")]
+use crate::protocol::tx::LiveTransmitter;
+
+pub trait ModemBackend {
+    fn set_profile(&mut self, profile: Profile) -> Result<(), WavetrxError>;
+    fn send(&mut self, data: &[u8]) -> Result<(), WavetrxError>;
+    fn poll(&mut self) -> Vec<DecodedMessage>;
+}
+
+// Lets a layer such as `ReliableTransmitter` borrow a backend that another
+// layer owns
+impl<B> ModemBackend for &mut B
+where
+    B: ModemBackend + ?Sized,
+{
+    fn set_profile(&mut self, profile: Profile) -> Result<(), WavetrxError> {
+        (**self).set_profile(profile)
+    }
+
+    fn send(&mut self, data: &[u8]) -> Result<(), WavetrxError> {
+        (**self).send(data)
+    }
+
+    fn poll(&mut self) -> Vec<DecodedMessage> {
+        (**self).poll()
+    }
+}
+
+#[cfg(feature = "device")]
+pub struct AudioBackend {
+    transmitter: LiveTransmitter,
+    receiver: LiveReceiver,
+}
+
+#[cfg(feature = "device")]
+impl AudioBackend {
+    pub fn new(
+        profile: Profile,
+        output: (Device, StreamConfig),
+        input: (Device, StreamConfig),
+    ) -> Self {
+        let (output_device, output_config): (Device, StreamConfig) = output;
+        let (input_device, input_config): (Device, StreamConfig) = input;
+
+        let transmitter: LiveTransmitter =
+            LiveTransmitter::new(profile, output_device, output_config);
+        let receiver: LiveReceiver = LiveReceiver::new(profile, input_device, input_config);
+
+        AudioBackend {
+            transmitter,
+            receiver,
+        }
+    }
+
+    pub fn from_default_devices(profile: Profile) -> Result<Self, WavetrxError> {
+        let host: Host = cpal::default_host();
+        let output_device: Device = host
+            .default_output_device()
+            .ok_or_else(|| WavetrxError::DeviceError("No output device available".to_string()))?;
+        let input_device: Device = host
+            .default_input_device()
+            .ok_or_else(|| WavetrxError::DeviceError("No input device available".to_string()))?;
+
+        let output_config: StreamConfig = output_device.default_output_config()?.into();
+        let input_config: StreamConfig = input_device.default_input_config()?.into();
+
+        let output: (Device, StreamConfig) = (output_device, output_config);
+        let input: (Device, StreamConfig) = (input_device, input_config);
+        Ok(AudioBackend::new(profile, output, input))
+    }
+
+    pub fn start(&mut self) -> Result<(), WavetrxError> {
+        self.transmitter.start()?;
+        self.receiver.start()?;
+        Ok(())
+    }
+
+    pub fn swap_input(&mut self, device: Device, config: StreamConfig) -> Result<(), WavetrxError> {
+        self.receiver.swap_input(device, config)
+    }
+}
+
+#[cfg(feature = "device")]
+impl ModemBackend for AudioBackend {
+    fn set_profile(&mut self, profile: Profile) -> Result<(), WavetrxError> {
+        self.transmitter.set_profile(profile);
+        self.receiver.set_profile(profile);
+        Ok(())
+    }
+
+    fn send(&mut self, data: &[u8]) -> Result<(), WavetrxError> {
+        self.transmitter.send(data)
+    }
+
+    fn poll(&mut self) -> Vec<DecodedMessage> {
+        self.receiver.poll()
+    }
+}
+
+#[cfg(feature = "device")]
+impl ModemBackend for Transceiver {
+    fn set_profile(&mut self, profile: Profile) -> Result<(), WavetrxError> {
+        Transceiver::set_profile(self, profile);
+        Ok(())
+    }
+
+    fn send(&mut self, data: &[u8]) -> Result<(), WavetrxError> {
+        Transceiver::send(self, data)
+    }
+
+    fn poll(&mut self) -> Vec<DecodedMessage> {
+        Transceiver::poll(self)
+    }
+}
@@ -0,0 +1,68 @@
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AtCommand {
+    Attention,
+    Identify,
+    QueryProfile,
+    SetProfile(String),
+    Send(Vec<u8>),
+    QueryStatus,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AtParseError {
+    NotAt,
+    Unknown(String),
+    InvalidArgument(String),
+}
+
+impl AtCommand {
+    pub fn parse(line: &str) -> Result<Self, AtParseError> {
+        let line: &str = line.trim();
+        let body: &str = match line
+            .get(..2)
+            .is_some_and(|prefix| prefix.eq_ignore_ascii_case("AT"))
+        {
+            true => &line[2..],
+            false => return Err(AtParseError::NotAt),
+        };
+        let (name, argument): (&str, Option<&str>) = match body.split_once('=') {
+            Some((name, argument)) => (name, Some(argument)),
+            None => (body, None),
+        };
+
+        match (name.to_ascii_uppercase().as_str(), argument) {
+            ("", None) => Ok(AtCommand::Attention),
+            ("I", None) => Ok(AtCommand::Identify),
+            ("+PROFILE?", None) => Ok(AtCommand::QueryProfile),
+            ("+PROFILE", Some(profile)) => Ok(AtCommand::SetProfile(profile.trim().to_string())),
+            ("+SEND", Some(text)) => Ok(AtCommand::Send(text.as_bytes().to_vec())),
+            ("+SENDHEX", Some(hex)) => match decode_hex(hex.trim()) {
+                Some(data) => Ok(AtCommand::Send(data)),
+                None => Err(AtParseError::InvalidArgument(hex.to_string())),
+            },
+            ("+STATUS?", None) => Ok(AtCommand::QueryStatus),
+            _ => Err(AtParseError::Unknown(body.to_string())),
+        }
+    }
+}
+
+pub fn encode_hex(data: &[u8]) -> String {
+    let mut hex: String = String::with_capacity(data.len() * 2);
+    for byte in data {
+        hex.push_str(&format!("{:02X}", byte));
+    }
+    hex
+}
+
+pub fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) || !hex.is_ascii() {
+        return None;
+    }
+
+    let mut data: Vec<u8> = Vec::with_capacity(hex.len() / 2);
+    for idx in (0..hex.len()).step_by(2) {
+        let byte: u8 = u8::from_str_radix(&hex[idx..idx + 2], 16).ok()?;
+        data.push(byte);
+    }
+    Some(data)
+}
@@ -0,0 +1,12 @@
+mod backend;
+mod command;
+mod server;
+
+#[cfg(feature = "device")]
+pub use backend::AudioBackend;
+pub use backend::ModemBackend;
+pub use command::AtCommand;
+pub use command::AtParseError;
+pub use server::serve_stdio;
+pub use server::serve_tcp;
+pub use server::ControlServer;
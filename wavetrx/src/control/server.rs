@@ -0,0 +1,224 @@
+use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Write;
+use std::net::TcpListener;
+use std::net::TcpStream;
+use std::net::ToSocketAddrs;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
+use std::sync::mpsc::TryRecvError;
+use std::thread;
+use std::thread::sleep;
+use std::time::Duration;
+
+use super::backend::ModemBackend;
+use super::command::encode_hex;
+use super::command::AtCommand;
+
+use crate::protocol::profile::Profile;
+use crate::protocol::rx::DecodedMessage;
+use crate::utils::get_profile_by_name;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+pub struct ControlServer<B> {
+    backend: B,
+    profile_name: String,
+    sent: usize,
+    received: usize,
+}
+
+impl<B> ControlServer<B>
+where
+    B: ModemBackend,
+{
+    pub fn new(backend: B, profile_name: &str) -> Self {
+        let profile_name: String = profile_name.to_string();
+        let sent: usize = 0;
+        let received: usize = 0;
+        ControlServer {
+            backend,
+            profile_name,
+            sent,
+            received,
+        }
+    }
+
+    pub fn handle_line(&mut self, line: &str) -> Vec<String> {
+        let command: AtCommand = match AtCommand::parse(line) {
+            Ok(command) => command,
+            Err(_) => return vec!["ERROR".to_string()],
+        };
+
+        match self.handle_command(command) {
+            Some(mut lines) => {
+                lines.push("OK".to_string());
+                lines
+            }
+            None => vec!["ERROR".to_string()],
+        }
+    }
+
+    pub fn poll_unsolicited(&mut self) -> Vec<String> {
+        let messages: Vec<DecodedMessage> = self.backend.poll();
+        self.received += messages.len();
+
+        let mut lines: Vec<String> = Vec::new();
+        for message in messages {
+            let data: &[u8] = message.data();
+            lines.push(format!("+RX: {},{}", data.len(), encode_hex(data)));
+        }
+        lines
+    }
+
+    pub fn run<R, W>(&mut self, reader: R, writer: &mut W) -> io::Result<()>
+    where
+        R: BufRead + Send + 'static,
+        W: Write,
+    {
+        let (sender, receiver) = mpsc::channel::<String>();
+        thread::spawn(move || {
+            for line in reader.lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        loop {
+            if !self.process_pending(&receiver, writer)? {
+                return Ok(());
+            }
+
+            for line in self.poll_unsolicited() {
+                writeln!(writer, "{}", line)?;
+            }
+            writer.flush()?;
+            sleep(POLL_INTERVAL);
+        }
+    }
+}
+
+impl<B> ControlServer<B>
+where
+    B: ModemBackend,
+{
+    fn handle_command(&mut self, command: AtCommand) -> Option<Vec<String>> {
+        match command {
+            AtCommand::Attention => Some(Vec::new()),
+            AtCommand::Identify => Some(vec![format!("wavetrx {}", env!("CARGO_PKG_VERSION"))]),
+            AtCommand::QueryProfile => Some(vec![format!("+PROFILE: {}", self.profile_name)]),
+            AtCommand::SetProfile(name) => {
+                let profile: Profile = get_profile_by_name(&name).ok()?;
+                self.backend.set_profile(profile).ok()?;
+                self.profile_name = name;
+                Some(Vec::new())
+            }
+            AtCommand::Send(data) => {
+                self.backend.send(&data).ok()?;
+                self.sent += 1;
+                Some(Vec::new())
+            }
+            AtCommand::QueryStatus => Some(vec![format!(
+                "+STATUS: PROFILE={},TX={},RX={}",
+                self.profile_name, self.sent, self.received
+            )]),
+        }
+    }
+
+    fn process_pending<W>(
+        &mut self,
+        receiver: &Receiver<String>,
+        writer: &mut W,
+    ) -> io::Result<bool>
+    where
+        W: Write,
+    {
+        loop {
+            match receiver.try_recv() {
+                Ok(line) => {
+                    if line.trim().is_empty() {
+                        continue;
+                    }
+                    for response in self.handle_line(&line) {
+                        writeln!(writer, "{}", response)?;
+                    }
+                }
+                Err(TryRecvError::Empty) => return Ok(true),
+                Err(TryRecvError::Disconnected) => return Ok(false),
+            }
+        }
+    }
+}
+
+pub fn serve_stdio<B>(server: &mut ControlServer<B>) -> io::Result<()>
+where
+    B: ModemBackend,
+{
+    let reader: BufReader<io::Stdin> = BufReader::new(io::stdin());
+    let mut writer: io::Stdout = io::stdout();
+    server.run(reader, &mut writer)
+}
+
+pub fn serve_tcp<B, A>(server: &mut ControlServer<B>, addr: A) -> io::Result<()>
+where
+    B: ModemBackend,
+    A: ToSocketAddrs,
+{
+    let listener: TcpListener = TcpListener::bind(addr)?;
+    for stream in listener.incoming() {
+        let mut stream: TcpStream = stream?;
+        let reader: BufReader<TcpStream> = BufReader::new(stream.try_clone()?);
+        server.run(reader, &mut stream)?;
+    }
+    Ok(())
+}
+
+#[test]
+fn test_control_server() {
+    use crate::error::WavetrxError;
+
+    struct LoopbackBackend {
+        pending: Vec<DecodedMessage>,
+    }
+
+    impl ModemBackend for LoopbackBackend {
+        fn set_profile(&mut self, _: Profile) -> Result<(), WavetrxError> {
+            Ok(())
+        }
+
+        fn send(&mut self, data: &[u8]) -> Result<(), WavetrxError> {
+            let message: DecodedMessage = DecodedMessage::new(data.into(), 0, 0);
+            self.pending.push(message);
+            Ok(())
+        }
+
+        fn poll(&mut self) -> Vec<DecodedMessage> {
+            std::mem::take(&mut self.pending)
+        }
+    }
+
+    let backend: LoopbackBackend = LoopbackBackend {
+        pending: Vec::new(),
+    };
+    let mut server: ControlServer<LoopbackBackend> = ControlServer::new(backend, "fast");
+
+    assert_eq!(server.handle_line("AT"), vec!["OK"]);
+    assert_eq!(server.handle_line("at+profile=default"), vec!["OK"]);
+    assert_eq!(
+        server.handle_line("AT+PROFILE?"),
+        vec!["+PROFILE: default", "OK"]
+    );
+    assert_eq!(server.handle_line("AT+PROFILE=missing"), vec!["ERROR"]);
+    assert_eq!(server.handle_line("AT+SENDHEX=00FF"), vec!["OK"]);
+    assert_eq!(server.handle_line("HELLO"), vec!["ERROR"]);
+    assert_eq!(server.handle_line("€AT"), vec!["ERROR"]);
+    assert_eq!(server.handle_line("Aé"), vec!["ERROR"]);
+
+    assert_eq!(server.poll_unsolicited(), vec!["+RX: 2,00FF"]);
+    assert_eq!(
+        server.handle_line("AT+STATUS?"),
+        vec!["+STATUS: PROFILE=default,TX=1,RX=1", "OK"]
+    );
+}
@@ -0,0 +1,106 @@
+use core::f32::consts;
+
+use super::math;
+
+// Single-bin DFT with the coefficient worked out once, for callers that probe
+// the same tones on every block
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Goertzel {
+    bin: usize,
+    size: usize,
+    coeff: f32,
+}
+
+impl Goertzel {
+    pub fn new(frequency: f32, sample_rate: u32, size: usize) -> Self {
+        let bin: usize = frequency_bin(frequency, sample_rate, size);
+        let coeff: f32 = coefficient(bin, size);
+        Goertzel { bin, size, coeff }
+    }
+
+    pub fn bin(&self) -> usize {
+        self.bin
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    // Blocks of another length fall back to a freshly computed coefficient
+    pub fn magnitude_db(&self, samples: &[f32]) -> f32 {
+        if samples.len() != self.size {
+            return goertzel_db(samples, self.bin);
+        }
+        recurse(samples.iter().copied(), self.coeff)
+    }
+}
+
+// Nearest bin to `frequency` in a DFT of `size` samples
+pub fn frequency_bin(frequency: f32, sample_rate: u32, size: usize) -> usize {
+    let normalized_frequency: f32 = frequency / sample_rate as f32;
+    let scaled_frequency: f32 = size as f32 * normalized_frequency;
+    let biased_frequency: f32 = 0.5 + scaled_frequency;
+    biased_frequency as usize
+}
+
+// Level of bin `bin` across the whole block, in dB relative to full scale
+pub fn goertzel_db(samples: &[f32], bin: usize) -> f32 {
+    recurse(samples.iter().copied(), coefficient(bin, samples.len()))
+}
+
+// As `goertzel_db`, for a block of `size` samples produced on the fly
+pub fn goertzel_db_iter<I>(samples: I, size: usize, bin: usize) -> f32
+where
+    I: IntoIterator<Item = f32>,
+{
+    recurse(samples, coefficient(bin, size))
+}
+
+fn coefficient(bin: usize, size: usize) -> f32 {
+    let w: f32 = 2.0 * consts::PI * bin as f32 / size as f32;
+    2.0 * math::cos(w)
+}
+
+fn recurse<I>(samples: I, coeff: f32) -> f32
+where
+    I: IntoIterator<Item = f32>,
+{
+    let mut q1: f32 = 0.0;
+    let mut q2: f32 = 0.0;
+    let mut size: usize = 0;
+
+    for sample in samples {
+        let q0: f32 = coeff * q1 - q2 + sample;
+        q2 = q1;
+        q1 = q0;
+        size += 1;
+    }
+
+    let magnitude: f32 = math::sqrt((q1 * q1) + (q2 * q2) - (q1 * q2 * coeff));
+    let normalization_factor: f32 = 2.0 / size as f32;
+    let magnitude: f32 = magnitude * normalization_factor;
+    20.0 * math::log10(magnitude)
+}
+
+#[test]
+fn test_goertzel_tone_levels() {
+    use alloc::vec::Vec;
+
+    use super::tone::append_tone;
+
+    let sample_rate: u32 = 48_000;
+    let size: usize = 480;
+    let mut samples: Vec<f32> = Vec::new();
+    append_tone(&mut samples, 5_000.0, sample_rate, 10_000);
+    assert_eq!(samples.len(), size);
+
+    let tone: Goertzel = Goertzel::new(5_000.0, sample_rate, size);
+    let other: Goertzel = Goertzel::new(1_000.0, sample_rate, size);
+    assert_eq!(tone.bin(), 50);
+    assert!(tone.magnitude_db(&samples).abs() < 0.5);
+    assert!(other.magnitude_db(&samples) < -40.0);
+    assert_eq!(
+        tone.magnitude_db(&samples),
+        goertzel_db(&samples, tone.bin())
+    );
+}
@@ -0,0 +1,41 @@
+// Float intrinsics live in `std`; without it they come from libm. Going
+// through `std` when available keeps the output bit-identical to before
+#[cfg(feature = "std")]
+pub fn sin(x: f32) -> f32 {
+    x.sin()
+}
+
+#[cfg(not(feature = "std"))]
+pub fn sin(x: f32) -> f32 {
+    libm::sinf(x)
+}
+
+#[cfg(feature = "std")]
+pub fn cos(x: f32) -> f32 {
+    x.cos()
+}
+
+#[cfg(not(feature = "std"))]
+pub fn cos(x: f32) -> f32 {
+    libm::cosf(x)
+}
+
+#[cfg(feature = "std")]
+pub fn sqrt(x: f32) -> f32 {
+    x.sqrt()
+}
+
+#[cfg(not(feature = "std"))]
+pub fn sqrt(x: f32) -> f32 {
+    libm::sqrtf(x)
+}
+
+#[cfg(feature = "std")]
+pub fn log10(x: f32) -> f32 {
+    x.log10()
+}
+
+#[cfg(not(feature = "std"))]
+pub fn log10(x: f32) -> f32 {
+    libm::log10f(x)
+}
@@ -0,0 +1,23 @@
+// Allocation-only core of the modem: the receive state machine, the Goertzel
+// kernel and tone synthesis. Builds without `std`, so a microcontroller can
+// feed it I2S blocks directly; everything else in the crate needs `std`
+mod goertzel;
+mod math;
+mod resolver;
+mod tone;
+
+pub use goertzel::frequency_bin;
+pub use goertzel::goertzel_db;
+pub use goertzel::goertzel_db_iter;
+pub use goertzel::Goertzel;
+pub use resolver::RxMagnitudes;
+pub use resolver::RxOutput;
+pub use resolver::RxResolver;
+pub use resolver::RxState;
+pub use resolver::Timing;
+pub use tone::append_silence;
+pub use tone::append_tone;
+pub use tone::linear_fade;
+pub use tone::sample_size;
+pub use tone::sine;
+pub use tone::sine_fade;
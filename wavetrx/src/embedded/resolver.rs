@@ -1,4 +1,7 @@
+use alloc::vec::Vec;
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RxState {
     Start,
     End,
@@ -45,51 +48,95 @@ impl RxState {
             RxState::Start => magnitudes.start,
             RxState::End => magnitudes.end,
             RxState::Next => magnitudes.next,
-            RxState::Bit => magnitudes.prominent_bit_magnitude(),
+            RxState::Bit => magnitudes.prominent_symbol_magnitude(),
             RxState::Unset => return false,
         };
         magnitudes.within_threshold(value)
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+// Whether a Next marker separates consecutive bits
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Timing {
+    Marked,
+    Gapless { resync: usize },
+}
+
+impl Timing {
+    pub fn is_gapless(&self) -> bool {
+        matches!(self, Timing::Gapless { .. })
+    }
+
+    pub fn requires_next(&self, bit_idx: usize) -> bool {
+        match self {
+            Timing::Marked => true,
+            Timing::Gapless { resync } => *resync != 0 && (bit_idx + 1).is_multiple_of(*resync),
+        }
+    }
+}
+
+// Confidence is the dB margin of the chosen symbol over the runner-up tone
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum RxOutput {
-    Bit(u8),
+    Symbol { value: u8, confidence: f32 },
     End,
     Error,
     Undefined,
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct RxMagnitudes {
     pub start: f32,
     pub end: f32,
     pub next: f32,
-    pub high: f32,
-    pub low: f32,
+    pub symbols: Vec<f32>,
     pub threshold: f32,
 }
 
 impl RxMagnitudes {
-    pub fn new(start: f32, end: f32, next: f32, high: f32, low: f32, threshold: f32) -> Self {
+    pub fn new(start: f32, end: f32, next: f32, symbols: Vec<f32>, threshold: f32) -> Self {
         RxMagnitudes {
             start,
             end,
             next,
-            high,
-            low,
+            symbols,
             threshold,
         }
     }
 
-    pub fn prominent_bit(&self) -> u8 {
-        (self.high > self.low) as u8
+    pub fn prominent_symbol(&self) -> u8 {
+        let mut prominent: usize = 0;
+        for (idx, magnitude) in self.symbols.iter().enumerate() {
+            if *magnitude > self.symbols[prominent] {
+                prominent = idx;
+            }
+        }
+        prominent as u8
     }
 
-    pub fn prominent_bit_magnitude(&self) -> f32 {
-        if self.prominent_bit() == 1 {
-            self.high
+    pub fn prominent_symbol_magnitude(&self) -> f32 {
+        match self.symbols.get(self.prominent_symbol() as usize) {
+            Some(magnitude) => *magnitude,
+            None => f32::NEG_INFINITY,
+        }
+    }
+
+    pub fn symbol_confidence(&self) -> f32 {
+        let prominent: usize = self.prominent_symbol() as usize;
+        let runner_up: f32 = self
+            .symbols
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| *idx != prominent)
+            .map(|(_, magnitude)| *magnitude)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let margin: f32 = self.prominent_symbol_magnitude() - runner_up;
+        if margin.is_finite() {
+            margin
         } else {
-            self.low
+            0.0
         }
     }
 
@@ -98,7 +145,8 @@ impl RxMagnitudes {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RxMarker {
     marker: (RxState, RxState),
 }
@@ -139,40 +187,70 @@ impl RxMarker {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct RxResolver {
     c_marker: RxMarker,
     e_marker: RxMarker,
+    timing: Timing,
+    bit_count: usize,
 }
 
 impl RxResolver {
     pub fn new() -> Self {
+        Self::with_timing(Timing::Marked)
+    }
+
+    pub fn with_timing(timing: Timing) -> Self {
         let c_marker: RxMarker = RxMarker::with_expectation(RxState::Start);
         let e_marker: RxMarker = RxMarker::new();
+        let bit_count: usize = 0;
 
-        RxResolver { c_marker, e_marker }
+        RxResolver {
+            c_marker,
+            e_marker,
+            timing,
+            bit_count,
+        }
     }
 
     pub fn resolve(&mut self, magnitudes: &RxMagnitudes) -> RxOutput {
-        let initial_expectation: bool = self.evaluate_expectation(magnitudes);
+        let matched: Option<RxState> = self.evaluate_expectation(magnitudes);
+        let initial_expectation: bool = matched.is_some();
         let has_end: bool = self.evaluate_end(magnitudes);
 
         if let Some(resolve) = self.resolve_end(magnitudes, initial_expectation, has_end) {
             return resolve;
         }
 
-        if let Some(resolve) = self.resolve_expectation(magnitudes, initial_expectation) {
+        if let Some(resolve) = self.resolve_expectation(magnitudes, matched) {
             return resolve;
         }
 
         RxOutput::Undefined
     }
 
+    // For Start markers found outside the resolver, e.g. by matched filtering
+    pub fn assume_start(&mut self) {
+        self.c_marker.set_selection(RxState::Start);
+        self.c_marker.set_expectation(RxState::Next);
+    }
+
     pub fn reset(&mut self) {
         self.c_marker.unset_selection();
         self.c_marker.set_expectation(RxState::Start);
         self.e_marker.unset_selection();
         self.e_marker.unset_expectation();
+        self.bit_count = 0;
+    }
+
+    // The marker the next chunk is checked against
+    pub fn expectation(&self) -> RxState {
+        *self.c_marker.expectation()
+    }
+
+    pub fn bit_count(&self) -> usize {
+        self.bit_count
     }
 }
 
@@ -180,34 +258,42 @@ impl RxResolver {
     fn resolve_expectation(
         &mut self,
         magnitudes: &RxMagnitudes,
-        initial_expectation: bool,
+        matched: Option<RxState>,
     ) -> Option<RxOutput> {
-        if initial_expectation {
-            let selection: &RxState = self.c_marker.selection();
-            let expectation: &RxState = self.c_marker.expectation();
-
-            if selection.is_bit() && expectation.is_next() {
-                let bit: u8 = magnitudes.prominent_bit();
-                return Some(RxOutput::Bit(bit));
-            }
+        if let Some(RxState::Bit) = matched {
+            let value: u8 = magnitudes.prominent_symbol();
+            let confidence: f32 = magnitudes.symbol_confidence();
+            return Some(RxOutput::Symbol { value, confidence });
         }
         None
     }
 
-    fn evaluate_expectation(&mut self, magnitudes: &RxMagnitudes) -> bool {
-        let expectation: &RxState = self.c_marker.expectation();
+    fn evaluate_expectation(&mut self, magnitudes: &RxMagnitudes) -> Option<RxState> {
+        let expectation: RxState = *self.c_marker.expectation();
         if expectation.within_threshold(magnitudes) {
-            if expectation.is_start_or_bit() {
-                self.c_marker.set_selection(*expectation);
+            if expectation.is_start() {
+                self.c_marker.set_selection(expectation);
                 self.c_marker.set_expectation(RxState::Next);
+            } else if expectation.is_bit() {
+                self.c_marker.set_selection(expectation);
+                self.c_marker.set_expectation(self.expectation_after_bit());
+                self.bit_count += 1;
             } else if expectation.is_next() {
                 if self.c_marker.selection().is_start_or_bit() {
                     self.c_marker.set_expectation(RxState::Bit);
                 }
             }
-            return true;
+            return Some(expectation);
+        }
+        None
+    }
+
+    fn expectation_after_bit(&self) -> RxState {
+        if self.timing.requires_next(self.bit_count) {
+            RxState::Next
+        } else {
+            RxState::Bit
         }
-        false
     }
 
     fn resolve_end(
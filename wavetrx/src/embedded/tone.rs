@@ -0,0 +1,48 @@
+use alloc::vec::Vec;
+use core::f32::consts;
+
+use super::math;
+
+// Samples spanned by `duration` microseconds
+pub fn sample_size(sample_rate: u32, duration: usize) -> usize {
+    (sample_rate as usize * duration) / 1_000_000
+}
+
+pub fn sine(idx: usize, period: f32) -> f32 {
+    math::sin(2.0 * consts::PI * idx as f32 / period)
+}
+
+// Raised-cosine ramp over the first and last `fade_size` samples
+pub fn sine_fade(idx: usize, sample_size: usize, fade_size: usize) -> f32 {
+    if idx < fade_size {
+        0.5 * (1.0 - math::cos(consts::PI * idx as f32 / fade_size as f32))
+    } else if idx >= sample_size - fade_size {
+        let relative_i: usize = idx - (sample_size - fade_size);
+        0.5 * (1.0 + math::cos(consts::PI * relative_i as f32 / fade_size as f32))
+    } else {
+        1.0
+    }
+}
+
+pub fn linear_fade(idx: usize, sample_size: usize, fade_size: usize) -> f32 {
+    if idx < fade_size {
+        idx as f32 / fade_size as f32
+    } else if idx >= sample_size - fade_size {
+        (sample_size - idx) as f32 / fade_size as f32
+    } else {
+        1.0
+    }
+}
+
+// Unfaded full-scale tone lasting `duration` microseconds
+pub fn append_tone(samples: &mut Vec<f32>, frequency: f32, sample_rate: u32, duration: usize) {
+    let sample_size: usize = sample_size(sample_rate, duration);
+    let period: f32 = sample_rate as f32 / frequency;
+    samples.extend((0..sample_size).map(|idx| sine(idx, period)));
+}
+
+// Digital silence lasting `duration` microseconds
+pub fn append_silence(samples: &mut Vec<f32>, sample_rate: u32, duration: usize) {
+    let sample_size: usize = sample_size(sample_rate, duration);
+    samples.resize(samples.len() + sample_size, 0.0);
+}
@@ -0,0 +1,170 @@
+use std::error;
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+#[cfg(feature = "device")]
+use cpal::BuildStreamError;
+#[cfg(feature = "device")]
+use cpal::DefaultStreamConfigError;
+#[cfg(feature = "device")]
+use cpal::DeviceNameError;
+#[cfg(feature = "device")]
+use cpal::DevicesError;
+#[cfg(feature = "device")]
+use cpal::HostUnavailable;
+#[cfg(feature = "device")]
+use cpal::PauseStreamError;
+#[cfg(feature = "device")]
+use cpal::PlayStreamError;
+#[cfg(feature = "device")]
+use cpal::SupportedStreamConfigsError;
+
+use crate::protocol::framing::FrameError;
+
+#[derive(Debug)]
+pub enum WavetrxError {
+    Io(io::Error),
+    UnsupportedWav(String),
+    UnsupportedAudio(String),
+    ProfileInvalid(String),
+    DecodeFailed { reason: String },
+    InvalidInput(String),
+    Frame(FrameError),
+    DeviceError(String),
+    Unacknowledged { seq: u8, attempts: usize },
+    ChannelBusy(Duration),
+    BufferOverflow { depth: usize, max: usize },
+    FixtureMismatch { name: String, index: usize },
+}
+
+impl fmt::Display for WavetrxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WavetrxError::Io(err) => write!(f, "I/O error: {}", err),
+            WavetrxError::UnsupportedWav(reason) => write!(f, "Unsupported WAV: {}", reason),
+            WavetrxError::UnsupportedAudio(reason) => write!(f, "Unsupported audio: {}", reason),
+            WavetrxError::ProfileInvalid(reason) => write!(f, "Invalid profile: {}", reason),
+            WavetrxError::DecodeFailed { reason } => write!(f, "Decode failed: {}", reason),
+            WavetrxError::InvalidInput(reason) => write!(f, "Invalid input: {}", reason),
+            WavetrxError::Frame(err) => write!(f, "Frame error: {}", err),
+            WavetrxError::DeviceError(reason) => write!(f, "Audio device error: {}", reason),
+            WavetrxError::Unacknowledged { seq, attempts } => write!(
+                f,
+                "Frame {} not acknowledged after {} attempts",
+                seq, attempts
+            ),
+            WavetrxError::ChannelBusy(waited) => {
+                write!(f, "Channel still busy after {:?}", waited)
+            }
+            WavetrxError::BufferOverflow { depth, max } => write!(
+                f,
+                "Receive buffer overflow: {} samples over a maximum of {}",
+                depth, max
+            ),
+            WavetrxError::FixtureMismatch { name, index } => write!(
+                f,
+                "Fixture {} differs from the generated signal at sample {}",
+                name, index
+            ),
+        }
+    }
+}
+
+impl error::Error for WavetrxError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            WavetrxError::Io(err) => Some(err),
+            WavetrxError::Frame(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for WavetrxError {
+    fn from(err: io::Error) -> Self {
+        WavetrxError::Io(err)
+    }
+}
+
+#[cfg(feature = "wav")]
+impl From<hound::Error> for WavetrxError {
+    fn from(err: hound::Error) -> Self {
+        match err {
+            hound::Error::IoError(err) => WavetrxError::Io(err),
+            err => WavetrxError::UnsupportedWav(err.to_string()),
+        }
+    }
+}
+
+#[cfg(feature = "symphonia")]
+impl From<symphonia::core::errors::Error> for WavetrxError {
+    fn from(err: symphonia::core::errors::Error) -> Self {
+        match err {
+            symphonia::core::errors::Error::IoError(err) => WavetrxError::Io(err),
+            err => WavetrxError::UnsupportedAudio(err.to_string()),
+        }
+    }
+}
+
+impl From<FrameError> for WavetrxError {
+    fn from(err: FrameError) -> Self {
+        WavetrxError::Frame(err)
+    }
+}
+
+#[cfg(feature = "device")]
+impl From<BuildStreamError> for WavetrxError {
+    fn from(err: BuildStreamError) -> Self {
+        WavetrxError::DeviceError(err.to_string())
+    }
+}
+
+#[cfg(feature = "device")]
+impl From<PlayStreamError> for WavetrxError {
+    fn from(err: PlayStreamError) -> Self {
+        WavetrxError::DeviceError(err.to_string())
+    }
+}
+
+#[cfg(feature = "device")]
+impl From<PauseStreamError> for WavetrxError {
+    fn from(err: PauseStreamError) -> Self {
+        WavetrxError::DeviceError(err.to_string())
+    }
+}
+
+#[cfg(feature = "device")]
+impl From<DefaultStreamConfigError> for WavetrxError {
+    fn from(err: DefaultStreamConfigError) -> Self {
+        WavetrxError::DeviceError(err.to_string())
+    }
+}
+
+#[cfg(feature = "device")]
+impl From<DevicesError> for WavetrxError {
+    fn from(err: DevicesError) -> Self {
+        WavetrxError::DeviceError(err.to_string())
+    }
+}
+
+#[cfg(feature = "device")]
+impl From<DeviceNameError> for WavetrxError {
+    fn from(err: DeviceNameError) -> Self {
+        WavetrxError::DeviceError(err.to_string())
+    }
+}
+
+#[cfg(feature = "device")]
+impl From<SupportedStreamConfigsError> for WavetrxError {
+    fn from(err: SupportedStreamConfigsError) -> Self {
+        WavetrxError::DeviceError(err.to_string())
+    }
+}
+
+#[cfg(feature = "device")]
+impl From<HostUnavailable> for WavetrxError {
+    fn from(err: HostUnavailable) -> Self {
+        WavetrxError::DeviceError(err.to_string())
+    }
+}
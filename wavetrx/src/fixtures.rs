@@ -0,0 +1,240 @@
+use std::fs;
+use std::fs::File;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::path::Path;
+use std::path::PathBuf;
+
+use hound::WavReader;
+use hound::WavWriter;
+
+use crate::audio::types::AudioSpec;
+use crate::audio::types::SampleEncoding;
+use crate::consts::FastProfile;
+use crate::error::WavetrxError;
+use crate::protocol::fec::Fec;
+use crate::protocol::framing::BitOrder;
+use crate::protocol::framing::ByteOrder;
+use crate::protocol::framing::Checksum;
+use crate::protocol::framing::Framing;
+use crate::protocol::preamble::Preamble;
+use crate::protocol::profile::Bits;
+use crate::protocol::profile::Profile;
+use crate::protocol::profile::Timing;
+use crate::protocol::rx::DecodedMessage;
+use crate::protocol::rx::Receiver;
+use crate::protocol::tx::Transmitter;
+use crate::utils::get_default_profile;
+use crate::utils::get_fast_profile;
+
+// Kept small since the recordings are checked in: every fixture tone sits
+// below 12 kHz, and two bytes still cover a full frame
+const FIXTURE_SAMPLE_RATE: u32 = 24_000;
+const FIXTURE_BITS_PER_SAMPLE: u16 = 16;
+const FIXTURE_PAYLOAD: &[u8] = b"Wt";
+
+pub struct Fixture {
+    name: &'static str,
+    profile: Profile,
+    spec: AudioSpec,
+    payload: &'static [u8],
+}
+
+impl Fixture {
+    pub fn new(name: &'static str, profile: Profile, payload: &'static [u8]) -> Self {
+        let spec: AudioSpec = AudioSpec::new(
+            FIXTURE_SAMPLE_RATE,
+            FIXTURE_BITS_PER_SAMPLE,
+            1,
+            SampleEncoding::I32,
+        );
+
+        Fixture {
+            name,
+            profile,
+            spec,
+            payload,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        self.name
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        self.payload
+    }
+
+    pub fn path<P>(&self, dir: P) -> PathBuf
+    where
+        P: AsRef<Path>,
+    {
+        dir.as_ref().join(format!("{}.wav", self.name))
+    }
+
+    pub fn generate(&self) -> Result<Vec<i16>, WavetrxError> {
+        let transmitter: Transmitter = Transmitter::new(&self.profile, &self.spec);
+        let samples: Vec<f32> = transmitter.create(self.payload)?;
+
+        let samples: Vec<i16> = samples.iter().map(|&s| Self::quantize(s)).collect();
+        Ok(samples)
+    }
+
+    pub fn write<P>(&self, dir: P) -> Result<(), WavetrxError>
+    where
+        P: AsRef<Path>,
+    {
+        let samples: Vec<i16> = self.generate()?;
+
+        let mut writer: WavWriter<BufWriter<File>> =
+            WavWriter::create(self.path(dir), self.spec.into())?;
+        for sample in samples {
+            writer.write_sample(sample)?;
+        }
+        writer.finalize()?;
+
+        Ok(())
+    }
+
+    pub fn verify<P>(&self, dir: P) -> Result<(), WavetrxError>
+    where
+        P: AsRef<Path>,
+    {
+        let path: PathBuf = self.path(dir);
+        self.verify_samples(&path)?;
+        self.verify_decode(&path)?;
+        Ok(())
+    }
+}
+
+impl Fixture {
+    fn quantize(sample: f32) -> i16 {
+        (sample.clamp(-1.0, 1.0) * i16::MAX as f32).round() as i16
+    }
+
+    fn verify_samples(&self, path: &Path) -> Result<(), WavetrxError> {
+        let mut reader: WavReader<BufReader<File>> = WavReader::open(path)?;
+        let stored: Vec<i16> = reader.samples::<i16>().collect::<Result<_, _>>()?;
+        let generated: Vec<i16> = self.generate()?;
+
+        // Allow a single LSB of slack for platform differences in `sin`; a
+        // length mismatch differs at the first sample only one side has
+        let mismatch: Option<usize> = stored
+            .iter()
+            .zip(generated.iter())
+            .position(|(&a, &b)| (a as i32 - b as i32).abs() > 1)
+            .or((stored.len() != generated.len()).then(|| stored.len().min(generated.len())));
+
+        match mismatch {
+            Some(index) => Err(WavetrxError::FixtureMismatch {
+                name: self.name.to_string(),
+                index,
+            }),
+            None => Ok(()),
+        }
+    }
+
+    fn verify_decode(&self, path: &Path) -> Result<(), WavetrxError> {
+        let mut receiver: Receiver = Receiver::from_file(self.profile, path)?;
+        receiver.analyze_full_buffer();
+        let messages: Vec<DecodedMessage> = receiver.take_messages();
+
+        if messages.len() != 1 || messages[0].data() != self.payload {
+            let reason: String = format!(
+                "{}: expected {:?}, decoded {:?}",
+                self.name, self.payload, messages
+            );
+            return Err(WavetrxError::DecodeFailed { reason });
+        }
+        Ok(())
+    }
+}
+
+fn get_gapless_profile() -> Profile {
+    let timing: Timing = Timing::Gapless { resync: 8 };
+    let profile: Profile = get_fast_profile().with_timing(timing);
+    profile
+}
+
+fn get_lsb_profile() -> Profile {
+    let framing: Framing = Framing::new(BitOrder::LsbFirst, ByteOrder::LittleEndian);
+    let profile: Profile = get_fast_profile().with_framing(framing);
+    profile
+}
+
+fn get_crc16_profile() -> Profile {
+    let framing: Framing = Framing::default().with_checksum(Checksum::Crc16);
+    let profile: Profile = get_fast_profile().with_framing(framing);
+    profile
+}
+
+fn get_hamming_profile() -> Profile {
+    let profile: Profile = get_fast_profile().with_fec(Fec::Hamming74);
+    profile
+}
+
+fn get_4fsk_profile() -> Profile {
+    let bits: Bits = Bits::from_tones(&FastProfile::SYMBOL_TONES_4).unwrap();
+    let profile: Profile = get_fast_profile().with_bits(bits);
+    profile
+}
+
+fn get_8fsk_profile() -> Profile {
+    let bits: Bits = Bits::from_tones(&FastProfile::SYMBOL_TONES_8).unwrap();
+    let profile: Profile = get_fast_profile().with_bits(bits);
+    profile
+}
+
+fn get_chirp_profile() -> Profile {
+    let preamble: Preamble = Preamble::Chirp {
+        from: FastProfile::BIT_TONE_LOW,
+        to: FastProfile::MARKER_TONE_END,
+    };
+    let profile: Profile = get_fast_profile().with_preamble(preamble);
+    profile
+}
+
+fn get_framed_profile() -> Profile {
+    let framing: Framing = Framing::default()
+        .with_checksum(Checksum::Crc16)
+        .with_length_prefix(true);
+    let profile: Profile = get_fast_profile().with_framing(framing);
+    profile
+}
+
+pub fn canonical_fixtures() -> Vec<Fixture> {
+    let fixtures: Vec<Fixture> = vec![
+        Fixture::new("default", get_default_profile(), FIXTURE_PAYLOAD),
+        Fixture::new("fast", get_fast_profile(), FIXTURE_PAYLOAD),
+        Fixture::new("fast_gapless", get_gapless_profile(), FIXTURE_PAYLOAD),
+        Fixture::new("fast_lsb", get_lsb_profile(), FIXTURE_PAYLOAD),
+        Fixture::new("fast_crc16", get_crc16_profile(), FIXTURE_PAYLOAD),
+        Fixture::new("fast_hamming", get_hamming_profile(), FIXTURE_PAYLOAD),
+        Fixture::new("fast_framed", get_framed_profile(), FIXTURE_PAYLOAD),
+        Fixture::new("fast_4fsk", get_4fsk_profile(), FIXTURE_PAYLOAD),
+        Fixture::new("fast_8fsk", get_8fsk_profile(), FIXTURE_PAYLOAD),
+        Fixture::new("fast_chirp", get_chirp_profile(), FIXTURE_PAYLOAD),
+    ];
+    fixtures
+}
+
+pub fn write_fixtures<P>(dir: P) -> Result<(), WavetrxError>
+where
+    P: AsRef<Path>,
+{
+    fs::create_dir_all(&dir)?;
+    for fixture in canonical_fixtures() {
+        fixture.write(&dir)?;
+    }
+    Ok(())
+}
+
+pub fn verify_fixtures<P>(dir: P) -> Result<(), WavetrxError>
+where
+    P: AsRef<Path>,
+{
+    for fixture in canonical_fixtures() {
+        fixture.verify(&dir)?;
+    }
+    Ok(())
+}
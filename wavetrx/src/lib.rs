@@ -1,4 +1,30 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(not(any(feature = "std", feature = "libm")))]
+compile_error!("without `std`, enable the `libm` feature for float math");
+
+#[cfg(feature = "std")]
 pub mod audio;
+#[cfg(feature = "std")]
+pub mod calibrate;
+#[cfg(feature = "std")]
 pub mod consts;
+#[cfg(feature = "std")]
+pub mod control;
+pub mod embedded;
+#[cfg(feature = "std")]
+pub mod error;
+#[cfg(feature = "wav")]
+pub mod fixtures;
+#[cfg(feature = "std")]
 pub mod protocol;
+#[cfg(feature = "device")]
+pub mod selftest;
+#[cfg(feature = "std")]
+pub mod sim;
+#[cfg(feature = "std")]
 pub mod utils;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -0,0 +1,505 @@
+use std::collections::VecDeque;
+use std::thread::sleep;
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::control::ModemBackend;
+use crate::error::WavetrxError;
+use crate::protocol::framing::FrameError;
+use crate::protocol::rx::DecodedMessage;
+use crate::utils::random_seed;
+
+pub const ARQ_DATA: u8 = 0x02;
+pub const ARQ_ACK: u8 = 0x06;
+pub const ARQ_NACK: u8 = 0x15;
+pub const ARQ_MAX_FRAMES: usize = u8::MAX as usize;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+// Every frame names the transfer it belongs to, so the first chunk of one
+// transfer is never taken for a resend of the last chunk of the one before
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ArqFrame {
+    Data {
+        transfer: u8,
+        seq: u8,
+        total: u8,
+        chunk: Vec<u8>,
+    },
+    Ack {
+        transfer: u8,
+        seq: u8,
+    },
+    Nack {
+        transfer: u8,
+        seq: u8,
+    },
+}
+
+impl ArqFrame {
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            ArqFrame::Data {
+                transfer,
+                seq,
+                total,
+                chunk,
+            } => {
+                let mut bytes: Vec<u8> = Vec::with_capacity(chunk.len() + 4);
+                bytes.extend([ARQ_DATA, *transfer, *seq, *total]);
+                bytes.extend(chunk);
+                bytes
+            }
+            ArqFrame::Ack { transfer, seq } => vec![ARQ_ACK, *transfer, *seq],
+            ArqFrame::Nack { transfer, seq } => vec![ARQ_NACK, *transfer, *seq],
+        }
+    }
+
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [ARQ_DATA, transfer, seq, total, chunk @ ..] if seq < total => Some(ArqFrame::Data {
+                transfer: *transfer,
+                seq: *seq,
+                total: *total,
+                chunk: chunk.to_vec(),
+            }),
+            [ARQ_ACK, transfer, seq] => Some(ArqFrame::Ack {
+                transfer: *transfer,
+                seq: *seq,
+            }),
+            [ARQ_NACK, transfer, seq] => Some(ArqFrame::Nack {
+                transfer: *transfer,
+                seq: *seq,
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct ArqConfig {
+    pub chunk_size: usize,
+    pub retries: usize,
+    pub timeout: Duration,
+}
+
+impl ArqConfig {
+    // `timeout` runs from the moment a frame is queued, so it must cover its airtime
+    pub fn new(chunk_size: usize, retries: usize, timeout: Duration) -> Self {
+        ArqConfig {
+            chunk_size: chunk_size.max(1),
+            retries,
+            timeout,
+        }
+    }
+
+    pub fn split(&self, transfer: u8, data: &[u8]) -> Result<Vec<ArqFrame>, FrameError> {
+        let chunks: Vec<&[u8]> = if data.is_empty() {
+            vec![data]
+        } else {
+            data.chunks(self.chunk_size).collect()
+        };
+
+        if chunks.len() > ARQ_MAX_FRAMES {
+            let max: usize = ARQ_MAX_FRAMES * self.chunk_size;
+            return Err(FrameError::Oversized {
+                size: data.len(),
+                max,
+            });
+        }
+
+        let total: u8 = chunks.len() as u8;
+        let frames: Vec<ArqFrame> = chunks
+            .into_iter()
+            .enumerate()
+            .map(|(seq, chunk)| ArqFrame::Data {
+                transfer,
+                seq: seq as u8,
+                total,
+                chunk: chunk.to_vec(),
+            })
+            .collect();
+        Ok(frames)
+    }
+}
+
+impl Default for ArqConfig {
+    fn default() -> Self {
+        ArqConfig::new(16, 3, Duration::from_secs(2))
+    }
+}
+
+// Stop-and-wait: each frame is resent until acknowledged or retries run out.
+// Transfer ids start at a random value, so a restarted sender is not taken
+// for a resend of its previous run's last transfer
+pub struct ReliableTransmitter<B: ModemBackend> {
+    backend: B,
+    config: ArqConfig,
+    transfer: u8,
+}
+
+impl<B: ModemBackend> ReliableTransmitter<B> {
+    pub fn new(backend: B, config: ArqConfig) -> Self {
+        let transfer: u8 = random_seed() as u8;
+        ReliableTransmitter {
+            backend,
+            config,
+            transfer,
+        }
+    }
+
+    pub fn backend(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    pub fn into_inner(self) -> B {
+        self.backend
+    }
+
+    // Data frames heard meanwhile are ignored; see `ReliableLink`
+    pub fn send(&mut self, data: &[u8]) -> Result<(), WavetrxError> {
+        let transfer: u8 = self.transfer;
+        self.transfer = self.transfer.wrapping_add(1);
+        send_transfer(&mut self.backend, &self.config, transfer, data, None)
+    }
+}
+
+pub struct ReliableReceiver<B: ModemBackend> {
+    backend: B,
+    inbound: Reassembly,
+}
+
+impl<B: ModemBackend> ReliableReceiver<B> {
+    pub fn new(backend: B) -> Self {
+        ReliableReceiver {
+            backend,
+            inbound: Reassembly::default(),
+        }
+    }
+
+    pub fn backend(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    pub fn into_inner(self) -> B {
+        self.backend
+    }
+
+    // Partially received data is kept across calls that time out, and
+    // transfers completed by the same poll are returned one call at a time
+    pub fn receive(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, WavetrxError> {
+        receive_transfer(&mut self.backend, &mut self.inbound, timeout)
+    }
+}
+
+// Both directions of stop-and-wait over one backend, for links where each
+// end sends and receives at once. Data frames heard while waiting for an
+// ACK are acknowledged straight away and kept for `receive`
+pub struct ReliableLink<B: ModemBackend> {
+    backend: B,
+    config: ArqConfig,
+    transfer: u8,
+    inbound: Reassembly,
+}
+
+impl<B: ModemBackend> ReliableLink<B> {
+    pub fn new(backend: B, config: ArqConfig) -> Self {
+        let transfer: u8 = random_seed() as u8;
+        ReliableLink {
+            backend,
+            config,
+            transfer,
+            inbound: Reassembly::default(),
+        }
+    }
+
+    pub fn backend(&mut self) -> &mut B {
+        &mut self.backend
+    }
+
+    pub fn into_inner(self) -> B {
+        self.backend
+    }
+
+    pub fn send(&mut self, data: &[u8]) -> Result<(), WavetrxError> {
+        let transfer: u8 = self.transfer;
+        self.transfer = self.transfer.wrapping_add(1);
+        let inbound: Option<&mut Reassembly> = Some(&mut self.inbound);
+        send_transfer(&mut self.backend, &self.config, transfer, data, inbound)
+    }
+
+    pub fn receive(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, WavetrxError> {
+        receive_transfer(&mut self.backend, &mut self.inbound, timeout)
+    }
+}
+
+// The receiving half: the transfer being rebuilt, the last one completed,
+// whose resends are acknowledged again and dropped, and those not yet taken
+#[derive(Default)]
+struct Reassembly {
+    transfer: Option<u8>,
+    expected: u8,
+    data: Vec<u8>,
+    last_completed: Option<u8>,
+    completed: VecDeque<Vec<u8>>,
+}
+
+impl Reassembly {
+    // The reply owed to the sender of a Data frame; None for other frames
+    fn accept(&mut self, frame: ArqFrame) -> Option<ArqFrame> {
+        let (transfer, seq, total, chunk): (u8, u8, u8, Vec<u8>) = match frame {
+            ArqFrame::Data {
+                transfer,
+                seq,
+                total,
+                chunk,
+            } => (transfer, seq, total, chunk),
+            _ => return None,
+        };
+
+        // A resend after our ACK was lost; acknowledge it again and move on
+        if self.last_completed == Some(transfer) {
+            return Some(ArqFrame::Ack { transfer, seq });
+        }
+        // The sender gave up on the transfer in progress and began another
+        if self.transfer != Some(transfer) {
+            self.transfer = Some(transfer);
+            self.expected = 0;
+            self.data.clear();
+        }
+        if seq < self.expected {
+            return Some(ArqFrame::Ack { transfer, seq });
+        }
+        if seq > self.expected {
+            let seq: u8 = self.expected;
+            return Some(ArqFrame::Nack { transfer, seq });
+        }
+
+        self.data.extend(chunk);
+        self.expected += 1;
+        if self.expected == total {
+            self.transfer = None;
+            self.expected = 0;
+            self.last_completed = Some(transfer);
+            self.completed.push_back(std::mem::take(&mut self.data));
+        }
+        Some(ArqFrame::Ack { transfer, seq })
+    }
+}
+
+// Sends `data` frame by frame, each resent until acknowledged. Data frames
+// heard while waiting go to `inbound` when there is one
+fn send_transfer<B: ModemBackend>(
+    backend: &mut B,
+    config: &ArqConfig,
+    transfer: u8,
+    data: &[u8],
+    mut inbound: Option<&mut Reassembly>,
+) -> Result<(), WavetrxError> {
+    let frames: Vec<ArqFrame> = config.split(transfer, data)?;
+    let attempts: usize = config.retries + 1;
+
+    for (seq, frame) in frames.iter().enumerate() {
+        let seq: u8 = seq as u8;
+        let bytes: Vec<u8> = frame.encode();
+        let ack: ArqFrame = ArqFrame::Ack { transfer, seq };
+        let mut acknowledged: bool = false;
+        for _ in 0..attempts {
+            backend.send(&bytes)?;
+            let reply: Option<ArqFrame> =
+                wait_reply(backend, config, &ack, inbound.as_deref_mut())?;
+            if reply.as_ref() == Some(&ack) {
+                acknowledged = true;
+                break;
+            }
+        }
+        if !acknowledged {
+            return Err(WavetrxError::Unacknowledged { seq, attempts });
+        }
+    }
+    Ok(())
+}
+
+// The ACK or NACK for the frame `ack` answers, if one comes in time. The
+// rest of the poll it arrives in is still handled
+fn wait_reply<B: ModemBackend>(
+    backend: &mut B,
+    config: &ArqConfig,
+    ack: &ArqFrame,
+    mut inbound: Option<&mut Reassembly>,
+) -> Result<Option<ArqFrame>, WavetrxError> {
+    let nack: ArqFrame = match *ack {
+        ArqFrame::Ack { transfer, seq } => ArqFrame::Nack { transfer, seq },
+        _ => return Ok(None),
+    };
+    let deadline: Instant = Instant::now() + config.timeout;
+    while Instant::now() < deadline {
+        let mut reply: Option<ArqFrame> = None;
+        for message in backend.poll() {
+            match ArqFrame::decode(message.data()) {
+                Some(frame @ ArqFrame::Data { .. }) => {
+                    if let Some(inbound) = inbound.as_deref_mut() {
+                        if let Some(answer) = inbound.accept(frame) {
+                            backend.send(&answer.encode())?;
+                        }
+                    }
+                }
+                Some(frame) if frame == *ack || frame == nack => reply = Some(frame),
+                _ => {}
+            }
+        }
+        if reply.is_some() {
+            return Ok(reply);
+        }
+        sleep(POLL_INTERVAL);
+    }
+    Ok(None)
+}
+
+fn receive_transfer<B: ModemBackend>(
+    backend: &mut B,
+    inbound: &mut Reassembly,
+    timeout: Duration,
+) -> Result<Option<Vec<u8>>, WavetrxError> {
+    let deadline: Instant = Instant::now() + timeout;
+    loop {
+        if let Some(data) = inbound.completed.pop_front() {
+            return Ok(Some(data));
+        }
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        let messages: Vec<DecodedMessage> = backend.poll();
+        if messages.is_empty() {
+            sleep(POLL_INTERVAL);
+        }
+        for message in messages {
+            let frame: ArqFrame = match ArqFrame::decode(message.data()) {
+                Some(frame) => frame,
+                None => continue,
+            };
+            if let Some(answer) = inbound.accept(frame) {
+                backend.send(&answer.encode())?;
+            }
+        }
+    }
+}
+
+#[test]
+fn test_arq_frames() {
+    let config: ArqConfig = ArqConfig::new(4, 3, Duration::from_millis(100));
+    let frames: Vec<ArqFrame> = config.split(7, b"WaveTrx ARQ").unwrap();
+    assert_eq!(frames.len(), 3);
+
+    for frame in frames.iter() {
+        assert_eq!(ArqFrame::decode(&frame.encode()).as_ref(), Some(frame));
+    }
+    let ack: ArqFrame = ArqFrame::Ack {
+        transfer: 7,
+        seq: 2,
+    };
+    assert_eq!(ArqFrame::decode(&[ARQ_ACK, 7, 2]), Some(ack));
+    assert_eq!(ArqFrame::decode(&[ARQ_DATA, 7, 3, 3]), None);
+    assert!(config.split(7, &[0; 4 * 256]).is_err());
+}
+
+#[test]
+fn test_arq_retransmission() {
+    use std::sync::mpsc;
+    use std::thread;
+
+    use crate::protocol::payload::Payload;
+    use crate::protocol::profile::Profile;
+
+    // One end of a lossless pipe that loses the outgoing frames listed in `drop`
+    struct LinkBackend {
+        sender: mpsc::Sender<Vec<u8>>,
+        receiver: mpsc::Receiver<Vec<u8>>,
+        sent: usize,
+        drop: Vec<usize>,
+    }
+
+    impl ModemBackend for LinkBackend {
+        fn set_profile(&mut self, _: Profile) -> Result<(), WavetrxError> {
+            Ok(())
+        }
+
+        fn send(&mut self, data: &[u8]) -> Result<(), WavetrxError> {
+            if !self.drop.contains(&self.sent) {
+                let _ = self.sender.send(data.to_vec());
+            }
+            self.sent += 1;
+            Ok(())
+        }
+
+        fn poll(&mut self) -> Vec<DecodedMessage> {
+            self.receiver
+                .try_iter()
+                .map(|data| DecodedMessage::new(Payload::new(data), 0, 0))
+                .collect()
+        }
+    }
+
+    let (a_sender, b_receiver) = mpsc::channel::<Vec<u8>>();
+    let (b_sender, a_receiver) = mpsc::channel::<Vec<u8>>();
+    let a: LinkBackend = LinkBackend {
+        sender: a_sender,
+        receiver: a_receiver,
+        sent: 0,
+        drop: vec![1],
+    };
+    let b: LinkBackend = LinkBackend {
+        sender: b_sender,
+        receiver: b_receiver,
+        sent: 0,
+        drop: vec![1],
+    };
+
+    // Frame 1 is lost once, and so is the ACK for its resend; the two
+    // single-chunk transfers that follow both start at seq 0
+    let handle = thread::spawn(move || {
+        let mut receiver: ReliableReceiver<LinkBackend> = ReliableReceiver::new(b);
+        let received: Vec<Option<Vec<u8>>> = (0..3)
+            .map(|_| receiver.receive(Duration::from_secs(5)).unwrap())
+            .collect();
+        received
+    });
+
+    let config: ArqConfig = ArqConfig::new(4, 3, Duration::from_millis(100));
+    let mut transmitter: ReliableTransmitter<LinkBackend> = ReliableTransmitter::new(a, config);
+    transmitter.send(b"WaveTrx ARQ").unwrap();
+    assert_eq!(transmitter.backend().sent, 5);
+    transmitter.send(b"one").unwrap();
+    transmitter.send(b"two").unwrap();
+
+    let expected: Vec<Option<Vec<u8>>> = vec![
+        Some(b"WaveTrx ARQ".to_vec()),
+        Some(b"one".to_vec()),
+        Some(b"two".to_vec()),
+    ];
+    assert_eq!(handle.join().unwrap(), expected);
+
+    // Transfers caught by the same poll are delivered one call at a time
+    let (sender, receiver) = mpsc::channel::<Vec<u8>>();
+    let (replies, _) = mpsc::channel::<Vec<u8>>();
+    for (transfer, chunk) in [(3, b"one"), (4, b"two")] {
+        let frame: ArqFrame = ArqFrame::Data {
+            transfer,
+            seq: 0,
+            total: 1,
+            chunk: chunk.to_vec(),
+        };
+        sender.send(frame.encode()).unwrap();
+    }
+    let backend: LinkBackend = LinkBackend {
+        sender: replies,
+        receiver,
+        sent: 0,
+        drop: Vec::new(),
+    };
+    let mut receiver: ReliableReceiver<LinkBackend> = ReliableReceiver::new(backend);
+    let timeout: Duration = Duration::from_secs(1);
+    assert_eq!(receiver.receive(timeout).unwrap(), Some(b"one".to_vec()));
+    assert_eq!(receiver.receive(timeout).unwrap(), Some(b"two".to_vec()));
+    assert_eq!(receiver.backend().sent, 2);
+}
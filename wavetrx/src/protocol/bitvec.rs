@@ -0,0 +1,164 @@
+use crate::protocol::framing::BitOrder;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BitPadding {
+    Zero,
+    Truncate,
+}
+
+// Bits are packed in push order, MSB of each storage byte first;
+// trailing bits of the last storage byte are always zero
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BitVec {
+    bytes: Vec<u8>,
+    len: usize,
+}
+
+impl BitVec {
+    pub fn new() -> Self {
+        let bytes: Vec<u8> = Vec::new();
+        let len: usize = 0;
+        BitVec { bytes, len }
+    }
+
+    pub fn with_capacity(bits: usize) -> Self {
+        let bytes: Vec<u8> = Vec::with_capacity(bits.div_ceil(8));
+        let len: usize = 0;
+        BitVec { bytes, len }
+    }
+
+    pub fn from_bytes(bytes: &[u8], bit_order: BitOrder) -> Self {
+        let mut bits: BitVec = BitVec::with_capacity(bytes.len() * 8);
+        for &byte in bytes {
+            bits.push_byte(byte, bit_order);
+        }
+        bits
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push_bit(&mut self, bit: bool) {
+        if self.len.is_multiple_of(8) {
+            self.bytes.push(0);
+        }
+        if bit {
+            let last: usize = self.bytes.len() - 1;
+            self.bytes[last] |= BitOrder::MsbFirst.mask(self.len % 8);
+        }
+        self.len += 1;
+    }
+
+    pub fn push_byte(&mut self, byte: u8, bit_order: BitOrder) {
+        for idx in 0..8 {
+            self.push_bit(bit_order.bit(byte, idx));
+        }
+    }
+
+    pub fn get(&self, idx: usize) -> Option<bool> {
+        if idx >= self.len {
+            return None;
+        }
+        Some(BitOrder::MsbFirst.bit(self.bytes[idx / 8], idx % 8))
+    }
+
+    pub fn iter_bits(&self) -> BitIter<'_> {
+        BitIter { bits: self, idx: 0 }
+    }
+
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+        self.bytes.shrink_to_fit();
+        self.len = 0;
+    }
+
+    pub fn to_bytes(&self, bit_order: BitOrder, padding: BitPadding) -> Vec<u8> {
+        let byte_count: usize = match padding {
+            BitPadding::Zero => self.len.div_ceil(8),
+            BitPadding::Truncate => self.len / 8,
+        };
+
+        if bit_order == BitOrder::MsbFirst {
+            return self.bytes[..byte_count].to_vec();
+        }
+
+        let mut bytes: Vec<u8> = Vec::with_capacity(byte_count);
+        for byte_idx in 0..byte_count {
+            let mut byte: u8 = 0;
+            for idx in 0..8 {
+                if let Some(true) = self.get((byte_idx * 8) + idx) {
+                    byte |= bit_order.mask(idx);
+                }
+            }
+            bytes.push(byte);
+        }
+        bytes
+    }
+}
+
+impl FromIterator<bool> for BitVec {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = bool>,
+    {
+        let mut bits: BitVec = BitVec::new();
+        for bit in iter {
+            bits.push_bit(bit);
+        }
+        bits
+    }
+}
+
+pub struct BitIter<'a> {
+    bits: &'a BitVec,
+    idx: usize,
+}
+
+impl<'a> Iterator for BitIter<'a> {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bit: Option<bool> = self.bits.get(self.idx);
+        if bit.is_some() {
+            self.idx += 1;
+        }
+        bit
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining: usize = self.bits.len() - self.idx;
+        (remaining, Some(remaining))
+    }
+}
+
+#[test]
+fn test_bitvec_packing() {
+    let bits: BitVec = BitVec::from_bytes(&[0b1010_0001, 0b0000_0011], BitOrder::MsbFirst);
+    assert_eq!(bits.len(), 16);
+    assert_eq!(bits.get(0), Some(true));
+    assert_eq!(bits.get(1), Some(false));
+    assert_eq!(bits.get(16), None);
+
+    let lsb: Vec<u8> = bits.to_bytes(BitOrder::LsbFirst, BitPadding::Zero);
+    assert_eq!(lsb, vec![0b1000_0101, 0b1100_0000]);
+    assert_eq!(BitVec::from_bytes(&lsb, BitOrder::LsbFirst), bits);
+
+    let partial: BitVec = [true, true, false, true, true, true, true, true, false, true]
+        .into_iter()
+        .collect();
+    assert_eq!(partial.iter_bits().count(), 10);
+    assert_eq!(
+        partial.to_bytes(BitOrder::MsbFirst, BitPadding::Zero),
+        vec![0b1101_1111, 0b0100_0000]
+    );
+    assert_eq!(
+        partial.to_bytes(BitOrder::MsbFirst, BitPadding::Truncate),
+        vec![0b1101_1111]
+    );
+}
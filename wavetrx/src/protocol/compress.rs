@@ -0,0 +1,79 @@
+use crate::protocol::framing::FrameError;
+
+pub const FLAG_STORED: u8 = 0x00;
+pub const FLAG_DEFLATE: u8 = 0x01;
+
+// Guards against a corrupted stream inflating without bound
+#[cfg(feature = "compression")]
+const MAX_INFLATED_SIZE: usize = 1 << 20;
+#[cfg(feature = "compression")]
+const DEFLATE_LEVEL: u8 = 10;
+
+// Prefixes a flag byte; payloads that do not shrink, or builds without the
+// `compression` feature, are stored as-is behind `FLAG_STORED`
+pub fn compress(payload: &[u8]) -> Vec<u8> {
+    let deflated: Option<Vec<u8>> =
+        deflate(payload).filter(|deflated| deflated.len() < payload.len());
+    let mut output: Vec<u8> = match deflated {
+        Some(_) => vec![FLAG_DEFLATE],
+        None => vec![FLAG_STORED],
+    };
+    output.extend(deflated.as_deref().unwrap_or(payload));
+    output
+}
+
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, FrameError> {
+    match data.split_first() {
+        Some((&FLAG_STORED, payload)) => Ok(payload.to_vec()),
+        Some((&FLAG_DEFLATE, payload)) => inflate(payload).ok_or(FrameError::DecompressionFailed),
+        Some(_) => Err(FrameError::InvalidHeader),
+        None => Err(FrameError::Truncated {
+            expected: 1,
+            actual: 0,
+        }),
+    }
+}
+
+#[cfg(feature = "compression")]
+fn deflate(payload: &[u8]) -> Option<Vec<u8>> {
+    Some(miniz_oxide::deflate::compress_to_vec(
+        payload,
+        DEFLATE_LEVEL,
+    ))
+}
+
+#[cfg(not(feature = "compression"))]
+fn deflate(_payload: &[u8]) -> Option<Vec<u8>> {
+    None
+}
+
+#[cfg(feature = "compression")]
+fn inflate(data: &[u8]) -> Option<Vec<u8>> {
+    miniz_oxide::inflate::decompress_to_vec_with_limit(data, MAX_INFLATED_SIZE).ok()
+}
+
+#[cfg(not(feature = "compression"))]
+fn inflate(_data: &[u8]) -> Option<Vec<u8>> {
+    None
+}
+
+#[test]
+fn test_compress_roundtrip() {
+    let short: Vec<u8> = compress(b"Wt");
+    assert_eq!(short, vec![FLAG_STORED, b'W', b't']);
+    assert_eq!(decompress(&short), Ok(b"Wt".to_vec()));
+
+    let text: Vec<u8> = b"wavetrx ".repeat(32);
+    let packed: Vec<u8> = compress(&text);
+    assert_eq!(decompress(&packed), Ok(text.clone()));
+    if cfg!(feature = "compression") {
+        assert_eq!(packed[0], FLAG_DEFLATE);
+        assert!(packed.len() < text.len() / 4);
+    }
+
+    assert_eq!(decompress(&[0x7F]), Err(FrameError::InvalidHeader));
+    assert_eq!(
+        decompress(&[FLAG_DEFLATE, 0xFF, 0xFF]),
+        Err(FrameError::DecompressionFailed)
+    );
+}
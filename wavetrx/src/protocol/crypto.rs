@@ -0,0 +1,83 @@
+use chacha20poly1305::aead::Aead;
+use chacha20poly1305::aead::AeadCore;
+use chacha20poly1305::aead::KeyInit;
+use chacha20poly1305::aead::OsRng;
+use chacha20poly1305::ChaCha20Poly1305;
+use chacha20poly1305::Key;
+use chacha20poly1305::Nonce;
+
+use crate::protocol::framing::FrameError;
+
+pub const KEY_SIZE: usize = 32;
+pub const NONCE_SIZE: usize = 12;
+pub const TAG_SIZE: usize = 16;
+
+// Sealed payloads are laid out as nonce || ciphertext || tag; a fresh random
+// nonce per payload keeps a shared key safe across transmissions
+#[derive(Clone)]
+pub struct PayloadCipher {
+    cipher: ChaCha20Poly1305,
+}
+
+impl PayloadCipher {
+    pub fn new(key: &[u8; KEY_SIZE]) -> Self {
+        let cipher: ChaCha20Poly1305 = ChaCha20Poly1305::new(Key::from_slice(key));
+        PayloadCipher { cipher }
+    }
+
+    pub fn overhead() -> usize {
+        NONCE_SIZE + TAG_SIZE
+    }
+
+    pub fn seal(&self, payload: &[u8]) -> Result<Vec<u8>, FrameError> {
+        let max: usize = u16::MAX as usize - Self::overhead();
+        if payload.len() > max {
+            return Err(FrameError::Oversized {
+                size: payload.len(),
+                max,
+            });
+        }
+
+        let nonce: Nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext: Vec<u8> =
+            self.cipher
+                .encrypt(&nonce, payload)
+                .map_err(|_| FrameError::Oversized {
+                    size: payload.len(),
+                    max,
+                })?;
+
+        let mut sealed: Vec<u8> = nonce.to_vec();
+        sealed.extend(ciphertext);
+        Ok(sealed)
+    }
+
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>, FrameError> {
+        if sealed.len() < Self::overhead() {
+            return Err(FrameError::Truncated {
+                expected: Self::overhead(),
+                actual: sealed.len(),
+            });
+        }
+
+        let (nonce, ciphertext): (&[u8], &[u8]) = sealed.split_at(NONCE_SIZE);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| FrameError::AuthenticationFailed)
+    }
+}
+
+#[test]
+fn test_payload_cipher() {
+    let cipher: PayloadCipher = PayloadCipher::new(&[7; KEY_SIZE]);
+    let mut sealed: Vec<u8> = cipher.seal(b"WaveTrx").unwrap();
+    assert_eq!(sealed.len(), 7 + PayloadCipher::overhead());
+    assert_ne!(cipher.seal(b"WaveTrx").unwrap(), sealed);
+    assert_eq!(cipher.open(&sealed), Ok(b"WaveTrx".to_vec()));
+
+    let other: PayloadCipher = PayloadCipher::new(&[8; KEY_SIZE]);
+    assert_eq!(other.open(&sealed), Err(FrameError::AuthenticationFailed));
+
+    sealed[NONCE_SIZE] ^= 0x01;
+    assert_eq!(cipher.open(&sealed), Err(FrameError::AuthenticationFailed));
+}
@@ -0,0 +1,214 @@
+use std::f32::consts;
+use std::time::Duration;
+
+use crate::audio::spectrum::GoertzelMagnitude;
+use crate::audio::types::AudioSpec;
+use crate::consts::DTMF_GAP;
+use crate::consts::DTMF_HIGH_HZ;
+use crate::consts::DTMF_LOW_HZ;
+use crate::consts::DTMF_MARGIN_DB;
+use crate::consts::DTMF_MIN_DB;
+use crate::consts::DTMF_TONE;
+use crate::consts::DTMF_WINDOW;
+use crate::error::WavetrxError;
+use crate::protocol::profile::Pulses;
+use crate::protocol::profile::SizedPulses;
+
+// Rows follow the low group, columns the high group
+const KEYPAD: [[char; 4]; 4] = [
+    ['1', '2', '3', 'A'],
+    ['4', '5', '6', 'B'],
+    ['7', '8', '9', 'C'],
+    ['*', '0', '#', 'D'],
+];
+
+// Nibble values 0x0..=0xF in digit order, so each byte is a digit pair
+const NIBBLE_DIGITS: [char; 16] = [
+    '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', 'A', 'B', 'C', 'D', '*', '#',
+];
+
+pub fn bytes_to_digits(data: &[u8]) -> String {
+    let mut digits: String = String::with_capacity(data.len() * 2);
+    for byte in data {
+        digits.push(NIBBLE_DIGITS[(byte >> 4) as usize]);
+        digits.push(NIBBLE_DIGITS[(byte & 0x0F) as usize]);
+    }
+    digits
+}
+
+pub fn digits_to_bytes(digits: &str) -> Result<Vec<u8>, WavetrxError> {
+    let nibbles: Vec<u8> = digits.chars().map(digit_nibble).collect::<Result<_, _>>()?;
+    if !nibbles.len().is_multiple_of(2) {
+        let reason: String = format!("{} DTMF digits do not form whole bytes", nibbles.len());
+        return Err(WavetrxError::DecodeFailed { reason });
+    }
+    Ok(nibbles
+        .chunks_exact(2)
+        .map(|pair| (pair[0] << 4) | pair[1])
+        .collect())
+}
+
+pub fn digit_frequencies(digit: char) -> Option<(f32, f32)> {
+    let digit: char = digit.to_ascii_uppercase();
+    for (row, keys) in KEYPAD.iter().enumerate() {
+        if let Some(column) = keys.iter().position(|key| *key == digit) {
+            return Some((DTMF_LOW_HZ[row], DTMF_HIGH_HZ[column]));
+        }
+    }
+    None
+}
+
+pub struct DtmfEncoder {
+    spec: AudioSpec,
+    tone: Duration,
+    gap: Duration,
+}
+
+impl DtmfEncoder {
+    pub fn new(spec: &AudioSpec) -> Self {
+        let spec: AudioSpec = *spec;
+        let tone: Duration = DTMF_TONE;
+        let gap: Duration = DTMF_GAP;
+        DtmfEncoder { spec, tone, gap }
+    }
+
+    // Q.24 asks for at least 40ms of tone and 40ms of pause per digit
+    pub fn with_timing(mut self, tone: Duration, gap: Duration) -> Self {
+        self.tone = tone;
+        self.gap = gap;
+        self
+    }
+
+    pub fn encode(&self, data: &[u8]) -> Vec<f32> {
+        let digits: String = bytes_to_digits(data);
+        let mut samples: Vec<f32> = Vec::new();
+        for digit in digits.chars() {
+            if let Some((low, high)) = digit_frequencies(digit) {
+                self.append_digit(&mut samples, low, high);
+            }
+        }
+        samples
+    }
+
+    pub fn encode_digits(&self, digits: &str) -> Result<Vec<f32>, WavetrxError> {
+        let mut samples: Vec<f32> = Vec::new();
+        for digit in digits.chars() {
+            let (low, high): (f32, f32) = digit_frequencies(digit).ok_or_else(|| {
+                WavetrxError::InvalidInput(format!("Not a DTMF digit: {}", digit))
+            })?;
+            self.append_digit(&mut samples, low, high);
+        }
+        Ok(samples)
+    }
+}
+
+impl DtmfEncoder {
+    // Each tone runs at half scale so the pair never clips
+    fn append_digit(&self, samples: &mut Vec<f32>, low: f32, high: f32) {
+        let sample_rate: f32 = self.spec.sample_rate() as f32;
+        let tone_size: usize = (self.tone.as_secs_f32() * sample_rate) as usize;
+        let gap_size: usize = (self.gap.as_secs_f32() * sample_rate) as usize;
+
+        for idx in 0..tone_size {
+            let t: f32 = idx as f32 / sample_rate;
+            let low_norm: f32 = (2.0 * consts::PI * low * t).sin();
+            let high_norm: f32 = (2.0 * consts::PI * high * t).sin();
+            samples.push(0.5 * (low_norm + high_norm));
+        }
+        samples.extend(vec![0.0; gap_size]);
+    }
+}
+
+// Scans fixed windows with Goertzel; a digit is reported once per run of
+// windows that detect it, so repeats need a pause in between
+pub struct DtmfDecoder {
+    magnitude: GoertzelMagnitude,
+    window: usize,
+}
+
+impl DtmfDecoder {
+    pub fn new(spec: &AudioSpec) -> Self {
+        let pulses: SizedPulses = Pulses::new(DTMF_WINDOW, DTMF_GAP).into_sized(spec);
+        let magnitude: GoertzelMagnitude = GoertzelMagnitude::new(&pulses, spec);
+        let window: usize = pulses.tone_size().max(1);
+        DtmfDecoder { magnitude, window }
+    }
+
+    pub fn decode_digits(&self, samples: &[f32]) -> String {
+        let mut digits: String = String::new();
+        let mut previous: Option<char> = None;
+        for window in samples.chunks_exact(self.window) {
+            let detected: Option<char> = self.detect(window);
+            if let Some(digit) = detected.filter(|digit| previous != Some(*digit)) {
+                digits.push(digit);
+            }
+            previous = detected;
+        }
+        digits
+    }
+
+    pub fn decode(&self, samples: &[f32]) -> Result<Vec<u8>, WavetrxError> {
+        digits_to_bytes(&self.decode_digits(samples))
+    }
+}
+
+impl DtmfDecoder {
+    fn detect(&self, window: &[f32]) -> Option<char> {
+        let row: usize = self.strongest(window, &DTMF_LOW_HZ)?;
+        let column: usize = self.strongest(window, &DTMF_HIGH_HZ)?;
+        Some(KEYPAD[row][column])
+    }
+
+    // The loudest tone of a group must be present and clearly ahead of the rest
+    fn strongest(&self, window: &[f32], frequencies: &[f32; 4]) -> Option<usize> {
+        let magnitudes: Vec<f32> = frequencies
+            .iter()
+            .map(|frequency| self.magnitude.get_magnitude(window, *frequency))
+            .collect();
+
+        let mut best: usize = 0;
+        for (idx, magnitude) in magnitudes.iter().enumerate() {
+            if *magnitude > magnitudes[best] {
+                best = idx;
+            }
+        }
+        let runner_up: f32 = magnitudes
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| *idx != best)
+            .map(|(_, magnitude)| *magnitude)
+            .fold(f32::NEG_INFINITY, f32::max);
+
+        let level: f32 = magnitudes[best];
+        if level < DTMF_MIN_DB || level - runner_up < DTMF_MARGIN_DB {
+            return None;
+        }
+        Some(best)
+    }
+}
+
+fn digit_nibble(digit: char) -> Result<u8, WavetrxError> {
+    let digit: char = digit.to_ascii_uppercase();
+    match NIBBLE_DIGITS.iter().position(|nibble| *nibble == digit) {
+        Some(nibble) => Ok(nibble as u8),
+        None => {
+            let reason: String = format!("Not a DTMF digit: {}", digit);
+            Err(WavetrxError::DecodeFailed { reason })
+        }
+    }
+}
+
+#[test]
+fn test_dtmf_digits() {
+    assert_eq!(bytes_to_digits(&[0x00, 0x9F, 0xAE]), "009#A*");
+    assert_eq!(digits_to_bytes("009#a*").unwrap(), vec![0x00, 0x9F, 0xAE]);
+    // Odd counts and stray characters fail the same way
+    for digits in ["123", "1X"] {
+        assert!(matches!(
+            digits_to_bytes(digits),
+            Err(WavetrxError::DecodeFailed { .. })
+        ));
+    }
+    assert_eq!(digit_frequencies('5'), Some((770.0, 1_336.0)));
+    assert_eq!(digit_frequencies('D'), Some((941.0, 1_633.0)));
+}
@@ -0,0 +1,61 @@
+use crate::protocol::bitvec::BitVec;
+
+// Codeword layout: p1 p2 d1 p3 d2 d3 d4
+pub fn encode(bits: &BitVec) -> BitVec {
+    let mut encoded: BitVec = BitVec::with_capacity((bits.len() / 4) * 7 + 7);
+    let data: Vec<bool> = bits.iter_bits().collect();
+
+    for nibble in data.chunks(4) {
+        let mut d: [bool; 4] = [false; 4];
+        d[..nibble.len()].copy_from_slice(nibble);
+
+        let p1: bool = d[0] ^ d[1] ^ d[3];
+        let p2: bool = d[0] ^ d[2] ^ d[3];
+        let p3: bool = d[1] ^ d[2] ^ d[3];
+        for bit in [p1, p2, d[0], p3, d[1], d[2], d[3]] {
+            encoded.push_bit(bit);
+        }
+    }
+    encoded
+}
+
+pub fn decode(bits: &BitVec) -> BitVec {
+    let mut decoded: BitVec = BitVec::with_capacity((bits.len() / 7) * 4);
+    let codewords: Vec<bool> = bits.iter_bits().collect();
+
+    for codeword in codewords.chunks_exact(7) {
+        let mut c: [bool; 7] = [false; 7];
+        c.copy_from_slice(codeword);
+
+        let s1: usize = (c[0] ^ c[2] ^ c[4] ^ c[6]) as usize;
+        let s2: usize = (c[1] ^ c[2] ^ c[5] ^ c[6]) as usize;
+        let s3: usize = (c[3] ^ c[4] ^ c[5] ^ c[6]) as usize;
+        let syndrome: usize = s1 | (s2 << 1) | (s3 << 2);
+        if syndrome != 0 {
+            c[syndrome - 1] = !c[syndrome - 1];
+        }
+
+        for bit in [c[2], c[4], c[5], c[6]] {
+            decoded.push_bit(bit);
+        }
+    }
+    decoded
+}
+
+#[test]
+fn test_hamming_single_error() {
+    use crate::protocol::framing::BitOrder;
+
+    let bits: BitVec = BitVec::from_bytes(b"Wt", BitOrder::MsbFirst);
+    let encoded: BitVec = encode(&bits);
+    assert_eq!(encoded.len(), 28);
+
+    for flip in 0..encoded.len() {
+        let corrupted: BitVec = encoded
+            .iter_bits()
+            .enumerate()
+            .map(|(idx, bit)| bit ^ (idx == flip))
+            .collect();
+        assert_eq!(decode(&corrupted), bits);
+    }
+}
@@ -0,0 +1,117 @@
+mod hamming;
+mod reed_solomon;
+
+use crate::protocol::bitvec::BitPadding;
+use crate::protocol::bitvec::BitVec;
+use crate::protocol::framing::BitOrder;
+use crate::protocol::framing::FrameError;
+use crate::protocol::profile::ProfileError;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Fec {
+    None,
+    Hamming74,
+    ReedSolomon { parity: usize },
+}
+
+impl Fec {
+    pub fn reed_solomon(parity: usize) -> Result<Self, ProfileError> {
+        let max: usize = reed_solomon::max_parity();
+        match (1..=max).contains(&parity) {
+            true => Ok(Fec::ReedSolomon { parity }),
+            false => Err(ProfileError::InvalidParity { parity, max }),
+        }
+    }
+
+    pub fn encode(&self, frame: &[u8], bit_order: BitOrder) -> BitVec {
+        match self {
+            Fec::None => BitVec::from_bytes(frame, bit_order),
+            Fec::Hamming74 => hamming::encode(&BitVec::from_bytes(frame, bit_order)),
+            Fec::ReedSolomon { parity } => {
+                let encoded: Vec<u8> = reed_solomon::encode(frame, *parity);
+                BitVec::from_bytes(&encoded, bit_order)
+            }
+        }
+    }
+
+    pub fn decode(&self, bits: &BitVec, bit_order: BitOrder) -> Result<Vec<u8>, FrameError> {
+        match self {
+            Fec::None => Ok(bits.to_bytes(bit_order, BitPadding::Truncate)),
+            Fec::Hamming74 => {
+                let decoded: BitVec = hamming::decode(bits);
+                Ok(decoded.to_bytes(bit_order, BitPadding::Truncate))
+            }
+            Fec::ReedSolomon { parity } => {
+                let encoded: Vec<u8> = bits.to_bytes(bit_order, BitPadding::Truncate);
+                reed_solomon::decode(&encoded, *parity)
+            }
+        }
+    }
+
+    pub fn encoded_bits(&self, frame_len: usize) -> usize {
+        match self {
+            Fec::None => frame_len * 8,
+            Fec::Hamming74 => (frame_len * 8).div_ceil(4) * 7,
+            Fec::ReedSolomon { parity } => {
+                let blocks: usize = frame_len.div_ceil(reed_solomon::max_block_data(*parity));
+                (frame_len + (blocks * parity)) * 8
+            }
+        }
+    }
+
+    // Bits in one codeword; interleaving spreads bursts across these
+    pub fn codeword_bits(&self) -> usize {
+        match self {
+            Fec::None => 8,
+            Fec::Hamming74 => 7,
+            Fec::ReedSolomon { parity } => (reed_solomon::max_block_data(*parity) + parity) * 8,
+        }
+    }
+
+    // Longest whole encoded frame within `bits` received bits; what follows
+    // is padding from the last symbol
+    pub fn aligned_bits(&self, bits: usize) -> usize {
+        let unit: usize = match self {
+            Fec::None | Fec::ReedSolomon { .. } => 8,
+            Fec::Hamming74 => 14,
+        };
+        bits - bits % unit
+    }
+
+    // Reed-Solomon is systematic, so the leading bytes are readable before the
+    // block completes; they are only verified once the whole frame is decoded
+    pub fn decode_prefix(
+        &self,
+        bits: &BitVec,
+        bit_order: BitOrder,
+        bytes: usize,
+    ) -> Option<Vec<u8>> {
+        let needed: usize = match self {
+            Fec::None | Fec::ReedSolomon { .. } => bytes * 8,
+            Fec::Hamming74 => self.encoded_bits(bytes),
+        };
+        if bits.len() < needed {
+            return None;
+        }
+
+        let prefix: BitVec = bits.iter_bits().take(needed).collect();
+        let prefix: BitVec = match self {
+            Fec::Hamming74 => hamming::decode(&prefix),
+            _ => prefix,
+        };
+        Some(prefix.to_bytes(bit_order, BitPadding::Truncate))
+    }
+}
+
+#[test]
+fn test_reed_solomon_parity_bounds() {
+    assert_eq!(Fec::reed_solomon(16), Ok(Fec::ReedSolomon { parity: 16 }));
+    assert_eq!(Fec::reed_solomon(254), Ok(Fec::ReedSolomon { parity: 254 }));
+    for parity in [0, 255, 300] {
+        assert_eq!(
+            Fec::reed_solomon(parity),
+            Err(ProfileError::InvalidParity { parity, max: 254 })
+        );
+    }
+}
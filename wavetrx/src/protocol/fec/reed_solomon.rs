@@ -0,0 +1,294 @@
+use std::sync::OnceLock;
+
+use crate::protocol::framing::FrameError;
+
+const PRIMITIVE: u16 = 0x11D;
+const FIELD_SIZE: usize = 255;
+
+struct GaloisField {
+    exp: [u8; FIELD_SIZE * 2],
+    log: [u8; FIELD_SIZE + 1],
+}
+
+impl GaloisField {
+    fn get() -> &'static GaloisField {
+        static FIELD: OnceLock<GaloisField> = OnceLock::new();
+        FIELD.get_or_init(|| {
+            let mut exp: [u8; FIELD_SIZE * 2] = [0; FIELD_SIZE * 2];
+            let mut log: [u8; FIELD_SIZE + 1] = [0; FIELD_SIZE + 1];
+            let mut x: u16 = 1;
+            for (idx, value) in exp.iter_mut().take(FIELD_SIZE).enumerate() {
+                *value = x as u8;
+                log[x as usize] = idx as u8;
+                x <<= 1;
+                if (x & 0x100) != 0 {
+                    x ^= PRIMITIVE;
+                }
+            }
+            exp.copy_within(0..FIELD_SIZE, FIELD_SIZE);
+            GaloisField { exp, log }
+        })
+    }
+
+    fn mul(&self, x: u8, y: u8) -> u8 {
+        if x == 0 || y == 0 {
+            return 0;
+        }
+        self.exp[self.log[x as usize] as usize + self.log[y as usize] as usize]
+    }
+
+    fn div(&self, x: u8, y: u8) -> u8 {
+        if x == 0 {
+            return 0;
+        }
+        let exponent: usize = (self.log[x as usize] as usize + FIELD_SIZE
+            - self.log[y as usize] as usize)
+            % FIELD_SIZE;
+        self.exp[exponent]
+    }
+
+    fn pow(&self, x: u8, power: i32) -> u8 {
+        let exponent: i32 = (self.log[x as usize] as i32 * power).rem_euclid(FIELD_SIZE as i32);
+        self.exp[exponent as usize]
+    }
+
+    fn inverse(&self, x: u8) -> u8 {
+        self.exp[FIELD_SIZE - self.log[x as usize] as usize]
+    }
+
+    fn poly_scale(&self, p: &[u8], x: u8) -> Vec<u8> {
+        p.iter().map(|&coef| self.mul(coef, x)).collect()
+    }
+
+    fn poly_add(&self, p: &[u8], q: &[u8]) -> Vec<u8> {
+        let len: usize = p.len().max(q.len());
+        let mut r: Vec<u8> = vec![0; len];
+        for (idx, &coef) in p.iter().enumerate() {
+            r[idx + len - p.len()] = coef;
+        }
+        for (idx, &coef) in q.iter().enumerate() {
+            r[idx + len - q.len()] ^= coef;
+        }
+        r
+    }
+
+    fn poly_mul(&self, p: &[u8], q: &[u8]) -> Vec<u8> {
+        let mut r: Vec<u8> = vec![0; p.len() + q.len() - 1];
+        for (j, &q_coef) in q.iter().enumerate() {
+            for (i, &p_coef) in p.iter().enumerate() {
+                r[i + j] ^= self.mul(p_coef, q_coef);
+            }
+        }
+        r
+    }
+
+    fn poly_eval(&self, p: &[u8], x: u8) -> u8 {
+        let mut y: u8 = p[0];
+        for &coef in &p[1..] {
+            y = self.mul(y, x) ^ coef;
+        }
+        y
+    }
+
+    fn poly_remainder(&self, dividend: &[u8], divisor: &[u8]) -> Vec<u8> {
+        let mut out: Vec<u8> = dividend.to_vec();
+        for i in 0..(dividend.len() - (divisor.len() - 1)) {
+            let coef: u8 = out[i];
+            if coef != 0 {
+                for (j, &divisor_coef) in divisor.iter().enumerate().skip(1) {
+                    out[i + j] ^= self.mul(divisor_coef, coef);
+                }
+            }
+        }
+        out.split_off(dividend.len() - (divisor.len() - 1))
+    }
+}
+
+// A codeword needs at least one data byte beside its parity
+pub fn max_parity() -> usize {
+    FIELD_SIZE - 1
+}
+
+pub fn max_block_data(parity: usize) -> usize {
+    FIELD_SIZE.saturating_sub(parity).max(1)
+}
+
+pub fn encode(data: &[u8], parity: usize) -> Vec<u8> {
+    let field: &GaloisField = GaloisField::get();
+    let generator: Vec<u8> = generator_poly(field, parity);
+
+    let mut encoded: Vec<u8> = Vec::with_capacity(data.len() + parity);
+    for block in data.chunks(max_block_data(parity)) {
+        let mut message: Vec<u8> = block.to_vec();
+        message.resize(block.len() + parity, 0);
+        let remainder: Vec<u8> = field.poly_remainder(&message, &generator);
+
+        encoded.extend_from_slice(block);
+        encoded.extend(remainder);
+    }
+    encoded
+}
+
+pub fn decode(encoded: &[u8], parity: usize) -> Result<Vec<u8>, FrameError> {
+    let mut decoded: Vec<u8> = Vec::with_capacity(encoded.len());
+    for block in encoded.chunks(max_block_data(parity) + parity) {
+        if block.len() <= parity {
+            return Err(FrameError::Truncated {
+                expected: parity + 1,
+                actual: block.len(),
+            });
+        }
+        let corrected: Vec<u8> = correct_block(block, parity)?;
+        decoded.extend_from_slice(&corrected[..block.len() - parity]);
+    }
+    Ok(decoded)
+}
+
+fn generator_poly(field: &GaloisField, parity: usize) -> Vec<u8> {
+    let mut generator: Vec<u8> = vec![1];
+    for idx in 0..parity {
+        generator = field.poly_mul(&generator, &[1, field.pow(2, idx as i32)]);
+    }
+    generator
+}
+
+// Syndromes are prefixed with a zero so that index arithmetic in the
+// Berlekamp-Massey step can reach one position before the first syndrome
+fn syndromes(field: &GaloisField, block: &[u8], parity: usize) -> Vec<u8> {
+    let mut syndromes: Vec<u8> = vec![0; parity + 1];
+    for idx in 0..parity {
+        syndromes[idx + 1] = field.poly_eval(block, field.pow(2, idx as i32));
+    }
+    syndromes
+}
+
+fn correct_block(block: &[u8], parity: usize) -> Result<Vec<u8>, FrameError> {
+    let field: &GaloisField = GaloisField::get();
+    let synd: Vec<u8> = syndromes(field, block, parity);
+    if synd.iter().all(|&coef| coef == 0) {
+        return Ok(block.to_vec());
+    }
+
+    let error_locator: Vec<u8> = find_error_locator(field, &synd, parity)?;
+    let reversed: Vec<u8> = error_locator.iter().rev().copied().collect();
+    let error_positions: Vec<usize> = find_errors(field, &reversed, block.len())?;
+    let corrected: Vec<u8> = correct_errata(field, block, &synd, &error_positions);
+
+    let synd: Vec<u8> = syndromes(field, &corrected, parity);
+    if synd.iter().any(|&coef| coef != 0) {
+        return Err(FrameError::Uncorrectable);
+    }
+    Ok(corrected)
+}
+
+fn find_error_locator(
+    field: &GaloisField,
+    synd: &[u8],
+    parity: usize,
+) -> Result<Vec<u8>, FrameError> {
+    let mut error_locator: Vec<u8> = vec![1];
+    let mut old_locator: Vec<u8> = vec![1];
+    let shift: usize = synd.len() - parity;
+
+    for idx in 0..parity {
+        let k: usize = idx + shift;
+        let mut delta: u8 = synd[k];
+        for j in 1..error_locator.len() {
+            delta ^= field.mul(error_locator[error_locator.len() - 1 - j], synd[k - j]);
+        }
+
+        old_locator.push(0);
+        if delta != 0 {
+            if old_locator.len() > error_locator.len() {
+                let new_locator: Vec<u8> = field.poly_scale(&old_locator, delta);
+                old_locator = field.poly_scale(&error_locator, field.inverse(delta));
+                error_locator = new_locator;
+            }
+            let scaled: Vec<u8> = field.poly_scale(&old_locator, delta);
+            error_locator = field.poly_add(&error_locator, &scaled);
+        }
+    }
+
+    let leading: usize = error_locator.iter().take_while(|&&coef| coef == 0).count();
+    error_locator.drain(..leading);
+
+    let errors: usize = error_locator.len().saturating_sub(1);
+    if errors * 2 > parity {
+        return Err(FrameError::Uncorrectable);
+    }
+    Ok(error_locator)
+}
+
+fn find_errors(
+    field: &GaloisField,
+    error_locator: &[u8],
+    length: usize,
+) -> Result<Vec<usize>, FrameError> {
+    let errors: usize = error_locator.len() - 1;
+    let mut positions: Vec<usize> = Vec::with_capacity(errors);
+    for idx in 0..length {
+        if field.poly_eval(error_locator, field.pow(2, idx as i32)) == 0 {
+            positions.push(length - 1 - idx);
+        }
+    }
+
+    if positions.len() != errors {
+        return Err(FrameError::Uncorrectable);
+    }
+    Ok(positions)
+}
+
+fn correct_errata(field: &GaloisField, block: &[u8], synd: &[u8], positions: &[usize]) -> Vec<u8> {
+    let coef_positions: Vec<usize> = positions.iter().map(|&pos| block.len() - 1 - pos).collect();
+
+    let mut errata_locator: Vec<u8> = vec![1];
+    for &pos in &coef_positions {
+        let term: Vec<u8> = field.poly_add(&[1], &[field.pow(2, pos as i32), 0]);
+        errata_locator = field.poly_mul(&errata_locator, &term);
+    }
+
+    let reversed_synd: Vec<u8> = synd.iter().rev().copied().collect();
+    let mut divisor: Vec<u8> = vec![0; errata_locator.len() + 1];
+    divisor[0] = 1;
+    let product: Vec<u8> = field.poly_mul(&reversed_synd, &errata_locator);
+    let evaluator: Vec<u8> = field.poly_remainder(&product, &divisor);
+
+    let x: Vec<u8> = coef_positions
+        .iter()
+        .map(|&pos| field.pow(2, -((FIELD_SIZE - pos) as i32)))
+        .collect();
+
+    let mut magnitudes: Vec<u8> = vec![0; block.len()];
+    for (i, &xi) in x.iter().enumerate() {
+        let xi_inv: u8 = field.inverse(xi);
+
+        let mut locator_prime: u8 = 1;
+        for (j, &xj) in x.iter().enumerate() {
+            if j != i {
+                locator_prime = field.mul(locator_prime, 1 ^ field.mul(xi_inv, xj));
+            }
+        }
+
+        let y: u8 = field.mul(xi, field.poly_eval(&evaluator, xi_inv));
+        magnitudes[positions[i]] = field.div(y, locator_prime);
+    }
+
+    field.poly_add(block, &magnitudes)
+}
+
+#[test]
+fn test_reed_solomon_correction() {
+    let data: Vec<u8> = b"WaveTrx Reed-Solomon".to_vec();
+    let encoded: Vec<u8> = encode(&data, 8);
+    assert_eq!(encoded.len(), data.len() + 8);
+    assert_eq!(decode(&encoded, 8), Ok(data.clone()));
+
+    let mut corrupted: Vec<u8> = encoded.clone();
+    for idx in [0, 5, 13, 25] {
+        corrupted[idx] ^= 0x5A;
+    }
+    assert_eq!(decode(&corrupted, 8), Ok(data.clone()));
+
+    corrupted[9] ^= 0xFF;
+    assert_ne!(decode(&corrupted, 8), Ok(data));
+}
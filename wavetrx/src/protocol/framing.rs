@@ -0,0 +1,534 @@
+use std::error;
+use std::fmt;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BitOrder {
+    MsbFirst,
+    LsbFirst,
+}
+
+impl BitOrder {
+    pub fn mask(&self, idx: usize) -> u8 {
+        match self {
+            BitOrder::MsbFirst => 1 << (7 - idx),
+            BitOrder::LsbFirst => 1 << idx,
+        }
+    }
+
+    pub fn bit(&self, byte: u8, idx: usize) -> bool {
+        (byte & self.mask(idx)) != 0
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ByteOrder {
+    BigEndian,
+    LittleEndian,
+}
+
+impl ByteOrder {
+    pub fn u16_to_bytes(&self, value: u16) -> [u8; 2] {
+        match self {
+            ByteOrder::BigEndian => value.to_be_bytes(),
+            ByteOrder::LittleEndian => value.to_le_bytes(),
+        }
+    }
+
+    pub fn u16_from_bytes(&self, bytes: [u8; 2]) -> u16 {
+        match self {
+            ByteOrder::BigEndian => u16::from_be_bytes(bytes),
+            ByteOrder::LittleEndian => u16::from_le_bytes(bytes),
+        }
+    }
+
+    pub fn u32_to_bytes(&self, value: u32) -> [u8; 4] {
+        match self {
+            ByteOrder::BigEndian => value.to_be_bytes(),
+            ByteOrder::LittleEndian => value.to_le_bytes(),
+        }
+    }
+
+    pub fn u32_from_bytes(&self, bytes: [u8; 4]) -> u32 {
+        match self {
+            ByteOrder::BigEndian => u32::from_be_bytes(bytes),
+            ByteOrder::LittleEndian => u32::from_le_bytes(bytes),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Checksum {
+    None,
+    Crc16,
+}
+
+impl Checksum {
+    pub fn size(&self) -> usize {
+        match self {
+            Checksum::None => 0,
+            Checksum::Crc16 => 2,
+        }
+    }
+}
+
+// Width of the optional destination address in the frame header
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Addressing {
+    #[default]
+    None,
+    U8,
+    U16,
+}
+
+impl Addressing {
+    pub fn size(&self) -> usize {
+        match self {
+            Addressing::None => 0,
+            Addressing::U8 => 1,
+            Addressing::U16 => 2,
+        }
+    }
+
+    // The all-ones address is accepted by every station
+    pub fn broadcast(&self) -> u16 {
+        match self {
+            Addressing::None => 0,
+            Addressing::U8 => u8::MAX as u16,
+            Addressing::U16 => u16::MAX,
+        }
+    }
+}
+
+// CRC-16/CCITT-FALSE
+pub fn crc16(data: &[u8]) -> u16 {
+    let mut crc: u16 = 0xFFFF;
+    for &byte in data {
+        crc ^= (byte as u16) << 8;
+        for _ in 0..8 {
+            if (crc & 0x8000) != 0 {
+                crc = (crc << 1) ^ 0x1021;
+            } else {
+                crc <<= 1;
+            }
+        }
+    }
+    crc
+}
+
+// CRC-32/ISO-HDLC, as used by zip and PNG
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc: u32 = 0xFFFF_FFFF;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            if (crc & 1) != 0 {
+                crc = (crc >> 1) ^ 0xEDB8_8320;
+            } else {
+                crc >>= 1;
+            }
+        }
+    }
+    !crc
+}
+
+// Sender and per-sender counter of a sequenced frame; the source has the
+// width of the address and is absent when frames carry no address
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sequence {
+    pub source: Option<u16>,
+    pub number: u8,
+}
+
+impl Sequence {
+    pub fn new(source: Option<u16>, number: u8) -> Self {
+        Sequence { source, number }
+    }
+}
+
+// Destination, sequence and payload of a decoded frame
+pub type SequencedFrame = (Option<u16>, Option<Sequence>, Vec<u8>);
+
+pub const FRAME_PREAMBLE: u8 = 0xA5;
+pub const FRAME_HEADER_SIZE: usize = 3;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FrameError {
+    Truncated { expected: usize, actual: usize },
+    ChecksumMismatch { expected: u16, actual: u16 },
+    InvalidHeader,
+    Oversized { size: usize, max: usize },
+    Uncorrectable,
+    AddressOutOfRange { address: u16, max: u16 },
+    AuthenticationFailed,
+    DecompressionFailed,
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FrameError::Truncated { expected, actual } => {
+                write!(
+                    f,
+                    "Frame truncated: expected {} bytes, got {}",
+                    expected, actual
+                )
+            }
+            FrameError::ChecksumMismatch { expected, actual } => write!(
+                f,
+                "Checksum mismatch: expected {:#06X}, got {:#06X}",
+                expected, actual
+            ),
+            FrameError::InvalidHeader => write!(f, "Invalid frame header"),
+            FrameError::Oversized { size, max } => {
+                write!(
+                    f,
+                    "Payload of {} bytes exceeds the {} byte limit",
+                    size, max
+                )
+            }
+            FrameError::Uncorrectable => write!(f, "Too many errors to correct"),
+            FrameError::AddressOutOfRange { address, max } => {
+                write!(f, "Address {:#06X} exceeds the {:#06X} limit", address, max)
+            }
+            FrameError::AuthenticationFailed => {
+                write!(f, "Payload failed authentication; wrong key or tampered")
+            }
+            FrameError::DecompressionFailed => write!(f, "Compressed payload is corrupt"),
+        }
+    }
+}
+
+impl error::Error for FrameError {}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Framing {
+    pub bit_order: BitOrder,
+    pub byte_order: ByteOrder,
+    pub checksum: Checksum,
+    pub length_prefix: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub addressing: Addressing,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub compression: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub sequence: bool,
+}
+
+impl Framing {
+    pub fn new(bit_order: BitOrder, byte_order: ByteOrder) -> Self {
+        let checksum: Checksum = Checksum::None;
+        let length_prefix: bool = false;
+        let addressing: Addressing = Addressing::None;
+        let compression: bool = false;
+        let sequence: bool = false;
+        Framing {
+            bit_order,
+            byte_order,
+            checksum,
+            length_prefix,
+            addressing,
+            compression,
+            sequence,
+        }
+    }
+
+    pub fn with_checksum(mut self, checksum: Checksum) -> Self {
+        self.checksum = checksum;
+        self
+    }
+
+    pub fn with_length_prefix(mut self, length_prefix: bool) -> Self {
+        self.length_prefix = length_prefix;
+        self
+    }
+
+    pub fn with_addressing(mut self, addressing: Addressing) -> Self {
+        self.addressing = addressing;
+        self
+    }
+
+    // Payloads carry a one-byte flag saying whether they were deflated; this
+    // only compresses with the `compression` feature but always decodes stored
+    pub fn with_compression(mut self, compression: bool) -> Self {
+        self.compression = compression;
+        self
+    }
+
+    // The header carries the sender's address and a counter after the
+    // destination, so receivers can drop retransmitted or echoed frames
+    pub fn with_sequence(mut self, sequence: bool) -> Self {
+        self.sequence = sequence;
+        self
+    }
+
+    pub fn header_size(&self) -> usize {
+        self.sequence_offset() + self.sequence_size()
+    }
+
+    pub fn frame_size(&self, payload_len: usize) -> usize {
+        self.header_size() + payload_len + self.checksum.size()
+    }
+
+    pub fn header(&self, payload: &[u8]) -> Result<Vec<u8>, FrameError> {
+        if !self.length_prefix {
+            return Ok(Vec::new());
+        }
+
+        let length: u16 = u16::try_from(payload.len()).map_err(|_| FrameError::Oversized {
+            size: payload.len(),
+            max: u16::MAX as usize,
+        })?;
+        let mut header: Vec<u8> = vec![FRAME_PREAMBLE];
+        header.extend(self.byte_order.u16_to_bytes(length));
+        Ok(header)
+    }
+
+    pub fn parse_header(&self, header: &[u8]) -> Result<usize, FrameError> {
+        if header.len() < FRAME_HEADER_SIZE {
+            return Err(FrameError::Truncated {
+                expected: FRAME_HEADER_SIZE,
+                actual: header.len(),
+            });
+        }
+        if header[0] != FRAME_PREAMBLE {
+            return Err(FrameError::InvalidHeader);
+        }
+        Ok(self.byte_order.u16_from_bytes([header[1], header[2]]) as usize)
+    }
+
+    pub fn trailer(&self, body: &[u8]) -> Vec<u8> {
+        match self.checksum {
+            Checksum::None => Vec::new(),
+            Checksum::Crc16 => self.byte_order.u16_to_bytes(crc16(body)).to_vec(),
+        }
+    }
+
+    // With addressing enabled the frame goes to the broadcast address
+    pub fn encode(&self, payload: &[u8]) -> Result<Vec<u8>, FrameError> {
+        self.encode_to(payload, self.addressing.broadcast())
+    }
+
+    pub fn encode_to(&self, payload: &[u8], address: u16) -> Result<Vec<u8>, FrameError> {
+        self.encode_sequenced(payload, address, Sequence::default())
+    }
+
+    // Without a source the sequence is sent from the broadcast address
+    pub fn encode_sequenced(
+        &self,
+        payload: &[u8],
+        address: u16,
+        sequence: Sequence,
+    ) -> Result<Vec<u8>, FrameError> {
+        let mut frame: Vec<u8> = self.header(payload)?;
+        frame.extend(self.address_bytes(address)?);
+        if self.sequence {
+            let source: u16 = sequence.source.unwrap_or(self.addressing.broadcast());
+            frame.extend(self.address_bytes(source)?);
+            frame.push(sequence.number);
+        }
+        frame.extend_from_slice(payload);
+        frame.extend(self.trailer(&frame));
+        Ok(frame)
+    }
+
+    pub fn decode(&self, frame: &[u8]) -> Result<Vec<u8>, FrameError> {
+        let (_, payload): (Option<u16>, Vec<u8>) = self.decode_addressed(frame)?;
+        Ok(payload)
+    }
+
+    pub fn decode_addressed(&self, frame: &[u8]) -> Result<(Option<u16>, Vec<u8>), FrameError> {
+        let (address, _, payload): SequencedFrame = self.decode_sequenced(frame)?;
+        Ok((address, payload))
+    }
+
+    // The address and sequence are only read once the checksum has vouched
+    // for them
+    pub fn decode_sequenced(&self, frame: &[u8]) -> Result<SequencedFrame, FrameError> {
+        let frame_size: usize = match self.length_prefix {
+            true => self.frame_size(self.parse_header(frame)?),
+            false => frame.len().max(self.checksum.size()),
+        };
+        if frame.len() < frame_size {
+            return Err(FrameError::Truncated {
+                expected: frame_size,
+                actual: frame.len(),
+            });
+        }
+
+        let trailer_size: usize = self.checksum.size();
+        let (body, trailer): (&[u8], &[u8]) =
+            frame[..frame_size].split_at(frame_size - trailer_size);
+        if let Checksum::Crc16 = self.checksum {
+            let expected: u16 = self.byte_order.u16_from_bytes([trailer[0], trailer[1]]);
+            let actual: u16 = crc16(body);
+            if expected != actual {
+                return Err(FrameError::ChecksumMismatch { expected, actual });
+            }
+        }
+        if body.len() < self.header_size() {
+            return Err(FrameError::Truncated {
+                expected: self.header_size(),
+                actual: body.len(),
+            });
+        }
+        let address: Option<u16> = self.parse_address(body, self.address_offset());
+        let sequence: Option<Sequence> = self.parse_sequence(body);
+        Ok((address, sequence, body[self.header_size()..].to_vec()))
+    }
+}
+
+impl Framing {
+    fn address_offset(&self) -> usize {
+        match self.length_prefix {
+            true => FRAME_HEADER_SIZE,
+            false => 0,
+        }
+    }
+
+    fn sequence_offset(&self) -> usize {
+        self.address_offset() + self.addressing.size()
+    }
+
+    fn sequence_size(&self) -> usize {
+        match self.sequence {
+            true => self.addressing.size() + 1,
+            false => 0,
+        }
+    }
+
+    fn address_bytes(&self, address: u16) -> Result<Vec<u8>, FrameError> {
+        match self.addressing {
+            Addressing::None => Ok(Vec::new()),
+            Addressing::U8 => match u8::try_from(address) {
+                Ok(address) => Ok(vec![address]),
+                Err(_) => Err(FrameError::AddressOutOfRange {
+                    address,
+                    max: u8::MAX as u16,
+                }),
+            },
+            Addressing::U16 => Ok(self.byte_order.u16_to_bytes(address).to_vec()),
+        }
+    }
+
+    fn parse_address(&self, body: &[u8], offset: usize) -> Option<u16> {
+        match self.addressing {
+            Addressing::None => None,
+            Addressing::U8 => Some(body[offset] as u16),
+            Addressing::U16 => Some(
+                self.byte_order
+                    .u16_from_bytes([body[offset], body[offset + 1]]),
+            ),
+        }
+    }
+
+    fn parse_sequence(&self, body: &[u8]) -> Option<Sequence> {
+        if !self.sequence {
+            return None;
+        }
+        let offset: usize = self.sequence_offset();
+        let source: Option<u16> = self.parse_address(body, offset);
+        let number: u8 = body[offset + self.addressing.size()];
+        Some(Sequence::new(source, number))
+    }
+}
+
+impl Default for Framing {
+    fn default() -> Self {
+        Framing::new(BitOrder::MsbFirst, ByteOrder::BigEndian)
+    }
+}
+
+#[test]
+fn test_crc16_frame() {
+    assert_eq!(crc16(b"123456789"), 0x29B1);
+
+    let framing: Framing = Framing::default().with_checksum(Checksum::Crc16);
+    let mut frame: Vec<u8> = framing.encode(b"WaveTrx").unwrap();
+    assert_eq!(frame.len(), 9);
+    assert_eq!(framing.decode(&frame), Ok(b"WaveTrx".to_vec()));
+
+    frame[0] ^= 0x01;
+    let result: Result<Vec<u8>, FrameError> = framing.decode(&frame);
+    assert!(matches!(result, Err(FrameError::ChecksumMismatch { .. })));
+}
+
+#[test]
+fn test_length_prefixed_frame() {
+    let framing: Framing = Framing::default()
+        .with_checksum(Checksum::Crc16)
+        .with_length_prefix(true);
+    let mut frame: Vec<u8> = framing.encode(b"WaveTrx").unwrap();
+    assert_eq!(frame[..3], [FRAME_PREAMBLE, 0x00, 0x07]);
+    assert_eq!(framing.parse_header(&frame), Ok(7));
+
+    frame.extend([0x00, 0x00]);
+    assert_eq!(framing.decode(&frame), Ok(b"WaveTrx".to_vec()));
+    assert!(matches!(
+        framing.decode(&frame[..8]),
+        Err(FrameError::Truncated { .. })
+    ));
+
+    frame[0] = 0x00;
+    assert_eq!(framing.decode(&frame), Err(FrameError::InvalidHeader));
+}
+
+#[test]
+fn test_addressed_frame() {
+    let framing: Framing = Framing::default()
+        .with_checksum(Checksum::Crc16)
+        .with_length_prefix(true)
+        .with_addressing(Addressing::U16);
+    let frame: Vec<u8> = framing.encode_to(b"Wt", 0x0102).unwrap();
+    assert_eq!(frame[..5], [FRAME_PREAMBLE, 0x00, 0x02, 0x01, 0x02]);
+    assert_eq!(
+        framing.decode_addressed(&frame),
+        Ok((Some(0x0102), b"Wt".to_vec()))
+    );
+
+    let framing: Framing = Framing::default().with_addressing(Addressing::U8);
+    let frame: Vec<u8> = framing.encode(b"Wt").unwrap();
+    assert_eq!(frame, vec![0xFF, b'W', b't']);
+    assert_eq!(
+        framing.decode_addressed(&frame),
+        Ok((Some(0xFF), b"Wt".to_vec()))
+    );
+    assert!(matches!(
+        framing.encode_to(b"Wt", 0x0100),
+        Err(FrameError::AddressOutOfRange { .. })
+    ));
+}
+
+#[test]
+fn test_sequenced_frame() {
+    let framing: Framing = Framing::default()
+        .with_checksum(Checksum::Crc16)
+        .with_addressing(Addressing::U8)
+        .with_sequence(true);
+    assert_eq!(framing.header_size(), 3);
+    let sequence: Sequence = Sequence::new(Some(0x07), 0x2A);
+    let frame: Vec<u8> = framing.encode_sequenced(b"Wt", 0x01, sequence).unwrap();
+    assert_eq!(frame[..5], [0x01, 0x07, 0x2A, b'W', b't']);
+    assert_eq!(
+        framing.decode_sequenced(&frame),
+        Ok((Some(0x01), Some(sequence), b"Wt".to_vec()))
+    );
+    assert_eq!(framing.decode(&frame), Ok(b"Wt".to_vec()));
+
+    // Without addressing only the counter is sent
+    let framing: Framing = Framing::default().with_sequence(true);
+    let frame: Vec<u8> = framing
+        .encode_sequenced(b"Wt", 0, Sequence::new(Some(0x07), 3))
+        .unwrap();
+    assert_eq!(frame, vec![0x03, b'W', b't']);
+    assert_eq!(
+        framing.decode_sequenced(&frame),
+        Ok((None, Some(Sequence::new(None, 3)), b"Wt".to_vec()))
+    );
+}
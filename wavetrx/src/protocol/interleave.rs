@@ -0,0 +1,139 @@
+use crate::protocol::bitvec::BitVec;
+use crate::protocol::fec::Fec;
+
+// Reorders FEC output before modulation so a burst of corrupted symbols,
+// e.g. from a door slam, lands on many codewords instead of wiping out one
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Interleaving {
+    #[default]
+    None,
+    // Each block holds `depth` codewords as rows and is sent column by
+    // column, so a burst of up to `depth` bits hits every codeword at most once
+    Block {
+        depth: usize,
+    },
+}
+
+impl Interleaving {
+    pub fn is_none(&self) -> bool {
+        matches!(
+            self,
+            Interleaving::None | Interleaving::Block { depth: 0 | 1 }
+        )
+    }
+
+    // Bits per interleaving block; the last block of a frame may be shorter
+    pub fn block_size(&self, fec: &Fec) -> usize {
+        match self {
+            Interleaving::None => fec.codeword_bits(),
+            Interleaving::Block { depth } => (*depth).max(1) * fec.codeword_bits(),
+        }
+    }
+
+    pub fn interleave(&self, bits: &BitVec, fec: &Fec) -> BitVec {
+        if self.is_none() {
+            return bits.iter_bits().collect();
+        }
+        let source: Vec<bool> = bits.iter_bits().collect();
+        self.order(source.len(), fec)
+            .into_iter()
+            .map(|idx| source[idx])
+            .collect()
+    }
+
+    // Trailing padding from the last symbol is dropped first, as it would
+    // otherwise shift the layout of the final block
+    pub fn deinterleave(&self, bits: &BitVec, fec: &Fec) -> BitVec {
+        if self.is_none() {
+            return bits.iter_bits().collect();
+        }
+        let len: usize = fec.aligned_bits(bits.len());
+        self.restore(bits, len, fec)
+    }
+
+    // The leading bits in their original order, once the first block has
+    // fully arrived; frames shorter than a block only resolve at the end
+    pub fn prefix(&self, bits: &BitVec, fec: &Fec) -> Option<BitVec> {
+        if self.is_none() {
+            return Some(bits.iter_bits().collect());
+        }
+        let block_size: usize = self.block_size(fec);
+        if bits.len() < block_size {
+            return None;
+        }
+        Some(self.restore(bits, block_size, fec))
+    }
+}
+
+impl Interleaving {
+    // Source index of every sent bit, block by block
+    fn order(&self, len: usize, fec: &Fec) -> Vec<usize> {
+        let row: usize = fec.codeword_bits().max(1);
+        let block_size: usize = self.block_size(fec);
+
+        let mut order: Vec<usize> = Vec::with_capacity(len);
+        for start in (0..len).step_by(block_size) {
+            let size: usize = block_size.min(len - start);
+            let rows: usize = size.div_ceil(row);
+            for column in 0..row {
+                for idx in (0..rows)
+                    .map(|r| r * row + column)
+                    .filter(|idx| *idx < size)
+                {
+                    order.push(start + idx);
+                }
+            }
+        }
+        order
+    }
+
+    // The first `len` received bits back in their original order
+    fn restore(&self, bits: &BitVec, len: usize, fec: &Fec) -> BitVec {
+        let mut source: Vec<bool> = vec![false; len];
+        for (bit, idx) in bits.iter_bits().zip(self.order(len, fec)) {
+            source[idx] = bit;
+        }
+        source.into_iter().collect()
+    }
+}
+
+#[test]
+fn test_interleaved_burst() {
+    use crate::protocol::framing::BitOrder;
+
+    let fec: Fec = Fec::Hamming74;
+    let interleaving: Interleaving = Interleaving::Block { depth: 5 };
+    let frame: Vec<u8> = b"door slam".to_vec();
+    let encoded: BitVec = fec.encode(&frame, BitOrder::MsbFirst);
+
+    // Five bits in a row flipped, plus two bits of symbol padding
+    let burst = |bits: &BitVec| -> BitVec {
+        let mut bits: BitVec = bits
+            .iter_bits()
+            .enumerate()
+            .map(|(idx, bit)| bit ^ (20..25).contains(&idx))
+            .collect();
+        bits.push_bit(false);
+        bits.push_bit(false);
+        bits
+    };
+
+    let sent: BitVec = interleaving.interleave(&encoded, &fec);
+    assert_eq!(sent.len(), encoded.len());
+    assert_ne!(sent, encoded);
+    let received: BitVec = interleaving.deinterleave(&burst(&sent), &fec);
+    assert_eq!(fec.decode(&received, BitOrder::MsbFirst).unwrap(), frame);
+    assert_ne!(
+        fec.decode(&burst(&encoded), BitOrder::MsbFirst).unwrap(),
+        frame
+    );
+
+    // The first block is readable on its own
+    let prefix: BitVec = interleaving.prefix(&sent, &fec).unwrap();
+    assert!(encoded
+        .iter_bits()
+        .zip(prefix.iter_bits())
+        .all(|(a, b)| a == b));
+    assert_eq!(prefix.len(), 35);
+}
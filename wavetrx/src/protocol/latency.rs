@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+// Where the time went between `Transceiver::measure_latency` being called
+// and its answer being decoded
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LatencyReport {
+    encode: Duration,
+    queue: Duration,
+    airtime: Duration,
+    decode: Duration,
+}
+
+impl LatencyReport {
+    pub fn new(encode: Duration, queue: Duration, airtime: Duration, decode: Duration) -> Self {
+        LatencyReport {
+            encode,
+            queue,
+            airtime,
+            decode,
+        }
+    }
+
+    // Building the samples from the payload
+    pub fn encode(&self) -> Duration {
+        self.encode
+    }
+
+    // Audio already queued ahead of the frame, plus the device's output latency
+    pub fn queue(&self) -> Duration {
+        self.queue
+    }
+
+    // Length of the frame itself
+    pub fn airtime(&self) -> Duration {
+        self.airtime
+    }
+
+    // From the frame leaving the speaker to the answer being decoded; for a
+    // reply this takes in the far end's turnaround and the reply's airtime
+    pub fn decode(&self) -> Duration {
+        self.decode
+    }
+
+    pub fn total(&self) -> Duration {
+        self.encode + self.queue + self.airtime + self.decode
+    }
+}
+
+#[test]
+fn test_latency_report() {
+    let report: LatencyReport = LatencyReport::new(
+        Duration::from_millis(2),
+        Duration::from_millis(40),
+        Duration::from_millis(600),
+        Duration::from_millis(25),
+    );
+    assert_eq!(report.total(), Duration::from_millis(667));
+    assert!(report.total() > report.airtime());
+}
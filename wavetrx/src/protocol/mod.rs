@@ -1,3 +1,23 @@
+pub mod arq;
+pub mod bitvec;
+pub mod compress;
+#[cfg(feature = "crypto")]
+pub mod crypto;
+pub mod dtmf;
+pub mod fec;
+pub mod framing;
+pub mod interleave;
+pub mod latency;
+pub mod modulation;
+pub mod morse;
+pub mod ofdm;
+pub mod payload;
+pub mod preamble;
 pub mod profile;
+pub mod ranging;
 pub mod rx;
+pub mod scramble;
+#[cfg(feature = "device")]
+pub mod transceiver;
+pub mod transfer;
 pub mod tx;
@@ -0,0 +1,31 @@
+use super::Demodulator;
+use super::Modulator;
+
+use crate::audio::spectrum::MagnitudeBackend;
+use crate::audio::types::NormSamples;
+use crate::error::WavetrxError;
+use crate::protocol::rx::Receiver;
+use crate::protocol::tx::Transmitter;
+
+impl Modulator for Transmitter {
+    fn modulate(&self, data: &[u8]) -> Result<Vec<f32>, WavetrxError> {
+        self.create(data)
+    }
+}
+
+impl<M> Demodulator for Receiver<M>
+where
+    M: MagnitudeBackend,
+{
+    fn push_samples(&mut self, samples: &[f32]) {
+        self.add_samples(&mut NormSamples::from_slice(samples));
+        self.analyze_full_buffer();
+    }
+
+    fn take_payloads(&mut self) -> Vec<Vec<u8>> {
+        self.take_messages()
+            .into_iter()
+            .map(|message| message.data().to_vec())
+            .collect()
+    }
+}
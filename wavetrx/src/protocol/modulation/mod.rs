@@ -0,0 +1,72 @@
+mod fsk;
+mod psk;
+
+pub use psk::DbpskDemodulator;
+pub use psk::DbpskModulator;
+
+use crate::audio::spectrum::GoertzelMagnitude;
+use crate::audio::types::AudioSpec;
+use crate::error::WavetrxError;
+use crate::protocol::profile::Profile;
+use crate::protocol::rx::Receiver;
+use crate::protocol::tx::Transmitter;
+
+// How the encoded bits ride on the audio. `Fsk` is the marker and tone scheme
+// of `Transmitter` and `Receiver`; `Dbpsk` flips the phase of a single carrier
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Modulation {
+    #[default]
+    Fsk,
+    Dbpsk {
+        carrier: f32,
+    },
+}
+
+impl Modulation {
+    pub fn carrier(&self) -> Option<f32> {
+        match self {
+            Modulation::Fsk => None,
+            Modulation::Dbpsk { carrier } => Some(*carrier),
+        }
+    }
+
+    // For the FSK-only `Transmitter` and `Receiver`; other schemes go through
+    // `modulator` and `demodulator`
+    pub fn require_fsk(&self) -> Result<(), WavetrxError> {
+        match self {
+            Modulation::Fsk => Ok(()),
+            modulation => Err(WavetrxError::ProfileInvalid(format!(
+                "{:?} modulation needs modulator() and demodulator()",
+                modulation
+            ))),
+        }
+    }
+}
+
+pub trait Modulator {
+    fn modulate(&self, data: &[u8]) -> Result<Vec<f32>, WavetrxError>;
+}
+
+// Fed blocks of samples; payloads come out in the order they completed
+pub trait Demodulator {
+    fn push_samples(&mut self, samples: &[f32]);
+    fn take_payloads(&mut self) -> Vec<Vec<u8>>;
+}
+
+pub fn modulator(profile: &Profile, spec: &AudioSpec) -> Box<dyn Modulator> {
+    match profile.modulation {
+        Modulation::Fsk => Box::new(Transmitter::new(profile, spec)),
+        Modulation::Dbpsk { .. } => Box::new(DbpskModulator::new(profile, spec)),
+    }
+}
+
+pub fn demodulator(profile: &Profile, spec: &AudioSpec) -> Box<dyn Demodulator> {
+    match profile.modulation {
+        Modulation::Fsk => {
+            let receiver: Receiver<GoertzelMagnitude> = Receiver::new(*profile, *spec);
+            Box::new(receiver)
+        }
+        Modulation::Dbpsk { .. } => Box::new(DbpskDemodulator::new(profile, spec)),
+    }
+}
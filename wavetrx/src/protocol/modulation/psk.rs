@@ -0,0 +1,274 @@
+use std::f32::consts;
+use std::mem;
+
+use log::warn;
+
+use super::Demodulator;
+use super::Modulator;
+
+use crate::audio::types::AudioSpec;
+use crate::consts::DBPSK_CARRIER_HZ;
+use crate::consts::DBPSK_CHIRP;
+use crate::consts::DBPSK_CHIRP_SPAN_HZ;
+use crate::embedded::sample_size;
+use crate::embedded::sine_fade;
+use crate::error::WavetrxError;
+use crate::protocol::bitvec::BitPadding;
+use crate::protocol::bitvec::BitVec;
+use crate::protocol::compress::decompress;
+use crate::protocol::framing::BitOrder;
+use crate::protocol::framing::FrameError;
+use crate::protocol::preamble::chirp_phase;
+use crate::protocol::profile::Profile;
+use crate::protocol::rx::PreambleDetector;
+use crate::protocol::tx::Transmitter;
+
+// Coded frame length in bits, sent uncoded ahead of the frame
+const LENGTH_PREFIX_BITS: usize = 16;
+
+// Symbols last one profile tone and run back to back; the carrier phase is
+// counted from the first symbol so it carries across symbol boundaries
+#[derive(Copy, Clone, Debug)]
+struct Carrier {
+    frequency: f32,
+    sample_rate: f32,
+    symbol_size: usize,
+    chirp_size: usize,
+}
+
+impl Carrier {
+    // FSK profiles fall back to `DBPSK_CARRIER_HZ`
+    fn new(profile: &Profile, spec: &AudioSpec) -> Self {
+        let frequency: f32 = profile.modulation.carrier().unwrap_or(DBPSK_CARRIER_HZ);
+        let sample_rate: f32 = spec.sample_rate() as f32;
+        let symbol_size: usize = profile.pulses.into_sized(spec).tone_size().max(1);
+        let chirp_size: usize = sample_size(spec.sample_rate(), DBPSK_CHIRP.as_micros() as usize);
+        Carrier {
+            frequency,
+            sample_rate,
+            symbol_size,
+            chirp_size,
+        }
+    }
+
+    fn chirp(&self) -> (f32, f32) {
+        (
+            self.frequency - DBPSK_CHIRP_SPAN_HZ,
+            self.frequency + DBPSK_CHIRP_SPAN_HZ,
+        )
+    }
+
+    // Whole cycles are dropped in f64 so long frames keep their precision
+    fn phase(&self, idx: usize) -> f32 {
+        let cycles: f64 = self.frequency as f64 * idx as f64 / self.sample_rate as f64;
+        2.0 * consts::PI * cycles.fract() as f32
+    }
+}
+
+// Frames are built by the `Transmitter` pipeline, so addressing, sequence
+// numbers and sealing carry over; only the modem differs
+pub struct DbpskModulator {
+    encoder: Transmitter,
+    spec: AudioSpec,
+    carrier: Carrier,
+}
+
+impl DbpskModulator {
+    pub fn new(profile: &Profile, spec: &AudioSpec) -> Self {
+        let encoder: Transmitter = Transmitter::new(profile, spec);
+        Self::from_transmitter(encoder)
+    }
+
+    // Keeps the transmitter's destination, station id, sequence and cipher
+    pub fn from_transmitter(encoder: Transmitter) -> Self {
+        let spec: AudioSpec = encoder.spec();
+        let carrier: Carrier = Carrier::new(&encoder.profile(), &spec);
+        DbpskModulator {
+            encoder,
+            spec,
+            carrier,
+        }
+    }
+}
+
+impl Modulator for DbpskModulator {
+    // Chirp, a reference symbol, then one phase flip per set bit
+    fn modulate(&self, data: &[u8]) -> Result<Vec<f32>, WavetrxError> {
+        let bits: BitVec = self.encode_bits(data)?;
+        let (from, to): (f32, f32) = self.carrier.chirp();
+        let chirp_size: usize = self.carrier.chirp_size;
+        let symbol_size: usize = self.carrier.symbol_size;
+
+        let mut samples: Vec<f32> = Vec::with_capacity(chirp_size + (bits.len() + 2) * symbol_size);
+        for idx in 0..chirp_size {
+            let phase: f32 = chirp_phase(from, to, idx, chirp_size, &self.spec);
+            samples.push(phase.sin() * sine_fade(idx, chirp_size, chirp_size / 20));
+        }
+
+        let mut inverted: bool = false;
+        self.append_symbol(&mut samples, 0, inverted);
+        for (symbol_idx, bit) in bits.iter_bits().enumerate() {
+            inverted ^= bit;
+            self.append_symbol(&mut samples, symbol_idx + 1, inverted);
+        }
+        samples.extend(vec![0.0; symbol_size]);
+        Ok(samples)
+    }
+}
+
+impl DbpskModulator {
+    fn encode_bits(&self, data: &[u8]) -> Result<BitVec, FrameError> {
+        let coded: BitVec = self.encoder.encode_frame(data)?;
+        let length: u16 = u16::try_from(coded.len()).map_err(|_| FrameError::Oversized {
+            size: coded.len() / 8,
+            max: u16::MAX as usize / 8,
+        })?;
+
+        let mut bits: BitVec = BitVec::from_bytes(&length.to_be_bytes(), BitOrder::MsbFirst);
+        for bit in coded.iter_bits() {
+            bits.push_bit(bit);
+        }
+        Ok(bits)
+    }
+
+    // A short raised-cosine dip at each edge keeps the phase flips from splattering
+    fn append_symbol(&self, samples: &mut Vec<f32>, symbol_idx: usize, inverted: bool) {
+        let symbol_size: usize = self.carrier.symbol_size;
+        let sign: f32 = if inverted { -1.0 } else { 1.0 };
+        for idx in 0..symbol_size {
+            let phase: f32 = self.carrier.phase(symbol_idx * symbol_size + idx);
+            let fade: f32 = sine_fade(idx, symbol_size, symbol_size / 8);
+            samples.push(sign * phase.cos() * fade);
+        }
+    }
+}
+
+// Syncs on the chirp, correlates each symbol against the carrier and reads
+// a bit from the phase change between neighbouring symbols
+pub struct DbpskDemodulator {
+    profile: Profile,
+    carrier: Carrier,
+    detector: PreambleDetector,
+    buffer: Vec<f32>,
+    payloads: Vec<Vec<u8>>,
+}
+
+impl DbpskDemodulator {
+    pub fn new(profile: &Profile, spec: &AudioSpec) -> Self {
+        let profile: Profile = *profile;
+        let carrier: Carrier = Carrier::new(&profile, spec);
+        let (from, to): (f32, f32) = carrier.chirp();
+        let detector: PreambleDetector =
+            PreambleDetector::chirp(from, to, carrier.chirp_size, spec);
+        let buffer: Vec<f32> = Vec::new();
+        let payloads: Vec<Vec<u8>> = Vec::new();
+        DbpskDemodulator {
+            profile,
+            carrier,
+            detector,
+            buffer,
+            payloads,
+        }
+    }
+
+    pub fn resync(&mut self) {
+        self.buffer.clear();
+    }
+}
+
+impl Demodulator for DbpskDemodulator {
+    fn push_samples(&mut self, samples: &[f32]) {
+        self.buffer.extend_from_slice(samples);
+
+        while let Some(start) = self.detector.find(&self.buffer) {
+            // The peak search needs a full chirp past the first match
+            if self.buffer.len() < start + 2 * self.detector.len() {
+                self.buffer.drain(..start);
+                return;
+            }
+            let first: usize = start + self.detector.len();
+            let (bits, end): (BitVec, usize) = match self.read_bits(first) {
+                Some(read) => read,
+                None => {
+                    self.buffer.drain(..start);
+                    return;
+                }
+            };
+            match self.decode_bits(&bits) {
+                Ok(payload) => self.payloads.push(payload),
+                Err(err) => warn!("DBPSK frame error: {}", err),
+            }
+            self.buffer.drain(..end);
+        }
+
+        // Only a partial chirp can still be waiting at the end
+        let keep: usize = self.detector.len();
+        if self.buffer.len() > keep {
+            self.buffer.drain(..self.buffer.len() - keep);
+        }
+    }
+
+    fn take_payloads(&mut self) -> Vec<Vec<u8>> {
+        mem::take(&mut self.payloads)
+    }
+}
+
+impl DbpskDemodulator {
+    // Coded frame bits and the buffer index past them, once all have arrived
+    fn read_bits(&self, first: usize) -> Option<(BitVec, usize)> {
+        let mut previous: (f32, f32) = self.correlate(first, 0)?;
+        let mut bits: BitVec = BitVec::new();
+        let mut total: usize = LENGTH_PREFIX_BITS;
+
+        while bits.len() < total {
+            let current: (f32, f32) = self.correlate(first, bits.len() + 1)?;
+            let dot: f32 = current.0 * previous.0 + current.1 * previous.1;
+            bits.push_bit(dot < 0.0);
+            previous = current;
+
+            if bits.len() == LENGTH_PREFIX_BITS {
+                let prefix: Vec<u8> = bits.to_bytes(BitOrder::MsbFirst, BitPadding::Truncate);
+                total += u16::from_be_bytes([prefix[0], prefix[1]]) as usize;
+            }
+        }
+
+        let coded: BitVec = bits.iter_bits().skip(LENGTH_PREFIX_BITS).collect();
+        let end: usize = first + (total + 1) * self.carrier.symbol_size;
+        Some((coded, end))
+    }
+
+    // In-phase and quadrature sums of one symbol against the carrier
+    fn correlate(&self, first: usize, symbol_idx: usize) -> Option<(f32, f32)> {
+        let symbol_size: usize = self.carrier.symbol_size;
+        let offset: usize = symbol_idx * symbol_size;
+        let samples: &[f32] = self
+            .buffer
+            .get(first + offset..first + offset + symbol_size)?;
+
+        let mut in_phase: f32 = 0.0;
+        let mut quadrature: f32 = 0.0;
+        for (idx, sample) in samples.iter().enumerate() {
+            let phase: f32 = self.carrier.phase(offset + idx);
+            in_phase += sample * phase.cos();
+            quadrature += sample * phase.sin();
+        }
+        Some((in_phase, quadrature))
+    }
+
+    fn decode_bits(&self, bits: &BitVec) -> Result<Vec<u8>, FrameError> {
+        let bits: BitVec = self.profile.scrambling.descramble(bits);
+        let bits: BitVec = self
+            .profile
+            .interleaving
+            .deinterleave(&bits, &self.profile.fec);
+        let frame: Vec<u8> = self
+            .profile
+            .fec
+            .decode(&bits, self.profile.framing.bit_order)?;
+        let data: Vec<u8> = self.profile.framing.decode(&frame)?;
+        match self.profile.framing.compression {
+            true => decompress(&data),
+            false => Ok(data),
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use std::time::Duration;
+
+use crate::audio::spectrum::GoertzelMagnitude;
+use crate::audio::types::AudioSpec;
+use crate::consts::MORSE_FREQUENCY;
+use crate::consts::MORSE_MIN_DB;
+use crate::consts::MORSE_WINDOWS_PER_UNIT;
+use crate::consts::MORSE_WPM;
+use crate::error::WavetrxError;
+use crate::protocol::profile::Pulses;
+use crate::protocol::profile::SizedPulses;
+use crate::protocol::tx::ToneGenerator;
+
+const MORSE_TABLE: [(char, &str); 54] = [
+    ('A', ".-"),
+    ('B', "-..."),
+    ('C', "-.-."),
+    ('D', "-.."),
+    ('E', "."),
+    ('F', "..-."),
+    ('G', "--."),
+    ('H', "...."),
+    ('I', ".."),
+    ('J', ".---"),
+    ('K', "-.-"),
+    ('L', ".-.."),
+    ('M', "--"),
+    ('N', "-."),
+    ('O', "---"),
+    ('P', ".--."),
+    ('Q', "--.-"),
+    ('R', ".-."),
+    ('S', "..."),
+    ('T', "-"),
+    ('U', "..-"),
+    ('V', "...-"),
+    ('W', ".--"),
+    ('X', "-..-"),
+    ('Y', "-.--"),
+    ('Z', "--.."),
+    ('0', "-----"),
+    ('1', ".----"),
+    ('2', "..---"),
+    ('3', "...--"),
+    ('4', "....-"),
+    ('5', "....."),
+    ('6', "-...."),
+    ('7', "--..."),
+    ('8', "---.."),
+    ('9', "----."),
+    ('.', ".-.-.-"),
+    (',', "--..--"),
+    ('?', "..--.."),
+    ('\'', ".----."),
+    ('!', "-.-.--"),
+    ('/', "-..-."),
+    ('(', "-.--."),
+    (')', "-.--.-"),
+    ('&', ".-..."),
+    (':', "---..."),
+    (';', "-.-.-."),
+    ('=', "-...-"),
+    ('+', ".-.-."),
+    ('-', "-....-"),
+    ('_', "..--.-"),
+    ('"', ".-..-."),
+    ('$', "...-..-"),
+    ('@', ".--.-."),
+];
+
+pub fn char_to_code(character: char) -> Option<&'static str> {
+    let character: char = character.to_ascii_uppercase();
+    MORSE_TABLE
+        .iter()
+        .find(|(key, _)| *key == character)
+        .map(|(_, code)| *code)
+}
+
+pub fn code_to_char(code: &str) -> Option<char> {
+    MORSE_TABLE
+        .iter()
+        .find(|(_, value)| *value == code)
+        .map(|(key, _)| *key)
+}
+
+// PARIS timing: one dot lasts 1.2 / WPM seconds
+pub fn unit_duration(wpm: f32) -> Duration {
+    Duration::from_secs_f32(1.2 / wpm)
+}
+
+fn check_wpm(wpm: f32) -> Result<f32, WavetrxError> {
+    match wpm.is_finite() && wpm > 0.0 {
+        true => Ok(wpm),
+        false => Err(WavetrxError::InvalidInput(format!(
+            "Morse speed must be above 0 WPM, got {}",
+            wpm
+        ))),
+    }
+}
+
+pub struct MorseEncoder {
+    spec: AudioSpec,
+    wpm: f32,
+    frequency: f32,
+}
+
+impl MorseEncoder {
+    pub fn new(spec: &AudioSpec) -> Self {
+        let spec: AudioSpec = *spec;
+        let wpm: f32 = MORSE_WPM;
+        let frequency: f32 = MORSE_FREQUENCY;
+        MorseEncoder {
+            spec,
+            wpm,
+            frequency,
+        }
+    }
+
+    // Fails unless `wpm` is above zero
+    pub fn with_wpm(mut self, wpm: f32) -> Result<Self, WavetrxError> {
+        self.wpm = check_wpm(wpm)?;
+        Ok(self)
+    }
+
+    pub fn with_frequency(mut self, frequency: f32) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    // Words are split on whitespace; characters outside the table are rejected.
+    // Every element is followed by one unit of silence, which the letter and
+    // word gaps stretch to three and seven units
+    pub fn encode(&self, text: &str) -> Result<Vec<f32>, WavetrxError> {
+        let unit: usize = unit_duration(self.wpm).as_micros() as usize;
+        let fade: f32 = 0.1;
+        let mut tone: ToneGenerator = ToneGenerator::new(&self.spec)?;
+
+        for (word_idx, word) in text.split_whitespace().enumerate() {
+            if word_idx > 0 {
+                tone.append_silence(unit * 6)?;
+            }
+            for (char_idx, character) in word.chars().enumerate() {
+                let code: &str = char_to_code(character).ok_or_else(|| {
+                    WavetrxError::InvalidInput(format!("No Morse code for: {}", character))
+                })?;
+                if char_idx > 0 {
+                    tone.append_silence(unit * 2)?;
+                }
+                for element in code.chars() {
+                    let length: usize = if element == '-' { unit * 3 } else { unit };
+                    tone.append_sine_faded_tone(self.frequency, length, fade)?;
+                    tone.append_silence(unit)?;
+                }
+            }
+        }
+        Ok(tone.samples())
+    }
+}
+
+// Keys the envelope on a Goertzel probe of the carrier and classifies the
+// on/off runs against the configured dot length
+pub struct MorseDecoder {
+    magnitude: GoertzelMagnitude,
+    window: usize,
+    frequency: f32,
+}
+
+impl MorseDecoder {
+    pub fn new(spec: &AudioSpec) -> Self {
+        MorseDecoder::build(spec, MORSE_WPM, MORSE_FREQUENCY)
+    }
+
+    // Fails unless `wpm` is above zero
+    pub fn with_settings(spec: &AudioSpec, wpm: f32, frequency: f32) -> Result<Self, WavetrxError> {
+        Ok(MorseDecoder::build(spec, check_wpm(wpm)?, frequency))
+    }
+
+    // Unknown symbols decode as '?' rather than aborting the whole message
+    pub fn decode(&self, samples: &[f32]) -> String {
+        let mut text: String = String::new();
+        let mut code: String = String::new();
+
+        for (keyed, windows) in self.runs(samples) {
+            let units: f32 = windows as f32 / MORSE_WINDOWS_PER_UNIT as f32;
+            if keyed {
+                code.push(if units < 2.0 { '.' } else { '-' });
+                continue;
+            }
+            // Gaps run one unit within a letter, three between letters and
+            // seven between words; each is split at the midpoint
+            if units >= 2.0 {
+                self.flush(&mut text, &mut code);
+            }
+            if units >= 5.0 && !text.is_empty() {
+                text.push(' ');
+            }
+        }
+        self.flush(&mut text, &mut code);
+        text.trim_end().to_string()
+    }
+}
+
+impl MorseDecoder {
+    fn build(spec: &AudioSpec, wpm: f32, frequency: f32) -> Self {
+        let window: Duration = unit_duration(wpm) / MORSE_WINDOWS_PER_UNIT as u32;
+        let pulses: SizedPulses = Pulses::new(window, window).into_sized(spec);
+        let magnitude: GoertzelMagnitude = GoertzelMagnitude::new(&pulses, spec);
+        let window: usize = pulses.tone_size().max(1);
+        MorseDecoder {
+            magnitude,
+            window,
+            frequency,
+        }
+    }
+
+    fn runs(&self, samples: &[f32]) -> Vec<(bool, usize)> {
+        let mut runs: Vec<(bool, usize)> = Vec::new();
+        for window in samples.chunks_exact(self.window) {
+            let keyed: bool = self.magnitude.get_magnitude(window, self.frequency) >= MORSE_MIN_DB;
+            match runs.last_mut() {
+                Some((state, count)) if *state == keyed => *count += 1,
+                _ => runs.push((keyed, 1)),
+            }
+        }
+        runs
+    }
+
+    fn flush(&self, text: &mut String, code: &mut String) {
+        if code.is_empty() {
+            return;
+        }
+        text.push(code_to_char(code).unwrap_or('?'));
+        code.clear();
+    }
+}
+
+#[test]
+fn test_morse_table() {
+    assert_eq!(char_to_code('s'), Some("..."));
+    assert_eq!(code_to_char("---"), Some('O'));
+    assert_eq!(char_to_code('#'), None);
+    assert_eq!(unit_duration(20.0).as_millis(), 60);
+}
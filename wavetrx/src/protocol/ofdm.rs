@@ -0,0 +1,293 @@
+use std::time::Duration;
+
+use crate::audio::spectrum::FourierMagnitude;
+use crate::audio::types::AudioSpec;
+use crate::consts::OFDM_BASE_HZ;
+use crate::consts::OFDM_CHIRP;
+use crate::consts::OFDM_GUARD;
+use crate::consts::OFDM_MAX_SUBCARRIERS;
+use crate::consts::OFDM_SUBCARRIERS;
+use crate::consts::OFDM_SYMBOL;
+use crate::consts::OFDM_THRESHOLD_DB;
+use crate::embedded::sample_size;
+use crate::error::WavetrxError;
+use crate::protocol::bitvec::BitPadding;
+use crate::protocol::bitvec::BitVec;
+use crate::protocol::framing::BitOrder;
+use crate::protocol::profile::Pulses;
+use crate::protocol::profile::SizedPulses;
+use crate::protocol::rx::PreambleDetector;
+use crate::protocol::tx::ToneGenerator;
+
+// Bytes ahead of the payload giving its length, big-endian
+const LENGTH_PREFIX: usize = 2;
+
+// Subcarrier count and symbol timing shared by both ends of an OFDM link
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct OfdmLayout {
+    subcarriers: usize,
+    symbol: Duration,
+    guard: Duration,
+}
+
+impl OfdmLayout {
+    pub fn new() -> Self {
+        OfdmLayout {
+            subcarriers: OFDM_SUBCARRIERS,
+            symbol: OFDM_SYMBOL,
+            guard: OFDM_GUARD,
+        }
+    }
+
+    // Clamped to 1..=OFDM_MAX_SUBCARRIERS; each one carries a bit per symbol
+    pub fn with_subcarriers(mut self, subcarriers: usize) -> Self {
+        self.subcarriers = subcarriers.clamp(1, OFDM_MAX_SUBCARRIERS);
+        self
+    }
+
+    // Subcarrier spacing is 1 / `symbol`, so shorter symbols spread wider
+    pub fn with_timing(mut self, symbol: Duration, guard: Duration) -> Self {
+        self.symbol = symbol;
+        self.guard = guard;
+        self
+    }
+
+    pub fn subcarriers(&self) -> usize {
+        self.subcarriers
+    }
+
+    pub fn bitrate(&self) -> f32 {
+        self.subcarriers as f32 / (self.symbol + self.guard).as_secs_f32()
+    }
+
+    // Whole cycles per symbol keep the subcarriers orthogonal to each other
+    pub fn frequencies(&self, spec: &AudioSpec) -> Vec<f32> {
+        let sample_rate: f32 = spec.sample_rate() as f32;
+        let window: f32 = self.pulses(spec).tone_size() as f32;
+        let spacing: f32 = sample_rate / window;
+        let base_bin: f32 = (OFDM_BASE_HZ / spacing).round();
+        (0..self.subcarriers)
+            .map(|idx| (base_bin + idx as f32) * spacing)
+            .collect()
+    }
+}
+
+impl OfdmLayout {
+    fn pulses(&self, spec: &AudioSpec) -> SizedPulses {
+        Pulses::new(self.symbol, self.guard).into_sized(spec)
+    }
+
+    fn symbol_duration(&self) -> usize {
+        (self.symbol + self.guard).as_micros() as usize
+    }
+
+    fn chirp(&self, spec: &AudioSpec) -> (f32, f32) {
+        let frequencies: Vec<f32> = self.frequencies(spec);
+        (frequencies[0], frequencies[frequencies.len() - 1])
+    }
+}
+
+impl Default for OfdmLayout {
+    fn default() -> Self {
+        OfdmLayout::new()
+    }
+}
+
+// Chirp, an all-on reference symbol, then the length-prefixed payload with
+// one bit per subcarrier and symbol
+pub struct OfdmEncoder {
+    spec: AudioSpec,
+    layout: OfdmLayout,
+}
+
+impl OfdmEncoder {
+    pub fn new(spec: &AudioSpec) -> Self {
+        let spec: AudioSpec = *spec;
+        let layout: OfdmLayout = OfdmLayout::new();
+        OfdmEncoder { spec, layout }
+    }
+
+    pub fn with_layout(mut self, layout: OfdmLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub fn layout(&self) -> OfdmLayout {
+        self.layout
+    }
+
+    pub fn encode(&self, data: &[u8]) -> Result<Vec<f32>, WavetrxError> {
+        let length: u16 = u16::try_from(data.len()).map_err(|_| {
+            WavetrxError::InvalidInput(format!("{} bytes exceed an OFDM frame", data.len()))
+        })?;
+        let frequencies: Vec<f32> = self.layout.frequencies(&self.spec);
+        let nyquist: f32 = self.spec.sample_rate() as f32 / 2.0;
+        if frequencies[frequencies.len() - 1] >= nyquist {
+            let reason: String = format!("Subcarriers reach past {} Hz", nyquist);
+            return Err(WavetrxError::InvalidInput(reason));
+        }
+
+        let mut bytes: Vec<u8> = length.to_be_bytes().to_vec();
+        bytes.extend_from_slice(data);
+
+        let mut tone: ToneGenerator = ToneGenerator::new(&self.spec)?;
+        let (from, to): (f32, f32) = self.layout.chirp(&self.spec);
+        tone.append_chirp(from, to, OFDM_CHIRP.as_micros() as usize, 0.05)?;
+        self.append_symbol(&mut tone, &frequencies)?;
+        let bits: Vec<bool> = BitVec::from_bytes(&bytes, BitOrder::MsbFirst)
+            .iter_bits()
+            .collect();
+        for bits in bits.chunks(frequencies.len()) {
+            let active: Vec<f32> = frequencies
+                .iter()
+                .zip(bits.iter())
+                .filter(|(_, bit)| **bit)
+                .map(|(frequency, _)| *frequency)
+                .collect();
+            self.append_symbol(&mut tone, &active)?;
+        }
+        tone.append_silence(self.layout.guard.as_micros() as usize)?;
+        Ok(tone.samples())
+    }
+}
+
+impl OfdmEncoder {
+    // Guard and symbol are one continuous tone, so the guard is a cyclic prefix
+    fn append_symbol(
+        &self,
+        tone: &mut ToneGenerator,
+        frequencies: &[f32],
+    ) -> Result<(), WavetrxError> {
+        let level: f32 = 1.0 / self.layout.subcarriers as f32;
+        tone.append_multitone(frequencies, level, self.layout.symbol_duration())
+    }
+}
+
+// Finds the chirp by matched filtering, then reads each symbol with a single
+// FFT and compares every subcarrier against the reference symbol
+pub struct OfdmDecoder {
+    spec: AudioSpec,
+    layout: OfdmLayout,
+    detector: PreambleDetector,
+    magnitude: FourierMagnitude,
+}
+
+impl OfdmDecoder {
+    pub fn new(spec: &AudioSpec) -> Self {
+        OfdmDecoder::with_layout(spec, OfdmLayout::new())
+    }
+
+    pub fn with_layout(spec: &AudioSpec, layout: OfdmLayout) -> Self {
+        let spec: AudioSpec = *spec;
+        let (from, to): (f32, f32) = layout.chirp(&spec);
+        let chirp_size: usize = sample_size(spec.sample_rate(), OFDM_CHIRP.as_micros() as usize);
+        let detector: PreambleDetector = PreambleDetector::chirp(from, to, chirp_size, &spec);
+        let magnitude: FourierMagnitude = FourierMagnitude::new(&layout.pulses(&spec), &spec);
+        OfdmDecoder {
+            spec,
+            layout,
+            detector,
+            magnitude,
+        }
+    }
+
+    pub fn layout(&self) -> OfdmLayout {
+        self.layout
+    }
+
+    pub fn decode(&self, samples: &[f32]) -> Result<Vec<u8>, WavetrxError> {
+        let start: usize = self.detector.find(samples).ok_or_else(|| {
+            let reason: String = "No OFDM preamble found".to_string();
+            WavetrxError::DecodeFailed { reason }
+        })?;
+        let frequencies: Vec<f32> = self.layout.frequencies(&self.spec);
+        let mut symbols: SymbolReader = self.symbol_reader(samples, start + self.detector.len());
+
+        let reference: Vec<f32> = symbols.next_levels(&frequencies)?;
+        let mut bits: BitVec = BitVec::new();
+        let mut length: Option<usize> = None;
+        loop {
+            if let Some(length) = length {
+                if bits.len() >= (LENGTH_PREFIX + length) * 8 {
+                    break;
+                }
+            } else if bits.len() >= LENGTH_PREFIX * 8 {
+                let prefix: Vec<u8> = bits.to_bytes(BitOrder::MsbFirst, BitPadding::Truncate);
+                length = Some(u16::from_be_bytes([prefix[0], prefix[1]]) as usize);
+                continue;
+            }
+
+            let levels: Vec<f32> = symbols.next_levels(&frequencies)?;
+            for (level, reference) in levels.iter().zip(reference.iter()) {
+                bits.push_bit(*level > reference - OFDM_THRESHOLD_DB);
+            }
+        }
+
+        let length: usize = length.unwrap_or(0);
+        let bytes: Vec<u8> = bits.to_bytes(BitOrder::MsbFirst, BitPadding::Truncate);
+        Ok(bytes[LENGTH_PREFIX..LENGTH_PREFIX + length].to_vec())
+    }
+}
+
+impl OfdmDecoder {
+    // The window sits mid-way through the spare samples, so the sync may be
+    // off by half a guard in either direction
+    fn symbol_reader<'a>(&'a self, samples: &'a [f32], start: usize) -> SymbolReader<'a> {
+        let stride: usize = sample_size(self.spec.sample_rate(), self.layout.symbol_duration());
+        let window: usize = self.layout.pulses(&self.spec).tone_size();
+        let offset: usize = (stride - window) / 2;
+        SymbolReader {
+            magnitude: &self.magnitude,
+            samples,
+            idx: start + offset,
+            stride,
+            window,
+        }
+    }
+}
+
+struct SymbolReader<'a> {
+    magnitude: &'a FourierMagnitude,
+    samples: &'a [f32],
+    idx: usize,
+    stride: usize,
+    window: usize,
+}
+
+impl SymbolReader<'_> {
+    fn next_levels(&mut self, frequencies: &[f32]) -> Result<Vec<f32>, WavetrxError> {
+        let end: usize = self.idx + self.window;
+        if end > self.samples.len() {
+            let reason: String = "OFDM frame is cut short".to_string();
+            return Err(WavetrxError::DecodeFailed { reason });
+        }
+        let levels: Vec<f32> = self
+            .magnitude
+            .get_magnitudes(&self.samples[self.idx..end], frequencies);
+        self.idx += self.stride;
+        Ok(levels)
+    }
+}
+
+#[test]
+fn test_ofdm_subcarriers_orthogonal() {
+    use crate::audio::types::SampleEncoding;
+
+    let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+    let layout: OfdmLayout = OfdmLayout::new().with_subcarriers(40);
+    assert_eq!(layout.subcarriers(), OFDM_MAX_SUBCARRIERS);
+
+    let frequencies: Vec<f32> = layout.frequencies(&spec);
+    assert_eq!(frequencies[0], 2_000.0);
+    assert_eq!(frequencies[1] - frequencies[0], 200.0);
+
+    // A lone subcarrier leaves its neighbours' bins empty
+    let pulses: SizedPulses = layout.pulses(&spec);
+    let magnitude: FourierMagnitude = FourierMagnitude::new(&pulses, &spec);
+    let mut tone: ToneGenerator = ToneGenerator::new(&spec).unwrap();
+    tone.append_multitone(&frequencies[3..4], 0.5, 5_000)
+        .unwrap();
+    let levels: Vec<f32> = magnitude.get_magnitudes(&tone.samples(), &frequencies);
+    assert!((levels[3] - 20.0 * 0.5f32.log10()).abs() < 0.1);
+    assert!(levels[2] < -60.0 && levels[4] < -60.0);
+}
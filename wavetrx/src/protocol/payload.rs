@@ -0,0 +1,79 @@
+use std::borrow::Cow;
+use std::str;
+use std::str::Utf8Error;
+
+use crate::protocol::bitvec::BitVec;
+use crate::protocol::framing::BitOrder;
+use crate::utils::bits_to_bytes;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Payload(Vec<u8>);
+
+impl Payload {
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Payload(bytes)
+    }
+
+    pub fn from_bits(bits: &BitVec, bit_order: BitOrder) -> Self {
+        let bytes: Vec<u8> = bits_to_bytes(bits, bit_order);
+        Payload(bytes)
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.0
+    }
+
+    pub fn as_utf8(&self) -> Result<&str, Utf8Error> {
+        str::from_utf8(&self.0)
+    }
+
+    pub fn as_utf8_lossy(&self) -> Cow<'_, str> {
+        String::from_utf8_lossy(&self.0)
+    }
+}
+
+impl From<Vec<u8>> for Payload {
+    fn from(bytes: Vec<u8>) -> Self {
+        Payload::new(bytes)
+    }
+}
+
+impl From<&[u8]> for Payload {
+    fn from(bytes: &[u8]) -> Self {
+        Payload::new(bytes.to_vec())
+    }
+}
+
+impl From<&str> for Payload {
+    fn from(string: &str) -> Self {
+        Payload::new(string.as_bytes().to_vec())
+    }
+}
+
+impl AsRef<[u8]> for Payload {
+    fn as_ref(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[test]
+fn test_payload_binary() {
+    let payload: Payload = Payload::from(&[0x57, 0xFF, 0x00, 0x78][..]);
+
+    assert!(payload.as_utf8().is_err());
+    assert_eq!(payload.as_utf8_lossy(), "W\u{FFFD}\u{0}x");
+    assert_eq!(payload.as_bytes(), &[0x57, 0xFF, 0x00, 0x78]);
+}
@@ -0,0 +1,63 @@
+use std::f32::consts;
+
+use crate::audio::types::AudioSpec;
+use crate::protocol::profile::SizedPulses;
+
+// The chirp spans this many tone pulses; it must stay shorter than the
+// eight pulses the receiver keeps buffered between start searches
+pub const PREAMBLE_PULSES: usize = 4;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Preamble {
+    None,
+    Chirp { from: f32, to: f32 },
+}
+
+impl Preamble {
+    pub fn is_none(&self) -> bool {
+        matches!(self, Preamble::None)
+    }
+
+    pub fn sample_size(&self, pulses: &SizedPulses) -> usize {
+        match self {
+            Preamble::None => 0,
+            Preamble::Chirp { .. } => pulses.tone_size() * PREAMBLE_PULSES,
+        }
+    }
+
+    pub fn phase(&self, idx: usize, size: usize, spec: &AudioSpec) -> f32 {
+        match self {
+            Preamble::None => 0.0,
+            Preamble::Chirp { from, to } => chirp_phase(*from, *to, idx, size, spec),
+        }
+    }
+}
+
+// A chirp in place of the Start tone cannot be triggered by an ambient tone
+// that happens to sit on the Start frequency
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StartMarker {
+    #[default]
+    Tone,
+    Chirp {
+        from: f32,
+        to: f32,
+    },
+}
+
+impl StartMarker {
+    pub fn is_chirp(&self) -> bool {
+        matches!(self, StartMarker::Chirp { .. })
+    }
+}
+
+// Instantaneous phase of a linear sweep from `from` to `to` over `size` samples
+pub fn chirp_phase(from: f32, to: f32, idx: usize, size: usize, spec: &AudioSpec) -> f32 {
+    let sample_rate: f32 = spec.sample_rate() as f32;
+    let t: f32 = idx as f32 / sample_rate;
+    let duration: f32 = size as f32 / sample_rate;
+    let sweep: f32 = (to - from) / (2.0 * duration);
+    2.0 * consts::PI * (from * t + sweep * t * t)
+}
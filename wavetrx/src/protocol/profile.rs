@@ -1,10 +1,37 @@
+use std::error;
+use std::fmt;
+#[cfg(feature = "serde")]
+use std::fs;
 use std::ops::Div;
 use std::ops::Mul;
+#[cfg(feature = "serde")]
+use std::path::Path;
 use std::time::Duration;
 
+use crate::audio::spectrum::WindowFunction;
 use crate::audio::types::AudioSpec;
+use crate::audio::types::SampleEncoding;
+use crate::consts::DefaultProfile;
+use crate::consts::DBPSK_CHIRP_SPAN_HZ;
+use crate::consts::DB_THRESHOLD;
+use crate::consts::VALIDATION_SAMPLE_RATE;
+use crate::error::WavetrxError;
+use crate::protocol::fec::Fec;
+use crate::protocol::framing::Framing;
+use crate::protocol::interleave::Interleaving;
+use crate::protocol::modulation::Modulation;
+use crate::protocol::preamble::Preamble;
+use crate::protocol::preamble::StartMarker;
+use crate::protocol::scramble::Scrambling;
+
+pub use crate::embedded::Timing;
 
 #[derive(Copy, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
 pub struct Frequency(f32);
 
 impl Frequency {
@@ -13,7 +40,13 @@ impl Frequency {
     }
 }
 
+// Serialized as whole microseconds
 #[derive(Copy, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "u64", into = "u64")
+)]
 pub struct PulseDuration(Duration);
 
 impl PulseDuration {
@@ -92,6 +125,18 @@ impl PulseDuration {
     }
 }
 
+impl From<u64> for PulseDuration {
+    fn from(micros: u64) -> Self {
+        PulseDuration::from_micros(micros)
+    }
+}
+
+impl From<PulseDuration> for u64 {
+    fn from(duration: PulseDuration) -> Self {
+        duration.as_micros::<u64>()
+    }
+}
+
 impl Into<PulseDuration> for Duration {
     fn into(self) -> PulseDuration {
         PulseDuration::from_duration(self)
@@ -99,6 +144,7 @@ impl Into<PulseDuration> for Duration {
 }
 
 #[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Markers {
     pub start: Frequency,
     pub end: Frequency,
@@ -114,17 +160,97 @@ impl Markers {
     }
 }
 
+pub const MAX_SYMBOL_TONES: usize = 8;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SymbolWidth {
+    Binary,
+    Quad,
+    Octal,
+}
+
+impl SymbolWidth {
+    pub fn from_tones(tones: usize) -> Option<Self> {
+        match tones {
+            2 => Some(SymbolWidth::Binary),
+            4 => Some(SymbolWidth::Quad),
+            8 => Some(SymbolWidth::Octal),
+            _ => None,
+        }
+    }
+
+    pub fn bits_per_symbol(&self) -> usize {
+        match self {
+            SymbolWidth::Binary => 1,
+            SymbolWidth::Quad => 2,
+            SymbolWidth::Octal => 3,
+        }
+    }
+
+    pub fn tones(&self) -> usize {
+        1 << self.bits_per_symbol()
+    }
+}
+
+// Symbol values index into `tones`; for binary symbols 0 is low and 1 is high
 #[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Bits {
     pub high: Frequency,
     pub low: Frequency,
+    pub width: SymbolWidth,
+    tones: [Frequency; MAX_SYMBOL_TONES],
 }
 
 impl Bits {
     pub fn new(high: f32, low: f32) -> Self {
         let high: Frequency = Frequency(high);
         let low: Frequency = Frequency(low);
-        Self { high, low }
+        let width: SymbolWidth = SymbolWidth::Binary;
+
+        let mut tones: [Frequency; MAX_SYMBOL_TONES] = [Frequency(0.0); MAX_SYMBOL_TONES];
+        tones[0] = low;
+        tones[1] = high;
+        Self {
+            high,
+            low,
+            width,
+            tones,
+        }
+    }
+
+    pub fn from_tones(frequencies: &[f32]) -> Result<Self, WavetrxError> {
+        let width: SymbolWidth = match SymbolWidth::from_tones(frequencies.len()) {
+            Some(width) => width,
+            None => {
+                let reason: String =
+                    format!("Expected 2, 4 or 8 symbol tones, got {}", frequencies.len());
+                return Err(WavetrxError::ProfileInvalid(reason));
+            }
+        };
+
+        let mut tones: [Frequency; MAX_SYMBOL_TONES] = [Frequency(0.0); MAX_SYMBOL_TONES];
+        for (tone, &frequency) in tones.iter_mut().zip(frequencies.iter()) {
+            *tone = Frequency(frequency);
+        }
+
+        let low: Frequency = tones[0];
+        let high: Frequency = tones[frequencies.len() - 1];
+        Ok(Self {
+            high,
+            low,
+            width,
+            tones,
+        })
+    }
+
+    pub fn tones(&self) -> &[Frequency] {
+        &self.tones[..self.width.tones()]
+    }
+
+    pub fn bits_per_symbol(&self) -> usize {
+        self.width.bits_per_symbol()
     }
 
     pub fn from_boolean(&self, bit: bool) -> Frequency {
@@ -133,12 +259,45 @@ impl Bits {
             false => self.low,
         }
     }
+
+    pub fn from_symbol(&self, symbol: u8) -> Frequency {
+        self.tones()[symbol as usize]
+    }
+}
+
+// How consecutive pulses meet. `Gated` fades each tone and leaves the gap
+// silent; `RaisedCosine` ramps each tone over `overlap` of its length and,
+// with a zero gap, cross-fades it into the next one instead of clicking
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Shaping {
+    #[default]
+    Gated,
+    RaisedCosine {
+        overlap: f32,
+    },
+}
+
+impl Shaping {
+    pub fn ramp_size(&self, tone_size: usize) -> usize {
+        match self {
+            Shaping::Gated => 0,
+            Shaping::RaisedCosine { overlap } => {
+                (tone_size as f32 * overlap.clamp(0.0, 0.5)) as usize
+            }
+        }
+    }
 }
 
 #[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pulses {
+    #[cfg_attr(feature = "serde", serde(rename = "tone_us"))]
     pub tone: PulseDuration,
+    #[cfg_attr(feature = "serde", serde(rename = "gap_us"))]
     pub gap: PulseDuration,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub shaping: Shaping,
 }
 
 impl Pulses {
@@ -159,16 +318,28 @@ impl Pulses {
     pub fn new(tone: Duration, gap: Duration) -> Self {
         let tone: PulseDuration = tone.into();
         let gap: PulseDuration = gap.into();
-        Self { tone, gap }
+        let shaping: Shaping = Shaping::Gated;
+        Self { tone, gap, shaping }
+    }
+
+    pub fn with_shaping(mut self, shaping: Shaping) -> Self {
+        self.shaping = shaping;
+        self
     }
 
     pub fn into_sized(&self, spec: &AudioSpec) -> SizedPulses {
         let tone_size: usize = self.get_tone_sample_size(spec);
         let gap_size: usize = self.get_gap_sample_size(spec);
+        // Tones only run into each other when no gap separates them
+        let overlap_size: usize = match gap_size {
+            0 => self.shaping.ramp_size(tone_size),
+            _ => 0,
+        };
 
         SizedPulses {
             tone_size,
             gap_size,
+            overlap_size,
         }
     }
 }
@@ -177,6 +348,7 @@ impl Pulses {
 pub struct SizedPulses {
     tone_size: usize,
     gap_size: usize,
+    overlap_size: usize,
 }
 
 impl SizedPulses {
@@ -187,24 +359,305 @@ impl SizedPulses {
     pub fn gap_size(&self) -> usize {
         self.gap_size
     }
+
+    pub fn overlap_size(&self) -> usize {
+        self.overlap_size
+    }
+
+    // Distance from the start of one pulse to the start of the next
+    pub fn symbol_size(&self) -> usize {
+        self.tone_size + self.gap_size - self.overlap_size
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ProfileError {
+    AboveNyquist { frequency: f32, nyquist: f32 },
+    OverlappingTones { frequency: f32 },
+    InsufficientSeparation { low: f32, high: f32, min: f32 },
+    ToneTooShort { samples: usize, min: usize },
+    InvalidOverlap { overlap: f32 },
+    InvalidCarrier { carrier: f32, min: f32 },
+    InvalidParity { parity: usize, max: usize },
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileError::AboveNyquist { frequency, nyquist } => write!(
+                f,
+                "{} Hz is above the {} Hz Nyquist limit; lower it or raise the sample rate",
+                frequency, nyquist
+            ),
+            ProfileError::OverlappingTones { frequency } => write!(
+                f,
+                "{} Hz is used by more than one marker or symbol",
+                frequency
+            ),
+            ProfileError::InsufficientSeparation { low, high, min } => write!(
+                f,
+                "{} Hz and {} Hz are closer than the {} Hz bin width; spread them or lengthen the tone",
+                low, high, min
+            ),
+            ProfileError::ToneTooShort { samples, min } => write!(
+                f,
+                "Tone spans {} samples but needs at least {} to resolve the lowest frequency",
+                samples, min
+            ),
+            ProfileError::InvalidOverlap { overlap } => write!(
+                f,
+                "Raised-cosine overlap {} must be above 0 and at most 0.5",
+                overlap
+            ),
+            ProfileError::InvalidCarrier { carrier, min } => write!(
+                f,
+                "Carrier at {} Hz must be above {} Hz to fit its sync chirp",
+                carrier, min
+            ),
+            ProfileError::InvalidParity { parity, max } => write!(
+                f,
+                "Reed-Solomon parity of {} bytes must be between 1 and {} to fit a 255-byte codeword",
+                parity, max
+            ),
+        }
+    }
+}
+
+impl error::Error for ProfileError {}
+
+impl From<ProfileError> for WavetrxError {
+    fn from(err: ProfileError) -> Self {
+        WavetrxError::ProfileInvalid(err.to_string())
+    }
 }
 
 #[derive(Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Profile {
     pub markers: Markers,
     pub bits: Bits,
     pub pulses: Pulses,
+    pub timing: Timing,
+    pub framing: Framing,
+    pub fec: Fec,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub interleaving: Interleaving,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub scrambling: Scrambling,
+    pub preamble: Preamble,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub start_marker: StartMarker,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub modulation: Modulation,
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub window: WindowFunction,
+    pub threshold: f32,
+    pub sample_rate: Option<u32>,
 }
 
 impl Profile {
     pub fn new(markers: Markers, bits: Bits, pulses: Pulses) -> Self {
+        let timing: Timing = Timing::Marked;
+        let framing: Framing = Framing::default();
+        let fec: Fec = Fec::None;
+        let interleaving: Interleaving = Interleaving::None;
+        let scrambling: Scrambling = Scrambling::None;
+        let preamble: Preamble = Preamble::None;
+        let start_marker: StartMarker = StartMarker::Tone;
+        let modulation: Modulation = Modulation::Fsk;
+        let window: WindowFunction = WindowFunction::None;
+        let threshold: f32 = DB_THRESHOLD;
+        let sample_rate: Option<u32> = None;
         Profile {
             markers,
             bits,
             pulses,
+            timing,
+            framing,
+            fec,
+            interleaving,
+            scrambling,
+            preamble,
+            start_marker,
+            modulation,
+            window,
+            threshold,
+            sample_rate,
         }
     }
 
+    pub fn builder() -> ProfileBuilder {
+        ProfileBuilder::new()
+    }
+
+    pub fn with_bits(mut self, bits: Bits) -> Self {
+        self.bits = bits;
+        self
+    }
+
+    pub fn with_timing(mut self, timing: Timing) -> Self {
+        self.timing = timing;
+        self
+    }
+
+    pub fn with_shaping(mut self, shaping: Shaping) -> Self {
+        self.pulses.shaping = shaping;
+        self
+    }
+
+    pub fn with_framing(mut self, framing: Framing) -> Self {
+        self.framing = framing;
+        self
+    }
+
+    pub fn with_fec(mut self, fec: Fec) -> Self {
+        self.fec = fec;
+        self
+    }
+
+    pub fn with_interleaving(mut self, interleaving: Interleaving) -> Self {
+        self.interleaving = interleaving;
+        self
+    }
+
+    pub fn with_scrambling(mut self, scrambling: Scrambling) -> Self {
+        self.scrambling = scrambling;
+        self
+    }
+
+    // Only `modulation::modulator` and `modulation::demodulator` act on this
+    pub fn with_modulation(mut self, modulation: Modulation) -> Self {
+        self.modulation = modulation;
+        self
+    }
+
+    pub fn with_preamble(mut self, preamble: Preamble) -> Self {
+        self.preamble = preamble;
+        self
+    }
+
+    pub fn with_start_marker(mut self, start_marker: StartMarker) -> Self {
+        self.start_marker = start_marker;
+        self
+    }
+
+    // Taper applied to every analysis chunk; tighter tone spacing benefits most
+    pub fn with_window(mut self, window: WindowFunction) -> Self {
+        self.window = window;
+        self
+    }
+
+    // Magnitudes within +/- threshold dB of full scale count as detected;
+    // widen it for quiet rooms, narrow it for loud speakers
+    pub fn with_threshold(mut self, threshold: f32) -> Self {
+        self.threshold = threshold;
+        self
+    }
+
+    // Receivers resample their input to this rate; unset decodes at the input rate
+    pub fn with_sample_rate(mut self, sample_rate: u32) -> Self {
+        self.sample_rate = Some(sample_rate);
+        self
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn from_file<P>(path: P) -> Result<Self, WavetrxError>
+    where
+        P: AsRef<Path>,
+    {
+        let contents: String = fs::read_to_string(path)?;
+        serde_json::from_str(&contents).map_err(|err| WavetrxError::ProfileInvalid(err.to_string()))
+    }
+
+    #[cfg(feature = "serde")]
+    pub fn to_file<P>(&self, path: P) -> Result<(), WavetrxError>
+    where
+        P: AsRef<Path>,
+    {
+        let contents: String = serde_json::to_string_pretty(self)
+            .map_err(|err| WavetrxError::ProfileInvalid(err.to_string()))?;
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    // Every marker and symbol tone must sit below Nyquist, in its own FFT bin,
+    // and at least one bin above DC; Reed-Solomon parity must fit a codeword
+    pub fn validate(&self, spec: &AudioSpec) -> Result<(), ProfileError> {
+        let nyquist: f32 = spec.sample_rate() as f32 / 2.0;
+        let min_freq_sep: f32 = self.min_frequency_separation(spec);
+
+        if let Fec::ReedSolomon { parity } = self.fec {
+            Fec::reed_solomon(parity)?;
+        }
+
+        let mut frequencies: Vec<f32> = self.frequencies();
+        let mut chirps: Vec<(f32, f32)> = Vec::new();
+        if let Preamble::Chirp { from, to } = self.preamble {
+            chirps.push((from, to));
+        }
+        if let StartMarker::Chirp { from, to } = self.start_marker {
+            chirps.push((from, to));
+        }
+        if let Modulation::Dbpsk { carrier } = self.modulation {
+            if carrier <= DBPSK_CHIRP_SPAN_HZ {
+                return Err(ProfileError::InvalidCarrier {
+                    carrier,
+                    min: DBPSK_CHIRP_SPAN_HZ,
+                });
+            }
+            chirps.push((carrier - DBPSK_CHIRP_SPAN_HZ, carrier + DBPSK_CHIRP_SPAN_HZ));
+        }
+        for (from, to) in chirps {
+            let top: f32 = from.max(to);
+            if top >= nyquist {
+                return Err(ProfileError::AboveNyquist {
+                    frequency: top,
+                    nyquist,
+                });
+            }
+        }
+        frequencies.sort_by(|a, b| a.total_cmp(b));
+
+        let tone_size: usize = self.pulses.into_sized(spec).tone_size();
+        let lowest: f32 = frequencies[0];
+        if tone_size == 0 || lowest < min_freq_sep {
+            let min: usize = (spec.sample_rate() as f32 / lowest).ceil() as usize;
+            return Err(ProfileError::ToneTooShort {
+                samples: tone_size,
+                min: min.max(1),
+            });
+        }
+
+        for pair in frequencies.windows(2) {
+            let (low, high): (f32, f32) = (pair[0], pair[1]);
+            if low == high {
+                return Err(ProfileError::OverlappingTones { frequency: low });
+            }
+            if high - low < min_freq_sep {
+                return Err(ProfileError::InsufficientSeparation {
+                    low,
+                    high,
+                    min: min_freq_sep,
+                });
+            }
+        }
+
+        let highest: f32 = frequencies[frequencies.len() - 1];
+        if highest >= nyquist {
+            return Err(ProfileError::AboveNyquist {
+                frequency: highest,
+                nyquist,
+            });
+        }
+
+        if let Shaping::RaisedCosine { overlap } = self.pulses.shaping {
+            if !(overlap > 0.0 && overlap <= 0.5) {
+                return Err(ProfileError::InvalidOverlap { overlap });
+            }
+        }
+        Ok(())
+    }
+
     pub fn min_frequency_separation(&self, spec: &AudioSpec) -> f32 {
         let sample_rate: f32 = spec.sample_rate() as f32;
         let tone_micros: f32 = self.pulses.tone.as_micros::<u128>() as f32;
@@ -213,6 +666,162 @@ impl Profile {
         let min_freq_sep: f32 = sample_rate / sample_size;
         min_freq_sep
     }
+
+    // Marker tones followed by the symbol tones
+    pub fn frequencies(&self) -> Vec<f32> {
+        let mut frequencies: Vec<f32> = vec![
+            self.markers.start.0,
+            self.markers.end.0,
+            self.markers.next.0,
+        ];
+        frequencies.extend(self.bits.tones().iter().map(|tone| tone.0));
+        frequencies
+    }
+}
+
+// Starts from the default profile; `build` validates against the profile
+// sample rate, or 48 kHz when none is set
+pub struct ProfileBuilder {
+    profile: Profile,
+}
+
+impl ProfileBuilder {
+    pub fn new() -> Self {
+        let markers: Markers = Markers::new(
+            DefaultProfile::MARKER_TONE_START,
+            DefaultProfile::MARKER_TONE_END,
+            DefaultProfile::MARKER_TONE_NEXT,
+        );
+        let bits: Bits = Bits::new(DefaultProfile::BIT_TONE_HIGH, DefaultProfile::BIT_TONE_LOW);
+        let pulses: Pulses = Pulses::new(
+            DefaultProfile::PULSE_LENGTH_US,
+            DefaultProfile::PULSE_GAP_US,
+        );
+
+        let profile: Profile = Profile::new(markers, bits, pulses);
+        ProfileBuilder { profile }
+    }
+
+    pub fn start_hz(mut self, hz: f32) -> Self {
+        self.profile.markers.start = Frequency(hz);
+        self
+    }
+
+    pub fn end_hz(mut self, hz: f32) -> Self {
+        self.profile.markers.end = Frequency(hz);
+        self
+    }
+
+    pub fn next_hz(mut self, hz: f32) -> Self {
+        self.profile.markers.next = Frequency(hz);
+        self
+    }
+
+    pub fn bit_high_hz(mut self, hz: f32) -> Self {
+        self.profile.bits = Bits::new(hz, self.profile.bits.low.0);
+        self
+    }
+
+    pub fn bit_low_hz(mut self, hz: f32) -> Self {
+        self.profile.bits = Bits::new(self.profile.bits.high.0, hz);
+        self
+    }
+
+    pub fn bits(mut self, bits: Bits) -> Self {
+        self.profile.bits = bits;
+        self
+    }
+
+    pub fn tone_ms(self, ms: u64) -> Self {
+        self.tone_us(ms * 1_000)
+    }
+
+    pub fn gap_ms(self, ms: u64) -> Self {
+        self.gap_us(ms * 1_000)
+    }
+
+    pub fn tone_us(mut self, us: u64) -> Self {
+        self.profile.pulses.tone = PulseDuration::from_micros(us);
+        self
+    }
+
+    pub fn gap_us(mut self, us: u64) -> Self {
+        self.profile.pulses.gap = PulseDuration::from_micros(us);
+        self
+    }
+
+    pub fn timing(mut self, timing: Timing) -> Self {
+        self.profile.timing = timing;
+        self
+    }
+
+    pub fn shaping(mut self, shaping: Shaping) -> Self {
+        self.profile.pulses.shaping = shaping;
+        self
+    }
+
+    pub fn framing(mut self, framing: Framing) -> Self {
+        self.profile.framing = framing;
+        self
+    }
+
+    pub fn fec(mut self, fec: Fec) -> Self {
+        self.profile.fec = fec;
+        self
+    }
+
+    pub fn interleaving(mut self, interleaving: Interleaving) -> Self {
+        self.profile.interleaving = interleaving;
+        self
+    }
+
+    pub fn scrambling(mut self, scrambling: Scrambling) -> Self {
+        self.profile.scrambling = scrambling;
+        self
+    }
+
+    pub fn modulation(mut self, modulation: Modulation) -> Self {
+        self.profile.modulation = modulation;
+        self
+    }
+
+    pub fn preamble(mut self, preamble: Preamble) -> Self {
+        self.profile.preamble = preamble;
+        self
+    }
+
+    pub fn start_marker(mut self, start_marker: StartMarker) -> Self {
+        self.profile.start_marker = start_marker;
+        self
+    }
+
+    pub fn window(mut self, window: WindowFunction) -> Self {
+        self.profile.window = window;
+        self
+    }
+
+    pub fn threshold(mut self, threshold: f32) -> Self {
+        self.profile.threshold = threshold;
+        self
+    }
+
+    pub fn sample_rate(mut self, sample_rate: u32) -> Self {
+        self.profile.sample_rate = Some(sample_rate);
+        self
+    }
+
+    pub fn build(self) -> Result<Profile, ProfileError> {
+        let sample_rate: u32 = self.profile.sample_rate.unwrap_or(VALIDATION_SAMPLE_RATE);
+        let spec: AudioSpec = AudioSpec::new(sample_rate, 32, 1, SampleEncoding::F32);
+        self.profile.validate(&spec)?;
+        Ok(self.profile)
+    }
+}
+
+impl Default for ProfileBuilder {
+    fn default() -> Self {
+        ProfileBuilder::new()
+    }
 }
 
 impl core::fmt::Debug for Profile {
@@ -223,12 +832,26 @@ impl core::fmt::Debug for Profile {
             "Start: {:?} Hz\nEnd: {:?} Hz\nNext: {:?} Hz\n",
             self.markers.start.0, self.markers.end.0, self.markers.next.0
         ))?;
+        if let StartMarker::Chirp { from, to } = self.start_marker {
+            f.write_str(&format!("Start Chirp: {:?} Hz -> {:?} Hz\n", from, to))?;
+        }
 
         f.write_str("\n-Bits-\n")?;
-        f.write_str(&format!(
-            "High: {:?} Hz\nLow: {:?} Hz\n",
-            self.bits.high.0, self.bits.low.0
-        ))?;
+        match self.bits.width {
+            SymbolWidth::Binary => f.write_str(&format!(
+                "High: {:?} Hz\nLow: {:?} Hz\n",
+                self.bits.high.0, self.bits.low.0
+            ))?,
+            width => {
+                let tones: Vec<f32> = self.bits.tones().iter().map(|tone| tone.0).collect();
+                f.write_str(&format!(
+                    "Tones: {:?} Hz\nSymbol Width: {:?} ({} bits)\n",
+                    tones,
+                    width,
+                    width.bits_per_symbol()
+                ))?
+            }
+        }
 
         f.write_str("\n-Pulses-\n")?;
         f.write_str(&format!(
@@ -236,7 +859,125 @@ impl core::fmt::Debug for Profile {
             self.pulses.tone.0.as_micros(),
             self.pulses.gap.0.as_micros()
         ))?;
+        match self.pulses.shaping {
+            Shaping::Gated => f.write_str("Shaping: Gated\n")?,
+            Shaping::RaisedCosine { overlap } => f.write_str(&format!(
+                "Shaping: Raised cosine ({:.0}% overlap)\n",
+                overlap * 100.0
+            ))?,
+        }
+
+        f.write_str("\n-Timing-\n")?;
+        match self.timing {
+            Timing::Marked => f.write_str("Marked\n")?,
+            Timing::Gapless { resync } => {
+                f.write_str(&format!("Gapless (Resync: {} bits)\n", resync))?
+            }
+        }
+
+        f.write_str("\n-Framing-\n")?;
+        f.write_str(&format!(
+            "Bit Order: {:?}\nByte Order: {:?}\nChecksum: {:?}\nLength Prefix: {}\nAddressing: {:?}\nCompression: {}\nSequence: {}\n",
+            self.framing.bit_order,
+            self.framing.byte_order,
+            self.framing.checksum,
+            self.framing.length_prefix,
+            self.framing.addressing,
+            self.framing.compression,
+            self.framing.sequence
+        ))?;
+
+        f.write_str("\n-Modulation-\n")?;
+        match self.modulation {
+            Modulation::Fsk => f.write_str("FSK\n")?,
+            Modulation::Dbpsk { carrier } => {
+                f.write_str(&format!("DBPSK: {:?} Hz carrier\n", carrier))?
+            }
+        }
+
+        f.write_str("\n-FEC-\n")?;
+        f.write_str(&format!("{:?}\n", self.fec))?;
+        f.write_str(&format!("Interleaving: {:?}\n", self.interleaving))?;
+        f.write_str(&format!("Scrambling: {:?}\n", self.scrambling))?;
+
+        f.write_str("\n-Preamble-\n")?;
+        match self.preamble {
+            Preamble::None => f.write_str("None\n")?,
+            Preamble::Chirp { from, to } => {
+                f.write_str(&format!("Chirp: {:?} Hz -> {:?} Hz\n", from, to))?
+            }
+        }
+
+        f.write_str("\n-Detection-\n")?;
+        f.write_str(&format!("Threshold: {} dB\n", self.threshold))?;
+        f.write_str(&format!("Window: {:?}\n", self.window))?;
+        match self.sample_rate {
+            Some(sample_rate) => f.write_str(&format!("Sample Rate: {} Hz\n", sample_rate))?,
+            None => f.write_str("Sample Rate: Input\n")?,
+        }
 
         Ok(())
     }
 }
+
+#[test]
+fn test_profile_validation() {
+    use crate::utils::get_fast_profile;
+
+    let spec: AudioSpec = AudioSpec::new(48_000, 16, 1, SampleEncoding::I32);
+    assert_eq!(get_fast_profile().validate(&spec), Ok(()));
+
+    let profile: Profile = get_fast_profile().with_bits(Bits::new(7_000.0, 1_000.0));
+    assert_eq!(
+        profile.validate(&spec),
+        Err(ProfileError::OverlappingTones { frequency: 7_000.0 })
+    );
+
+    let profile: Profile = get_fast_profile().with_bits(Bits::new(7_500.0, 1_000.0));
+    assert!(matches!(
+        profile.validate(&spec),
+        Err(ProfileError::InsufficientSeparation { low: 7_000.0, .. })
+    ));
+
+    let spec: AudioSpec = AudioSpec::new(16_000, 16, 1, SampleEncoding::I32);
+    assert_eq!(
+        get_fast_profile().validate(&spec),
+        Err(ProfileError::AboveNyquist {
+            frequency: 9_000.0,
+            nyquist: 8_000.0
+        })
+    );
+
+    let mut profile: Profile = get_fast_profile();
+    profile.pulses = Pulses::new(Duration::from_micros(500), Duration::from_micros(100));
+    assert!(matches!(
+        profile.validate(&AudioSpec::new(48_000, 16, 1, SampleEncoding::I32)),
+        Err(ProfileError::ToneTooShort { samples: 24, .. })
+    ));
+}
+
+#[test]
+fn test_profile_builder() {
+    use crate::utils::get_fast_profile;
+
+    let profile: Profile = Profile::builder()
+        .start_hz(7_000.0)
+        .end_hz(9_000.0)
+        .next_hz(3_000.0)
+        .bit_high_hz(5_000.0)
+        .bit_low_hz(1_000.0)
+        .tone_ms(1)
+        .gap_us(100)
+        .build()
+        .unwrap();
+    assert_eq!(
+        format!("{:?}", profile),
+        format!("{:?}", get_fast_profile())
+    );
+
+    let result: Result<Profile, ProfileError> = Profile::builder().bit_high_hz(9_000.0).build();
+    assert_eq!(
+        result.err(),
+        Some(ProfileError::OverlappingTones { frequency: 9_000.0 })
+    );
+}
@@ -0,0 +1,247 @@
+use std::time::Duration;
+
+use crate::audio::types::AudioSpec;
+use crate::consts::RANGE_CLOCK_PPM;
+use crate::consts::SPEED_OF_SOUND;
+use crate::embedded::sine_fade;
+use crate::protocol::preamble::chirp_phase;
+use crate::protocol::preamble::PREAMBLE_PULSES;
+use crate::protocol::profile::Profile;
+use crate::protocol::rx::PreambleDetector;
+
+pub const RANGE_REPORT: u8 = 0x52;
+
+// Share of each ranging chirp spent fading in and out
+const RANGE_FADE: f32 = 0.1;
+
+// Two-way ranging: A plays an up-chirp ping, B answers with a down-chirp
+// reply, and each side times both chirps on its own microphone. Only the
+// difference of those times matters, so neither side's playback or capture
+// latency enters the distance; B reports its turnaround in a frame after
+pub struct Ranging {
+    from: f32,
+    to: f32,
+    profile: Profile,
+}
+
+impl Ranging {
+    // The chirps sweep across the whole band of the profile's tones
+    pub fn new(profile: &Profile) -> Self {
+        let frequencies: Vec<f32> = profile.frequencies();
+        let from: f32 = frequencies.iter().copied().fold(f32::MAX, f32::min);
+        let to: f32 = frequencies.iter().copied().fold(f32::MIN, f32::max);
+        Ranging {
+            from,
+            to,
+            profile: *profile,
+        }
+    }
+
+    pub fn sample_size(&self, spec: &AudioSpec) -> usize {
+        self.profile.pulses.into_sized(spec).tone_size() * PREAMBLE_PULSES
+    }
+
+    pub fn ping(&self, spec: &AudioSpec) -> Vec<f32> {
+        self.chirp(self.from, self.to, spec)
+    }
+
+    pub fn reply(&self, spec: &AudioSpec) -> Vec<f32> {
+        self.chirp(self.to, self.from, spec)
+    }
+
+    pub fn ping_locator(&self, spec: &AudioSpec) -> ChirpLocator {
+        let size: usize = self.sample_size(spec);
+        ChirpLocator::new(PreambleDetector::chirp(self.from, self.to, size, spec))
+    }
+
+    pub fn reply_locator(&self, spec: &AudioSpec) -> ChirpLocator {
+        let size: usize = self.sample_size(spec);
+        ChirpLocator::new(PreambleDetector::chirp(self.to, self.from, size, spec))
+    }
+}
+
+impl Ranging {
+    fn chirp(&self, from: f32, to: f32, spec: &AudioSpec) -> Vec<f32> {
+        let size: usize = self.sample_size(spec);
+        let fade_size: usize = (size as f32 * RANGE_FADE) as usize;
+        (0..size)
+            .map(|idx| {
+                chirp_phase(from, to, idx, size, spec).sin() * sine_fade(idx, size, fade_size)
+            })
+            .collect()
+    }
+}
+
+// Finds a chirp in audio that arrives piece by piece, rescanning only what
+// hasn't been ruled out yet
+pub struct ChirpLocator {
+    detector: PreambleDetector,
+    scanned: usize,
+}
+
+impl ChirpLocator {
+    pub fn new(detector: PreambleDetector) -> Self {
+        let scanned: usize = 0;
+        ChirpLocator { detector, scanned }
+    }
+
+    pub fn len(&self) -> usize {
+        self.detector.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.detector.is_empty()
+    }
+
+    // Ignores everything before `idx`, e.g. audio preceding an earlier chirp
+    pub fn skip_to(&mut self, idx: usize) {
+        self.scanned = self.scanned.max(idx);
+    }
+
+    // Onset of the chirp in `samples`, once enough audio follows the first
+    // match to settle on its correlation peak
+    pub fn locate(&mut self, samples: &[f32]) -> Option<usize> {
+        let size: usize = self.len();
+        let start: usize = self.scanned.min(samples.len());
+        match self.detector.find(&samples[start..]) {
+            Some(idx) if start + idx + 2 * size <= samples.len() => Some(start + idx),
+            Some(idx) => {
+                self.scanned = start + idx;
+                None
+            }
+            None => {
+                self.scanned = samples.len().saturating_sub(size).max(start);
+                None
+            }
+        }
+    }
+
+    // Drops audio already ruled out so a long wait doesn't grow `samples`;
+    // returns how many samples went
+    pub fn trim(&mut self, samples: &mut Vec<f32>) -> usize {
+        let drained: usize = self.scanned.min(samples.len());
+        samples.drain(..drained);
+        self.scanned -= drained;
+        drained
+    }
+}
+
+// B's time from hearing the ping to hearing its own reply
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RangeReport {
+    turnaround: Duration,
+}
+
+impl RangeReport {
+    pub fn new(turnaround: Duration) -> Self {
+        RangeReport { turnaround }
+    }
+
+    pub fn turnaround(&self) -> Duration {
+        self.turnaround
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let micros: u32 = self.turnaround.as_micros().min(u32::MAX as u128) as u32;
+        let mut bytes: Vec<u8> = vec![RANGE_REPORT];
+        bytes.extend(micros.to_be_bytes());
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [RANGE_REPORT, a, b, c, d] => {
+                let micros: u32 = u32::from_be_bytes([*a, *b, *c, *d]);
+                Some(RangeReport::new(Duration::from_micros(micros as u64)))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RangeEstimate {
+    meters: f32,
+    uncertainty: f32,
+}
+
+impl RangeEstimate {
+    // `round_trip` is A's time from hearing its ping to hearing the reply.
+    // The uncertainty covers one sample of onset error per chirp on either
+    // side, plus the two sample clocks drifting apart over the turnaround
+    pub fn new(round_trip: Duration, turnaround: Duration, sample_rate: u32) -> Self {
+        let flight: f32 = round_trip.saturating_sub(turnaround).as_secs_f32() / 2.0;
+        let onset: f32 = 2.0 / sample_rate.max(1) as f32;
+        let drift: f32 = turnaround.as_secs_f32() * RANGE_CLOCK_PPM * 1e-6;
+        RangeEstimate {
+            meters: flight * SPEED_OF_SOUND,
+            uncertainty: (onset * onset + drift * drift).sqrt() / 2.0 * SPEED_OF_SOUND,
+        }
+    }
+
+    pub fn meters(&self) -> f32 {
+        self.meters
+    }
+
+    // One standard deviation, in meters
+    pub fn uncertainty(&self) -> f32 {
+        self.uncertainty
+    }
+}
+
+// Duration spanned by `samples` at `sample_rate`
+pub fn samples_to_duration(samples: usize, sample_rate: u32) -> Duration {
+    Duration::from_secs_f64(samples as f64 / sample_rate.max(1) as f64)
+}
+
+#[test]
+fn test_ranging() {
+    use crate::audio::types::SampleEncoding;
+    use crate::sim::NoiseSource;
+    use crate::utils::get_fast_profile;
+
+    let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+    let ranging: Ranging = Ranging::new(&get_fast_profile());
+
+    // A's microphone: its own ping, then B's reply 2 m and 150 ms later
+    let turnaround: usize = 7_200;
+    let flight: usize = (2.0 / SPEED_OF_SOUND * 48_000.0).round() as usize;
+    let ping_at: usize = 1_234;
+    let reply_at: usize = ping_at + turnaround + 2 * flight;
+    let mut noise: NoiseSource = NoiseSource::new(7);
+    let mut samples: Vec<f32> = (0..reply_at + 20_000)
+        .map(|_| 0.02 * noise.gaussian())
+        .collect();
+    for (idx, sample) in ranging.ping(&spec).iter().enumerate() {
+        samples[ping_at + idx] += 0.5 * sample;
+    }
+    for (idx, sample) in ranging.reply(&spec).iter().enumerate() {
+        samples[reply_at + idx] += 0.2 * sample;
+    }
+
+    // Audio arrives in pieces, and the ping on its own never matches a reply
+    let mut ping: ChirpLocator = ranging.ping_locator(&spec);
+    let mut reply: ChirpLocator = ranging.reply_locator(&spec);
+    let mut found: Option<(usize, usize)> = None;
+    for end in (0..=samples.len()).step_by(480) {
+        if let Some(ping_idx) = ping.locate(&samples[..end]) {
+            reply.skip_to(ping_idx + ping.len());
+            if let Some(reply_idx) = reply.locate(&samples[..end]) {
+                found = Some((ping_idx, reply_idx));
+                break;
+            }
+        }
+    }
+    let (ping_idx, reply_idx): (usize, usize) = found.unwrap();
+    assert!(ping_idx.abs_diff(ping_at) <= 1);
+    assert!(reply_idx.abs_diff(reply_at) <= 1);
+
+    let report: RangeReport = RangeReport::new(samples_to_duration(turnaround, 48_000));
+    let report: RangeReport = RangeReport::decode(&report.encode()).unwrap();
+    let round_trip: Duration = samples_to_duration(reply_idx - ping_idx, 48_000);
+    let estimate: RangeEstimate = RangeEstimate::new(round_trip, report.turnaround(), 48_000);
+    assert!((estimate.meters() - 2.0).abs() < 0.05);
+    assert!(estimate.uncertainty() > 0.0 && estimate.uncertainty() < 0.05);
+    assert_eq!(RangeReport::decode(b"R"), None);
+}
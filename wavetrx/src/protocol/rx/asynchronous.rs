@@ -0,0 +1,174 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+use cpal::Device;
+use cpal::StreamConfig;
+use tokio::sync::mpsc::error::TryRecvError;
+use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::live::LiveReceiver;
+use super::message::DecodedMessage;
+
+use crate::error::WavetrxError;
+use crate::protocol::profile::Profile;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+type Started = Result<(), WavetrxError>;
+
+// Decodes on a dedicated thread, since the input stream cannot leave the
+// thread that built it, and hands finished messages to async code
+pub struct AsyncReceiver {
+    messages: UnboundedReceiver<DecodedMessage>,
+    running: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl AsyncReceiver {
+    pub fn new(
+        profile: Profile,
+        device: Device,
+        config: StreamConfig,
+    ) -> Result<Self, WavetrxError> {
+        Self::spawn(move || {
+            let mut receiver: LiveReceiver = LiveReceiver::new(profile, device, config);
+            receiver.start()?;
+            Ok(move || receiver.poll())
+        })
+    }
+
+    pub async fn next_message(&mut self) -> Result<Vec<u8>, WavetrxError> {
+        let message: DecodedMessage = self.next_decoded().await?;
+        Ok(message.into_data())
+    }
+
+    pub async fn next_decoded(&mut self) -> Result<DecodedMessage, WavetrxError> {
+        match self.messages.recv().await {
+            Some(message) => Ok(message),
+            None => Err(Self::stopped()),
+        }
+    }
+
+    pub fn try_next_message(&mut self) -> Result<Option<Vec<u8>>, WavetrxError> {
+        match self.messages.try_recv() {
+            Ok(message) => Ok(Some(message.into_data())),
+            Err(TryRecvError::Empty) => Ok(None),
+            Err(TryRecvError::Disconnected) => Err(Self::stopped()),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::Relaxed)
+    }
+
+    pub fn stop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl AsyncReceiver {
+    // `setup` runs on the decode thread; its error is returned from here
+    fn spawn<F, P>(setup: F) -> Result<Self, WavetrxError>
+    where
+        F: FnOnce() -> Result<P, WavetrxError> + Send + 'static,
+        P: FnMut() -> Vec<DecodedMessage>,
+    {
+        let (sender, messages): (
+            UnboundedSender<DecodedMessage>,
+            UnboundedReceiver<DecodedMessage>,
+        ) = tokio::sync::mpsc::unbounded_channel();
+        let (ready_sender, ready): (mpsc::Sender<Started>, mpsc::Receiver<Started>) =
+            mpsc::channel();
+        let running: Arc<AtomicBool> = Arc::new(AtomicBool::new(true));
+
+        let thread_running: Arc<AtomicBool> = running.clone();
+        let handle: JoinHandle<()> = thread::spawn(move || {
+            let mut poll: P = match setup() {
+                Ok(poll) => {
+                    let _ = ready_sender.send(Ok(()));
+                    poll
+                }
+                Err(err) => {
+                    let _ = ready_sender.send(Err(err));
+                    return;
+                }
+            };
+
+            while thread_running.load(Ordering::Relaxed) {
+                for message in poll() {
+                    if sender.send(message).is_err() {
+                        return;
+                    }
+                }
+                thread::sleep(POLL_INTERVAL);
+            }
+        });
+
+        let started: Started = ready.recv().unwrap_or_else(|_| Err(Self::stopped()));
+        if let Err(err) = started {
+            let _ = handle.join();
+            return Err(err);
+        }
+
+        Ok(AsyncReceiver {
+            messages,
+            running,
+            handle: Some(handle),
+        })
+    }
+
+    fn stopped() -> WavetrxError {
+        WavetrxError::DeviceError("Receiver thread stopped".to_string())
+    }
+}
+
+impl Drop for AsyncReceiver {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+#[test]
+fn test_async_receiver() {
+    use crate::protocol::payload::Payload;
+
+    let mut pending: Vec<Vec<u8>> = vec![b"Wt".to_vec(), b"Async".to_vec()];
+    let mut receiver: AsyncReceiver = AsyncReceiver::spawn(move || {
+        Ok(move || {
+            if pending.is_empty() {
+                return Vec::new();
+            }
+            let data: Vec<u8> = pending.remove(0);
+            vec![DecodedMessage::new(Payload::new(data), 0, 0)]
+        })
+    })
+    .unwrap();
+
+    let runtime: tokio::runtime::Runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    runtime.block_on(async {
+        assert_eq!(receiver.next_message().await.unwrap(), b"Wt");
+        assert_eq!(receiver.next_message().await.unwrap(), b"Async");
+    });
+
+    receiver.stop();
+    assert!(!receiver.is_running());
+    assert!(receiver.try_next_message().is_err());
+
+    let failed: Result<AsyncReceiver, WavetrxError> = AsyncReceiver::spawn(|| {
+        Err::<fn() -> Vec<DecodedMessage>, WavetrxError>(WavetrxError::DeviceError(
+            "No device".to_string(),
+        ))
+    });
+    assert!(failed.is_err());
+}
@@ -0,0 +1,124 @@
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::Ordering;
+use std::sync::Mutex;
+use std::thread;
+
+use super::message::DecodedMessage;
+use super::receiver::Receiver;
+
+use crate::audio::types::AudioSpec;
+use crate::audio::types::NormSamples;
+use crate::error::WavetrxError;
+use crate::protocol::profile::Profile;
+use crate::utils::read_audio_file;
+
+pub struct DecodeProgress<'a> {
+    pub file_idx: usize,
+    pub path: &'a Path,
+    pub processed: usize,
+    pub total: usize,
+}
+
+impl<'a> DecodeProgress<'a> {
+    pub fn fraction(&self) -> f32 {
+        if self.total == 0 {
+            return 1.0;
+        }
+        self.processed as f32 / self.total as f32
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.processed >= self.total
+    }
+}
+
+pub struct FileDecode {
+    pub path: PathBuf,
+    pub messages: Result<Vec<DecodedMessage>, WavetrxError>,
+}
+
+pub fn decode_files<P, F>(
+    profile: &Profile,
+    paths: &[P],
+    parallelism: usize,
+    progress: F,
+) -> Vec<FileDecode>
+where
+    P: AsRef<Path> + Sync,
+    F: Fn(DecodeProgress<'_>) + Sync,
+{
+    let parallelism: usize = parallelism.clamp(1, paths.len().max(1));
+    let next_idx: AtomicUsize = AtomicUsize::new(0);
+    let results: Mutex<Vec<Option<FileDecode>>> =
+        Mutex::new((0..paths.len()).map(|_| None).collect());
+
+    thread::scope(|scope| {
+        for _ in 0..parallelism {
+            scope.spawn(|| loop {
+                let file_idx: usize = next_idx.fetch_add(1, Ordering::Relaxed);
+                if file_idx >= paths.len() {
+                    break;
+                }
+
+                let path: &Path = paths[file_idx].as_ref();
+                let messages: Result<Vec<DecodedMessage>, WavetrxError> =
+                    decode_file(profile, file_idx, path, &progress);
+
+                let decode: FileDecode = FileDecode {
+                    path: path.to_path_buf(),
+                    messages,
+                };
+                if let Ok(mut results_guard) = results.lock() {
+                    results_guard[file_idx] = Some(decode);
+                }
+            });
+        }
+    });
+
+    let results: Vec<Option<FileDecode>> = results.into_inner().unwrap_or_default();
+    results.into_iter().flatten().collect()
+}
+
+fn decode_file<F>(
+    profile: &Profile,
+    file_idx: usize,
+    path: &Path,
+    progress: &F,
+) -> Result<Vec<DecodedMessage>, WavetrxError>
+where
+    F: Fn(DecodeProgress<'_>) + Sync,
+{
+    let (mut samples, spec): (NormSamples, AudioSpec) = read_audio_file(path)?;
+    samples.normalize(1.0, 0.1);
+
+    let total: usize = samples.0.len();
+    let chunk_size: usize = (spec.sample_rate() as usize).max(1);
+    let mut processed: usize = 0;
+    let mut receiver: Receiver = Receiver::new(*profile, spec);
+
+    progress(DecodeProgress {
+        file_idx,
+        path,
+        processed,
+        total,
+    });
+
+    for chunk in samples.0.chunks(chunk_size) {
+        let mut chunk: NormSamples = NormSamples::from_slice(chunk);
+        processed += chunk.0.len();
+
+        receiver.add_samples(&mut chunk);
+        receiver.analyze_full_buffer();
+
+        progress(DecodeProgress {
+            file_idx,
+            path,
+            processed,
+            total,
+        });
+    }
+
+    Ok(receiver.take_messages())
+}
@@ -0,0 +1,287 @@
+use std::time::Duration;
+
+use crate::consts::SEQUENCE_HISTORY;
+use crate::consts::SEQUENCE_WINDOW;
+use crate::consts::START_MAX_FAILS;
+use crate::consts::START_SKIP_CYCLES;
+use crate::consts::START_WARMUP_TONES;
+
+// What the receiver does when added audio would take its buffer past the
+// maximum. `DropOldest` discards the front of the buffer, and with it any
+// message being decoded; `Error` refuses the new audio
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BufferOverflow {
+    #[default]
+    DropOldest,
+    Error,
+}
+
+// Tuning for the Start marker search. Fewer allowed fails and a longer
+// skip find a marker sooner but settle on its peak less precisely, and a
+// shorter warm-up starts searching earlier on less context
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RxConfig {
+    max_consecutive_fails: usize,
+    skip_cycles: usize,
+    warmup_tones: usize,
+    max_buffer: Option<Duration>,
+    overflow: BufferOverflow,
+    dedup: bool,
+    dedup_history: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    dedup_window: Option<Duration>,
+}
+
+impl RxConfig {
+    pub fn new() -> Self {
+        RxConfig {
+            max_consecutive_fails: START_MAX_FAILS,
+            skip_cycles: START_SKIP_CYCLES,
+            warmup_tones: START_WARMUP_TONES,
+            max_buffer: None,
+            overflow: BufferOverflow::DropOldest,
+            dedup: true,
+            dedup_history: SEQUENCE_HISTORY,
+            dedup_window: Some(SEQUENCE_WINDOW),
+        }
+    }
+
+    // Steps past the best match without improving on it before it is taken
+    pub fn with_max_consecutive_fails(mut self, max_consecutive_fails: usize) -> Self {
+        self.max_consecutive_fails = max_consecutive_fails;
+        self
+    }
+
+    // Cycles of the Start tone skipped per step while nothing matches
+    pub fn with_skip_cycles(mut self, skip_cycles: usize) -> Self {
+        self.skip_cycles = skip_cycles.max(1);
+        self
+    }
+
+    // Tones of audio buffered before the search runs, and kept between searches
+    pub fn with_warmup_tones(mut self, warmup_tones: usize) -> Self {
+        self.warmup_tones = warmup_tones.max(2);
+        self
+    }
+
+    // Unbounded by default, as whole files are added to the buffer at once
+    pub fn with_max_buffer(mut self, max_buffer: Duration, overflow: BufferOverflow) -> Self {
+        self.max_buffer = Some(max_buffer);
+        self.overflow = overflow;
+        self
+    }
+
+    // Drops sequenced frames whose (source, sequence) pair was among the last
+    // `history` decoded; only applies when the profile's framing is sequenced
+    pub fn with_dedup(mut self, dedup: bool, history: usize) -> Self {
+        self.dedup = dedup;
+        self.dedup_history = history.max(1);
+        self
+    }
+
+    // How long after a pair was heard a frame repeating it is still dropped,
+    // in audio time; a sender restarted later may reuse its numbers. None
+    // keeps pairs until the history pushes them out
+    pub fn with_dedup_window(mut self, window: Option<Duration>) -> Self {
+        self.dedup_window = window;
+        self
+    }
+
+    pub fn max_consecutive_fails(&self) -> usize {
+        self.max_consecutive_fails
+    }
+
+    pub fn skip_cycles(&self) -> usize {
+        self.skip_cycles
+    }
+
+    pub fn warmup_tones(&self) -> usize {
+        self.warmup_tones
+    }
+
+    pub fn max_buffer(&self) -> Option<Duration> {
+        self.max_buffer
+    }
+
+    pub fn overflow(&self) -> BufferOverflow {
+        self.overflow
+    }
+
+    pub fn dedup(&self) -> bool {
+        self.dedup
+    }
+
+    pub fn dedup_history(&self) -> usize {
+        self.dedup_history
+    }
+
+    pub fn dedup_window(&self) -> Option<Duration> {
+        self.dedup_window
+    }
+}
+
+impl Default for RxConfig {
+    fn default() -> Self {
+        RxConfig::new()
+    }
+}
+
+#[test]
+fn test_rx_config() {
+    use crate::audio::types::AudioSpec;
+    use crate::audio::types::NormSamples;
+    use crate::audio::types::SampleEncoding;
+    use crate::protocol::rx::Receiver;
+    use crate::protocol::tx::Transmitter;
+    use crate::utils::get_fast_profile;
+
+    let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+    let samples: Vec<f32> = Transmitter::new(&get_fast_profile(), &spec)
+        .create(b"cfg")
+        .unwrap();
+    let config: RxConfig = RxConfig::new()
+        .with_max_consecutive_fails(3)
+        .with_skip_cycles(4)
+        .with_warmup_tones(0);
+    assert_eq!(config.warmup_tones(), 2);
+
+    // A short warm-up still finds the marker, and a new profile keeps the config
+    let mut receiver: Receiver = Receiver::with_config(get_fast_profile(), spec, config);
+    receiver.set_profile(get_fast_profile());
+    assert_eq!(receiver.config(), config);
+    for chunk in samples.chunks(256) {
+        receiver.add_samples(&mut NormSamples::from_slice(chunk));
+        receiver.analyze_full_buffer();
+    }
+    let messages: Vec<Vec<u8>> = receiver
+        .take_messages()
+        .into_iter()
+        .map(|message| message.into_data())
+        .collect();
+    assert_eq!(messages, vec![b"cfg".to_vec()]);
+}
+
+#[test]
+fn test_buffer_overflow() {
+    use crate::audio::types::AudioSpec;
+    use crate::audio::types::NormSamples;
+    use crate::audio::types::SampleEncoding;
+    use crate::error::WavetrxError;
+    use crate::protocol::rx::Receiver;
+    use crate::protocol::tx::Transmitter;
+    use crate::sim::NoiseSource;
+    use crate::utils::get_fast_profile;
+
+    let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+    let mut noise: NoiseSource = NoiseSource::new(3);
+    let mut music: Vec<f32> = (0..144_000).map(|_| 0.1 * noise.gaussian()).collect();
+    let frame: Vec<f32> = Transmitter::new(&get_fast_profile(), &spec)
+        .create(b"late")
+        .unwrap();
+
+    // Only the newest second is kept, and the frame that follows still decodes
+    let config: RxConfig =
+        RxConfig::new().with_max_buffer(Duration::from_secs(1), BufferOverflow::DropOldest);
+    let mut receiver: Receiver = Receiver::with_config(get_fast_profile(), spec, config);
+    receiver.add_samples(&mut NormSamples::from_vec(music.clone()));
+    assert_eq!(receiver.buffer_depth(), 48_000);
+    assert_eq!(receiver.overflowed_samples(), 96_000);
+    receiver.add_samples(&mut NormSamples::from_vec(frame));
+    receiver.analyze_full_buffer();
+    let messages: Vec<Vec<u8>> = receiver
+        .take_messages()
+        .into_iter()
+        .map(|message| message.into_data())
+        .collect();
+    assert_eq!(messages, vec![b"late".to_vec()]);
+    assert_eq!(receiver.peak_buffer_depth(), 48_000);
+
+    // Refused audio leaves the buffer as it was
+    let config: RxConfig =
+        RxConfig::new().with_max_buffer(Duration::from_secs(2), BufferOverflow::Error);
+    let mut receiver: Receiver = Receiver::with_config(get_fast_profile(), spec, config);
+    let mut tail: NormSamples = NormSamples::from_vec(music.split_off(48_000));
+    assert!(receiver.try_add_samples(&mut tail).is_ok());
+    let mut rest: NormSamples = NormSamples::from_vec(music);
+    let result = receiver.try_add_samples(&mut rest);
+    assert!(matches!(
+        result,
+        Err(WavetrxError::BufferOverflow {
+            depth: 144_000,
+            max: 96_000,
+        })
+    ));
+    assert_eq!(receiver.buffer_depth(), 96_000);
+    assert_eq!(rest.0.len(), 48_000);
+}
+
+#[test]
+fn test_dedup() {
+    use crate::audio::types::AudioSpec;
+    use crate::audio::types::NormSamples;
+    use crate::audio::types::SampleEncoding;
+    use crate::protocol::framing::Checksum;
+    use crate::protocol::framing::Framing;
+    use crate::protocol::profile::Profile;
+    use crate::protocol::rx::Receiver;
+    use crate::protocol::tx::Transmitter;
+    use crate::utils::get_fast_profile;
+
+    let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+    let profile: Profile = get_fast_profile();
+    let framing: Framing = profile
+        .framing
+        .with_checksum(Checksum::Crc16)
+        .with_sequence(true);
+    let profile: Profile = profile.with_framing(framing);
+
+    // An echo of the first frame, then a resend of it and a new frame
+    let mut transmitter: Transmitter = Transmitter::new(&profile, &spec).with_source(0x12);
+    let number: u8 = transmitter.sequence();
+    let first: Vec<f32> = transmitter.create(b"once").unwrap();
+    let mut samples: Vec<f32> = first.repeat(2);
+    transmitter.set_sequence(number);
+    samples.extend(transmitter.create(b"once").unwrap());
+    samples.extend(transmitter.create(b"next").unwrap());
+    assert_eq!(transmitter.sequence(), number.wrapping_add(2));
+
+    let decode = |config: RxConfig| -> (Vec<Vec<u8>>, usize) {
+        let mut receiver: Receiver = Receiver::with_config(profile, spec, config);
+        receiver.add_samples(&mut NormSamples::from_vec(samples.clone()));
+        receiver.analyze_full_buffer();
+        let messages: Vec<Vec<u8>> = receiver
+            .take_messages()
+            .into_iter()
+            .map(|message| message.into_data())
+            .collect();
+        (messages, receiver.metrics().duplicates())
+    };
+
+    let (messages, duplicates): (Vec<Vec<u8>>, usize) = decode(RxConfig::new());
+    assert_eq!(messages, vec![b"once".to_vec(), b"next".to_vec()]);
+    assert_eq!(duplicates, 2);
+
+    let (messages, duplicates): (Vec<Vec<u8>>, usize) =
+        decode(RxConfig::new().with_dedup(false, 1));
+    assert_eq!(messages.len(), 4);
+    assert_eq!(duplicates, 0);
+
+    // A sender restarted after the window reuses its numbers and is heard,
+    // while a receiver restored from a snapshot still knows the last frame
+    let config: RxConfig = RxConfig::new().with_dedup_window(Some(Duration::from_secs(1)));
+    let mut receiver: Receiver = Receiver::with_config(profile, spec, config);
+    receiver.add_samples(&mut NormSamples::from_vec(first.clone()));
+    receiver.analyze_full_buffer();
+    let mut restored: Receiver = Receiver::with_config(profile, spec, config);
+    restored.restore(receiver.snapshot());
+    restored.add_samples(&mut NormSamples::from_vec(first.clone()));
+    let mut later: Vec<f32> = vec![0.0; 96_000];
+    later.extend(first);
+    restored.add_samples(&mut NormSamples::from_vec(later));
+    restored.analyze_full_buffer();
+    assert_eq!(receiver.take_messages().len(), 1);
+    assert_eq!(restored.take_messages().len(), 1);
+    assert_eq!(restored.metrics().duplicates(), 1);
+}
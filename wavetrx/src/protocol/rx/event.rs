@@ -0,0 +1,15 @@
+use super::report::RxReport;
+
+use crate::protocol::framing::FrameError;
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RxEvent {
+    StartDetected { sample: usize },
+    SymbolReceived { value: u8, confidence: f32 },
+    BitReceived(bool),
+    MessageComplete { data: Vec<u8>, report: RxReport },
+    DecodeError(FrameError),
+    // The capture device was lost and the stream reopened on `name`
+    DeviceChanged { name: String },
+}
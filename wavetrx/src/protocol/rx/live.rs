@@ -0,0 +1,394 @@
+#[cfg(feature = "wav")]
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+use std::time::SystemTime;
+
+use cpal::traits::DeviceTrait;
+use cpal::traits::HostTrait;
+use cpal::Device;
+use cpal::StreamConfig;
+use log::info;
+use log::warn;
+
+use super::event::RxEvent;
+use super::message::DecodedMessage;
+use super::receiver::Receiver;
+use super::wake::WakeDetector;
+
+#[cfg(feature = "wav")]
+use crate::audio::archive::ArchiveConfig;
+#[cfg(feature = "wav")]
+use crate::audio::archive::WavArchive;
+use crate::audio::devices::find_input_device;
+use crate::audio::recorder::InputRecorder;
+use crate::audio::spectrum::GoertzelMagnitude;
+use crate::audio::spectrum::MagnitudeBackend;
+use crate::audio::squelch::Squelch;
+use crate::audio::types::AudioSpec;
+use crate::audio::types::ChannelMode;
+use crate::audio::types::NormSamples;
+use crate::audio::types::SampleEncoding;
+use crate::consts::DEVICE_RECONNECT_INTERVAL;
+use crate::error::WavetrxError;
+#[cfg(feature = "crypto")]
+use crate::protocol::crypto::PayloadCipher;
+use crate::protocol::profile::Profile;
+use crate::protocol::tx::CarrierSense;
+
+pub struct LiveReceiver<M = GoertzelMagnitude> {
+    recorder: InputRecorder,
+    receiver: Receiver<M>,
+    input: AudioSpec,
+    wake: Option<WakeDetector>,
+    reconnect: bool,
+    last_reconnect: Option<Instant>,
+    #[cfg(feature = "wav")]
+    archive: Option<WavArchive>,
+}
+
+impl<M> LiveReceiver<M>
+where
+    M: MagnitudeBackend,
+{
+    pub fn new(profile: Profile, device: Device, config: StreamConfig) -> Self {
+        let input: AudioSpec = Self::input_spec(&config);
+        let mut receiver: Receiver<M> = Receiver::new(profile, input);
+        receiver.set_squelch(Some(Squelch::default()));
+        let recorder: InputRecorder = InputRecorder::new(device, config);
+        let wake: Option<WakeDetector> = None;
+        let reconnect: bool = true;
+        let last_reconnect: Option<Instant> = None;
+        LiveReceiver {
+            recorder,
+            receiver,
+            input,
+            wake,
+            reconnect,
+            last_reconnect,
+            #[cfg(feature = "wav")]
+            archive: None,
+        }
+    }
+
+    pub fn start(&mut self) -> Result<(), WavetrxError> {
+        self.recorder.record()
+    }
+
+    // Hands the input device back, e.g. for a call, keeping all decode state;
+    // audio missed while paused is treated as a capture gap
+    pub fn pause(&mut self) -> Result<(), WavetrxError> {
+        self.recorder.pause()
+    }
+
+    pub fn resume(&mut self) -> Result<(), WavetrxError> {
+        self.recorder.resume()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.recorder.is_paused()
+    }
+
+    // On by default: when the capture device disappears, polling reopens the
+    // same device, or the default input if it does not come back, and emits
+    // `RxEvent::DeviceChanged`. Decode settings and subscribers are kept
+    pub fn set_auto_reconnect(&mut self, enabled: bool) {
+        self.reconnect = enabled;
+    }
+
+    // The decode spec: mono, at the profile rate when it sets one
+    pub fn spec(&self) -> AudioSpec {
+        self.receiver.spec()
+    }
+
+    pub fn recorder(&self) -> &InputRecorder {
+        &self.recorder
+    }
+
+    pub fn profile(&self) -> Profile {
+        self.receiver.profile()
+    }
+
+    pub fn set_profile(&mut self, profile: Profile) {
+        self.receiver.set_profile(profile);
+        if self.wake.is_some() {
+            self.set_low_power(true);
+        }
+    }
+
+    // Idle input only runs through a single marker detector; the full
+    // pipeline is woken by a plausible Start and sleeps again once quiet
+    pub fn set_low_power(&mut self, enabled: bool) {
+        self.wake = match enabled {
+            true => Some(WakeDetector::new(&self.receiver.profile(), &self.input)),
+            false => None,
+        };
+    }
+
+    // Always awake when low-power listening is off
+    pub fn is_awake(&self) -> bool {
+        match &self.wake {
+            Some(wake) => wake.is_awake(),
+            None => true,
+        }
+    }
+
+    pub fn set_threshold(&mut self, threshold: f32) {
+        self.receiver.set_threshold(threshold);
+    }
+
+    pub fn set_adaptive(&mut self, enabled: bool) {
+        self.receiver.set_adaptive(enabled);
+    }
+
+    pub fn set_squelch(&mut self, squelch: Option<Squelch>) {
+        self.receiver.set_squelch(squelch);
+    }
+
+    pub fn set_station_id(&mut self, station: Option<u16>) {
+        self.receiver.set_station_id(station);
+    }
+
+    pub fn set_diagnostics(&mut self, enabled: bool) {
+        self.receiver.set_diagnostics(enabled);
+    }
+
+    pub fn set_dump_dir(&mut self, dir: Option<PathBuf>) {
+        self.receiver.set_dump_dir(dir);
+    }
+
+    // Keeps the raw capture, all channels at the input rate, in rotating WAV
+    // files in `dir` while decoding, low-power sleep included. `swap_input`
+    // stops it, as the new input may not fit the files' format
+    #[cfg(feature = "wav")]
+    pub fn start_archive<P>(&mut self, dir: P, config: ArchiveConfig) -> Result<(), WavetrxError>
+    where
+        P: AsRef<Path>,
+    {
+        self.archive = Some(WavArchive::start(dir, &self.input, config)?);
+        Ok(())
+    }
+
+    #[cfg(feature = "wav")]
+    pub fn stop_archive(&mut self) {
+        self.archive = None;
+    }
+
+    #[cfg(feature = "crypto")]
+    pub fn set_cipher(&mut self, cipher: Option<PayloadCipher>) {
+        self.receiver.set_cipher(cipher);
+    }
+
+    pub fn noise_floor(&self) -> Option<f32> {
+        self.receiver.noise_floor()
+    }
+
+    pub fn set_channel_mode(&mut self, mode: ChannelMode) {
+        self.receiver.set_channel_mode(mode);
+    }
+
+    pub fn subscribe(&mut self) -> mpsc::Receiver<RxEvent> {
+        self.receiver.subscribe()
+    }
+
+    // Events are delivered from a forwarding thread as `poll` produces them
+    pub fn on_event<F>(&mut self, mut callback: F)
+    where
+        F: FnMut(RxEvent) + Send + 'static,
+    {
+        let events: mpsc::Receiver<RxEvent> = self.subscribe();
+        thread::spawn(move || {
+            for event in events {
+                callback(event);
+            }
+        });
+    }
+
+    pub fn poll(&mut self) -> Vec<DecodedMessage> {
+        self.capture();
+        self.receiver.take_messages()
+    }
+
+    // Mono capture since the last poll at the input rate, for callers that
+    // time the audio themselves. It is still decoded; messages wait for `poll`
+    pub fn take_input(&mut self) -> NormSamples {
+        let channels: usize = self.input.channels() as usize;
+        self.capture().into_mono(channels, ChannelMode::Downmix)
+    }
+
+    // The spec of `take_input`: mono, at the capture rate
+    pub fn capture_spec(&self) -> AudioSpec {
+        self.input.with_channels(1)
+    }
+
+    // True while the capture since the last poll carries energy at the
+    // profile tones
+    pub fn sense(&mut self, carrier: &CarrierSense) -> bool {
+        let samples: NormSamples = self.take_input();
+        carrier.is_busy(&samples.0, &self.profile(), &self.capture_spec())
+    }
+
+    pub fn gaps(&self) -> usize {
+        self.recorder.gaps()
+    }
+
+    // Drops whatever was captured since the last poll without decoding it
+    pub fn discard(&mut self) {
+        self.recorder.take_frame();
+    }
+
+    // Keeps the receiver buffer and resolver state; only the capture side changes
+    pub fn swap_input(&mut self, device: Device, config: StreamConfig) -> Result<(), WavetrxError> {
+        let spec: AudioSpec = Self::input_spec(&config);
+        let mut recorder: InputRecorder = InputRecorder::new(device, config);
+        recorder.record()?;
+
+        let time: Option<SystemTime> = self.recorder.next_sample_time();
+        if let Some(frame) = self.recorder.take_frame() {
+            let end: Option<SystemTime> =
+                time.and_then(|time| time.checked_add(self.duration(&frame.0)));
+            self.feed(frame, end);
+        }
+
+        #[cfg(feature = "wav")]
+        if spec.sample_rate() != self.input.sample_rate()
+            || spec.channels() != self.input.channels()
+        {
+            self.stop_archive();
+        }
+        self.recorder = recorder;
+        self.receiver.set_input_spec(spec);
+        self.input = spec;
+        if self.wake.is_some() {
+            self.set_low_power(true);
+        }
+        Ok(())
+    }
+}
+
+impl<M> LiveReceiver<M>
+where
+    M: MagnitudeBackend,
+{
+    fn input_spec(config: &StreamConfig) -> AudioSpec {
+        let channels: u16 = config.channels.max(1);
+        AudioSpec::new(config.sample_rate.0, 32, channels, SampleEncoding::F32)
+    }
+
+    // Audio before a capture gap is decoded first; the receiver then resyncs
+    // so symbols after the gap are never stitched onto a stale alignment.
+    // Returns what was captured, as interleaved input frames
+    fn capture(&mut self) -> NormSamples {
+        if self.reconnect && self.recorder.is_device_lost() {
+            self.reconnect_input();
+        }
+        let time: Option<SystemTime> = self.recorder.next_sample_time();
+        let (mut frame, mut gap): (NormSamples, Option<usize>) =
+            match self.recorder.take_frame_with_gap() {
+                Some(captured) => captured,
+                None => return NormSamples::from_vec(Vec::new()),
+            };
+        let captured: Vec<f32> = frame.0.clone();
+        #[cfg(feature = "wav")]
+        if let Some(archive) = &self.archive {
+            archive.write(&captured);
+        }
+        // Stamped from the end, as the wake detector hands back the audio
+        // leading up to the end of the capture
+        let end: Option<SystemTime> =
+            time.and_then(|time| time.checked_add(self.duration(&captured)));
+
+        // Asleep, nothing is decoded, so a gap needs no resync either
+        if let Some(wake) = self.wake.as_mut().filter(|wake| !wake.is_awake()) {
+            match wake.listen(&frame.0) {
+                Some(frames) => frame = NormSamples::from_vec(frames),
+                None => return NormSamples::from_vec(captured),
+            }
+            gap = None;
+        }
+
+        match gap {
+            Some(offset) => {
+                let after: Vec<f32> = frame.0.split_off(offset);
+                let before: Option<SystemTime> = self.start_time(end, &after);
+                self.feed(frame, before);
+                self.receiver.resync();
+                self.feed(NormSamples::from_vec(after), end);
+            }
+            None => self.feed(frame, end),
+        }
+
+        let receiving: bool = self.receiver.is_receiving();
+        let slept: bool = match self.wake.as_mut() {
+            Some(wake) => wake.settle(captured.len(), receiving),
+            None => false,
+        };
+        if slept {
+            self.receiver.resync();
+        }
+        NormSamples::from_vec(captured)
+    }
+
+    // `end` is the wall-clock time just after the frame's last sample
+    // Attempts are spaced out, as each one enumerates the host's devices
+    fn reconnect_input(&mut self) {
+        let due: bool = match self.last_reconnect {
+            Some(last) => last.elapsed() >= DEVICE_RECONNECT_INTERVAL,
+            None => true,
+        };
+        if !due {
+            return;
+        }
+        self.last_reconnect = Some(Instant::now());
+
+        let device: Option<Device> = self
+            .recorder
+            .device_name()
+            .and_then(|name| find_input_device(&name).ok())
+            .or_else(|| cpal::default_host().default_input_device());
+        let device: Device = match device {
+            Some(device) => device,
+            None => return,
+        };
+        let name: String = device.name().unwrap_or_default();
+        let config: StreamConfig = match device.default_input_config() {
+            Ok(config) => config.into(),
+            Err(err) => {
+                warn!("Could not configure {}: {}", name, err);
+                return;
+            }
+        };
+
+        match self.swap_input(device, config) {
+            Ok(()) => {
+                info!("Capture moved to {}", name);
+                // Whatever was under way was cut by the dropout
+                self.receiver.resync();
+                self.last_reconnect = None;
+                self.receiver.emit(RxEvent::DeviceChanged { name });
+            }
+            Err(err) => warn!("Could not reopen {}: {}", name, err),
+        }
+    }
+
+    fn feed(&mut self, mut frame: NormSamples, end: Option<SystemTime>) {
+        match self.start_time(end, &frame.0) {
+            Some(time) => self.receiver.add_samples_at(&mut frame, time),
+            None => self.receiver.add_samples(&mut frame),
+        }
+        self.receiver.analyze_full_buffer();
+    }
+
+    fn start_time(&self, end: Option<SystemTime>, frames: &[f32]) -> Option<SystemTime> {
+        end?.checked_sub(self.duration(frames))
+    }
+
+    // Length of interleaved input frames at the capture rate
+    fn duration(&self, frames: &[f32]) -> Duration {
+        let channels: usize = self.input.channels() as usize;
+        self.input.sample_timestamp(frames.len() / channels.max(1))
+    }
+}
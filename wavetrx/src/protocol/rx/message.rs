@@ -0,0 +1,94 @@
+use std::borrow::Cow;
+use std::time::Duration;
+
+use super::report::RxReport;
+
+use crate::audio::types::AudioSpec;
+use crate::protocol::payload::Payload;
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct DecodedMessage {
+    payload: Payload,
+    start: usize,
+    end: usize,
+    confidences: Vec<f32>,
+    report: Option<RxReport>,
+}
+
+impl DecodedMessage {
+    pub fn new(payload: Payload, start: usize, end: usize) -> Self {
+        let confidences: Vec<f32> = Vec::new();
+        let report: Option<RxReport> = None;
+        DecodedMessage {
+            payload,
+            start,
+            end,
+            confidences,
+            report,
+        }
+    }
+
+    pub fn with_confidences(mut self, confidences: Vec<f32>) -> Self {
+        self.confidences = confidences;
+        self
+    }
+
+    pub fn with_report(mut self, report: RxReport) -> Self {
+        self.report = Some(report);
+        self
+    }
+
+    pub fn payload(&self) -> &Payload {
+        &self.payload
+    }
+
+    pub fn data(&self) -> &[u8] {
+        self.payload.as_bytes()
+    }
+
+    pub fn into_data(self) -> Vec<u8> {
+        self.payload.into_bytes()
+    }
+
+    pub fn as_utf8_lossy(&self) -> Cow<'_, str> {
+        self.payload.as_utf8_lossy()
+    }
+
+    // Per-symbol dB margins in arrival order, usable as soft decisions
+    pub fn confidences(&self) -> &[f32] {
+        &self.confidences
+    }
+
+    pub fn min_confidence(&self) -> Option<f32> {
+        self.confidences.iter().copied().reduce(f32::min)
+    }
+
+    pub fn mean_confidence(&self) -> Option<f32> {
+        if self.confidences.is_empty() {
+            return None;
+        }
+        let total: f32 = self.confidences.iter().sum();
+        Some(total / self.confidences.len() as f32)
+    }
+
+    pub fn report(&self) -> Option<&RxReport> {
+        self.report.as_ref()
+    }
+
+    pub fn start_sample(&self) -> usize {
+        self.start
+    }
+
+    pub fn end_sample(&self) -> usize {
+        self.end
+    }
+
+    pub fn start_time(&self, spec: &AudioSpec) -> Duration {
+        spec.sample_timestamp(self.start)
+    }
+
+    pub fn end_time(&self, spec: &AudioSpec) -> Duration {
+        spec.sample_timestamp(self.end)
+    }
+}
@@ -0,0 +1,133 @@
+use std::time::Duration;
+
+use super::report::RxReport;
+
+// Running link-health counters of a receiver since it was created. The SNR
+// is estimated per message as the mean lead of the winning tone over the
+// runner-up, as in `RxReport::mean_margin`
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RxMetrics {
+    messages: usize,
+    failures: usize,
+    false_starts: usize,
+    duplicates: usize,
+    symbols: usize,
+    margin_sum: f32,
+    elapsed: Duration,
+}
+
+impl RxMetrics {
+    pub fn new() -> Self {
+        RxMetrics::default()
+    }
+
+    // Audio time the counters cover
+    pub fn with_elapsed(mut self, elapsed: Duration) -> Self {
+        self.elapsed = elapsed;
+        self
+    }
+
+    pub fn record_message(&mut self, report: &RxReport) {
+        self.messages += 1;
+        self.margin_sum += report.mean_margin();
+    }
+
+    pub fn record_failure(&mut self) {
+        self.failures += 1;
+    }
+
+    // A Start marker that was followed by something other than a message
+    pub fn record_false_start(&mut self) {
+        self.false_starts += 1;
+    }
+
+    // A sequenced frame already decoded from the same source
+    pub fn record_duplicate(&mut self) {
+        self.duplicates += 1;
+    }
+
+    pub fn record_symbol(&mut self) {
+        self.symbols += 1;
+    }
+
+    pub fn messages_decoded(&self) -> usize {
+        self.messages
+    }
+
+    pub fn decode_failures(&self) -> usize {
+        self.failures
+    }
+
+    pub fn false_starts(&self) -> usize {
+        self.false_starts
+    }
+
+    pub fn duplicates(&self) -> usize {
+        self.duplicates
+    }
+
+    pub fn symbols(&self) -> usize {
+        self.symbols
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    // None until a message has been decoded
+    pub fn average_snr_db(&self) -> Option<f32> {
+        match self.messages {
+            0 => None,
+            messages => Some(self.margin_sum / messages as f32),
+        }
+    }
+
+    pub fn symbols_per_second(&self) -> f32 {
+        match self.elapsed.is_zero() {
+            true => 0.0,
+            false => self.symbols as f32 / self.elapsed.as_secs_f32(),
+        }
+    }
+}
+
+#[test]
+fn test_rx_metrics() {
+    use std::sync::mpsc;
+
+    use crate::audio::types::AudioSpec;
+    use crate::audio::types::NormSamples;
+    use crate::audio::types::SampleEncoding;
+    use crate::protocol::rx::Receiver;
+    use crate::protocol::tx::Transmitter;
+    use crate::utils::get_fast_profile;
+
+    let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+    let transmitter: Transmitter = Transmitter::new(&get_fast_profile(), &spec);
+    let mut samples: Vec<f32> = transmitter.create(b"one").unwrap();
+    samples.extend(transmitter.create(b"two").unwrap());
+
+    let mut receiver: Receiver = Receiver::new(get_fast_profile(), spec);
+    let (sender, updates) = mpsc::channel::<RxMetrics>();
+    receiver.set_metrics_callback(Some(Box::new(move |metrics: &RxMetrics| {
+        let _ = sender.send(metrics.clone());
+    })));
+    receiver.add_samples(&mut NormSamples::from_vec(samples.clone()));
+    receiver.analyze_full_buffer();
+
+    let metrics: RxMetrics = receiver.metrics();
+    assert_eq!(metrics.messages_decoded(), 2);
+    assert_eq!(metrics.decode_failures(), 0);
+    assert_eq!(metrics.false_starts(), 0);
+    assert!(metrics.average_snr_db().unwrap() > 10.0);
+    assert_eq!(metrics.elapsed(), spec.sample_timestamp(samples.len()));
+
+    // Every symbol of both messages, at the profile's symbol rate
+    let symbol_size: usize = get_fast_profile().pulses.into_sized(&spec).symbol_size();
+    let rate: f32 = metrics.symbols() as f32 / metrics.elapsed().as_secs_f32();
+    assert_eq!(metrics.symbols_per_second(), rate);
+    assert!(metrics.symbols() as f32 * 2.0 * symbol_size as f32 <= samples.len() as f32);
+    let updates: Vec<RxMetrics> = updates.try_iter().collect();
+    assert_eq!(updates.len(), 2);
+    assert_eq!(updates[1].messages_decoded(), 2);
+}
@@ -1,5 +1,48 @@
+#[cfg(feature = "async")]
+mod asynchronous;
+#[cfg(feature = "wav")]
+mod batch;
+mod config;
+mod event;
+#[cfg(feature = "device")]
+mod live;
+mod message;
+mod metrics;
+mod raw;
 mod receiver;
-mod resolver;
+mod report;
+mod stream;
+mod sync;
+mod wake;
+mod worker;
 
+pub use crate::embedded::RxMagnitudes;
+pub use crate::embedded::RxOutput;
+pub use crate::embedded::RxResolver;
+pub use crate::embedded::RxState;
+#[cfg(feature = "async")]
+pub use asynchronous::AsyncReceiver;
+#[cfg(feature = "wav")]
+pub use batch::decode_files;
+#[cfg(feature = "wav")]
+pub use batch::DecodeProgress;
+#[cfg(feature = "wav")]
+pub use batch::FileDecode;
+pub use config::BufferOverflow;
+pub use config::RxConfig;
+pub use event::RxEvent;
+#[cfg(feature = "device")]
+pub use live::LiveReceiver;
+pub use message::DecodedMessage;
+pub use metrics::RxMetrics;
+pub use raw::RawSymbol;
+pub use raw::RawSymbolReceiver;
+pub use raw::SymbolKind;
+pub use receiver::MetricsCallback;
 pub use receiver::Receiver;
-pub use resolver::RxResolver;
+pub use receiver::RxSnapshot;
+pub use report::RxReport;
+pub use stream::StreamReceiver;
+pub use sync::PreambleDetector;
+pub use wake::WakeDetector;
+pub use worker::DecodeWorker;
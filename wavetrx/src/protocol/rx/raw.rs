@@ -0,0 +1,304 @@
+use std::mem;
+use std::time::Duration;
+
+use crate::audio::resampler::LinearResampler;
+use crate::audio::spectrum::FourierMagnitude;
+use crate::audio::spectrum::MagnitudeBackend;
+use crate::audio::spectrum::SampleScale;
+use crate::audio::types::AudioSpec;
+use crate::audio::types::ChannelMode;
+use crate::audio::types::NormSamples;
+use crate::consts::MAX_CHANNELS;
+use crate::consts::TIMING_RECOVERY_DIVISOR;
+use crate::protocol::profile::Profile;
+use crate::protocol::profile::SizedPulses;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SymbolKind {
+    Start,
+    End,
+    Next,
+    Bits(u8),
+}
+
+// One detected pulse; `sample` counts from the first sample added, at the
+// working rate, and `magnitude` is in dB relative to the pulse's own level
+#[derive(Copy, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RawSymbol {
+    kind: SymbolKind,
+    magnitude: f32,
+    sample: usize,
+    timestamp: Duration,
+}
+
+impl RawSymbol {
+    pub fn new(kind: SymbolKind, magnitude: f32, sample: usize, spec: &AudioSpec) -> Self {
+        let timestamp: Duration = spec.sample_timestamp(sample);
+        RawSymbol {
+            kind,
+            magnitude,
+            sample,
+            timestamp,
+        }
+    }
+
+    pub fn kind(&self) -> SymbolKind {
+        self.kind
+    }
+
+    pub fn magnitude(&self) -> f32 {
+        self.magnitude
+    }
+
+    pub fn sample(&self) -> usize {
+        self.sample
+    }
+
+    pub fn timestamp(&self) -> Duration {
+        self.timestamp
+    }
+}
+
+// The modem without the protocol: every pulse of a profile tone is reported
+// as it is heard, with no Start/Next/End state machine, framing or FEC, so
+// higher layers can build their own protocols on the profile's tones
+pub struct RawSymbolReceiver<M = FourierMagnitude> {
+    pulses: SizedPulses,
+    spec: AudioSpec,
+    channels: usize,
+    channel_mode: ChannelMode,
+    resampler: LinearResampler,
+    buffer: Vec<f32>,
+    magnitude: M,
+    probes: Vec<f32>,
+    threshold: f32,
+    drained: usize,
+    symbols: Vec<RawSymbol>,
+}
+
+impl<M> RawSymbolReceiver<M>
+where
+    M: MagnitudeBackend,
+{
+    // `spec` describes the input, as for `Receiver`
+    pub fn new(profile: Profile, spec: AudioSpec) -> Self {
+        let channels: usize = (spec.channels() as usize).clamp(1, MAX_CHANNELS);
+        let channel_mode: ChannelMode = ChannelMode::default();
+        let sample_rate: u32 = profile.sample_rate.unwrap_or(spec.sample_rate());
+        let resampler: LinearResampler = LinearResampler::new(spec.sample_rate(), sample_rate);
+        let spec: AudioSpec = spec.with_channels(1).with_sample_rate(sample_rate);
+        let pulses: SizedPulses = profile.pulses.into_sized(&spec);
+        let magnitude: M = M::new(&pulses, &spec).with_window(profile.window);
+
+        // Start, End and Next followed by the symbol tones
+        let probes: Vec<f32> = [
+            profile.markers.start.hz(),
+            profile.markers.end.hz(),
+            profile.markers.next.hz(),
+        ]
+        .into_iter()
+        .chain(profile.bits.tones().iter().map(|tone| tone.hz()))
+        .collect();
+
+        RawSymbolReceiver {
+            pulses,
+            spec,
+            channels,
+            channel_mode,
+            resampler,
+            buffer: Vec::new(),
+            magnitude,
+            probes,
+            threshold: profile.threshold,
+            drained: 0,
+            symbols: Vec::new(),
+        }
+    }
+
+    pub fn spec(&self) -> AudioSpec {
+        self.spec
+    }
+
+    pub fn set_channel_mode(&mut self, mode: ChannelMode) {
+        self.channel_mode = mode;
+    }
+
+    pub fn set_threshold(&mut self, threshold: f32) {
+        self.threshold = threshold;
+    }
+
+    pub fn threshold(&self) -> f32 {
+        self.threshold
+    }
+
+    pub fn add_samples(&mut self, samples: &mut NormSamples) {
+        let frames: NormSamples = NormSamples::from_vec(mem::take(&mut samples.0));
+        let samples: NormSamples = frames.into_mono(self.channels, self.channel_mode);
+        let mut samples: Vec<f32> = self.resampler.process(&samples.0);
+        self.buffer.append(&mut samples);
+    }
+
+    // Scans everything added so far; the last pulse or so is held back until
+    // enough audio follows it to find its peak
+    pub fn analyze_buffer(&mut self) {
+        self.scan(2 * self.pulses.tone_size().max(1));
+    }
+
+    // Scans what is left at the end of the input, down to the last full pulse
+    pub fn flush(&mut self) {
+        self.scan(self.pulses.tone_size().max(1));
+    }
+
+    pub fn take_symbols(&mut self) -> Vec<RawSymbol> {
+        mem::take(&mut self.symbols)
+    }
+
+    // Drops buffered audio after a break in the input
+    pub fn reset(&mut self) {
+        self.drained += self.buffer.len();
+        self.buffer.clear();
+    }
+}
+
+impl<M> RawSymbolReceiver<M>
+where
+    M: MagnitudeBackend,
+{
+    // Pulses are only looked for where `lookahead` samples remain after them
+    fn scan(&mut self, lookahead: usize) {
+        let tone_size: usize = self.pulses.tone_size().max(1);
+        let hop: usize = (tone_size / 4).max(1);
+
+        let mut idx: usize = 0;
+        while idx + lookahead <= self.buffer.len() {
+            let frequency: f32 = match self.detect(idx) {
+                Some((_, _, frequency)) => frequency,
+                None => {
+                    idx += hop;
+                    continue;
+                }
+            };
+
+            let peak_idx: usize = self.find_peak(idx, frequency);
+            if let Some((kind, magnitude, _)) = self.detect(peak_idx) {
+                let sample: usize = self.drained + peak_idx;
+                let symbol: RawSymbol = RawSymbol::new(kind, magnitude, sample, &self.spec);
+                self.symbols.push(symbol);
+            }
+            idx = peak_idx + tone_size;
+        }
+
+        let drained: usize = idx.min(self.buffer.len());
+        self.buffer.drain(..drained);
+        self.drained += drained;
+    }
+
+    // The strongest tone in the pulse at `idx`, if any is within threshold
+    fn detect(&self, idx: usize) -> Option<(SymbolKind, f32, f32)> {
+        let samples: &[f32] = &self.buffer[idx..idx + self.pulses.tone_size()];
+        let scale: SampleScale = SampleScale::normalize_floor(samples, 1.0, 0.1);
+        let magnitudes: Vec<f32> =
+            self.magnitude
+                .get_scaled_magnitudes(samples, &scale, &self.probes);
+
+        let (probe, magnitude): (usize, f32) = magnitudes
+            .iter()
+            .copied()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))?;
+        if !(magnitude >= -self.threshold && magnitude <= self.threshold) {
+            return None;
+        }
+        let kind: SymbolKind = match probe {
+            0 => SymbolKind::Start,
+            1 => SymbolKind::End,
+            2 => SymbolKind::Next,
+            symbol => SymbolKind::Bits((symbol - 3) as u8),
+        };
+        Some((kind, magnitude, self.probes[probe]))
+    }
+
+    // A pulse is first seen while the window only partly covers it, so the
+    // window is walked across the following tone length towards the peak
+    fn find_peak(&self, idx: usize, frequency: f32) -> usize {
+        let tone_size: usize = self.pulses.tone_size();
+        let limit: usize = self.buffer.len() - tone_size;
+        let magnitude_at = |idx: usize| -> f32 {
+            let samples: &[f32] = &self.buffer[idx..idx + tone_size];
+            let scale: SampleScale = SampleScale::normalize_floor(samples, 1.0, 0.1);
+            self.magnitude
+                .get_scaled_magnitude(samples, &scale, frequency)
+        };
+
+        let mut step: usize = (tone_size / TIMING_RECOVERY_DIVISOR).max(1);
+        let mut best_idx: usize = idx;
+        let mut best_magnitude: f32 = magnitude_at(idx);
+        for candidate in (idx..=(idx + tone_size).min(limit)).step_by(step) {
+            let magnitude: f32 = magnitude_at(candidate);
+            if magnitude > best_magnitude {
+                best_idx = candidate;
+                best_magnitude = magnitude;
+            }
+        }
+
+        step /= 2;
+        while step > 0 {
+            let centre: usize = best_idx;
+            let early: Option<usize> = centre.checked_sub(step).filter(|early| *early >= idx);
+            let late: Option<usize> = Some(centre + step).filter(|late| *late <= limit);
+            for candidate in [early, late].into_iter().flatten() {
+                let magnitude: f32 = magnitude_at(candidate);
+                if magnitude > best_magnitude {
+                    best_idx = candidate;
+                    best_magnitude = magnitude;
+                }
+            }
+            step /= 2;
+        }
+        best_idx
+    }
+}
+
+#[test]
+fn test_raw_symbols() {
+    use crate::audio::types::SampleEncoding;
+    use crate::protocol::bitvec::BitVec;
+    use crate::protocol::tx::Transmitter;
+    use crate::utils::get_fast_profile;
+
+    let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+    let profile: Profile = get_fast_profile();
+    let samples: Vec<f32> = Transmitter::new(&profile, &spec).create(b"Hi").unwrap();
+
+    let mut receiver: RawSymbolReceiver = RawSymbolReceiver::new(profile, spec);
+    let mut symbols: Vec<RawSymbol> = Vec::new();
+    for chunk in samples.chunks(700) {
+        receiver.add_samples(&mut NormSamples::from_slice(chunk));
+        receiver.analyze_buffer();
+        symbols.extend(receiver.take_symbols());
+    }
+    receiver.flush();
+    symbols.extend(receiver.take_symbols());
+
+    // Every pulse of the frame, markers included, in the order it was sent
+    let frame: Vec<u8> = profile.framing.encode(b"Hi").unwrap();
+    let bits: BitVec = profile.fec.encode(&frame, profile.framing.bit_order);
+    let mut expected: Vec<SymbolKind> = vec![SymbolKind::Start, SymbolKind::Next];
+    for bit in bits.iter_bits() {
+        expected.push(SymbolKind::Bits(bit as u8));
+        expected.push(SymbolKind::Next);
+    }
+    expected.extend([SymbolKind::End, SymbolKind::Next]);
+    let kinds: Vec<SymbolKind> = symbols.iter().map(|symbol| symbol.kind()).collect();
+    assert_eq!(kinds, expected);
+
+    let symbol_size: usize = profile.pulses.into_sized(&spec).symbol_size();
+    for pair in symbols.windows(2) {
+        let spacing: usize = pair[1].sample() - pair[0].sample();
+        assert!(spacing.abs_diff(symbol_size) <= symbol_size / 16);
+        assert!(pair[0].timestamp() < pair[1].timestamp());
+        assert!(pair[0].magnitude().abs() <= profile.threshold);
+    }
+}
@@ -1,80 +1,318 @@
+use std::collections::VecDeque;
+use std::mem;
 use std::path::Path;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::sync::mpsc::Sender;
+use std::time::Duration;
+use std::time::SystemTime;
 
-use super::resolver::RxMagnitudes;
-use super::resolver::RxOutput;
-use super::resolver::RxResolver;
+use log::debug;
+use log::info;
+use log::log_enabled;
+use log::trace;
+use log::warn;
+use log::Level;
 
+use super::config::BufferOverflow;
+use super::config::RxConfig;
+use super::event::RxEvent;
+use super::message::DecodedMessage;
+use super::metrics::RxMetrics;
+use super::report::RxReport;
+use super::sync::PreambleDetector;
+
+#[cfg(feature = "wav")]
+use crate::audio::archive::WavArchive;
+use crate::audio::io::SampleSource;
+use crate::audio::resampler::LinearResampler;
 use crate::audio::spectrum::FourierMagnitude;
-use crate::audio::spectrum::Normalizer;
+use crate::audio::spectrum::MagnitudeBackend;
+use crate::audio::spectrum::NoiseEstimator;
+use crate::audio::spectrum::SampleScale;
+use crate::audio::squelch::Squelch;
 use crate::audio::types::AudioSpec;
+use crate::audio::types::ChannelMode;
 use crate::audio::types::NormSamples;
 
+use crate::consts::MAX_CHANNELS;
+use crate::consts::NOISE_MARGIN_DB;
+use crate::consts::TIMING_RECOVERY_DIVISOR;
+use crate::embedded::sample_size;
+use crate::embedded::RxMagnitudes;
+use crate::embedded::RxOutput;
+use crate::embedded::RxResolver;
+use crate::error::WavetrxError;
+use crate::protocol::bitvec::BitVec;
+use crate::protocol::compress::decompress;
+#[cfg(feature = "crypto")]
+use crate::protocol::crypto::PayloadCipher;
+use crate::protocol::fec::Fec;
+use crate::protocol::framing::BitOrder;
+use crate::protocol::framing::FrameError;
+use crate::protocol::framing::Sequence;
+use crate::protocol::framing::SequencedFrame;
+use crate::protocol::payload::Payload;
+use crate::protocol::preamble::StartMarker;
+use crate::protocol::profile::Frequency;
 use crate::protocol::profile::Profile;
 use crate::protocol::profile::SizedPulses;
-use crate::utils::bits_to_string;
-use crate::utils::read_wav_file;
+#[cfg(feature = "wav")]
+use crate::utils::read_audio_file;
 
-use crate::consts::DB_THRESHOLD;
+pub type MetricsCallback = Box<dyn FnMut(&RxMetrics) + Send>;
 
-pub struct Receiver {
+pub struct Receiver<M = FourierMagnitude> {
     profile: Profile,
     pulses: SizedPulses,
     spec: AudioSpec,
-    bits: Vec<u8>,
+    config: RxConfig,
+    channels: usize,
+    channel_mode: ChannelMode,
+    resampler: LinearResampler,
+    bits: BitVec,
+    confidences: Vec<f32>,
     buffer: NormSamples,
     resolver: RxResolver,
-    magnitude: FourierMagnitude,
+    magnitude: M,
+    probes: Vec<f32>,
+    detector: Option<PreambleDetector>,
+    noise: Option<NoiseEstimator>,
+    squelch: Option<Squelch>,
+    station: Option<u16>,
+    #[cfg(feature = "crypto")]
+    cipher: Option<PayloadCipher>,
     st_idx: Option<usize>,
+    drained: usize,
+    clock: Option<(usize, SystemTime)>,
+    peak_depth: usize,
+    overflowed: usize,
+    message_start: Option<usize>,
+    messages: Vec<DecodedMessage>,
+    frame_errors: Vec<FrameError>,
+    failed_frames: usize,
+    timing_slips: usize,
+    timing_locked: bool,
+    expected_bits: Option<usize>,
+    sequences: VecDeque<(Sequence, usize)>,
+    listeners: Vec<Sender<RxEvent>>,
+    metrics: RxMetrics,
+    on_metrics: Option<MetricsCallback>,
+    diagnostics: bool,
+    dump_dir: Option<PathBuf>,
+    dumps: usize,
+    #[cfg(feature = "wav")]
+    archive: Option<WavArchive>,
 }
 
-impl Receiver {
+impl<M> Receiver<M>
+where
+    M: MagnitudeBackend,
+{
+    // `spec` describes the input; interleaved channels are collapsed to one and
+    // resampled to the profile rate, so the working spec is mono at that rate
     pub fn new(profile: Profile, spec: AudioSpec) -> Self {
+        Receiver::with_config(profile, spec, RxConfig::default())
+    }
+
+    pub fn with_config(profile: Profile, spec: AudioSpec, config: RxConfig) -> Self {
+        let channels: usize = (spec.channels() as usize).clamp(1, MAX_CHANNELS);
+        let channel_mode: ChannelMode = ChannelMode::default();
+        let sample_rate: u32 = profile.sample_rate.unwrap_or(spec.sample_rate());
+        let resampler: LinearResampler = LinearResampler::new(spec.sample_rate(), sample_rate);
+        let spec: AudioSpec = spec.with_channels(1).with_sample_rate(sample_rate);
         let pulses: SizedPulses = profile.pulses.into_sized(&spec);
         let buffer: NormSamples = NormSamples::new();
-        let bits: Vec<u8> = Vec::new();
-        let resolver: RxResolver = RxResolver::new();
-        let magnitude: FourierMagnitude = FourierMagnitude::new(&pulses, &spec);
+        let bits: BitVec = BitVec::new();
+        let confidences: Vec<f32> = Vec::new();
+        let resolver: RxResolver = RxResolver::with_timing(profile.timing);
+        let magnitude: M = M::new(&pulses, &spec).with_window(profile.window);
+        let probes: Vec<f32> = Self::probe_frequencies(&profile);
+        let detector: Option<PreambleDetector> = match (profile.preamble, profile.start_marker) {
+            (preamble, _) if !preamble.is_none() => {
+                Some(PreambleDetector::new(&preamble, &pulses, &spec))
+            }
+            (_, StartMarker::Chirp { from, to }) => {
+                Some(PreambleDetector::chirp(from, to, pulses.tone_size(), &spec))
+            }
+            (_, StartMarker::Tone) => None,
+        };
+        let noise: Option<NoiseEstimator> = None;
+        let squelch: Option<Squelch> = None;
+        let station: Option<u16> = None;
+        #[cfg(feature = "crypto")]
+        let cipher: Option<PayloadCipher> = None;
         let st_idx: Option<usize> = None;
+        let drained: usize = 0;
+        let clock: Option<(usize, SystemTime)> = None;
+        let peak_depth: usize = 0;
+        let overflowed: usize = 0;
+        let message_start: Option<usize> = None;
+        let messages: Vec<DecodedMessage> = Vec::new();
+        let frame_errors: Vec<FrameError> = Vec::new();
+        let failed_frames: usize = 0;
+        let timing_slips: usize = 0;
+        let timing_locked: bool = false;
+        let expected_bits: Option<usize> = None;
+        let sequences: VecDeque<(Sequence, usize)> = VecDeque::new();
+        let listeners: Vec<Sender<RxEvent>> = Vec::new();
+        let metrics: RxMetrics = RxMetrics::new();
+        let on_metrics: Option<MetricsCallback> = None;
+        let diagnostics: bool = false;
+        let dump_dir: Option<PathBuf> = None;
+        let dumps: usize = 0;
+        #[cfg(feature = "wav")]
+        let archive: Option<WavArchive> = None;
         Receiver {
             profile,
             pulses,
             spec,
+            config,
+            channels,
+            channel_mode,
+            resampler,
             bits,
+            confidences,
             buffer,
             resolver,
             magnitude,
+            probes,
+            detector,
+            noise,
+            squelch,
+            station,
+            #[cfg(feature = "crypto")]
+            cipher,
             st_idx,
+            drained,
+            clock,
+            peak_depth,
+            overflowed,
+            message_start,
+            messages,
+            frame_errors,
+            failed_frames,
+            timing_slips,
+            timing_locked,
+            expected_bits,
+            sequences,
+            listeners,
+            metrics,
+            on_metrics,
+            diagnostics,
+            dump_dir,
+            dumps,
+            #[cfg(feature = "wav")]
+            archive,
         }
     }
 
-    pub fn from_file<P>(profile: Profile, filename: P) -> Self
+    #[cfg(feature = "wav")]
+    pub fn from_file<P>(profile: Profile, filename: P) -> Result<Self, WavetrxError>
     where
         P: AsRef<Path>,
     {
-        let (mut buffer, spec) = read_wav_file(filename);
-        buffer.normalize(1.0, 0.1);
+        profile.modulation.require_fsk()?;
+        let (mut buffer, spec): (NormSamples, AudioSpec) = read_audio_file(filename)?;
+        if spec.channels() as usize > MAX_CHANNELS {
+            let reason: String = format!("{} channels", spec.channels());
+            return Err(WavetrxError::UnsupportedWav(reason));
+        }
 
-        let pulses: SizedPulses = profile.pulses.into_sized(&spec);
-        let bits: Vec<u8> = Vec::new();
-        let resolver: RxResolver = RxResolver::new();
-        let magnitude: FourierMagnitude = FourierMagnitude::new(&pulses, &spec);
-        let st_idx: Option<usize> = None;
+        let mut receiver: Receiver<M> = Receiver::new(profile, spec);
+        receiver.add_samples(&mut buffer);
+        Ok(receiver)
+    }
 
-        Self {
-            profile,
-            pulses,
-            spec,
-            bits,
-            buffer,
-            resolver,
-            magnitude,
-            st_idx,
+    // Audio refused by a full buffer under `BufferOverflow::Error` is dropped
+    // with a warning; `try_add_samples` reports it instead
+    pub fn add_samples(&mut self, samples: &mut NormSamples) {
+        if let Err(err) = self.try_add_samples(samples) {
+            warn!("{}", err);
         }
     }
 
-    pub fn add_samples(&mut self, samples: &mut NormSamples) {
+    // `time` is the wall-clock capture time of the first sample; reports of
+    // messages decoded from then on carry start and end times from it
+    pub fn add_samples_at(&mut self, samples: &mut NormSamples, time: SystemTime) {
+        let position: usize = self.drained + self.buffer.0.len();
+        match self.try_add_samples(samples) {
+            Ok(()) => self.clock = Some((position, time)),
+            Err(err) => warn!("{}", err),
+        }
+    }
+
+    // Fails without taking the samples when the profile is not FSK or the
+    // buffer is full under `BufferOverflow::Error`
+    pub fn try_add_samples(&mut self, samples: &mut NormSamples) -> Result<(), WavetrxError> {
+        self.profile.modulation.require_fsk()?;
+        // Sized up front so refused audio is neither archived nor resampled
+        let incoming: usize = self.resampler.output_len(samples.0.len() / self.channels);
+        let max: Option<usize> = self.max_buffer_size();
+        let depth: usize = self.buffer.0.len() + incoming;
+        if let Some(max) = max.filter(|max| depth > *max) {
+            if self.config.overflow() == BufferOverflow::Error {
+                self.overflowed += incoming;
+                return Err(WavetrxError::BufferOverflow { depth, max });
+            }
+        }
+
+        let frames: NormSamples = NormSamples::from_vec(mem::take(&mut samples.0));
+        #[cfg(feature = "wav")]
+        if let Some(archive) = &self.archive {
+            archive.write(&frames.0);
+        }
+        let samples: NormSamples = frames.into_mono(self.channels, self.channel_mode);
+        let mut samples: NormSamples = NormSamples::from_vec(self.resampler.process(&samples.0));
+        // Empty chunks come from streamed sources and the resampler alike
+        if samples.0.is_empty() {
+            return Ok(());
+        }
+
+        if let Some(squelch) = self.squelch.as_mut() {
+            squelch.update(&samples.0);
+        }
         samples.normalize(1.0, 0.1);
         self.buffer.0.append(&mut samples.0);
+        if let Some(max) = max.filter(|max| depth > *max) {
+            self.drop_oldest(depth - max);
+        }
+        self.peak_depth = self.peak_depth.max(self.buffer.0.len());
+        Ok(())
+    }
+
+    pub fn spec(&self) -> AudioSpec {
+        self.spec
+    }
+
+    pub fn profile(&self) -> Profile {
+        self.profile
+    }
+
+    pub fn channels(&self) -> usize {
+        self.channels
+    }
+
+    pub fn config(&self) -> RxConfig {
+        self.config
+    }
+
+    // Applies from the next Start marker search
+    pub fn set_config(&mut self, config: RxConfig) {
+        self.config = config;
+    }
+
+    // Switches the input format mid-stream; buffered samples and decode state are kept
+    pub fn set_input_spec(&mut self, spec: AudioSpec) {
+        self.channels = (spec.channels() as usize).clamp(1, MAX_CHANNELS);
+        // The working rate stays put; only the conversion into it changes
+        if spec.sample_rate() != self.resampler.from_rate() {
+            self.resampler = LinearResampler::new(spec.sample_rate(), self.spec.sample_rate());
+        }
+    }
+
+    pub fn set_channel_mode(&mut self, mode: ChannelMode) {
+        self.channel_mode = mode;
     }
 
     pub fn analyze_buffer(&mut self) {
@@ -85,23 +323,338 @@ impl Receiver {
                 self.read_ahead(st_idx);
             }
         } else {
-            if self.buffer.0.len() >= (tone_size * 8) {
+            if self.buffer.0.len() >= self.warmup_size() {
+                if self.is_squelched() {
+                    return self.refresh_all_states();
+                }
                 if let Some(st_idx) = self.find_start_idx() {
-                    self.set_st_idx(st_idx);
-                    println!("# Detected Start Signal");
+                    let decode_idx: usize = self.skip_start_chirp(st_idx);
+                    self.set_st_idx(decode_idx);
+                    let sample: usize = self.drained + st_idx;
+                    self.message_start = Some(sample);
+                    info!("Start marker detected at sample {}", sample);
+                    self.emit(RxEvent::StartDetected { sample });
                 } else {
+                    self.estimate_noise();
                     self.refresh_all_states();
                 }
             }
         }
     }
 
-    pub fn save_buffer(&self, filename: &str) {
-        self.buffer.save_file(filename, &self.spec);
+    pub fn analyze_full_buffer(&mut self) {
+        loop {
+            let buffer_len: usize = self.buffer.0.len();
+            let st_idx: Option<usize> = self.st_idx;
+
+            self.analyze_buffer();
+
+            if self.buffer.0.len() == buffer_len && self.st_idx == st_idx {
+                break;
+            }
+        }
+    }
+
+    // Raw payload of the most recent message that has not been taken yet;
+    // None when nothing is waiting, as opposed to an empty payload
+    pub fn message_bytes(&self) -> Option<Vec<u8>> {
+        self.messages.last().map(|message| message.data().to_vec())
+    }
+
+    // Decodes one block of at most `block` samples from the source; returns
+    // how many were read, 0 when the source had none ready
+    pub fn read_from<S>(&mut self, source: &mut S, block: usize) -> usize
+    where
+        S: SampleSource + ?Sized,
+    {
+        let mut samples: Vec<f32> = vec![0.0; block];
+        let count: usize = source.read(&mut samples);
+        samples.truncate(count);
+        self.add_samples(&mut NormSamples::from_vec(samples));
+        self.analyze_full_buffer();
+        count
+    }
+
+    // Samples buffered at the working rate and not yet ruled out
+    pub fn buffer_depth(&self) -> usize {
+        self.buffer.0.len()
+    }
+
+    pub fn peak_buffer_depth(&self) -> usize {
+        self.peak_depth
+    }
+
+    // Samples lost to a full buffer, dropped or refused
+    pub fn overflowed_samples(&self) -> usize {
+        self.overflowed
+    }
+
+    // True from a detected Start marker until its message ends or is dropped
+    pub fn is_receiving(&self) -> bool {
+        self.st_idx.is_some()
+    }
+
+    pub fn take_messages(&mut self) -> Vec<DecodedMessage> {
+        let messages: Vec<DecodedMessage> = mem::take(&mut self.messages);
+        messages
+    }
+
+    // Drops the buffered audio and any partial message after a break in the
+    // input, so decoding restarts by hunting for a Start marker
+    pub fn resync(&mut self) {
+        self.drained += self.buffer.0.len();
+        self.buffer.0.clear();
+        self.clear_bits();
+        self.resolver.reset();
+        self.unset_st_idx();
+        self.message_start = None;
+        self.expected_bits = None;
+        self.timing_slips = 0;
+        self.timing_locked = false;
+    }
+
+    pub fn set_threshold(&mut self, threshold: f32) {
+        self.profile.threshold = threshold;
+    }
+
+    // Adaptive mode derives the threshold from the noise between messages;
+    // the profile threshold still applies until a floor has been measured
+    pub fn set_adaptive(&mut self, enabled: bool) {
+        self.noise = if enabled {
+            Some(NoiseEstimator::new(NOISE_MARGIN_DB))
+        } else {
+            None
+        };
+    }
+
+    // The squelch is checked on raw input levels, before any FFT work; it is
+    // ignored while a message is being decoded
+    pub fn set_squelch(&mut self, squelch: Option<Squelch>) {
+        self.squelch = squelch;
+    }
+
+    pub fn is_squelched(&self) -> bool {
+        match &self.squelch {
+            Some(squelch) => !squelch.is_open(),
+            None => false,
+        }
+    }
+
+    // Addressed frames for other stations are dropped without an event;
+    // broadcast and unaddressed frames are always accepted
+    pub fn set_station_id(&mut self, station: Option<u16>) {
+        self.station = station;
+    }
+
+    pub fn station_id(&self) -> Option<u16> {
+        self.station
+    }
+
+    // Frames that fail authentication surface as `FrameError::AuthenticationFailed`
+    #[cfg(feature = "crypto")]
+    pub fn set_cipher(&mut self, cipher: Option<PayloadCipher>) {
+        self.cipher = cipher;
+    }
+
+    // Logs every chunk's detected magnitudes at trace level; costly, for tuning only
+    pub fn set_diagnostics(&mut self, enabled: bool) {
+        self.diagnostics = enabled;
+    }
+
+    pub fn is_diagnostics(&self) -> bool {
+        self.diagnostics
+    }
+
+    // Writes the working samples of every decoded or failed frame into `dir`,
+    // one WAV per frame; nothing is written while unset or without `wav`
+    pub fn set_dump_dir(&mut self, dir: Option<PathBuf>) {
+        self.dump_dir = dir;
+    }
+
+    // Tees all added audio, as given, into the archive; its spec should be
+    // the input spec. Replacing or unsetting it completes the last file
+    #[cfg(feature = "wav")]
+    pub fn set_archive(&mut self, archive: Option<WavArchive>) {
+        self.archive = archive;
+    }
+
+    pub fn dump_dir(&self) -> Option<&Path> {
+        self.dump_dir.as_deref()
+    }
+
+    pub fn is_adaptive(&self) -> bool {
+        self.noise.is_some()
+    }
+
+    pub fn noise_floor(&self) -> Option<f32> {
+        self.noise.as_ref().and_then(|noise| noise.floor())
+    }
+
+    pub fn threshold(&self) -> f32 {
+        match &self.noise {
+            Some(noise) => noise.threshold(self.profile.threshold),
+            None => self.profile.threshold,
+        }
+    }
+
+    pub fn subscribe(&mut self) -> mpsc::Receiver<RxEvent> {
+        let (sender, receiver) = mpsc::channel::<RxEvent>();
+        self.listeners.push(sender);
+        receiver
+    }
+
+    // Also used by wrappers to report what happens around the receiver, such
+    // as a change of capture device
+    pub fn emit(&mut self, event: RxEvent) {
+        self.listeners
+            .retain(|listener| listener.send(event.clone()).is_ok());
+    }
+
+    // Resets the decode state for the new profile but keeps subscribers
+    pub fn set_profile(&mut self, profile: Profile) {
+        let listeners: Vec<Sender<RxEvent>> = mem::take(&mut self.listeners);
+        let metrics: RxMetrics = mem::take(&mut self.metrics);
+        let on_metrics: Option<MetricsCallback> = self.on_metrics.take();
+        let channel_mode: ChannelMode = self.channel_mode;
+        let noise: Option<NoiseEstimator> = self.noise.take();
+        let squelch: Option<Squelch> = self.squelch;
+        let station: Option<u16> = self.station;
+        let diagnostics: bool = self.diagnostics;
+        let dump_dir: Option<PathBuf> = self.dump_dir.take();
+        let dumps: usize = self.dumps;
+        #[cfg(feature = "crypto")]
+        let cipher: Option<PayloadCipher> = self.cipher.take();
+        #[cfg(feature = "wav")]
+        let archive: Option<WavArchive> = self.archive.take();
+        let spec: AudioSpec = self
+            .spec
+            .with_channels(self.channels as u16)
+            .with_sample_rate(self.resampler.from_rate());
+        *self = Receiver::with_config(profile, spec, self.config);
+        self.listeners = listeners;
+        self.metrics = metrics;
+        self.on_metrics = on_metrics;
+        self.channel_mode = channel_mode;
+        self.noise = noise;
+        self.squelch = squelch;
+        self.station = station;
+        self.diagnostics = diagnostics;
+        self.dump_dir = dump_dir;
+        self.dumps = dumps;
+        #[cfg(feature = "crypto")]
+        {
+            self.cipher = cipher;
+        }
+        #[cfg(feature = "wav")]
+        {
+            self.archive = archive;
+        }
+    }
+
+    // Captures the decode state for `restore`, e.g. before a service restart;
+    // settings, subscribers and the noise floor are not part of it
+    pub fn snapshot(&self) -> RxSnapshot {
+        RxSnapshot {
+            buffer: self.buffer.0.clone(),
+            bits: self.bits.clone(),
+            confidences: self.confidences.clone(),
+            resolver: self.resolver.clone(),
+            st_idx: self.st_idx,
+            drained: self.drained,
+            message_start: self.message_start,
+            expected_bits: self.expected_bits,
+            timing_slips: self.timing_slips,
+            timing_locked: self.timing_locked,
+            failed_frames: self.failed_frames,
+            sequences: self.sequences.clone(),
+        }
+    }
+
+    // Replaces the decode state; the receiver should use the profile and
+    // input spec the snapshot was taken with
+    pub fn restore(&mut self, snapshot: RxSnapshot) {
+        self.buffer = NormSamples::from_vec(snapshot.buffer);
+        self.bits = snapshot.bits;
+        self.confidences = snapshot.confidences;
+        self.resolver = snapshot.resolver;
+        self.st_idx = snapshot.st_idx;
+        self.drained = snapshot.drained;
+        self.message_start = snapshot.message_start;
+        self.expected_bits = snapshot.expected_bits;
+        self.timing_slips = snapshot.timing_slips;
+        self.timing_locked = snapshot.timing_locked;
+        self.failed_frames = snapshot.failed_frames;
+        self.sequences = snapshot.sequences;
+    }
+
+    // Counters since the receiver was created, covering all audio added so far
+    pub fn metrics(&self) -> RxMetrics {
+        let samples: usize = self.drained + self.buffer.0.len();
+        let elapsed: Duration = self.spec.sample_timestamp(samples);
+        self.metrics.clone().with_elapsed(elapsed)
+    }
+
+    // Called whenever a message, decode failure or false start is counted
+    pub fn set_metrics_callback(&mut self, callback: Option<MetricsCallback>) {
+        self.on_metrics = callback;
+    }
+
+    pub fn take_frame_errors(&mut self) -> Vec<FrameError> {
+        let frame_errors: Vec<FrameError> = mem::take(&mut self.frame_errors);
+        frame_errors
+    }
+
+    #[cfg(feature = "wav")]
+    pub fn save_buffer(&self, filename: &str) -> Result<(), WavetrxError> {
+        self.buffer.save_file(filename, &self.spec)
     }
 }
 
-impl Receiver {
+impl<M> Receiver<M>
+where
+    M: MagnitudeBackend,
+{
+    // Audio buffered before a Start marker search, and kept after one fails
+    fn warmup_size(&self) -> usize {
+        self.pulses.tone_size() * self.config.warmup_tones()
+    }
+
+    fn max_buffer_size(&self) -> Option<usize> {
+        let max_buffer: Duration = self.config.max_buffer()?;
+        let size: usize = sample_size(self.spec.sample_rate(), max_buffer.as_micros() as usize);
+        Some(size.max(self.warmup_size()))
+    }
+
+    // Makes room for `excess` samples; a message being decoded can't survive
+    // losing the front of the buffer, so decoding restarts at a Start marker
+    fn drop_oldest(&mut self, excess: usize) {
+        warn!("Receive buffer full, dropping {} samples", excess);
+        if self.st_idx.is_some() {
+            self.clear_bits();
+            self.resolver.reset();
+            self.unset_st_idx();
+            self.message_start = None;
+            self.expected_bits = None;
+            self.timing_slips = 0;
+            self.timing_locked = false;
+        }
+        self.overflowed += excess;
+        self.drain_buffer_to_start_index(excess);
+    }
+
+    fn update_metrics<F>(&mut self, update: F)
+    where
+        F: FnOnce(&mut RxMetrics),
+    {
+        update(&mut self.metrics);
+        if self.on_metrics.is_some() {
+            let metrics: RxMetrics = self.metrics();
+            if let Some(callback) = self.on_metrics.as_mut() {
+                callback(&metrics);
+            }
+        }
+    }
+
     fn set_st_idx(&mut self, idx: usize) {
         self.st_idx = Some(idx);
     }
@@ -115,13 +668,17 @@ impl Receiver {
         self.clear_bits();
         self.resolver.reset();
         self.unset_st_idx();
+        self.message_start = None;
+        self.expected_bits = None;
+        self.timing_slips = 0;
+        self.timing_locked = false;
     }
 
     fn drain_buffer(&mut self) {
         if let Some(st_idx) = self.st_idx {
             self.drain_buffer_to_start_index(st_idx)
         } else {
-            let idx: usize = self.buffer.0.len() - (self.pulses.tone_size() * 8);
+            let idx: usize = self.buffer.0.len().saturating_sub(self.warmup_size());
             self.drain_buffer_to_start_index(idx);
         }
         self.buffer.0.shrink_to_fit();
@@ -129,37 +686,258 @@ impl Receiver {
 
     fn clear_bits(&mut self) {
         self.bits.clear();
-        self.bits.shrink_to_fit();
+        self.confidences.clear();
     }
 
     fn drain_buffer_to_start_index(&mut self, idx: usize) {
         if idx < self.buffer.0.len() {
             self.buffer.0.drain(..idx);
+            self.drained += idx;
         } else {
+            self.drained += self.buffer.0.len();
             self.buffer.0.clear();
         }
     }
 
+    fn push_symbol(&mut self, symbol: u8, confidence: f32) {
+        self.confidences.push(confidence);
+        self.metrics.record_symbol();
+        self.emit(RxEvent::SymbolReceived {
+            value: symbol,
+            confidence,
+        });
+
+        let bits_per_symbol: usize = self.profile.bits.bits_per_symbol();
+        for offset in (0..bits_per_symbol).rev() {
+            let bit: bool = (symbol >> offset) & 1 == 1;
+            self.bits.push_bit(bit);
+            self.emit(RxEvent::BitReceived(bit));
+        }
+    }
+
+    fn build_report(&self, st_idx: usize) -> RxReport {
+        let end: usize = self.drained + st_idx + self.pulses.tone_size();
+        let start: usize = self.message_start.unwrap_or(end);
+
+        RxReport::new(start, end, &self.confidences, &self.spec)
+            .with_wall_clock(self.wall_time(start), self.wall_time(end))
+            .with_noise_floor(self.noise_floor())
+            .with_timing_slips(self.timing_slips)
+            .with_frame_errors(self.failed_frames)
+    }
+
+    // Extrapolated from the latest timed chunk at the working rate
+    fn wall_time(&self, sample: usize) -> Option<SystemTime> {
+        let (position, time): (usize, SystemTime) = self.clock?;
+        match sample >= position {
+            true => time.checked_add(self.spec.sample_timestamp(sample - position)),
+            false => time.checked_sub(self.spec.sample_timestamp(position - sample)),
+        }
+    }
+
+    // Files are numbered in decode order and named after the frame's first sample
+    #[cfg(feature = "wav")]
+    fn dump_frame(&mut self, label: &str, report: &RxReport) {
+        let dir: &Path = match &self.dump_dir {
+            Some(dir) => dir,
+            None => return,
+        };
+        let start: usize = report.start_sample().saturating_sub(self.drained);
+        let end: usize = report.end_sample().saturating_sub(self.drained);
+        let end: usize = end.min(self.buffer.0.len());
+        let start: usize = start.min(end);
+
+        let filename: String = format!("{}_{:04}_{}.wav", label, self.dumps, report.start_sample());
+        let samples: NormSamples = NormSamples::from_vec(self.buffer.0[start..end].to_vec());
+        if let Err(err) = samples.save_file(dir.join(&filename), &self.spec) {
+            warn!("Could not write {}: {}", filename, err);
+        }
+        self.dumps += 1;
+    }
+
+    #[cfg(not(feature = "wav"))]
+    fn dump_frame(&mut self, _label: &str, _report: &RxReport) {}
+
+    fn push_message(&mut self, payload: Payload, report: RxReport) {
+        let start: usize = report.start_sample();
+        let end: usize = report.end_sample();
+        let confidences: Vec<f32> = self.confidences.clone();
+        let message: DecodedMessage = DecodedMessage::new(payload, start, end)
+            .with_confidences(confidences)
+            .with_report(report);
+        self.messages.push(message);
+    }
+
+    fn resolve_frame(&mut self, st_idx: usize) {
+        let bit_order: BitOrder = self.profile.framing.bit_order;
+        let bits: BitVec = self.profile.scrambling.descramble(&self.bits);
+        let bits: BitVec = self
+            .profile
+            .interleaving
+            .deinterleave(&bits, &self.profile.fec);
+        let decoded: Result<SequencedFrame, FrameError> = self
+            .profile
+            .fec
+            .decode(&bits, bit_order)
+            .and_then(|frame| self.profile.framing.decode_sequenced(&frame));
+        let decoded: Result<(Option<Sequence>, Vec<u8>), FrameError> = match decoded {
+            Ok((address, _, _)) if !self.accepts(address) => return,
+            Ok((_, sequence, data)) => self
+                .open(data)
+                .and_then(|data| self.unpack(data))
+                .map(|data| (sequence, data)),
+            Err(err) => Err(err),
+        };
+
+        let position: usize = self.drained + st_idx;
+        self.expire_sequences(position);
+        match decoded {
+            Ok((Some(sequence), _)) if self.is_duplicate(&sequence) => {
+                info!("Dropped duplicate frame: {:?}", sequence);
+                self.remember_sequence(sequence, position);
+                self.update_metrics(RxMetrics::record_duplicate);
+                self.failed_frames = 0;
+            }
+            Ok((sequence, data)) => {
+                if let Some(sequence) = sequence {
+                    self.remember_sequence(sequence, position);
+                }
+                let payload: Payload = Payload::new(data);
+                info!("Decoded message: {}", payload.as_utf8_lossy());
+                let report: RxReport = self.build_report(st_idx);
+                self.dump_frame("message", &report);
+                self.emit(RxEvent::MessageComplete {
+                    data: payload.as_bytes().to_vec(),
+                    report: report.clone(),
+                });
+                self.update_metrics(|metrics| metrics.record_message(&report));
+                self.push_message(payload, report);
+                self.failed_frames = 0;
+            }
+            Err(err) => {
+                warn!("Frame error: {}", err);
+                let report: RxReport = self.build_report(st_idx);
+                self.dump_frame("error", &report);
+                self.emit(RxEvent::DecodeError(err.clone()));
+                self.frame_errors.push(err);
+                self.failed_frames += 1;
+                self.update_metrics(RxMetrics::record_failure);
+            }
+        }
+    }
+
+    fn open(&self, data: Vec<u8>) -> Result<Vec<u8>, FrameError> {
+        #[cfg(feature = "crypto")]
+        if let Some(cipher) = &self.cipher {
+            return cipher.open(&data);
+        }
+        Ok(data)
+    }
+
+    fn unpack(&self, data: Vec<u8>) -> Result<Vec<u8>, FrameError> {
+        match self.profile.framing.compression {
+            true => decompress(&data),
+            false => Ok(data),
+        }
+    }
+
+    fn is_duplicate(&self, sequence: &Sequence) -> bool {
+        self.config.dedup() && self.sequences.iter().any(|(seen, _)| seen == sequence)
+    }
+
+    // Least recently seen pairs are forgotten first, as a sender's counter wraps
+    fn remember_sequence(&mut self, sequence: Sequence, position: usize) {
+        self.sequences.retain(|(seen, _)| *seen != sequence);
+        self.sequences.push_back((sequence, position));
+        while self.sequences.len() > self.config.dedup_history() {
+            self.sequences.pop_front();
+        }
+    }
+
+    // Pairs heard longer ago than the dedup window, in audio time, before
+    // the frame at `position`; a restarted sender may be reusing them
+    fn expire_sequences(&mut self, position: usize) {
+        let window: Duration = match self.config.dedup_window() {
+            Some(window) => window,
+            None => return,
+        };
+        let spec: AudioSpec = self.spec;
+        self.sequences
+            .retain(|(_, at)| spec.sample_timestamp(position.saturating_sub(*at)) <= window);
+    }
+
+    fn accepts(&self, address: Option<u16>) -> bool {
+        match (self.station, address) {
+            (Some(station), Some(address)) => {
+                address == station || address == self.profile.framing.addressing.broadcast()
+            }
+            _ => true,
+        }
+    }
+
+    // With a length header the frame can be resolved as soon as its last bit
+    // arrives, so a missed End marker no longer loses the message
+    fn frame_length_reached(&mut self) -> bool {
+        if !self.profile.framing.length_prefix {
+            return false;
+        }
+
+        if self.expected_bits.is_none() {
+            let header_size: usize = self.profile.framing.header_size();
+            let bit_order: BitOrder = self.profile.framing.bit_order;
+            let fec: Fec = self.profile.fec;
+            let bits: BitVec = self.profile.scrambling.descramble(&self.bits);
+            if let Some(header) = self
+                .profile
+                .interleaving
+                .prefix(&bits, &fec)
+                .and_then(|bits| fec.decode_prefix(&bits, bit_order, header_size))
+            {
+                let expected_bits: usize = match self.profile.framing.parse_header(&header) {
+                    Ok(payload_len) => {
+                        let frame_size: usize = self.profile.framing.frame_size(payload_len);
+                        self.profile.fec.encoded_bits(frame_size)
+                    }
+                    Err(_) => self.bits.len(),
+                };
+                self.expected_bits = Some(expected_bits);
+            }
+        }
+
+        match self.expected_bits {
+            Some(expected_bits) => self.bits.len() >= expected_bits,
+            None => false,
+        }
+    }
+
     fn read_ahead(&mut self, mut st_idx: usize) {
         let tone_size: usize = self.pulses.tone_size();
-        let gap_size: usize = self.pulses.gap_size();
-        let size_to_next: usize = tone_size + gap_size;
+        let size_to_next: usize = self.pulses.symbol_size();
 
         while (st_idx + tone_size) < self.buffer.0.len() {
             match self.receive_bits(st_idx) {
-                RxOutput::Bit(bit) => {
-                    self.bits.push(bit);
-                    print!("# Bits Received: {}  \r", self.bits.len());
+                RxOutput::Symbol { value, confidence } => {
+                    self.push_symbol(value, confidence);
+                    debug!("Bits received: {}", self.bits.len());
+
+                    if self.frame_length_reached() {
+                        self.resolve_frame(st_idx);
+                        return self.refresh_all_states();
+                    }
                 }
                 RxOutput::End => {
-                    let string: String = bits_to_string(&self.bits);
-                    println!("\n# Decoded Bits: {}\n", string);
+                    self.resolve_frame(st_idx);
                     return self.refresh_all_states();
                 }
+                // Losing the stride before any bit means the Start was noise
                 RxOutput::Error => {
+                    match self.bits.is_empty() {
+                        true => self.update_metrics(RxMetrics::record_false_start),
+                        false => self.update_metrics(RxMetrics::record_failure),
+                    }
                     return self.refresh_all_states();
                 }
-                RxOutput::Undefined => {}
+                RxOutput::Undefined => st_idx = self.follow_timing(st_idx),
             }
 
             st_idx += size_to_next;
@@ -167,20 +945,93 @@ impl Receiver {
         }
     }
 
+    // Every Next marker passes through here. The first markers pull a coarse
+    // Start match onto the pulses; once one needs no correction the stride is
+    // locked, and only a correction after that counts as a slip
+    fn follow_timing(&mut self, st_idx: usize) -> usize {
+        let recovered: usize = match self.recover_timing(st_idx) {
+            Some(recovered) => recovered,
+            None => return st_idx,
+        };
+        match (recovered == st_idx, self.timing_locked) {
+            (true, _) => self.timing_locked = true,
+            (false, true) => self.timing_slips += 1,
+            (false, false) => {}
+        }
+        recovered
+    }
+
+    // Clock drift between devices walks the pulses out of the fixed stride, so
+    // re-centre on each Next marker by climbing towards its peak magnitude.
+    // None when the marker is too far off to search around
+    fn recover_timing(&self, st_idx: usize) -> Option<usize> {
+        let frequency: f32 = self.profile.markers.next.hz();
+        let tone_size: usize = self.pulses.tone_size();
+        let en_limit: usize = self.buffer.0.len().saturating_sub(tone_size);
+
+        let mut best_idx: usize = st_idx;
+        let threshold: f32 = self.threshold();
+        let mut best_magnitude: f32 = self.get_timing_magnitude(st_idx, frequency);
+        if best_magnitude < -threshold || best_magnitude > threshold {
+            return None;
+        }
+
+        let mut step: usize = (tone_size / TIMING_RECOVERY_DIVISOR).max(1);
+        while step > 0 {
+            let centre: usize = best_idx;
+            let early: Option<usize> = centre.checked_sub(step);
+            let late: Option<usize> = Some(centre + step).filter(|idx| *idx <= en_limit);
+
+            for idx in [early, late].into_iter().flatten() {
+                let magnitude: f32 = self.get_timing_magnitude(idx, frequency);
+                if magnitude > best_magnitude {
+                    best_idx = idx;
+                    best_magnitude = magnitude;
+                }
+            }
+            step /= 2;
+        }
+        Some(best_idx)
+    }
+
+    // The Start marker follows the preamble after one gap; without a preamble
+    // the detector matches a chirped Start marker directly
+    fn find_preamble_idx(&self) -> Option<usize> {
+        let detector: &PreambleDetector = self.detector.as_ref()?;
+        let preamble_idx: usize = detector.find(&self.buffer.0)?;
+        if self.profile.preamble.is_none() {
+            return Some(preamble_idx);
+        }
+        let st_idx: usize = preamble_idx + detector.len() + self.pulses.gap_size();
+        Some(st_idx)
+    }
+
+    // A chirp carries no Start tone for the resolver to see, so decoding
+    // resumes at the following Next marker
+    fn skip_start_chirp(&mut self, st_idx: usize) -> usize {
+        if !self.profile.start_marker.is_chirp() {
+            return st_idx;
+        }
+        self.resolver.assume_start();
+        st_idx + self.pulses.tone_size() + self.pulses.gap_size()
+    }
+
     fn find_start_idx(&mut self) -> Option<usize> {
+        if self.detector.is_some() {
+            return self.find_preamble_idx();
+        }
+
         let mut curr_best_idx: Option<usize> = None;
         let mut curr_best_magnitude: Option<f32> = None;
         let mut consecutive_fails: usize = 0;
-        let max_consecutive_fails: usize = 5;
+        let max_consecutive_fails: usize = self.config.max_consecutive_fails();
 
         let mut st_idx: usize = 0;
-        let skip_cycles: usize = 8;
+        let skip_cycles: usize = self.config.skip_cycles();
         let tone_size: usize = self.pulses.tone_size();
 
         while st_idx < (self.buffer.0.len() - tone_size) {
-            self.re_normalize_pulse_sized_samples(st_idx);
-            let samples: &[f32] = self.get_pulse_sized_samples(st_idx);
-            let start_magnitude: f32 = self.get_start_magnitude(samples);
+            let start_magnitude: f32 = self.get_start_magnitude(st_idx);
 
             let terminate: bool = self.start_idx_search(
                 st_idx,
@@ -208,9 +1059,10 @@ impl Receiver {
         consecutive_fails: &mut usize,
         max_consecutive_fails: usize,
     ) -> bool {
+        let threshold: f32 = self.threshold();
         match curr_best_magnitude {
             Some(previous_best_magnitude) => {
-                if start_magnitude >= *previous_best_magnitude && start_magnitude <= DB_THRESHOLD {
+                if start_magnitude >= *previous_best_magnitude && start_magnitude <= threshold {
                     *consecutive_fails = 0;
                     *curr_best_idx = Some(idx);
                     *curr_best_magnitude = Some(start_magnitude);
@@ -222,7 +1074,7 @@ impl Receiver {
                 }
             }
             None => {
-                if start_magnitude >= -DB_THRESHOLD && start_magnitude <= DB_THRESHOLD {
+                if start_magnitude >= -threshold && start_magnitude <= threshold {
                     *curr_best_idx = Some(idx);
                     *curr_best_magnitude = Some(start_magnitude);
                 }
@@ -242,63 +1094,114 @@ impl Receiver {
     }
 
     fn receive_bits(&mut self, st_idx: usize) -> RxOutput {
-        self.re_normalize_pulse_sized_samples(st_idx);
-        let samples: &[f32] = self.get_pulse_sized_samples(st_idx);
-        let magnitudes: RxMagnitudes = self.get_magnitudes(samples);
+        let magnitudes: RxMagnitudes = self.get_magnitudes(st_idx);
         let output: RxOutput = self.resolver.resolve(&magnitudes);
         output
     }
 
-    fn get_start_magnitude(&self, samples: &[f32]) -> f32 {
+    fn get_start_magnitude(&self, st_idx: usize) -> f32 {
         let frequency: f32 = self.profile.markers.start.hz();
-        let magnitude: f32 = self.magnitude.get_magnitude(samples, frequency);
-        magnitude
-    }
-
-    fn get_end_magnitude(&self, samples: &[f32]) -> f32 {
-        let frequency: f32 = self.profile.markers.end.hz();
-        let magnitude: f32 = self.magnitude.get_magnitude(samples, frequency);
-        magnitude
+        self.get_pulse_magnitude(st_idx, frequency)
     }
 
-    fn get_next_magnitude(&self, samples: &[f32]) -> f32 {
-        let frequency: f32 = self.profile.markers.next.hz();
-        let magnitude: f32 = self.magnitude.get_magnitude(samples, frequency);
-        magnitude
-    }
-
-    fn get_high_magnitude(&self, samples: &[f32]) -> f32 {
-        let frequency: f32 = self.profile.bits.high.hz();
-        let magnitude: f32 = self.magnitude.get_magnitude(samples, frequency);
-        magnitude
-    }
-
-    fn get_low_magnitude(&self, samples: &[f32]) -> f32 {
-        let frequency: f32 = self.profile.bits.low.hz();
-        let magnitude: f32 = self.magnitude.get_magnitude(samples, frequency);
-        magnitude
+    // Start, End and Next followed by the symbol tones, in profile order
+    fn probe_frequencies(profile: &Profile) -> Vec<f32> {
+        let markers: [f32; 3] = [
+            profile.markers.start.hz(),
+            profile.markers.end.hz(),
+            profile.markers.next.hz(),
+        ];
+        let tones: &[Frequency] = profile.bits.tones();
+        markers
+            .into_iter()
+            .chain(tones.iter().map(|tone| tone.hz()))
+            .collect()
     }
 
-    fn get_magnitudes(&self, samples: &[f32]) -> RxMagnitudes {
-        let start_magnitude: f32 = self.get_start_magnitude(samples);
-        let end_magnitude: f32 = self.get_end_magnitude(samples);
-        let next_magnitude: f32 = self.get_next_magnitude(samples);
-        let high_magnitude: f32 = self.get_high_magnitude(samples);
-        let low_magnitude: f32 = self.get_low_magnitude(samples);
+    // Every probe is read from the same spectrum of the chunk
+    fn get_magnitudes(&self, st_idx: usize) -> RxMagnitudes {
+        let samples: &[f32] = self.get_pulse_sized_samples(st_idx);
+        let scale: SampleScale = SampleScale::normalize_floor(samples, 1.0, 0.1);
+        let mut probed: Vec<f32> =
+            self.magnitude
+                .get_scaled_magnitudes(samples, &scale, &self.probes);
+        let symbol_magnitudes: Vec<f32> = probed.split_off(3);
+        let start_magnitude: f32 = probed[0];
+        let end_magnitude: f32 = probed[1];
+        let next_magnitude: f32 = probed[2];
 
         let magnitudes: RxMagnitudes = RxMagnitudes::new(
             start_magnitude,
             end_magnitude,
             next_magnitude,
-            high_magnitude,
-            low_magnitude,
-            DB_THRESHOLD,
+            symbol_magnitudes,
+            self.threshold(),
         );
 
-        // print_detected_magnitudes(&magnitudes);
+        if self.diagnostics && log_enabled!(Level::Trace) {
+            trace_detected_magnitudes(&magnitudes);
+        }
         magnitudes
     }
 
+    // Everything ahead of the retained tail held no Start marker; chunks that
+    // still show a tone are skipped and the rest are taken as noise
+    fn estimate_noise(&mut self) {
+        if self.noise.is_none() {
+            return;
+        }
+
+        let tone_size: usize = self.pulses.tone_size();
+        let en_idx: usize = self.buffer.0.len().saturating_sub(self.warmup_size());
+        let frequencies: Vec<f32> = self.profile_frequencies();
+        let threshold: f32 = self.threshold();
+
+        let mut levels: Vec<f32> = Vec::new();
+        for chunk in self.buffer.0[..en_idx].chunks_exact(tone_size) {
+            let scale: SampleScale = SampleScale::normalize_floor(chunk, 1.0, 0.1);
+            let level: f32 = self
+                .magnitude
+                .get_scaled_magnitudes(chunk, &scale, &frequencies)
+                .into_iter()
+                .fold(f32::NEG_INFINITY, f32::max);
+
+            // Leftovers of a message would drag the floor up to the signal
+            if level < -threshold {
+                levels.push(level);
+            }
+        }
+
+        if let Some(noise) = self.noise.as_mut() {
+            for level in levels {
+                noise.update(level);
+            }
+        }
+    }
+
+    fn profile_frequencies(&self) -> Vec<f32> {
+        let mut frequencies: Vec<f32> = vec![
+            self.profile.markers.start.hz(),
+            self.profile.markers.end.hz(),
+            self.profile.markers.next.hz(),
+        ];
+        for tone in self.profile.bits.tones() {
+            frequencies.push(tone.hz());
+        }
+        frequencies
+    }
+
+    fn get_timing_magnitude(&self, st_idx: usize, frequency: f32) -> f32 {
+        self.get_pulse_magnitude(st_idx, frequency)
+    }
+
+    // The pulse is read as if normalized on its own; the buffer is left as is
+    fn get_pulse_magnitude(&self, st_idx: usize, frequency: f32) -> f32 {
+        let samples: &[f32] = self.get_pulse_sized_samples(st_idx);
+        let scale: SampleScale = SampleScale::normalize_floor(samples, 1.0, 0.1);
+        self.magnitude
+            .get_scaled_magnitude(samples, &scale, frequency)
+    }
+
     fn get_minimum_chunk_size(&self, frequency: f32, cycles: usize) -> usize {
         let time_for_one_cycle: f32 = 1.0 / frequency;
         let chunk_time: f32 = cycles as f32 * time_for_one_cycle;
@@ -310,18 +1213,6 @@ impl Receiver {
         &self.buffer.0[st_idx..en_idx]
     }
 
-    fn get_mut_pulse_sized_samples<'a>(&'a mut self, st_idx: usize) -> &'a mut [f32] {
-        let en_idx: usize = self.get_pulse_sized_en_idx(st_idx);
-        &mut self.buffer.0[st_idx..en_idx]
-    }
-
-    fn re_normalize_pulse_sized_samples<'a>(&'a mut self, st_idx: usize) {
-        let samples: &mut [f32] = self.get_mut_pulse_sized_samples(st_idx);
-
-        let mut normalizer: Normalizer<'_> = Normalizer::new(samples);
-        normalizer.normalize_floor(1.0, 0.1);
-    }
-
     fn get_pulse_sized_en_idx(&self, st_idx: usize) -> usize {
         let en_idx: usize = st_idx + self.pulses.tone_size();
         if en_idx > self.buffer.0.len() {
@@ -331,28 +1222,157 @@ impl Receiver {
     }
 }
 
-#[allow(dead_code)]
-fn print_detected_magnitudes(magnitudes: &RxMagnitudes) {
-    let fields: [(&str, f32); 5] = [
-        ("Start", magnitudes.start),
-        ("End", magnitudes.end),
-        ("High", magnitudes.high),
-        ("Low", magnitudes.low),
-        ("Next", magnitudes.next),
-    ];
+// Decode state of a `Receiver` at one moment: the buffered audio, the
+// resolver's expectations and the bits of any message under way. Restored
+// into a receiver built with the same profile and input spec, decoding
+// carries on as if it had never stopped
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RxSnapshot {
+    buffer: Vec<f32>,
+    bits: BitVec,
+    confidences: Vec<f32>,
+    resolver: RxResolver,
+    st_idx: Option<usize>,
+    drained: usize,
+    message_start: Option<usize>,
+    expected_bits: Option<usize>,
+    timing_slips: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    timing_locked: bool,
+    failed_frames: usize,
+    sequences: VecDeque<(Sequence, usize)>,
+}
 
-    let mut printed: bool = false;
-    for (label, value) in fields.iter() {
-        if magnitudes.within_threshold(*value) {
-            if printed {
-                print!(" | ");
-            }
-            print!("{}: {:.2} dB", label, value);
-            printed = true;
-        }
+impl RxSnapshot {
+    // True while a message was being received when the snapshot was taken
+    pub fn is_receiving(&self) -> bool {
+        self.st_idx.is_some()
     }
 
-    if printed {
-        println!();
+    // Samples consumed before the snapshot, so restored positions line up
+    pub fn position(&self) -> usize {
+        self.drained + self.buffer.len()
     }
 }
+
+fn trace_detected_magnitudes(magnitudes: &RxMagnitudes) {
+    let mut fields: Vec<(String, f32)> = vec![
+        ("Start".to_string(), magnitudes.start),
+        ("End".to_string(), magnitudes.end),
+        ("Next".to_string(), magnitudes.next),
+    ];
+    for (idx, value) in magnitudes.symbols.iter().enumerate() {
+        fields.push((format!("Symbol {}", idx), *value));
+    }
+
+    let detected: Vec<String> = fields
+        .iter()
+        .filter(|(_, value)| magnitudes.within_threshold(*value))
+        .map(|(label, value)| format!("{}: {:.2} dB", label, value))
+        .collect();
+    if !detected.is_empty() {
+        trace!("{}", detected.join(" | "));
+    }
+}
+
+#[test]
+fn test_snapshot_resume() {
+    use crate::audio::types::AudioSpec;
+    use crate::audio::types::NormSamples;
+    use crate::audio::types::SampleEncoding;
+    use crate::protocol::tx::Transmitter;
+    use crate::utils::get_fast_profile;
+
+    let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+    let samples: Vec<f32> = Transmitter::new(&get_fast_profile(), &spec)
+        .create(b"resume me")
+        .unwrap();
+    let (head, tail): (&[f32], &[f32]) = samples.split_at(samples.len() / 2);
+
+    let mut receiver: Receiver = Receiver::new(get_fast_profile(), spec);
+    receiver.add_samples(&mut NormSamples::from_slice(head));
+    receiver.analyze_full_buffer();
+    let snapshot: RxSnapshot = receiver.snapshot();
+    assert!(snapshot.is_receiving());
+    assert_eq!(snapshot.position(), head.len());
+
+    // A service restart mid-message: the new receiver picks up where it stopped
+    #[cfg(feature = "serde")]
+    let snapshot: RxSnapshot = {
+        let json: String = serde_json::to_string(&snapshot).unwrap();
+        let restored: RxSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, snapshot);
+        restored
+    };
+    let mut restarted: Receiver = Receiver::new(get_fast_profile(), spec);
+    restarted.restore(snapshot);
+    assert!(restarted.is_receiving());
+    restarted.add_samples(&mut NormSamples::from_slice(tail));
+    restarted.analyze_full_buffer();
+    let messages: Vec<Vec<u8>> = restarted
+        .take_messages()
+        .into_iter()
+        .map(|message| message.into_data())
+        .collect();
+    assert_eq!(messages, vec![b"resume me".to_vec()]);
+}
+
+#[test]
+fn test_input_rate_change() {
+    use crate::audio::resampler::resample;
+    use crate::audio::types::AudioSpec;
+    use crate::audio::types::NormSamples;
+    use crate::audio::types::SampleEncoding;
+    use crate::protocol::tx::Transmitter;
+    use crate::utils::get_fast_profile;
+
+    let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+    let samples: Vec<f32> = Transmitter::new(&get_fast_profile(), &spec)
+        .create(b"new mic")
+        .unwrap();
+    let (head, tail): (&[f32], &[f32]) = samples.split_at(samples.len() / 2);
+
+    // The second half arrives from a 44.1 kHz device swapped in mid-message
+    let mut receiver: Receiver = Receiver::new(get_fast_profile(), spec);
+    receiver.add_samples(&mut NormSamples::from_slice(head));
+    receiver.set_input_spec(spec.with_sample_rate(44_100));
+    receiver.add_samples(&mut NormSamples::from_vec(resample(tail, 48_000, 44_100)));
+    receiver.analyze_full_buffer();
+
+    assert_eq!(receiver.spec().sample_rate(), 48_000);
+    assert_eq!(receiver.message_bytes().unwrap(), b"new mic");
+}
+
+#[test]
+#[cfg(feature = "wav")]
+fn test_archive_across_profile() {
+    use std::fs;
+
+    use crate::audio::archive::ArchiveConfig;
+    use crate::audio::types::AudioSpec;
+    use crate::audio::types::NormSamples;
+    use crate::audio::types::SampleEncoding;
+    use crate::utils::get_fast_profile;
+    use crate::utils::read_wav_file;
+
+    let dir: PathBuf =
+        std::env::temp_dir().join(format!("wavetrx_rx_archive_{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let spec: AudioSpec = AudioSpec::new(48_000, 16, 1, SampleEncoding::I32);
+    let config: ArchiveConfig = ArchiveConfig::new().with_prefix("rx");
+    let archive: WavArchive = WavArchive::start(&dir, &spec, config).unwrap();
+
+    // Audio added after the profile change lands in the same capture
+    let mut receiver: Receiver = Receiver::new(get_fast_profile(), spec);
+    receiver.set_archive(Some(archive));
+    receiver.add_samples(&mut NormSamples::from_vec(vec![0.25; 1_000]));
+    receiver.set_profile(get_fast_profile());
+    receiver.add_samples(&mut NormSamples::from_vec(vec![-0.25; 1_000]));
+    receiver.set_archive(None);
+
+    let (samples, _): (NormSamples, AudioSpec) = read_wav_file(dir.join("rx_000000.wav")).unwrap();
+    assert_eq!(samples.0.len(), 2_000);
+    assert!(samples.0[1_500] < 0.0);
+    let _ = fs::remove_dir_all(&dir);
+}
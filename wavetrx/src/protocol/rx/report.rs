@@ -0,0 +1,137 @@
+use std::time::Duration;
+use std::time::SystemTime;
+
+use crate::audio::types::AudioSpec;
+
+// Reception statistics for one completed message; margins are the per-symbol
+// dB lead of the winning tone over the runner-up
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RxReport {
+    start: usize,
+    end: usize,
+    start_time: Duration,
+    duration: Duration,
+    symbols: usize,
+    mean_margin: f32,
+    min_margin: f32,
+    noise_floor: Option<f32>,
+    timing_slips: usize,
+    frame_errors: usize,
+    #[cfg_attr(feature = "serde", serde(default))]
+    started_at: Option<SystemTime>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    ended_at: Option<SystemTime>,
+}
+
+impl RxReport {
+    pub fn new(start: usize, end: usize, margins: &[f32], spec: &AudioSpec) -> Self {
+        let start_time: Duration = spec.sample_timestamp(start);
+        let duration: Duration = spec.sample_timestamp(end.saturating_sub(start));
+        let symbols: usize = margins.len();
+        let mean_margin: f32 = match symbols {
+            0 => 0.0,
+            _ => margins.iter().sum::<f32>() / symbols as f32,
+        };
+        let min_margin: f32 = margins.iter().copied().reduce(f32::min).unwrap_or(0.0);
+        let noise_floor: Option<f32> = None;
+        let timing_slips: usize = 0;
+        let frame_errors: usize = 0;
+        let started_at: Option<SystemTime> = None;
+        let ended_at: Option<SystemTime> = None;
+        RxReport {
+            start,
+            end,
+            start_time,
+            duration,
+            symbols,
+            mean_margin,
+            min_margin,
+            noise_floor,
+            timing_slips,
+            frame_errors,
+            started_at,
+            ended_at,
+        }
+    }
+
+    pub fn with_noise_floor(mut self, noise_floor: Option<f32>) -> Self {
+        self.noise_floor = noise_floor;
+        self
+    }
+
+    pub fn with_timing_slips(mut self, timing_slips: usize) -> Self {
+        self.timing_slips = timing_slips;
+        self
+    }
+
+    pub fn with_frame_errors(mut self, frame_errors: usize) -> Self {
+        self.frame_errors = frame_errors;
+        self
+    }
+
+    pub fn with_wall_clock(
+        mut self,
+        started_at: Option<SystemTime>,
+        ended_at: Option<SystemTime>,
+    ) -> Self {
+        self.started_at = started_at;
+        self.ended_at = ended_at;
+        self
+    }
+
+    pub fn start_sample(&self) -> usize {
+        self.start
+    }
+
+    pub fn end_sample(&self) -> usize {
+        self.end
+    }
+
+    // Offset of the Start marker from the beginning of the stream
+    pub fn start_time(&self) -> Duration {
+        self.start_time
+    }
+
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    pub fn symbols(&self) -> usize {
+        self.symbols
+    }
+
+    pub fn mean_margin(&self) -> f32 {
+        self.mean_margin
+    }
+
+    pub fn min_margin(&self) -> f32 {
+        self.min_margin
+    }
+
+    // Only known when the receiver tracks the noise floor adaptively
+    pub fn noise_floor(&self) -> Option<f32> {
+        self.noise_floor
+    }
+
+    // Symbols that fell outside the expected stride and had to be re-centred
+    pub fn timing_slips(&self) -> usize {
+        self.timing_slips
+    }
+
+    // Frames that failed to decode since the previous completed message
+    pub fn frame_errors(&self) -> usize {
+        self.frame_errors
+    }
+
+    // Wall-clock capture time of the Start marker, when the audio was added
+    // with `Receiver::add_samples_at`
+    pub fn started_at(&self) -> Option<SystemTime> {
+        self.started_at
+    }
+
+    // Wall-clock capture time of the end of the End marker
+    pub fn ended_at(&self) -> Option<SystemTime> {
+        self.ended_at
+    }
+}
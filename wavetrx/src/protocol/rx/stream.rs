@@ -0,0 +1,151 @@
+#[cfg(feature = "async")]
+use std::pin::Pin;
+use std::sync::mpsc;
+#[cfg(feature = "async")]
+use std::task::Context;
+#[cfg(feature = "async")]
+use std::task::Poll;
+
+#[cfg(feature = "async")]
+use futures_core::Stream;
+
+use super::event::RxEvent;
+use super::receiver::Receiver;
+
+use crate::audio::spectrum::FourierMagnitude;
+use crate::audio::spectrum::MagnitudeBackend;
+use crate::audio::types::NormSamples;
+
+// Pulls sample chunks from any source, e.g. a StreamTransmitter, a file
+// reader or a network socket, and yields the receiver's events in order.
+// A chunk is only read once the events of the previous one are used up
+pub struct StreamReceiver<I, M = FourierMagnitude> {
+    receiver: Receiver<M>,
+    events: mpsc::Receiver<RxEvent>,
+    source: I,
+}
+
+impl<I, M> StreamReceiver<I, M>
+where
+    M: MagnitudeBackend,
+{
+    // Takes a configured receiver; chunks must match its input spec
+    pub fn new(mut receiver: Receiver<M>, source: I) -> Self {
+        let events: mpsc::Receiver<RxEvent> = receiver.subscribe();
+        StreamReceiver {
+            receiver,
+            events,
+            source,
+        }
+    }
+
+    pub fn receiver(&self) -> &Receiver<M> {
+        &self.receiver
+    }
+
+    pub fn receiver_mut(&mut self) -> &mut Receiver<M> {
+        &mut self.receiver
+    }
+
+    pub fn into_receiver(self) -> Receiver<M> {
+        self.receiver
+    }
+}
+
+impl<I, M> StreamReceiver<I, M>
+where
+    M: MagnitudeBackend,
+{
+    // Completed messages already travel in `MessageComplete`, so the
+    // receiver's own queue is dropped instead of growing with the stream
+    fn decode(&mut self, chunk: Vec<f32>) {
+        self.receiver.add_samples(&mut NormSamples::from_vec(chunk));
+        self.receiver.analyze_full_buffer();
+        self.receiver.take_messages();
+    }
+}
+
+impl<I, M> Iterator for StreamReceiver<I, M>
+where
+    I: Iterator<Item = Vec<f32>>,
+    M: MagnitudeBackend,
+{
+    type Item = RxEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Ok(event) = self.events.try_recv() {
+                return Some(event);
+            }
+            let chunk: Vec<f32> = self.source.next()?;
+            self.decode(chunk);
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+impl<S, M> Stream for StreamReceiver<S, M>
+where
+    S: Stream<Item = Vec<f32>> + Unpin,
+    M: MagnitudeBackend + Unpin,
+{
+    type Item = RxEvent;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this: &mut Self = self.get_mut();
+        loop {
+            if let Ok(event) = this.events.try_recv() {
+                return Poll::Ready(Some(event));
+            }
+            match Pin::new(&mut this.source).poll_next(cx) {
+                Poll::Ready(Some(chunk)) => this.decode(chunk),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(feature = "async")]
+#[test]
+fn test_stream_receiver_async() {
+    use std::collections::VecDeque;
+    use std::future;
+
+    use crate::audio::types::AudioSpec;
+    use crate::audio::types::SampleEncoding;
+    use crate::protocol::tx::Transmitter;
+    use crate::utils::get_fast_profile;
+
+    struct Chunks(VecDeque<Vec<f32>>);
+
+    impl Stream for Chunks {
+        type Item = Vec<f32>;
+
+        fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Vec<f32>>> {
+            Poll::Ready(self.get_mut().0.pop_front())
+        }
+    }
+
+    let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+    let samples: Vec<f32> = Transmitter::new(&get_fast_profile(), &spec)
+        .create(b"Wt")
+        .unwrap();
+    let chunks: Chunks = Chunks(samples.chunks(512).map(|chunk| chunk.to_vec()).collect());
+    let receiver: Receiver = Receiver::new(get_fast_profile(), spec);
+    let mut stream: StreamReceiver<Chunks> = StreamReceiver::new(receiver, chunks);
+
+    let runtime: tokio::runtime::Runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let data: Vec<Vec<u8>> = runtime.block_on(async {
+        let mut data: Vec<Vec<u8>> = Vec::new();
+        while let Some(event) = future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+            if let RxEvent::MessageComplete { data: message, .. } = event {
+                data.push(message);
+            }
+        }
+        data
+    });
+    assert_eq!(data, vec![b"Wt".to_vec()]);
+}
@@ -0,0 +1,124 @@
+use std::f32::consts::SQRT_2;
+
+use crate::audio::types::AudioSpec;
+use crate::protocol::preamble::chirp_phase;
+use crate::protocol::preamble::Preamble;
+use crate::protocol::profile::SizedPulses;
+
+const CORRELATION_THRESHOLD: f32 = 0.5;
+
+// Matched filter against the analytic (cos/sin) preamble, so the match does
+// not depend on the phase the signal arrives with
+pub struct PreambleDetector {
+    in_phase: Vec<f32>,
+    quadrature: Vec<f32>,
+}
+
+impl PreambleDetector {
+    pub fn new(preamble: &Preamble, pulses: &SizedPulses, spec: &AudioSpec) -> Self {
+        let size: usize = preamble.sample_size(pulses);
+        let phases: Vec<f32> = (0..size)
+            .map(|idx| preamble.phase(idx, size, spec))
+            .collect();
+        PreambleDetector::from_phases(&phases)
+    }
+
+    // Matches a single chirp of `size` samples, e.g. a chirped Start marker
+    pub fn chirp(from: f32, to: f32, size: usize, spec: &AudioSpec) -> Self {
+        let phases: Vec<f32> = (0..size)
+            .map(|idx| chirp_phase(from, to, idx, size, spec))
+            .collect();
+        PreambleDetector::from_phases(&phases)
+    }
+
+    pub fn len(&self) -> usize {
+        self.in_phase.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.in_phase.is_empty()
+    }
+
+    // A clean, aligned copy of the preamble scores close to 1.0
+    pub fn correlation(&self, samples: &[f32]) -> f32 {
+        let mut real: f32 = 0.0;
+        let mut imag: f32 = 0.0;
+        let mut energy: f32 = 0.0;
+
+        for ((sample, i), q) in samples
+            .iter()
+            .zip(self.in_phase.iter())
+            .zip(self.quadrature.iter())
+        {
+            real += sample * i;
+            imag += sample * q;
+            energy += sample * sample;
+        }
+
+        if energy == 0.0 {
+            return 0.0;
+        }
+
+        let magnitude: f32 = (real * real + imag * imag).sqrt();
+        magnitude * SQRT_2 / (energy.sqrt() * (self.len() as f32).sqrt())
+    }
+
+    // Offset of the first match in `samples`, refined to its correlation peak
+    pub fn find(&self, samples: &[f32]) -> Option<usize> {
+        let size: usize = self.len();
+        if self.is_empty() || samples.len() < size {
+            return None;
+        }
+
+        let last_idx: usize = samples.len() - size;
+        let first_idx: usize = (0..=last_idx)
+            .find(|&idx| self.correlation(&samples[idx..idx + size]) >= CORRELATION_THRESHOLD)?;
+
+        let mut best_idx: usize = first_idx;
+        let mut best_correlation: f32 = 0.0;
+        for idx in first_idx..=(first_idx + size).min(last_idx) {
+            let correlation: f32 = self.correlation(&samples[idx..idx + size]);
+            if correlation > best_correlation {
+                best_idx = idx;
+                best_correlation = correlation;
+            }
+        }
+        Some(best_idx)
+    }
+}
+
+impl PreambleDetector {
+    fn from_phases(phases: &[f32]) -> Self {
+        let in_phase: Vec<f32> = phases.iter().map(|phase| phase.cos()).collect();
+        let quadrature: Vec<f32> = phases.iter().map(|phase| phase.sin()).collect();
+
+        PreambleDetector {
+            in_phase,
+            quadrature,
+        }
+    }
+}
+
+#[test]
+fn test_preamble_detector() {
+    use crate::audio::types::SampleEncoding;
+    use crate::utils::get_fast_profile;
+
+    let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+    let pulses: SizedPulses = get_fast_profile().pulses.into_sized(&spec);
+    let preamble: Preamble = Preamble::Chirp {
+        from: 1_000.0,
+        to: 9_000.0,
+    };
+    let detector: PreambleDetector = PreambleDetector::new(&preamble, &pulses, &spec);
+
+    let offset: usize = 137;
+    let mut samples: Vec<f32> = vec![0.0; offset];
+    for idx in 0..detector.len() {
+        samples.push(0.5 * (preamble.phase(idx, detector.len(), &spec) + 0.3).sin());
+    }
+    samples.extend(vec![0.0; 100]);
+
+    assert_eq!(detector.find(&samples), Some(offset));
+    assert_eq!(detector.find(&vec![0.0; 1000]), None);
+}
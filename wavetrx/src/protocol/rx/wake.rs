@@ -0,0 +1,229 @@
+use std::mem;
+use std::time::Duration;
+
+use crate::audio::types::AudioSpec;
+use crate::consts::WAKE_CONTRAST_DB;
+use crate::consts::WAKE_FLOOR_DB;
+use crate::consts::WAKE_HOLD;
+use crate::consts::WAKE_SHARE;
+use crate::embedded::sample_size;
+use crate::embedded::Goertzel;
+use crate::protocol::preamble::StartMarker;
+use crate::protocol::profile::Profile;
+use crate::protocol::profile::SizedPulses;
+
+// Windows of audio kept from before a wake so the pipeline sees the marker
+const WAKE_PRE_ROLL: usize = 4;
+
+// First stage of a low-power receiver: while asleep only the marker that
+// opens every frame is watched, with one Goertzel filter over windows two
+// pulse periods long, and the full decode pipeline stays idle. Chirped Start
+// markers are followed by the Next tone, which is watched instead
+pub struct WakeDetector {
+    goertzel: Goertzel,
+    references: [Goertzel; 2],
+    channels: usize,
+    tone: usize,
+    hop: usize,
+    floor: f32,
+    pending: Vec<f32>,
+    pre_roll: Vec<f32>,
+    hold: usize,
+    idle: usize,
+    awake: bool,
+    wakes: usize,
+}
+
+impl WakeDetector {
+    // `spec` is the capture spec; audio is watched before any resampling
+    pub fn new(profile: &Profile, spec: &AudioSpec) -> Self {
+        let frequency: f32 = match profile.start_marker {
+            StartMarker::Tone => profile.markers.start.hz(),
+            StartMarker::Chirp { .. } => profile.markers.next.hz(),
+        };
+        let pulses: SizedPulses = profile.pulses.into_sized(&spec.with_channels(1));
+        let tone: usize = pulses.tone_size().max(1);
+        let hop: usize = pulses.symbol_size().max(1);
+        let goertzel: Goertzel = Goertzel::new(frequency, spec.sample_rate(), 2 * hop);
+
+        // Past the first spectral nulls of a pulse, where the marker itself
+        // reads low but a click or a sweep doesn't
+        let offset: f32 = 1.5 * spec.sample_rate() as f32 / tone as f32;
+        let references: [Goertzel; 2] = [
+            Goertzel::new(frequency - offset, spec.sample_rate(), 2 * hop),
+            Goertzel::new(frequency + offset, spec.sample_rate(), 2 * hop),
+        ];
+        let hold: usize = sample_size(spec.sample_rate(), WAKE_HOLD.as_micros() as usize);
+
+        WakeDetector {
+            goertzel,
+            references,
+            channels: (spec.channels() as usize).max(1),
+            tone,
+            hop,
+            floor: 10.0_f32.powf(WAKE_FLOOR_DB / 10.0),
+            pending: Vec::new(),
+            pre_roll: Vec::new(),
+            hold,
+            idle: 0,
+            awake: false,
+            wakes: 0,
+        }
+    }
+
+    // Quiet input time before the pipeline is put back to sleep
+    pub fn with_hold(mut self, hold: Duration, sample_rate: u32) -> Self {
+        self.hold = sample_size(sample_rate, hold.as_micros() as usize);
+        self
+    }
+
+    pub fn is_awake(&self) -> bool {
+        self.awake
+    }
+
+    // How often the detector has woken the pipeline
+    pub fn wakes(&self) -> usize {
+        self.wakes
+    }
+
+    // Scans interleaved `frames` while asleep. On a plausible marker the
+    // detector wakes and returns everything kept since shortly before it,
+    // which the pipeline should decode in place of `frames`
+    pub fn listen(&mut self, frames: &[f32]) -> Option<Vec<f32>> {
+        if self.awake {
+            return Some(frames.to_vec());
+        }
+
+        self.pre_roll.extend_from_slice(frames);
+        self.pending.extend(
+            frames
+                .chunks(self.channels)
+                .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32),
+        );
+
+        let window: usize = 2 * self.hop;
+        let mut start: usize = 0;
+        while start + window <= self.pending.len() {
+            if self.is_marker(&self.pending[start..start + window]) {
+                self.awake = true;
+                self.idle = 0;
+                self.wakes += 1;
+                self.pending.clear();
+                return Some(mem::take(&mut self.pre_roll));
+            }
+            start += self.hop;
+        }
+        self.pending.drain(..start);
+
+        let keep: usize = WAKE_PRE_ROLL * window * self.channels;
+        let excess: usize = self.pre_roll.len().saturating_sub(keep);
+        self.pre_roll.drain(..excess);
+        None
+    }
+
+    // Reports decoded audio while awake; `active` while a message is being
+    // received. Returns true once the input has been quiet for the hold time
+    // and the detector has gone back to sleep
+    pub fn settle(&mut self, samples: usize, active: bool) -> bool {
+        if !self.awake {
+            return false;
+        }
+        match active {
+            true => self.idle = 0,
+            false => self.idle += samples / self.channels,
+        }
+        if self.idle < self.hold {
+            return false;
+        }
+        self.sleep();
+        true
+    }
+
+    pub fn sleep(&mut self) {
+        self.awake = false;
+        self.idle = 0;
+        self.pending.clear();
+        self.pre_roll.clear();
+    }
+}
+
+impl WakeDetector {
+    // A tone of `tone` samples reads `tone / window` of its level over the
+    // whole window; scaled back up, its energy is compared with the window's.
+    // Clicks are broadband, so the marker also has to stand clear of the
+    // frequencies either side of it
+    fn is_marker(&self, window: &[f32]) -> bool {
+        let energy: f32 = window.iter().map(|sample| sample * sample).sum();
+        if energy <= self.floor * window.len() as f32 {
+            return false;
+        }
+
+        let marker_db: f32 = self.goertzel.magnitude_db(window);
+        let amplitude: f32 = 10.0_f32.powf(marker_db / 20.0);
+        let level: f32 = amplitude * window.len() as f32 / self.tone as f32;
+        let tone_energy: f32 = level * level * self.tone as f32 / 2.0;
+        if tone_energy / energy < WAKE_SHARE {
+            return false;
+        }
+        self.references
+            .iter()
+            .all(|reference| marker_db - reference.magnitude_db(window) >= WAKE_CONTRAST_DB)
+    }
+}
+
+#[test]
+fn test_wake_detector() {
+    use crate::audio::types::NormSamples;
+    use crate::audio::types::SampleEncoding;
+    use crate::protocol::rx::Receiver;
+    use crate::protocol::tx::ToneGenerator;
+    use crate::protocol::tx::Transmitter;
+    use crate::sim::NoiseSource;
+    use crate::utils::get_fast_profile;
+
+    let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+    let profile: Profile = get_fast_profile();
+    let mut noise: NoiseSource = NoiseSource::new(11);
+    let mut samples: Vec<f32> = (0..48_000).map(|_| 0.01 * noise.gaussian()).collect();
+
+    // An off-profile whistle and background noise leave it asleep
+    let mut whistle: ToneGenerator = ToneGenerator::new(&spec).unwrap();
+    whistle.append_tone(12_500.0, 200_000).unwrap();
+    for (sample, tone) in samples.iter_mut().zip(whistle.samples()) {
+        *sample += 0.3 * tone;
+    }
+    let frame: Vec<f32> = Transmitter::new(&profile, &spec)
+        .create(b"wake up")
+        .unwrap();
+    samples.extend(frame.iter().map(|sample| 0.5 * sample));
+    samples.extend(vec![0.0; 96_000]);
+
+    let mut wake: WakeDetector =
+        WakeDetector::new(&profile, &spec).with_hold(Duration::from_millis(500), 48_000);
+    let mut receiver: Receiver = Receiver::new(profile, spec);
+    let mut woke_at: Option<usize> = None;
+    let mut slept: bool = false;
+    for (idx, chunk) in samples.chunks(480).enumerate() {
+        let frames: Vec<f32> = match wake.listen(chunk) {
+            Some(frames) => frames,
+            None => continue,
+        };
+        woke_at.get_or_insert(idx * 480);
+        receiver.add_samples(&mut NormSamples::from_vec(frames));
+        receiver.analyze_full_buffer();
+        if wake.settle(chunk.len(), receiver.is_receiving()) {
+            slept = true;
+            break;
+        }
+    }
+
+    assert!(woke_at.unwrap() >= 48_000);
+    assert_eq!(wake.wakes(), 1);
+    assert!(slept && !wake.is_awake());
+    let messages: Vec<Vec<u8>> = receiver
+        .take_messages()
+        .into_iter()
+        .map(|message| message.into_data())
+        .collect();
+    assert_eq!(messages, vec![b"wake up".to_vec()]);
+}
@@ -0,0 +1,121 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::thread::JoinHandle;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use super::message::DecodedMessage;
+use super::receiver::Receiver;
+
+use crate::audio::spectrum::FourierMagnitude;
+use crate::audio::spectrum::MagnitudeBackend;
+use crate::audio::types::Backpressure;
+use crate::audio::types::FrameBuffer;
+use crate::audio::types::NormSamples;
+
+const IDLE_INTERVAL: Duration = Duration::from_millis(10);
+
+// Runs a Receiver on its own thread; `add_samples` only queues the frame, so
+// the caller never pays for the FFT work
+pub struct DecodeWorker<M = FourierMagnitude> {
+    frames: Arc<FrameBuffer>,
+    messages: mpsc::Receiver<DecodedMessage>,
+    running: Arc<AtomicBool>,
+    handle: Option<JoinHandle<Receiver<M>>>,
+}
+
+impl<M> DecodeWorker<M>
+where
+    M: MagnitudeBackend + Send + 'static,
+{
+    pub fn spawn(receiver: Receiver<M>, capacity: usize, policy: Backpressure) -> Self {
+        let frames: Arc<FrameBuffer> = FrameBuffer::bounded(capacity, policy);
+        let (sender, messages): (mpsc::Sender<DecodedMessage>, mpsc::Receiver<DecodedMessage>) =
+            mpsc::channel();
+        let running: Arc<AtomicBool> = Arc::new(AtomicBool::new(true));
+
+        let handle: JoinHandle<Receiver<M>> =
+            Self::spawn_thread(receiver, frames.clone(), sender, running.clone());
+        DecodeWorker {
+            frames,
+            messages,
+            running,
+            handle: Some(handle),
+        }
+    }
+
+    pub fn add_samples(&self, samples: NormSamples) {
+        self.frames.add_frame(samples);
+    }
+
+    // `time` is the wall-clock capture time of the first sample
+    pub fn add_samples_at(&self, samples: NormSamples, time: SystemTime) {
+        self.frames.add_timed_frame(samples, time);
+    }
+
+    pub fn take_messages(&self) -> Vec<DecodedMessage> {
+        self.messages.try_iter().collect()
+    }
+
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<DecodedMessage> {
+        self.messages.recv_timeout(timeout).ok()
+    }
+
+    pub fn queued_frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn dropped_frames(&self) -> usize {
+        self.frames.dropped()
+    }
+
+    // Frames already queued are decoded before the receiver is handed back
+    pub fn stop(mut self) -> Option<Receiver<M>> {
+        self.running.store(false, Ordering::Relaxed);
+        self.handle.take().and_then(|handle| handle.join().ok())
+    }
+}
+
+impl<M> DecodeWorker<M>
+where
+    M: MagnitudeBackend + Send + 'static,
+{
+    fn spawn_thread(
+        mut receiver: Receiver<M>,
+        frames: Arc<FrameBuffer>,
+        sender: mpsc::Sender<DecodedMessage>,
+        running: Arc<AtomicBool>,
+    ) -> JoinHandle<Receiver<M>> {
+        thread::spawn(move || {
+            loop {
+                match frames.take_timed_timeout(IDLE_INTERVAL) {
+                    Some((mut frame, time)) => {
+                        match time {
+                            Some(time) => receiver.add_samples_at(&mut frame, time),
+                            None => receiver.add_samples(&mut frame),
+                        }
+                        receiver.analyze_full_buffer();
+                        for message in receiver.take_messages() {
+                            let _ = sender.send(message);
+                        }
+                    }
+                    None if !running.load(Ordering::Relaxed) => break,
+                    None => {}
+                }
+            }
+            receiver
+        })
+    }
+}
+
+impl<M> Drop for DecodeWorker<M> {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use crate::protocol::bitvec::BitVec;
+
+// Whitens the bits sent after FEC and interleaving, so long runs of one value
+// don't become long runs of one tone. The sequence restarts with every frame
+// and is XORed in, so scrambling and descrambling are the same operation and
+// a prefix of the frame can be descrambled on its own
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Scrambling {
+    #[default]
+    None,
+    // PRBS15 from a 15-bit LFSR (x^15 + x^14 + 1); a zero seed, which would
+    // lock the register at zero, is replaced with all ones
+    Lfsr {
+        seed: u16,
+    },
+}
+
+impl Scrambling {
+    pub fn is_none(&self) -> bool {
+        matches!(self, Scrambling::None)
+    }
+
+    pub fn scramble(&self, bits: &BitVec) -> BitVec {
+        self.whiten(bits)
+    }
+
+    pub fn descramble(&self, bits: &BitVec) -> BitVec {
+        self.whiten(bits)
+    }
+}
+
+impl Scrambling {
+    fn whiten(&self, bits: &BitVec) -> BitVec {
+        let seed: u16 = match self {
+            Scrambling::None => return bits.iter_bits().collect(),
+            Scrambling::Lfsr { seed } => *seed,
+        };
+
+        let mut state: u16 = match seed & 0x7FFF {
+            0 => 0x7FFF,
+            state => state,
+        };
+        bits.iter_bits()
+            .map(|bit| {
+                let feedback: u16 = ((state >> 14) ^ (state >> 13)) & 1;
+                state = ((state << 1) | feedback) & 0x7FFF;
+                bit ^ (feedback == 1)
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_scrambling() {
+    use crate::audio::types::AudioSpec;
+    use crate::audio::types::NormSamples;
+    use crate::audio::types::SampleEncoding;
+    use crate::protocol::framing::BitOrder;
+    use crate::protocol::profile::Profile;
+    use crate::protocol::rx::Receiver;
+    use crate::protocol::tx::Transmitter;
+    use crate::utils::get_fast_profile;
+
+    let scrambling: Scrambling = Scrambling::Lfsr { seed: 0x4A80 };
+    let bits: BitVec = BitVec::from_bytes(&[0x00; 64], BitOrder::MsbFirst);
+    let sent: BitVec = scrambling.scramble(&bits);
+    assert_eq!(sent.len(), bits.len());
+    assert_eq!(scrambling.descramble(&sent), bits);
+
+    // A maximal-length 15-bit register never repeats a value more than 15 times
+    let longest_run: usize = sent
+        .iter_bits()
+        .fold((0, 0, None), |(longest, run, last), bit| {
+            let run: usize = if Some(bit) == last { run + 1 } else { 1 };
+            (longest.max(run), run, Some(bit))
+        })
+        .0;
+    assert!(longest_run <= 15);
+    let ones: usize = sent.iter_bits().filter(|bit| *bit).count();
+    assert!(ones.abs_diff(bits.len() / 2) < bits.len() / 8);
+
+    // Trailing symbol padding leaves the frame itself intact
+    let mut padded: BitVec = sent.clone();
+    padded.push_bit(false);
+    let received: BitVec = scrambling.descramble(&padded);
+    assert!(bits
+        .iter_bits()
+        .zip(received.iter_bits())
+        .all(|(a, b)| a == b));
+    assert_eq!(Scrambling::None.scramble(&bits), bits);
+    assert_ne!(Scrambling::Lfsr { seed: 0 }.scramble(&bits), bits);
+
+    // A frame of zeros survives the trip through the modem
+    let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+    let profile: Profile = get_fast_profile().with_scrambling(scrambling);
+    let samples: Vec<f32> = Transmitter::new(&profile, &spec)
+        .create(&[0x00; 8])
+        .unwrap();
+    let mut receiver: Receiver = Receiver::new(profile, spec);
+    receiver.add_samples(&mut NormSamples::from_vec(samples));
+    receiver.analyze_full_buffer();
+    let messages: Vec<Vec<u8>> = receiver
+        .take_messages()
+        .into_iter()
+        .map(|message| message.into_data())
+        .collect();
+    assert_eq!(messages, vec![vec![0x00; 8]]);
+}
@@ -0,0 +1,417 @@
+use std::collections::VecDeque;
+use std::io;
+use std::thread::sleep;
+use std::time::Duration;
+use std::time::Instant;
+
+use cpal::traits::DeviceTrait;
+use cpal::traits::HostTrait;
+use cpal::Device;
+use cpal::Host;
+use cpal::StreamConfig;
+
+use crate::audio::player::OutputPlayer;
+use crate::audio::types::AudioSpec;
+use crate::audio::types::NormSamples;
+use crate::consts::RANGE_TIMEOUT;
+use crate::error::WavetrxError;
+use crate::protocol::latency::LatencyReport;
+use crate::protocol::profile::Profile;
+use crate::protocol::ranging::samples_to_duration;
+use crate::protocol::ranging::ChirpLocator;
+use crate::protocol::ranging::RangeEstimate;
+use crate::protocol::ranging::RangeReport;
+use crate::protocol::ranging::Ranging;
+use crate::protocol::rx::DecodedMessage;
+use crate::protocol::rx::LiveReceiver;
+use crate::protocol::tx::CarrierSense;
+use crate::protocol::tx::LiveTransmitter;
+
+// Extra time the input stays muted after playback to let room echo die out
+const SQUELCH_TAIL: Duration = Duration::from_millis(100);
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+pub struct Transceiver {
+    transmitter: LiveTransmitter,
+    receiver: LiveReceiver,
+    squelch_until: Option<Instant>,
+    carrier_sense: Option<CarrierSense>,
+    pending: VecDeque<DecodedMessage>,
+}
+
+impl Transceiver {
+    pub fn new(
+        profile: Profile,
+        output: (Device, StreamConfig),
+        input: (Device, StreamConfig),
+    ) -> Self {
+        let (output_device, output_config): (Device, StreamConfig) = output;
+        let (input_device, input_config): (Device, StreamConfig) = input;
+
+        let transmitter: LiveTransmitter =
+            LiveTransmitter::new(profile, output_device, output_config);
+        let receiver: LiveReceiver = LiveReceiver::new(profile, input_device, input_config);
+        let squelch_until: Option<Instant> = None;
+        let carrier_sense: Option<CarrierSense> = None;
+        let pending: VecDeque<DecodedMessage> = VecDeque::new();
+
+        Transceiver {
+            transmitter,
+            receiver,
+            squelch_until,
+            carrier_sense,
+            pending,
+        }
+    }
+
+    pub fn from_default_devices(profile: Profile) -> Result<Self, WavetrxError> {
+        let host: Host = cpal::default_host();
+        let output_device: Device = host
+            .default_output_device()
+            .ok_or_else(|| WavetrxError::DeviceError("No output device available".to_string()))?;
+        let input_device: Device = host
+            .default_input_device()
+            .ok_or_else(|| WavetrxError::DeviceError("No input device available".to_string()))?;
+
+        let output_config: StreamConfig = output_device.default_output_config()?.into();
+        let input_config: StreamConfig = input_device.default_input_config()?.into();
+
+        let output: (Device, StreamConfig) = (output_device, output_config);
+        let input: (Device, StreamConfig) = (input_device, input_config);
+        Ok(Transceiver::new(profile, output, input))
+    }
+
+    // Listen before talk: `send` waits for the profile tones to go quiet
+    pub fn with_carrier_sense(mut self, carrier: CarrierSense) -> Self {
+        self.carrier_sense = Some(carrier);
+        self
+    }
+
+    pub fn set_carrier_sense(&mut self, carrier: Option<CarrierSense>) {
+        self.carrier_sense = carrier;
+    }
+
+    pub fn start(&mut self) -> Result<(), WavetrxError> {
+        self.transmitter.start()?;
+        self.receiver.start()?;
+        Ok(())
+    }
+
+    pub fn set_profile(&mut self, profile: Profile) {
+        self.transmitter.set_profile(profile);
+        self.receiver.set_profile(profile);
+    }
+
+    pub fn transmitter(&self) -> &LiveTransmitter {
+        &self.transmitter
+    }
+
+    pub fn receiver(&mut self) -> &mut LiveReceiver {
+        &mut self.receiver
+    }
+
+    // Half-duplex: input captured until our own frame has finished playing is dropped
+    pub fn send(&mut self, data: &[u8]) -> Result<(), WavetrxError> {
+        self.wait_for_channel()?;
+        self.transmitter.send(data)?;
+
+        let remaining: Duration = self.transmitter.player().queued()
+            + self.transmitter.player().output_latency()
+            + SQUELCH_TAIL;
+        self.squelch_until = Some(Instant::now() + remaining);
+        Ok(())
+    }
+
+    pub fn is_squelched(&self) -> bool {
+        match self.squelch_until {
+            Some(until) => Instant::now() < until,
+            None => false,
+        }
+    }
+
+    // Messages left queued by `receive` come first
+    pub fn poll(&mut self) -> Vec<DecodedMessage> {
+        let mut messages: Vec<DecodedMessage> = self.pending.drain(..).collect();
+        messages.extend(self.poll_receiver());
+        messages
+    }
+
+    // Blocks until the next message from the far end arrives or `timeout`
+    // elapses. Others decoded in the same poll are queued for the next call
+    pub fn receive(&mut self, timeout: Duration) -> Option<DecodedMessage> {
+        self.receive_matching(timeout, |_| true)
+    }
+
+    // Sends `data` and waits for the reply, e.g. an acknowledgement;
+    // the timeout starts once our own transmission has finished
+    pub fn exchange(
+        &mut self,
+        data: &[u8],
+        timeout: Duration,
+    ) -> Result<Option<DecodedMessage>, WavetrxError> {
+        self.send(data)?;
+        while self.is_squelched() {
+            self.receiver.discard();
+            sleep(POLL_INTERVAL);
+        }
+        Ok(self.receive(timeout))
+    }
+
+    // Times `data` from this call until an answer is decoded: the far end's
+    // first reply, or with `loopback` our own frame heard on our microphone.
+    // None when nothing arrives within `timeout` of the frame finishing
+    pub fn measure_latency(
+        &mut self,
+        data: &[u8],
+        loopback: bool,
+        timeout: Duration,
+    ) -> Result<Option<LatencyReport>, WavetrxError> {
+        self.wait_for_channel()?;
+        let started: Instant = Instant::now();
+        let samples: Vec<f32> = self.transmitter.transmitter().create(data)?;
+        let encoded: Instant = Instant::now();
+
+        let player: &OutputPlayer = self.transmitter.player();
+        let queue: Duration = player.queued() + player.output_latency();
+        let airtime: Duration = self.transmitter.spec().sample_timestamp(samples.len());
+        let length: usize = samples.len();
+        let queued: usize = player.add_samples(NormSamples::from_vec(samples));
+        if queued < length {
+            let reason: String = format!("Player took {} of {} samples", queued, length);
+            return Err(io::Error::new(io::ErrorKind::WriteZero, reason).into());
+        }
+
+        let sent: Instant = encoded + queue + airtime;
+        self.squelch_until = match loopback {
+            true => None,
+            false => Some(sent + SQUELCH_TAIL),
+        };
+        let wait: Duration = sent.saturating_duration_since(Instant::now()) + timeout;
+        let answer: Option<DecodedMessage> =
+            self.receive_matching(wait, |message| !loopback || message.data() == data);
+        let decode: Duration = Instant::now().saturating_duration_since(sent);
+        let encode: Duration = encoded - started;
+        Ok(answer.map(|_| LatencyReport::new(encode, queue, airtime, decode)))
+    }
+
+    // Pings the far end, which has to be in `answer_range`, and times its
+    // reply. None when the reply or its turnaround report never arrives
+    pub fn measure_range(&mut self) -> Result<Option<RangeEstimate>, WavetrxError> {
+        let ranging: Ranging = Ranging::new(&self.receiver.profile());
+        let spec: AudioSpec = self.receiver.capture_spec();
+        let gaps: usize = self.receiver.gaps();
+
+        self.receiver.take_input();
+        let ping: Vec<f32> = ranging.ping(&self.transmitter.spec());
+        self.transmitter
+            .player()
+            .add_samples(NormSamples::from_vec(ping));
+
+        // Our own ping is timed on our own microphone too
+        let deadline: Instant = Instant::now() + RANGE_TIMEOUT;
+        let mut samples: Vec<f32> = Vec::new();
+        let mut locator: ChirpLocator = ranging.ping_locator(&spec);
+        if self
+            .wait_for_chirp(&mut locator, &mut samples, deadline)
+            .is_none()
+        {
+            return Ok(None);
+        }
+
+        let mut locator: ChirpLocator = ranging.reply_locator(&spec);
+        locator.skip_to(ranging.sample_size(&spec));
+        let deadline: Instant = Instant::now() + RANGE_TIMEOUT;
+        let round_trip: usize = match self.wait_for_chirp(&mut locator, &mut samples, deadline) {
+            Some(idx) => idx,
+            None => return Ok(None),
+        };
+        self.check_gaps(gaps)?;
+
+        // Other traffic stays queued for `receive`
+        let message: DecodedMessage = match self.receive_matching(RANGE_TIMEOUT, |message| {
+            RangeReport::decode(message.data()).is_some()
+        }) {
+            Some(message) => message,
+            None => return Ok(None),
+        };
+        let report: RangeReport = match RangeReport::decode(message.data()) {
+            Some(report) => report,
+            None => return Ok(None),
+        };
+        let round_trip: Duration = samples_to_duration(round_trip, spec.sample_rate());
+        let estimate: RangeEstimate =
+            RangeEstimate::new(round_trip, report.turnaround(), spec.sample_rate());
+        Ok(Some(estimate))
+    }
+
+    // The other half of `measure_range`: waits up to `timeout` for a ping,
+    // replies, and reports the turnaround. None when no ping arrives
+    pub fn answer_range(&mut self, timeout: Duration) -> Result<Option<Duration>, WavetrxError> {
+        let ranging: Ranging = Ranging::new(&self.receiver.profile());
+        let spec: AudioSpec = self.receiver.capture_spec();
+
+        self.receiver.take_input();
+        let deadline: Instant = Instant::now() + timeout;
+        let mut samples: Vec<f32> = Vec::new();
+        let mut locator: ChirpLocator = ranging.ping_locator(&spec);
+        if self
+            .wait_for_chirp(&mut locator, &mut samples, deadline)
+            .is_none()
+        {
+            return Ok(None);
+        }
+        let gaps: usize = self.receiver.gaps();
+        let reply: Vec<f32> = ranging.reply(&self.transmitter.spec());
+        self.transmitter
+            .player()
+            .add_samples(NormSamples::from_vec(reply));
+
+        // The turnaround runs to our own reply as heard by our microphone
+        let mut locator: ChirpLocator = ranging.reply_locator(&spec);
+        locator.skip_to(ranging.sample_size(&spec));
+        let deadline: Instant = Instant::now() + RANGE_TIMEOUT;
+        let turnaround: usize = match self.wait_for_chirp(&mut locator, &mut samples, deadline) {
+            Some(idx) => idx,
+            None => return Ok(None),
+        };
+        self.check_gaps(gaps)?;
+
+        let turnaround: Duration = samples_to_duration(turnaround, spec.sample_rate());
+        self.send(&RangeReport::new(turnaround).encode())?;
+        Ok(Some(turnaround))
+    }
+}
+
+impl Transceiver {
+    fn poll_receiver(&mut self) -> Vec<DecodedMessage> {
+        if self.is_squelched() {
+            self.receiver.discard();
+            return Vec::new();
+        }
+        self.squelch_until = None;
+        self.receiver.poll()
+    }
+
+    fn receive_matching<P>(&mut self, timeout: Duration, mut accept: P) -> Option<DecodedMessage>
+    where
+        P: FnMut(&DecodedMessage) -> bool,
+    {
+        let deadline: Instant = Instant::now() + timeout;
+        loop {
+            let polled: Vec<DecodedMessage> = self.poll_receiver();
+            if let Some(message) = take_queued(&mut self.pending, polled, &mut accept) {
+                return Some(message);
+            }
+            if Instant::now() >= deadline {
+                return None;
+            }
+            sleep(POLL_INTERVAL);
+        }
+    }
+
+    // Onset of the chirp counted from the start of `samples` as passed in.
+    // Audio before the onset is drained, so on return the chirp leads `samples`
+    fn wait_for_chirp(
+        &mut self,
+        locator: &mut ChirpLocator,
+        samples: &mut Vec<f32>,
+        deadline: Instant,
+    ) -> Option<usize> {
+        let mut drained: usize = 0;
+        loop {
+            samples.extend(self.receiver.take_input().0);
+            if let Some(idx) = locator.locate(samples) {
+                samples.drain(..idx);
+                return Some(drained + idx);
+            }
+            drained += locator.trim(samples);
+            if Instant::now() >= deadline {
+                return None;
+            }
+            sleep(POLL_INTERVAL);
+        }
+    }
+
+    // A capture gap shifts every later sample, so the timing is void
+    fn check_gaps(&self, gaps: usize) -> Result<(), WavetrxError> {
+        if self.receiver.gaps() != gaps {
+            let reason: String = "Input dropped samples while ranging".to_string();
+            return Err(WavetrxError::DeviceError(reason));
+        }
+        Ok(())
+    }
+
+    // Our own frame still playing doesn't count as traffic, so back-to-back
+    // sends don't hold each other off
+    fn wait_for_channel(&mut self) -> Result<(), WavetrxError> {
+        let mut carrier: CarrierSense = match self.carrier_sense.take() {
+            Some(carrier) => carrier,
+            None => return Ok(()),
+        };
+        let result: Result<(), WavetrxError> = self.listen(&mut carrier);
+        self.carrier_sense = Some(carrier);
+        result
+    }
+
+    fn listen(&mut self, carrier: &mut CarrierSense) -> Result<(), WavetrxError> {
+        if self.is_squelched() {
+            return Ok(());
+        }
+
+        let started: Instant = Instant::now();
+        loop {
+            sleep(carrier.listen());
+            if !self.receiver.sense(carrier) {
+                return Ok(());
+            }
+            let waited: Duration = started.elapsed();
+            if waited >= carrier.max_wait() {
+                return Err(WavetrxError::ChannelBusy(waited));
+            }
+            sleep(carrier.backoff());
+        }
+    }
+}
+
+// Queues a poll's whole batch and takes out the oldest message `accept`
+// matches; the rest stay queued in arrival order
+fn take_queued<P>(
+    pending: &mut VecDeque<DecodedMessage>,
+    polled: Vec<DecodedMessage>,
+    accept: P,
+) -> Option<DecodedMessage>
+where
+    P: FnMut(&DecodedMessage) -> bool,
+{
+    pending.extend(polled);
+    let idx: usize = pending.iter().position(accept)?;
+    pending.remove(idx)
+}
+
+#[test]
+fn test_take_queued() {
+    use crate::protocol::payload::Payload;
+
+    let message = |data: &[u8]| DecodedMessage::new(Payload::new(data.to_vec()), 0, 0);
+    let mut pending: VecDeque<DecodedMessage> = VecDeque::new();
+
+    // One poll can complete several messages; none of them are lost
+    let polled: Vec<DecodedMessage> = vec![message(b"one"), message(b"two"), message(b"three")];
+    let first: Option<DecodedMessage> = take_queued(&mut pending, polled, |_| true);
+    assert_eq!(first.unwrap().data(), b"one");
+    let second: Option<DecodedMessage> = take_queued(&mut pending, Vec::new(), |_| true);
+    assert_eq!(second.unwrap().data(), b"two");
+
+    // A picky caller skips past queued traffic without dropping it
+    let polled: Vec<DecodedMessage> = vec![message(b"report")];
+    let report: Option<DecodedMessage> =
+        take_queued(&mut pending, polled, |message| message.data() == b"report");
+    assert_eq!(report.unwrap().data(), b"report");
+    let missing: Option<DecodedMessage> = take_queued(&mut pending, Vec::new(), |message| {
+        message.data() == b"none"
+    });
+    assert!(missing.is_none());
+
+    let left: Vec<&[u8]> = pending.iter().map(|message| message.data()).collect();
+    assert_eq!(left, vec![&b"three"[..]]);
+}
@@ -0,0 +1,360 @@
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fs;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use log::warn;
+
+use crate::error::WavetrxError;
+use crate::protocol::framing::crc32;
+use crate::protocol::framing::FrameError;
+use crate::utils::random_seed;
+
+pub const TRANSFER_HEADER: u8 = 0x01;
+pub const TRANSFER_CHUNK: u8 = 0x17;
+pub const TRANSFER_MAX_CHUNKS: usize = u16::MAX as usize;
+pub const TRANSFER_MAX_NAME: usize = u8::MAX as usize;
+// Numbered names tried before giving up when the received name is taken
+pub const TRANSFER_MAX_RENAMES: usize = 1_000;
+
+// Leading bytes of a header fragment: tag, id, size, hash, chunk count, name length
+const HEADER_SIZE: usize = 16;
+// Leading bytes of a chunk fragment: tag, id, sequence
+const CHUNK_HEADER_SIZE: usize = 7;
+
+// What the receiver needs to rebuild and check a file; the hash covers the
+// whole file, so a fragment corrupted past its frame checksum still shows
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileHeader {
+    name: String,
+    size: u32,
+    hash: u32,
+    chunks: u16,
+}
+
+impl FileHeader {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn size(&self) -> usize {
+        self.size as usize
+    }
+
+    pub fn hash(&self) -> u32 {
+        self.hash
+    }
+
+    pub fn chunks(&self) -> usize {
+        self.chunks as usize
+    }
+}
+
+// One message of a file transfer. `id` keeps fragments of different files
+// apart when transfers overlap
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TransferFrame {
+    Header { id: u32, header: FileHeader },
+    Chunk { id: u32, seq: u16, data: Vec<u8> },
+}
+
+impl TransferFrame {
+    // A header followed by the file in `chunk_size` fragments; only the last
+    // path component of `name` is sent. Every call picks a new random id
+    pub fn split(
+        name: &str,
+        data: &[u8],
+        chunk_size: usize,
+    ) -> Result<Vec<TransferFrame>, FrameError> {
+        let chunk_size: usize = chunk_size.max(1);
+        let chunks: Vec<&[u8]> = data.chunks(chunk_size).collect();
+        if chunks.len() > TRANSFER_MAX_CHUNKS || data.len() > u32::MAX as usize {
+            let max: usize = (TRANSFER_MAX_CHUNKS * chunk_size).min(u32::MAX as usize);
+            return Err(FrameError::Oversized {
+                size: data.len(),
+                max,
+            });
+        }
+
+        let name: String = file_name(name).unwrap_or_default();
+        if name.len() > TRANSFER_MAX_NAME {
+            return Err(FrameError::Oversized {
+                size: name.len(),
+                max: TRANSFER_MAX_NAME,
+            });
+        }
+
+        let hash: u32 = crc32(data);
+        let id: u32 = random_seed() as u32;
+        let header: FileHeader = FileHeader {
+            name,
+            size: data.len() as u32,
+            hash,
+            chunks: chunks.len() as u16,
+        };
+
+        let mut frames: Vec<TransferFrame> = vec![TransferFrame::Header { id, header }];
+        for (seq, chunk) in chunks.into_iter().enumerate() {
+            frames.push(TransferFrame::Chunk {
+                id,
+                seq: seq as u16,
+                data: chunk.to_vec(),
+            });
+        }
+        Ok(frames)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            TransferFrame::Header { id, header } => {
+                let mut bytes: Vec<u8> = Vec::with_capacity(HEADER_SIZE + header.name.len());
+                bytes.push(TRANSFER_HEADER);
+                bytes.extend(id.to_be_bytes());
+                bytes.extend(header.size.to_be_bytes());
+                bytes.extend(header.hash.to_be_bytes());
+                bytes.extend(header.chunks.to_be_bytes());
+                bytes.push(header.name.len() as u8);
+                bytes.extend(header.name.as_bytes());
+                bytes
+            }
+            TransferFrame::Chunk { id, seq, data } => {
+                let mut bytes: Vec<u8> = Vec::with_capacity(CHUNK_HEADER_SIZE + data.len());
+                bytes.push(TRANSFER_CHUNK);
+                bytes.extend(id.to_be_bytes());
+                bytes.extend(seq.to_be_bytes());
+                bytes.extend(data);
+                bytes
+            }
+        }
+    }
+
+    pub fn decode(bytes: &[u8]) -> Option<Self> {
+        match bytes {
+            [TRANSFER_HEADER, rest @ ..] if rest.len() >= HEADER_SIZE - 1 => {
+                let name_len: usize = rest[14] as usize;
+                let name: &[u8] = rest.get(15..15 + name_len)?;
+                let header: FileHeader = FileHeader {
+                    name: String::from_utf8(name.to_vec()).ok()?,
+                    size: u32::from_be_bytes([rest[4], rest[5], rest[6], rest[7]]),
+                    hash: u32::from_be_bytes([rest[8], rest[9], rest[10], rest[11]]),
+                    chunks: u16::from_be_bytes([rest[12], rest[13]]),
+                };
+                let id: u32 = u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]);
+                Some(TransferFrame::Header { id, header })
+            }
+            [TRANSFER_CHUNK, rest @ ..] if rest.len() >= CHUNK_HEADER_SIZE - 1 => {
+                Some(TransferFrame::Chunk {
+                    id: u32::from_be_bytes([rest[0], rest[1], rest[2], rest[3]]),
+                    seq: u16::from_be_bytes([rest[4], rest[5]]),
+                    data: rest[6..].to_vec(),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReceivedFile {
+    header: FileHeader,
+    path: PathBuf,
+    verified: bool,
+}
+
+impl ReceivedFile {
+    pub fn header(&self) -> &FileHeader {
+        &self.header
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // False when the size or hash of the rebuilt file don't match the header;
+    // the file is then kept as `<name>.partial`
+    pub fn verified(&self) -> bool {
+        self.verified
+    }
+}
+
+#[derive(Default)]
+struct PartialFile {
+    header: Option<FileHeader>,
+    chunks: BTreeMap<u16, Vec<u8>>,
+}
+
+// Collects transfer fragments from decoded messages and writes each file to
+// `dir` once its header and every chunk have arrived. Fragments may come in
+// any order; repeats replace what was there. Existing files are never
+// overwritten: a taken name gets a number appended to its stem
+pub struct FileAssembler {
+    dir: PathBuf,
+    partial: HashMap<u32, PartialFile>,
+}
+
+impl FileAssembler {
+    pub fn new<P>(dir: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        let dir: PathBuf = dir.as_ref().to_path_buf();
+        let partial: HashMap<u32, PartialFile> = HashMap::new();
+        FileAssembler { dir, partial }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    // Transfers with fragments still missing
+    pub fn pending(&self) -> usize {
+        self.partial.len()
+    }
+
+    // Messages that aren't transfer fragments are ignored
+    pub fn push(&mut self, message: &[u8]) -> Result<Option<ReceivedFile>, WavetrxError> {
+        let id: u32 = match TransferFrame::decode(message) {
+            Some(TransferFrame::Header { id, header }) => {
+                self.partial.entry(id).or_default().header = Some(header);
+                id
+            }
+            Some(TransferFrame::Chunk { id, seq, data }) => {
+                self.partial.entry(id).or_default().chunks.insert(seq, data);
+                id
+            }
+            None => return Ok(None),
+        };
+
+        let partial: &PartialFile = &self.partial[&id];
+        let header: FileHeader = match &partial.header {
+            Some(header) if (0..header.chunks).all(|seq| partial.chunks.contains_key(&seq)) => {
+                header.clone()
+            }
+            _ => return Ok(None),
+        };
+        let chunks: BTreeMap<u16, Vec<u8>> = self
+            .partial
+            .remove(&id)
+            .map(|partial| partial.chunks)
+            .unwrap_or_default();
+        self.write(id, header, chunks).map(Some)
+    }
+}
+
+impl FileAssembler {
+    fn write(
+        &self,
+        id: u32,
+        header: FileHeader,
+        chunks: BTreeMap<u16, Vec<u8>>,
+    ) -> Result<ReceivedFile, WavetrxError> {
+        let data: Vec<u8> = chunks
+            .into_values()
+            .take(header.chunks())
+            .flatten()
+            .collect();
+        let verified: bool = data.len() == header.size() && crc32(&data) == header.hash;
+        if !verified {
+            warn!("Transfer {} of {} failed verification", id, header.name);
+        }
+
+        // Names from the air never reach outside `dir`
+        let name: String =
+            file_name(&header.name).unwrap_or_else(|| format!("transfer-{:08x}", id));
+        let name: String = match verified {
+            true => name,
+            false => format!("{}.partial", name),
+        };
+        fs::create_dir_all(&self.dir)?;
+        let (path, mut file): (PathBuf, File) = self.create_new(&name)?;
+        file.write_all(&data)?;
+        Ok(ReceivedFile {
+            header,
+            path,
+            verified,
+        })
+    }
+
+    // `name`, else `<stem>-1.<ext>`, `<stem>-2.<ext>` and so on
+    fn create_new(&self, name: &str) -> Result<(PathBuf, File), WavetrxError> {
+        let (stem, extension): (&str, &str) = match name.find('.') {
+            Some(0) | None => (name, ""),
+            Some(idx) => name.split_at(idx),
+        };
+        for count in 0..=TRANSFER_MAX_RENAMES {
+            let path: PathBuf = match count {
+                0 => self.dir.join(name),
+                count => self.dir.join(format!("{}-{}{}", stem, count, extension)),
+            };
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(file) => return Ok((path, file)),
+                Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err.into()),
+            }
+        }
+        let reason: String = format!("No free name for {} in {}", name, self.dir.display());
+        Err(io::Error::new(io::ErrorKind::AlreadyExists, reason).into())
+    }
+}
+
+// The last path component, with drive prefixes and control characters out
+fn file_name(name: &str) -> Option<String> {
+    let name: &str = name.rsplit(['/', '\\', ':']).next()?;
+    match name {
+        "" | "." | ".." => None,
+        name if name.chars().any(char::is_control) => None,
+        name => Some(name.to_string()),
+    }
+}
+
+#[test]
+fn test_transfer_frames() {
+    assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+
+    let data: Vec<u8> = (0..150).map(|idx| idx as u8).collect();
+    let frames: Vec<TransferFrame> = TransferFrame::split("../notes/data.bin", &data, 64).unwrap();
+    assert_eq!(frames.len(), 4);
+    for frame in frames.iter() {
+        assert_eq!(TransferFrame::decode(&frame.encode()).as_ref(), Some(frame));
+    }
+    match &frames[0] {
+        TransferFrame::Header { header, .. } => {
+            assert_eq!(header.name(), "data.bin");
+            assert_eq!(header.chunks(), 3);
+        }
+        frame => panic!("Expected a header, got {:?}", frame),
+    }
+    assert_eq!(TransferFrame::decode(&[TRANSFER_CHUNK, 0, 1]), None);
+    assert_eq!(file_name("C:evil.bin").as_deref(), Some("evil.bin"));
+    assert_eq!(file_name("..\\"), None);
+
+    // Out of order, with a repeat and an unrelated message in between
+    let dir: PathBuf = std::env::temp_dir().join("wavetrx_transfer_frames");
+    let _ = fs::remove_dir_all(&dir);
+    let mut assembler: FileAssembler = FileAssembler::new(&dir);
+    let mut received: Option<ReceivedFile> = None;
+    for idx in [2, 0, 2, 1, 3] {
+        assert!(received.is_none());
+        assert_eq!(assembler.push(b"WaveTrx").unwrap(), None);
+        received = assembler.push(&frames[idx].encode()).unwrap();
+    }
+    let received: ReceivedFile = received.unwrap();
+    assert!(received.verified());
+    assert_eq!(received.path(), dir.join("data.bin"));
+    assert_eq!(fs::read(received.path()).unwrap(), data);
+    assert_eq!(assembler.pending(), 0);
+
+    // The same file again is kept beside the first, not written over it
+    let frames: Vec<TransferFrame> = TransferFrame::split("data.bin", &data, 64).unwrap();
+    let again: Option<ReceivedFile> = frames
+        .iter()
+        .filter_map(|frame| assembler.push(&frame.encode()).unwrap())
+        .next();
+    assert_eq!(again.unwrap().path(), dir.join("data-1.bin"));
+    fs::remove_dir_all(&dir).unwrap();
+}
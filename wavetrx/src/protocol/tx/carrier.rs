@@ -0,0 +1,139 @@
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use crate::audio::spectrum::GoertzelMagnitude;
+use crate::audio::spectrum::WindowFunction;
+use crate::audio::types::AudioSpec;
+use crate::consts::CARRIER_BACKOFF_MAX;
+use crate::consts::CARRIER_BACKOFF_MIN;
+use crate::consts::CARRIER_LISTEN;
+use crate::consts::CARRIER_MAX_WAIT;
+use crate::consts::CARRIER_SENSE_DB;
+use crate::embedded::sample_size;
+use crate::protocol::profile::Profile;
+use crate::protocol::profile::SizedPulses;
+use crate::sim::NoiseSource;
+
+// Listen-before-talk: the channel counts as busy while any profile tone in
+// the most recent `listen` of input reaches `threshold_db`. A busy channel
+// defers the transmission by a random backoff, so two stations that both
+// waited don't start again at the same moment
+pub struct CarrierSense {
+    threshold_db: f32,
+    listen: Duration,
+    backoff_min: Duration,
+    backoff_max: Duration,
+    max_wait: Duration,
+    rng: NoiseSource,
+}
+
+impl CarrierSense {
+    pub fn new() -> Self {
+        let seed: u64 = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or(0);
+        CarrierSense {
+            threshold_db: CARRIER_SENSE_DB,
+            listen: CARRIER_LISTEN,
+            backoff_min: CARRIER_BACKOFF_MIN,
+            backoff_max: CARRIER_BACKOFF_MAX,
+            max_wait: CARRIER_MAX_WAIT,
+            rng: NoiseSource::new(seed),
+        }
+    }
+
+    pub fn with_threshold(mut self, threshold_db: f32) -> Self {
+        self.threshold_db = threshold_db;
+        self
+    }
+
+    pub fn with_listen(mut self, listen: Duration) -> Self {
+        self.listen = listen;
+        self
+    }
+
+    pub fn with_backoff(mut self, min: Duration, max: Duration) -> Self {
+        self.backoff_min = min.min(max);
+        self.backoff_max = max.max(min);
+        self
+    }
+
+    // How long a transmission may be deferred before it fails
+    pub fn with_max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = max_wait;
+        self
+    }
+
+    pub fn threshold_db(&self) -> f32 {
+        self.threshold_db
+    }
+
+    pub fn listen(&self) -> Duration {
+        self.listen
+    }
+
+    pub fn max_wait(&self) -> Duration {
+        self.max_wait
+    }
+
+    // `samples` are mono at `spec`'s rate and not normalized, so levels are dBFS
+    pub fn is_busy(&self, samples: &[f32], profile: &Profile, spec: &AudioSpec) -> bool {
+        let pulses: SizedPulses = profile.pulses.into_sized(spec);
+        let window: usize = pulses.tone_size().max(1);
+        let listen: usize = sample_size(spec.sample_rate(), self.listen.as_micros() as usize);
+        let tail: &[f32] = &samples[samples.len().saturating_sub(listen.max(window))..];
+
+        // Windowed, so a loud tone next to the band doesn't leak into it
+        let magnitude: GoertzelMagnitude =
+            GoertzelMagnitude::new(&pulses, spec).with_window(WindowFunction::Blackman);
+        let frequencies: Vec<f32> = profile.frequencies();
+        tail.chunks_exact(window).any(|chunk| {
+            frequencies
+                .iter()
+                .any(|frequency| magnitude.get_magnitude(chunk, *frequency) >= self.threshold_db)
+        })
+    }
+
+    // Uniform between the minimum and maximum backoff
+    pub fn backoff(&mut self) -> Duration {
+        let span: Duration = self.backoff_max - self.backoff_min;
+        self.backoff_min + span.mul_f32(self.rng.uniform())
+    }
+}
+
+impl Default for CarrierSense {
+    fn default() -> Self {
+        CarrierSense::new()
+    }
+}
+
+#[test]
+fn test_carrier_sense() {
+    use crate::audio::types::SampleEncoding;
+    use crate::protocol::tx::ToneGenerator;
+    use crate::utils::get_fast_profile;
+
+    let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+    let profile: Profile = get_fast_profile();
+    let mut carrier: CarrierSense = CarrierSense::new()
+        .with_backoff(Duration::from_millis(30), Duration::from_millis(10))
+        .with_threshold(-30.0);
+
+    let tone = |frequency: f32, level: f32| -> Vec<f32> {
+        let mut tone: ToneGenerator = ToneGenerator::new(&spec).unwrap();
+        tone.append_multitone(&[frequency], level, 60_000).unwrap();
+        tone.samples()
+    };
+    assert!(!carrier.is_busy(&vec![0.0; 4_800], &profile, &spec));
+    assert!(carrier.is_busy(&tone(profile.markers.start.hz(), 0.1), &profile, &spec));
+    // Off-profile tones and profile tones below the threshold leave it clear
+    assert!(!carrier.is_busy(&tone(12_500.0, 0.5), &profile, &spec));
+    assert!(!carrier.is_busy(&tone(profile.markers.start.hz(), 0.01), &profile, &spec));
+
+    for _ in 0..16 {
+        let backoff: Duration = carrier.backoff();
+        assert!(backoff >= Duration::from_millis(10) && backoff <= Duration::from_millis(30));
+    }
+}
@@ -0,0 +1,136 @@
+use std::sync::mpsc;
+use std::thread;
+use std::thread::sleep;
+use std::time::Duration;
+
+use cpal::Device;
+use cpal::StreamConfig;
+
+use super::transmitter::Transmitter;
+
+use crate::audio::limiter::SoftLimiter;
+use crate::audio::player::OutputPlayer;
+use crate::audio::types::AudioSpec;
+use crate::audio::types::SampleEncoding;
+use crate::error::WavetrxError;
+#[cfg(feature = "crypto")]
+use crate::protocol::crypto::PayloadCipher;
+use crate::protocol::profile::Profile;
+
+pub struct LiveTransmitter {
+    player: OutputPlayer,
+    transmitter: Transmitter,
+    spec: AudioSpec,
+}
+
+impl LiveTransmitter {
+    pub fn new(profile: Profile, device: Device, config: StreamConfig) -> Self {
+        let spec: AudioSpec = AudioSpec::new(config.sample_rate.0, 32, 1, SampleEncoding::F32);
+        let transmitter: Transmitter = Transmitter::new(&profile, &spec);
+        let player: OutputPlayer = OutputPlayer::new(device, config, spec);
+        LiveTransmitter {
+            player,
+            transmitter,
+            spec,
+        }
+    }
+
+    pub fn start(&mut self) -> Result<(), WavetrxError> {
+        self.player.play()
+    }
+
+    // Queued audio is held, not dropped, and plays on from where it stopped
+    pub fn pause(&mut self) -> Result<(), WavetrxError> {
+        self.player.pause()
+    }
+
+    pub fn resume(&mut self) -> Result<(), WavetrxError> {
+        self.player.resume()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.player.is_paused()
+    }
+
+    pub fn spec(&self) -> AudioSpec {
+        self.spec
+    }
+
+    pub fn player(&self) -> &OutputPlayer {
+        &self.player
+    }
+
+    pub fn transmitter(&self) -> &Transmitter {
+        &self.transmitter
+    }
+
+    pub fn set_profile(&mut self, profile: Profile) {
+        self.transmitter.set_profile(profile);
+    }
+
+    pub fn set_destination(&mut self, address: Option<u16>) {
+        self.transmitter.set_destination(address);
+    }
+
+    pub fn set_gain(&mut self, gain: f32) {
+        self.transmitter.set_gain(gain);
+    }
+
+    pub fn set_limiter(&mut self, limiter: Option<SoftLimiter>) {
+        self.transmitter.set_limiter(limiter);
+    }
+
+    pub fn set_repeats(&mut self, repeats: usize, gap: Duration) {
+        self.transmitter.set_repeats(repeats, gap);
+    }
+
+    #[cfg(feature = "crypto")]
+    pub fn set_cipher(&mut self, cipher: Option<PayloadCipher>) {
+        self.transmitter.set_cipher(cipher);
+    }
+
+    // Queues the frame behind anything still playing and returns immediately
+    pub fn send(&self, data: &[u8]) -> Result<(), WavetrxError> {
+        self.transmitter.play(data, &self.player)
+    }
+
+    pub fn send_blocking(&self, data: &[u8]) -> Result<(), WavetrxError> {
+        self.send(data)?;
+        self.wait()
+    }
+
+    // The returned channel fires once the queued audio has left the speaker
+    pub fn send_async(&self, data: &[u8]) -> Result<mpsc::Receiver<()>, WavetrxError> {
+        self.send(data)?;
+
+        let remaining: Duration = self.remaining();
+        let (sender, receiver) = mpsc::channel::<()>();
+        thread::spawn(move || {
+            sleep(remaining);
+            let _ = sender.send(());
+        });
+        Ok(receiver)
+    }
+
+    // Cuts the transmission under way, and anything queued behind it; see
+    // `Transmitter::abort`
+    pub fn abort(&self, end_marker: bool) -> Result<usize, WavetrxError> {
+        self.transmitter.abort(&self.player, end_marker)
+    }
+
+    pub fn is_idle(&self) -> bool {
+        self.player.queued().is_zero()
+    }
+
+    pub fn wait(&self) -> Result<(), WavetrxError> {
+        self.player.wait()?;
+        sleep(self.player.output_latency());
+        Ok(())
+    }
+}
+
+impl LiveTransmitter {
+    fn remaining(&self) -> Duration {
+        self.player.queued() + self.player.output_latency()
+    }
+}
@@ -1,6 +1,18 @@
+mod carrier;
+#[cfg(feature = "device")]
+mod live;
+#[cfg(feature = "device")]
+mod schedule;
 mod tone;
 mod transmitter;
 
+pub use carrier::CarrierSense;
+#[cfg(feature = "device")]
+pub use live::LiveTransmitter;
+#[cfg(feature = "device")]
+pub use schedule::Schedule;
+#[cfg(feature = "device")]
+pub use schedule::ScheduledTransmitter;
 pub use tone::ToneGenerator;
 pub use transmitter::Transmitter;
 pub use transmitter::StreamTransmitter;
@@ -0,0 +1,138 @@
+use std::hint;
+use std::thread::sleep;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use super::transmitter::Transmitter;
+use crate::audio::player::OutputPlayer;
+use crate::audio::types::AudioSpec;
+use crate::audio::types::NormSamples;
+use crate::error::WavetrxError;
+use crate::protocol::profile::Profile;
+
+const SPIN_WINDOW: Duration = Duration::from_millis(2);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Schedule {
+    At(SystemTime),
+    Every { period: Duration, offset: Duration },
+}
+
+impl Schedule {
+    pub fn aligned(period: Duration) -> Self {
+        Schedule::Every {
+            period,
+            offset: Duration::ZERO,
+        }
+    }
+
+    pub fn next_after(&self, now: SystemTime) -> Option<SystemTime> {
+        match self {
+            Schedule::At(instant) => (*instant >= now).then_some(*instant),
+            Schedule::Every { period, offset } => {
+                if period.is_zero() {
+                    return None;
+                }
+
+                let period: u128 = period.as_nanos();
+                let offset: u128 = offset.as_nanos() % period;
+                let since: u128 = now.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+
+                let mut next: u128 = since - (since % period) + offset;
+                if next < since {
+                    next += period;
+                }
+                Some(UNIX_EPOCH + Duration::from_nanos(next as u64))
+            }
+        }
+    }
+}
+
+pub struct ScheduledTransmitter {
+    transmitter: Transmitter,
+}
+
+impl ScheduledTransmitter {
+    pub fn new(profile: &Profile, spec: &AudioSpec) -> Self {
+        let transmitter: Transmitter = Transmitter::new(profile, spec);
+        ScheduledTransmitter { transmitter }
+    }
+
+    pub fn transmit_at(
+        &self,
+        player: &OutputPlayer,
+        data: &[u8],
+        at: SystemTime,
+    ) -> Result<(), WavetrxError> {
+        let samples: Vec<f32> = self.transmitter.create(data)?;
+        Self::release_at(player, &samples, at);
+        Ok(())
+    }
+
+    pub fn transmit_scheduled(
+        &self,
+        player: &OutputPlayer,
+        data: &[u8],
+        schedule: Schedule,
+        count: usize,
+    ) -> Result<Vec<SystemTime>, WavetrxError> {
+        let samples: Vec<f32> = self.transmitter.create(data)?;
+        let mut instants: Vec<SystemTime> = Vec::with_capacity(count);
+
+        while instants.len() < count {
+            let now: SystemTime = match instants.last() {
+                Some(last) => *last + Duration::from_nanos(1),
+                None => SystemTime::now(),
+            };
+            let at: SystemTime = match schedule.next_after(now) {
+                Some(at) => at,
+                None => break,
+            };
+
+            Self::release_at(player, &samples, at);
+            instants.push(at);
+        }
+        Ok(instants)
+    }
+}
+
+impl ScheduledTransmitter {
+    // Hands the pre-rendered samples to the player early enough that the first
+    // one reaches the speaker at `at`, accounting for queued audio and device latency
+    fn release_at(player: &OutputPlayer, samples: &[f32], at: SystemTime) {
+        loop {
+            let lead: Duration = player.output_latency() + player.queued();
+            let release: SystemTime = at.checked_sub(lead).unwrap_or(UNIX_EPOCH);
+
+            match release.duration_since(SystemTime::now()) {
+                Ok(remaining) if remaining > SPIN_WINDOW => sleep(remaining - SPIN_WINDOW),
+                Ok(remaining) if !remaining.is_zero() => hint::spin_loop(),
+                _ => break,
+            }
+        }
+        player.add_samples(NormSamples::from_slice(samples));
+    }
+}
+
+#[test]
+fn test_schedule_alignment() {
+    let now: SystemTime = UNIX_EPOCH + Duration::from_secs(125);
+    let minute: Schedule = Schedule::aligned(Duration::from_secs(60));
+    assert_eq!(
+        minute.next_after(now),
+        Some(UNIX_EPOCH + Duration::from_secs(180))
+    );
+
+    let offset: Schedule = Schedule::Every {
+        period: Duration::from_secs(60),
+        offset: Duration::from_secs(10),
+    };
+    assert_eq!(
+        offset.next_after(now),
+        Some(UNIX_EPOCH + Duration::from_secs(130))
+    );
+
+    let past: Schedule = Schedule::At(UNIX_EPOCH + Duration::from_secs(100));
+    assert_eq!(past.next_after(now), None);
+}
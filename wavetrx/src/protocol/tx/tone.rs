@@ -1,115 +1,273 @@
-use std::f32::consts;
 use std::mem;
 
+use crate::audio::limiter::SoftLimiter;
 use crate::audio::types::AudioSpec;
+use crate::embedded::linear_fade;
+use crate::embedded::sample_size;
+use crate::embedded::sine;
+use crate::embedded::sine_fade;
+use crate::error::WavetrxError;
+use crate::protocol::preamble::chirp_phase;
+use crate::protocol::preamble::Preamble;
 
 pub struct ToneGenerator {
     samples: Vec<f32>,
     spec: AudioSpec,
+    gain: f32,
+    limiter: Option<SoftLimiter>,
+    tail: usize,
 }
 
 impl ToneGenerator {
-    pub fn new(spec: &AudioSpec) -> Result<Self, Box<dyn std::error::Error>> {
+    pub fn new(spec: &AudioSpec) -> Result<Self, WavetrxError> {
         let samples: Vec<f32> = Vec::new();
         let spec: AudioSpec = *spec;
+        let gain: f32 = 1.0;
+        let limiter: Option<SoftLimiter> = None;
+        let tail: usize = 0;
 
-        Ok(ToneGenerator { samples, spec })
+        Ok(ToneGenerator {
+            samples,
+            spec,
+            gain,
+            limiter,
+            tail,
+        })
+    }
+
+    // Linear output level, clamped to 0.0..=1.0; applies to every tone
+    pub fn with_gain(mut self, gain: f32) -> Self {
+        self.set_gain(gain);
+        self
+    }
+
+    pub fn set_gain(&mut self, gain: f32) {
+        self.gain = gain.clamp(0.0, 1.0);
+    }
+
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    pub fn with_limiter(mut self, limiter: SoftLimiter) -> Self {
+        self.limiter = Some(limiter);
+        self
+    }
+
+    pub fn set_limiter(&mut self, limiter: Option<SoftLimiter>) {
+        self.limiter = limiter;
     }
 
     pub fn samples(self) -> Vec<f32> {
         self.samples
     }
 
+    // The closing ramp of a cross-faded tone stays behind so the next tone
+    // can still be mixed into it
     pub fn take_samples(&mut self) -> Vec<f32> {
         let samples_len: usize = self.samples.len();
-        let samples: Vec<f32> = mem::replace(&mut self.samples, Vec::with_capacity(samples_len));
+        let tail: Vec<f32> = self.samples.split_off(samples_len - self.tail);
+        let mut remaining: Vec<f32> = Vec::with_capacity(samples_len);
+        remaining.extend(tail);
+        let samples: Vec<f32> = mem::replace(&mut self.samples, remaining);
         samples
     }
 
-    pub fn append_tone(
+    // A frequency of zero has no period; it appends silence instead
+    pub fn append_tone(&mut self, frequency: f32, duration: usize) -> Result<(), WavetrxError> {
+        if frequency == 0.0 {
+            return self.append_silence(duration);
+        }
+        let start: usize = self.samples.len();
+        crate::embedded::append_tone(
+            &mut self.samples,
+            frequency,
+            self.spec.sample_rate(),
+            duration,
+        );
+        self.apply_level(start);
+        Ok(())
+    }
+
+    // Gaps between pulses, or any pause a caller wants in the output; gain
+    // and limiter leave it untouched
+    pub fn append_silence(&mut self, duration: usize) -> Result<(), WavetrxError> {
+        crate::embedded::append_silence(&mut self.samples, self.spec.sample_rate(), duration);
+        self.tail = 0;
+        Ok(())
+    }
+
+    pub fn append_sine_faded_tone(
         &mut self,
         frequency: f32,
         duration: usize,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let sample_rate: usize = self.spec.sample_rate() as usize;
-
-        let sample_size: usize = (sample_rate * duration) / 1_000_000;
+        fade: f32,
+    ) -> Result<(), WavetrxError> {
+        let sample_rate: u32 = self.spec.sample_rate();
+        let sample_size: usize = sample_size(sample_rate, duration);
         let period: f32 = sample_rate as f32 / frequency;
+        let fade_size: usize = (sample_size as f32 * fade) as usize;
 
+        let start: usize = self.samples.len();
         for idx in 0..sample_size {
-            let sine_norm: f32 = self.get_sine_norm(idx, period);
+            let mut sine_norm: f32 = sine(idx, period);
+            sine_norm *= sine_fade(idx, sample_size, fade_size);
             self.samples.push(sine_norm);
         }
+        self.apply_level(start);
 
         Ok(())
     }
 
-    pub fn append_sine_faded_tone(
+    pub fn append_linear_faded_tone(
         &mut self,
         frequency: f32,
         duration: usize,
         fade: f32,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let sample_rate: usize = self.spec.sample_rate() as usize;
-        let sample_size: usize = ((sample_rate * duration) / 1_000_000) as usize;
+    ) -> Result<(), WavetrxError> {
+        let sample_rate: u32 = self.spec.sample_rate();
+        let sample_size: usize = sample_size(sample_rate, duration);
         let period: f32 = sample_rate as f32 / frequency;
         let fade_size: usize = (sample_size as f32 * fade) as usize;
 
+        let start: usize = self.samples.len();
         for idx in 0..sample_size {
-            let mut sine_norm: f32 = self.get_sine_norm(idx, period);
-            sine_norm *= self.get_sine_fade_coeff(idx, sample_size, fade_size);
+            let mut sine_norm: f32 = sine(idx, period);
+            sine_norm *= linear_fade(idx, sample_size, fade_size);
             self.samples.push(sine_norm);
         }
+        self.apply_level(start);
 
         Ok(())
     }
 
-    pub fn append_linear_faded_tone(
+    // Raised-cosine ramps of `ramp_size` samples at both ends; the opening ramp
+    // is mixed into the closing ramp of the previous cross-faded tone, if any
+    pub fn append_crossfaded_tone(
         &mut self,
         frequency: f32,
         duration: usize,
-        fade: f32,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let sample_rate: usize = self.spec.sample_rate() as usize;
-        let sample_size: usize = ((sample_rate * duration) / 1_000_000) as usize;
+        ramp_size: usize,
+    ) -> Result<(), WavetrxError> {
+        let sample_rate: u32 = self.spec.sample_rate();
+        let sample_size: usize = sample_size(sample_rate, duration);
         let period: f32 = sample_rate as f32 / frequency;
+        let ramp_size: usize = ramp_size.min(sample_size / 2);
+
+        let mut shaped: Vec<f32> = (0..sample_size)
+            .map(|idx| sine(idx, period) * sine_fade(idx, sample_size, ramp_size))
+            .collect();
+        Self::level(&mut shaped, self.gain, self.limiter.as_ref());
+
+        let overlap: usize = self.tail.min(ramp_size);
+        let start: usize = self.samples.len() - overlap;
+        for (slot, sample) in self.samples[start..].iter_mut().zip(shaped.iter()) {
+            *slot += sample;
+        }
+        self.samples.extend_from_slice(&shaped[overlap..]);
+        self.tail = ramp_size;
+
+        Ok(())
+    }
+
+    // Every frequency at `level`, summed; idle subcarriers are simply left out
+    pub fn append_multitone(
+        &mut self,
+        frequencies: &[f32],
+        level: f32,
+        duration: usize,
+    ) -> Result<(), WavetrxError> {
+        let sample_rate: u32 = self.spec.sample_rate();
+        let sample_size: usize = sample_size(sample_rate, duration);
+        let periods: Vec<f32> = frequencies
+            .iter()
+            .map(|frequency| sample_rate as f32 / frequency)
+            .collect();
+
+        let start: usize = self.samples.len();
+        for idx in 0..sample_size {
+            let sum: f32 = periods.iter().map(|period| sine(idx, *period)).sum();
+            self.samples.push(sum * level);
+        }
+        self.apply_level(start);
+
+        Ok(())
+    }
+
+    // Linear sweep from `from` to `to` Hz lasting `duration` microseconds
+    pub fn append_chirp(
+        &mut self,
+        from: f32,
+        to: f32,
+        duration: usize,
+        fade: f32,
+    ) -> Result<(), WavetrxError> {
+        let sample_size: usize = sample_size(self.spec.sample_rate(), duration);
         let fade_size: usize = (sample_size as f32 * fade) as usize;
 
+        let start: usize = self.samples.len();
         for idx in 0..sample_size {
-            let mut sine_norm: f32 = self.get_sine_norm(idx, period);
-            sine_norm *= self.get_linear_fade_coeff(idx, sample_size, fade_size);
+            let mut sine_norm: f32 = chirp_phase(from, to, idx, sample_size, &self.spec).sin();
+            sine_norm *= sine_fade(idx, sample_size, fade_size);
             self.samples.push(sine_norm);
         }
+        self.apply_level(start);
+
+        Ok(())
+    }
+
+    pub fn append_preamble(
+        &mut self,
+        preamble: &Preamble,
+        sample_size: usize,
+        fade: f32,
+    ) -> Result<(), WavetrxError> {
+        let fade_size: usize = (sample_size as f32 * fade) as usize;
+
+        let start: usize = self.samples.len();
+        for idx in 0..sample_size {
+            let mut sine_norm: f32 = preamble.phase(idx, sample_size, &self.spec).sin();
+            sine_norm *= sine_fade(idx, sample_size, fade_size);
+            self.samples.push(sine_norm);
+        }
+        self.apply_level(start);
 
         Ok(())
     }
 }
 
 impl ToneGenerator {
-    fn get_sine_norm(&self, idx: usize, period: f32) -> f32 {
-        (2.0 * consts::PI * idx as f32 / period).sin()
-    }
-
-    fn get_sine_fade_coeff(&self, idx: usize, sample_size: usize, fade_size: usize) -> f32 {
-        let fade_coefficient: f32 = if idx < fade_size {
-            0.5 * (1.0 - (consts::PI * idx as f32 / fade_size as f32).cos())
-        } else if idx >= sample_size - fade_size {
-            let relative_i: usize = idx - (sample_size - fade_size);
-            0.5 * (1.0 + (consts::PI * relative_i as f32 / fade_size as f32).cos())
-        } else {
-            1.0
-        };
-        fade_coefficient
-    }
-
-    fn get_linear_fade_coeff(&self, idx: usize, sample_size: usize, fade_size: usize) -> f32 {
-        let fade_coefficient: f32 = if idx < fade_size {
-            idx as f32 / fade_size as f32
-        } else if idx >= sample_size - fade_size {
-            (sample_size - idx) as f32 / fade_size as f32
-        } else {
-            1.0
-        };
-        fade_coefficient
+    // Anything appended after a cross-faded tone closes its ramp for good
+    fn apply_level(&mut self, start: usize) {
+        self.tail = 0;
+        Self::level(&mut self.samples[start..], self.gain, self.limiter.as_ref());
     }
+
+    fn level(samples: &mut [f32], gain: f32, limiter: Option<&SoftLimiter>) {
+        if gain != 1.0 {
+            for sample in samples.iter_mut() {
+                *sample *= gain;
+            }
+        }
+        if let Some(limiter) = limiter {
+            limiter.process(samples);
+        }
+    }
+}
+
+#[test]
+fn test_append_silence() {
+    use crate::audio::types::SampleEncoding;
+
+    let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+    let mut tone: ToneGenerator = ToneGenerator::new(&spec).unwrap().with_gain(0.5);
+    tone.append_tone(1_000.0, 1_000).unwrap();
+    tone.append_silence(2_500).unwrap();
+    // The old zero-frequency gap is the same silence
+    tone.append_tone(0.0, 500).unwrap();
+
+    let samples: Vec<f32> = tone.samples();
+    assert_eq!(samples.len(), 48 + 120 + 24);
+    assert!(samples[..48].iter().any(|sample| *sample != 0.0));
+    assert!(samples[48..].iter().all(|sample| *sample == 0.0));
 }
@@ -1,39 +1,263 @@
-use std::fs::File;
-use std::io::BufWriter;
-use std::slice::Iter;
-
-use hound;
-use hound::WavSpec;
-use hound::WavWriter;
+use std::fs;
+use std::io;
+use std::marker::PhantomData;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::atomic::AtomicU8;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
 
 use super::tone::ToneGenerator;
+use crate::audio::io::SampleSink;
+use crate::audio::limiter::SoftLimiter;
+#[cfg(feature = "device")]
+use crate::audio::player::OutputPlayer;
 use crate::audio::types::AudioSpec;
+#[cfg(any(feature = "device", feature = "wav"))]
+use crate::audio::types::NormSamples;
+use crate::consts::TRANSFER_CHUNK_SIZE;
+use crate::error::WavetrxError;
+use crate::protocol::bitvec::BitVec;
+use crate::protocol::compress::compress;
+#[cfg(feature = "crypto")]
+use crate::protocol::crypto::PayloadCipher;
+use crate::protocol::framing::FrameError;
+use crate::protocol::framing::Framing;
+use crate::protocol::framing::Sequence;
+use crate::protocol::preamble::Preamble;
+use crate::protocol::preamble::StartMarker;
 use crate::protocol::profile::Profile;
+use crate::protocol::profile::Shaping;
+use crate::protocol::profile::SizedPulses;
+use crate::protocol::transfer::TransferFrame;
+use crate::utils::random_seed;
 
 pub struct Transmitter {
     profile: Profile,
     spec: AudioSpec,
+    destination: Option<u16>,
+    source: Option<u16>,
+    sequence: AtomicU8,
+    gain: f32,
+    limiter: Option<SoftLimiter>,
+    repeats: usize,
+    repeat_gap: Duration,
+    #[cfg(feature = "crypto")]
+    cipher: Option<PayloadCipher>,
 }
 
 impl Transmitter {
     pub fn new(profile: &Profile, spec: &AudioSpec) -> Self {
         let profile: Profile = *profile;
         let spec: AudioSpec = spec.clone();
+        let destination: Option<u16> = None;
+        let source: Option<u16> = None;
+        let sequence: AtomicU8 = AtomicU8::new(random_seed() as u8);
+        let gain: f32 = 1.0;
+        let limiter: Option<SoftLimiter> = None;
+        let repeats: usize = 1;
+        let repeat_gap: Duration = Duration::ZERO;
+        #[cfg(feature = "crypto")]
+        let cipher: Option<PayloadCipher> = None;
+
+        Transmitter {
+            profile,
+            spec,
+            destination,
+            source,
+            sequence,
+            gain,
+            limiter,
+            repeats,
+            repeat_gap,
+            #[cfg(feature = "crypto")]
+            cipher,
+        }
+    }
+
+    // Ignored unless the profile's framing carries an address
+    pub fn with_destination(mut self, address: u16) -> Self {
+        self.destination = Some(address);
+        self
+    }
+
+    pub fn set_destination(&mut self, address: Option<u16>) {
+        self.destination = address;
+    }
+
+    pub fn destination(&self) -> Option<u16> {
+        self.destination
+    }
+
+    // Sent with each frame when the profile's framing is sequenced
+    pub fn with_source(mut self, address: u16) -> Self {
+        self.source = Some(address);
+        self
+    }
+
+    pub fn set_source(&mut self, address: Option<u16>) {
+        self.source = address;
+    }
+
+    pub fn source(&self) -> Option<u16> {
+        self.source
+    }
+
+    // Sequence number of the next frame, wrapping after 255; rewinding it
+    // resends a frame as a retransmission that receivers can drop. Starts at
+    // a random number, so a new sender doesn't repeat the last one's frames
+    pub fn set_sequence(&mut self, number: u8) {
+        self.sequence = AtomicU8::new(number);
+    }
+
+    pub fn sequence(&self) -> u8 {
+        self.sequence.load(Ordering::Relaxed)
+    }
+
+    pub fn set_profile(&mut self, profile: Profile) {
+        self.profile = profile;
+    }
+
+    pub fn profile(&self) -> Profile {
+        self.profile
+    }
+
+    pub fn spec(&self) -> AudioSpec {
+        self.spec
+    }
+
+    // Below full scale for speakers that distort; clamped to 0.0..=1.0
+    pub fn with_gain(mut self, gain: f32) -> Self {
+        self.set_gain(gain);
+        self
+    }
+
+    pub fn set_gain(&mut self, gain: f32) {
+        self.gain = gain.clamp(0.0, 1.0);
+    }
+
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    pub fn with_limiter(mut self, limiter: SoftLimiter) -> Self {
+        self.limiter = Some(limiter);
+        self
+    }
+
+    pub fn set_limiter(&mut self, limiter: Option<SoftLimiter>) {
+        self.limiter = limiter;
+    }
+
+    // Every message is sent `repeats` times with `gap` of silence between,
+    // for links with no way back to ask for a resend. The copies share one
+    // sequence number, so receivers with sequenced framing keep only the first
+    pub fn with_repeats(mut self, repeats: usize, gap: Duration) -> Self {
+        self.set_repeats(repeats, gap);
+        self
+    }
+
+    pub fn set_repeats(&mut self, repeats: usize, gap: Duration) {
+        self.repeats = repeats.max(1);
+        self.repeat_gap = gap;
+    }
+
+    pub fn repeats(&self) -> usize {
+        self.repeats
+    }
+
+    pub fn repeat_gap(&self) -> Duration {
+        self.repeat_gap
+    }
+
+    // Payloads grow by `PayloadCipher::overhead()` bytes once sealed
+    #[cfg(feature = "crypto")]
+    pub fn with_cipher(mut self, cipher: PayloadCipher) -> Self {
+        self.cipher = Some(cipher);
+        self
+    }
 
-        Transmitter { profile, spec }
+    #[cfg(feature = "crypto")]
+    pub fn set_cipher(&mut self, cipher: Option<PayloadCipher>) {
+        self.cipher = cipher;
     }
 
-    pub fn create(&self, data: &[u8]) -> Result<Vec<f32>, Box<dyn std::error::Error>> {
-        let mut tone: ToneGenerator = ToneGenerator::new(&self.spec)?;
+    pub fn create(&self, data: &[u8]) -> Result<Vec<f32>, WavetrxError> {
+        let bits: BitVec = self.encode_frame(data)?;
+        self.create_encoded(&bits)
+    }
+
+    // The transmit pipeline short of the modem: compression, sealing,
+    // address and sequence framing, FEC, interleaving and scrambling.
+    // Sequenced profiles take the next sequence number on every call
+    pub fn encode_frame(&self, data: &[u8]) -> Result<BitVec, FrameError> {
+        let data: Vec<u8> = match self.profile.framing.compression {
+            true => compress(data),
+            false => data.to_vec(),
+        };
+        let data: Vec<u8> = self.seal(&data)?;
+        let framing: Framing = self.profile.framing;
+        let address: u16 = self.destination.unwrap_or(framing.addressing.broadcast());
+        let frame: Vec<u8> = match framing.sequence {
+            true => {
+                let number: u8 = self.sequence.fetch_add(1, Ordering::Relaxed);
+                let sequence: Sequence = Sequence::new(self.source, number);
+                framing.encode_sequenced(&data, address, sequence)?
+            }
+            false => framing.encode_to(&data, address)?,
+        };
+        let bits: BitVec = self
+            .profile
+            .fec
+            .encode(&frame, self.profile.framing.bit_order);
+        let bits: BitVec = self
+            .profile
+            .interleaving
+            .interleave(&bits, &self.profile.fec);
+        Ok(self.profile.scrambling.scramble(&bits))
+    }
+
+    // Modulates bits from `encode_frame` into a full transmission
+    pub fn create_encoded(&self, bits: &BitVec) -> Result<Vec<f32>, WavetrxError> {
+        let mut tone: ToneGenerator = self.tone_generator()?;
         let fade: f32 = 0.1;
+        let symbols: Vec<u8> = self.encode_symbols(bits);
 
-        self.append_silence(&mut tone)?;
-        self.append_start(&mut tone, fade)?;
-        self.append_next(&mut tone, fade)?;
+        for repeat in 0..self.repeats {
+            if repeat > 0 {
+                tone.append_silence(self.repeat_gap.as_micros() as usize)?;
+            }
+            self.append_silence(&mut tone)?;
+            self.append_preamble(&mut tone, fade)?;
+            self.append_start(&mut tone, fade)?;
+            self.append_next(&mut tone, fade)?;
+            self.append_symbols(&mut tone, &symbols, 0..symbols.len(), fade)?;
+            self.append_end(&mut tone, fade)?;
+            self.append_next(&mut tone, fade)?;
+            self.append_silence(&mut tone)?;
+        }
+        Ok(tone.samples())
+    }
 
-        for &byte in data.iter() {
-            self.append_byte(&mut tone, byte, fade)?;
+    // Fails when the player has no room for the whole message, since a
+    // truncated frame never decodes
+    #[cfg(feature = "device")]
+    pub fn play(&self, data: &[u8], player: &OutputPlayer) -> Result<(), WavetrxError> {
+        let samples: Vec<f32> = self.create(data)?;
+        let total: usize = samples.len();
+        let queued: usize = player.add_samples(NormSamples::from_vec(samples));
+        if queued < total {
+            let reason: String = format!("Player queued {} of {} samples", queued, total);
+            return Err(io::Error::new(io::ErrorKind::WriteZero, reason).into());
         }
+        Ok(())
+    }
+
+    // The End marker on its own, to close a frame cut short so receivers
+    // drop it instead of waiting for the rest of its symbols
+    pub fn create_end(&self) -> Result<Vec<f32>, WavetrxError> {
+        let mut tone: ToneGenerator = self.tone_generator()?;
+        let fade: f32 = 0.1;
 
         self.append_end(&mut tone, fade)?;
         self.append_next(&mut tone, fade)?;
@@ -41,99 +265,200 @@ impl Transmitter {
         Ok(tone.samples())
     }
 
-    pub fn create_file(
-        &self,
-        filename: &str,
-        data: &[u8],
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let samples: Vec<f32> = self.create(data)?;
+    // Drops whatever the player still has queued, following it with an End
+    // marker when asked and something was cut off. Returns how many queued
+    // samples were dropped
+    #[cfg(feature = "device")]
+    pub fn abort(&self, player: &OutputPlayer, end_marker: bool) -> Result<usize, WavetrxError> {
+        let dropped: usize = player.clear();
+        if end_marker && dropped > 0 {
+            player.add_samples(NormSamples::from_vec(self.create_end()?));
+        }
+        Ok(dropped)
+    }
+
+    // Samples are written in the spec's encoding and bit depth
+    #[cfg(feature = "wav")]
+    pub fn create_file(&self, filename: &str, data: &[u8]) -> Result<(), WavetrxError> {
+        let samples: NormSamples = NormSamples::from_vec(self.create(data)?);
+        samples.save_file(filename, &self.spec)
+    }
 
-        let spec: WavSpec = self.spec.into();
-        let mut writer: WavWriter<BufWriter<File>> = WavWriter::create(filename, spec)?;
-        for sample in samples {
-            writer.write_sample(sample)?;
+    // Fails if the sink takes fewer samples than the transmission needs
+    pub fn write_to<K>(&self, data: &[u8], sink: &mut K) -> Result<(), WavetrxError>
+    where
+        K: SampleSink + ?Sized,
+    {
+        let samples: Vec<f32> = self.create(data)?;
+        let written: usize = sink.write(&samples);
+        if written < samples.len() {
+            let reason: String = format!("Sink took {} of {} samples", written, samples.len());
+            return Err(io::Error::new(io::ErrorKind::WriteZero, reason).into());
         }
+        sink.flush()
+    }
 
+    // Sends the file as a header and `TRANSFER_CHUNK_SIZE` fragments, one
+    // message each; a `FileAssembler` on the far end rebuilds and checks it
+    pub fn send_file<P, K>(&self, path: P, sink: &mut K) -> Result<(), WavetrxError>
+    where
+        P: AsRef<Path>,
+        K: SampleSink + ?Sized,
+    {
+        let path: &Path = path.as_ref();
+        let data: Vec<u8> = fs::read(path)?;
+        let name: String = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        for frame in TransferFrame::split(&name, &data, TRANSFER_CHUNK_SIZE)? {
+            self.write_to(&frame.encode(), sink)?;
+        }
         Ok(())
     }
 }
 
 impl Transmitter {
-    fn append_byte(
-        &self,
-        tone: &mut ToneGenerator,
-        byte: u8,
-        fade: f32,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        for i in (0..8).rev() {
-            let bit: bool = (byte & (1 << i)) != 0;
-            self.append_bit(tone, bit, fade)?;
-            self.append_next(tone, fade)?;
+    fn tone_generator(&self) -> Result<ToneGenerator, WavetrxError> {
+        self.profile.modulation.require_fsk()?;
+        let mut tone: ToneGenerator = ToneGenerator::new(&self.spec)?.with_gain(self.gain);
+        tone.set_limiter(self.limiter);
+        Ok(tone)
+    }
+
+    fn seal(&self, data: &[u8]) -> Result<Vec<u8>, FrameError> {
+        #[cfg(feature = "crypto")]
+        if let Some(cipher) = &self.cipher {
+            return cipher.seal(data);
         }
-        Ok(())
+        Ok(data.to_vec())
     }
 
-    fn append_start(
+    // Groups the encoded bits MSB-first into symbols, zero-padding the last one
+    fn encode_symbols(&self, bits: &BitVec) -> Vec<u8> {
+        let bits_per_symbol: usize = self.profile.bits.bits_per_symbol();
+
+        let mut symbols: Vec<u8> = Vec::with_capacity(bits.len().div_ceil(bits_per_symbol));
+        for symbol_idx in 0..bits.len().div_ceil(bits_per_symbol) {
+            let mut symbol: u8 = 0;
+            for offset in 0..bits_per_symbol {
+                let bit: bool = bits
+                    .get(symbol_idx * bits_per_symbol + offset)
+                    .unwrap_or(false);
+                symbol = (symbol << 1) | bit as u8;
+            }
+            symbols.push(symbol);
+        }
+        symbols
+    }
+
+    fn append_symbols(
         &self,
         tone: &mut ToneGenerator,
+        symbols: &[u8],
+        range: Range<usize>,
         fade: f32,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let tone_duration: usize = self.profile.pulses.tone.as_micros::<usize>();
-        let gap_duration: usize = self.profile.pulses.gap.as_micros::<usize>();
-        let frequency: f32 = self.profile.markers.start.hz();
+    ) -> Result<(), WavetrxError> {
+        for symbol_idx in range {
+            let symbol: u8 = match symbols.get(symbol_idx) {
+                Some(symbol) => *symbol,
+                None => break,
+            };
+            self.append_symbol(tone, symbol, fade)?;
 
-        tone.append_sine_faded_tone(frequency, tone_duration, fade)?;
-        tone.append_tone(0.0, gap_duration)?;
+            if self.profile.timing.requires_next(symbol_idx) {
+                self.append_next(tone, fade)?;
+            }
+        }
         Ok(())
     }
 
-    fn append_end(
-        &self,
-        tone: &mut ToneGenerator,
-        fade: f32,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let tone_duration: usize = self.profile.pulses.tone.as_micros::<usize>();
+    fn append_preamble(&self, tone: &mut ToneGenerator, fade: f32) -> Result<(), WavetrxError> {
+        let preamble: Preamble = self.profile.preamble;
+        if preamble.is_none() {
+            return Ok(());
+        }
+
+        let pulses: SizedPulses = self.profile.pulses.into_sized(&self.spec);
         let gap_duration: usize = self.profile.pulses.gap.as_micros::<usize>();
-        let frequency: f32 = self.profile.markers.end.hz();
 
-        tone.append_sine_faded_tone(frequency, tone_duration, fade)?;
-        tone.append_tone(0.0, gap_duration)?;
+        tone.append_preamble(&preamble, preamble.sample_size(&pulses), fade)?;
+        tone.append_silence(gap_duration)?;
         Ok(())
     }
 
-    fn append_next(
-        &self,
-        tone: &mut ToneGenerator,
-        fade: f32,
-    ) -> Result<(), Box<dyn std::error::Error>> {
+    fn append_start(&self, tone: &mut ToneGenerator, fade: f32) -> Result<(), WavetrxError> {
         let tone_duration: usize = self.profile.pulses.tone.as_micros::<usize>();
         let gap_duration: usize = self.profile.pulses.gap.as_micros::<usize>();
-        let frequency: f32 = self.profile.markers.next.hz();
+        let frequency: f32 = self.profile.markers.start.hz();
 
-        tone.append_sine_faded_tone(frequency, tone_duration, fade)?;
-        tone.append_tone(0.0, gap_duration)?;
+        match self.profile.start_marker {
+            StartMarker::Tone => self.append_pulse(tone, frequency, fade)?,
+            StartMarker::Chirp { from, to } => {
+                tone.append_chirp(from, to, tone_duration, fade)?;
+                tone.append_silence(gap_duration)?;
+            }
+        }
         Ok(())
     }
 
-    fn append_silence(&self, tone: &mut ToneGenerator) -> Result<(), Box<dyn std::error::Error>> {
+    fn append_end(&self, tone: &mut ToneGenerator, fade: f32) -> Result<(), WavetrxError> {
+        let frequency: f32 = self.profile.markers.end.hz();
+        self.append_pulse(tone, frequency, fade)
+    }
+
+    fn append_next(&self, tone: &mut ToneGenerator, fade: f32) -> Result<(), WavetrxError> {
+        let frequency: f32 = self.profile.markers.next.hz();
+        self.append_pulse(tone, frequency, fade)
+    }
+
+    fn append_silence(&self, tone: &mut ToneGenerator) -> Result<(), WavetrxError> {
         let gap_duration: usize = self.profile.pulses.gap.as_micros::<usize>();
         let gap_duration = gap_duration * 4;
-        tone.append_tone(0.0, gap_duration)?;
+        // Gapless profiles still need room for the receiver to read the last pulse
+        let gap_duration: usize = match gap_duration {
+            0 => self.profile.pulses.tone.as_micros::<usize>(),
+            _ => gap_duration,
+        };
+        tone.append_silence(gap_duration)?;
         Ok(())
     }
 
-    fn append_bit(
+    fn append_symbol(
+        &self,
+        tone: &mut ToneGenerator,
+        symbol: u8,
+        fade: f32,
+    ) -> Result<(), WavetrxError> {
+        let frequency: f32 = self.profile.bits.from_symbol(symbol).hz();
+        self.append_pulse(tone, frequency, fade)
+    }
+
+    // One tone plus its gap, shaped as the profile asks
+    fn append_pulse(
         &self,
         tone: &mut ToneGenerator,
-        bit: bool,
+        frequency: f32,
         fade: f32,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let frequency: f32 = self.profile.bits.from_boolean(bit).hz();
+    ) -> Result<(), WavetrxError> {
         let tone_duration: usize = self.profile.pulses.tone.as_micros::<usize>();
         let gap_duration: usize = self.profile.pulses.gap.as_micros::<usize>();
+        let shaping: Shaping = self.profile.pulses.shaping;
 
-        tone.append_sine_faded_tone(frequency, tone_duration, fade)?;
-        tone.append_tone(0.0, gap_duration)?;
+        match shaping {
+            Shaping::Gated => {
+                tone.append_sine_faded_tone(frequency, tone_duration, fade)?;
+                tone.append_silence(gap_duration)?;
+            }
+            Shaping::RaisedCosine { .. } => {
+                let pulses: SizedPulses = self.profile.pulses.into_sized(&self.spec);
+                let ramp_size: usize = shaping.ramp_size(pulses.tone_size());
+                tone.append_crossfaded_tone(frequency, tone_duration, ramp_size)?;
+                if pulses.gap_size() > 0 {
+                    tone.append_silence(gap_duration)?;
+                }
+            }
+        }
         Ok(())
     }
 }
@@ -148,70 +473,100 @@ pub struct StreamTransmitter<'a, const N: usize> {
     tx: Transmitter,
     tone: ToneGenerator,
     stage: StreamTxStage,
-    data: Iter<'a, u8>,
+    symbols: Vec<u8>,
+    symbol_idx: usize,
     fade: f32,
     close: bool,
+    data: PhantomData<&'a [u8]>,
 }
 
 impl<'a, const N: usize> StreamTransmitter<'a, N> {
-    pub fn new(profile: &Profile, spec: &AudioSpec, data: &'a [u8]) -> Self {
+    pub fn new(profile: &Profile, spec: &AudioSpec, data: &'a [u8]) -> Result<Self, WavetrxError> {
         let tx: Transmitter = Transmitter::new(profile, spec);
-        let tone: ToneGenerator = ToneGenerator::new(spec).unwrap();
+        let tone: ToneGenerator = tx.tone_generator()?;
         let stage: StreamTxStage = StreamTxStage::Start;
-        let data: Iter<'a, u8> = data.iter();
+        let bits: BitVec = tx.encode_frame(data)?;
+        let symbols: Vec<u8> = tx.encode_symbols(&bits);
+        let symbol_idx: usize = 0;
         let fade: f32 = 0.0;
         let close: bool = false;
+        let data: PhantomData<&'a [u8]> = PhantomData;
 
-        Self {
+        Ok(Self {
             tx,
             tone,
             stage,
-            data,
+            symbols,
+            symbol_idx,
             fade,
             close,
-        }
+            data,
+        })
     }
 
     pub fn set_fade(&mut self, fade: f32) {
         self.fade = fade;
     }
+
+    pub fn set_gain(&mut self, gain: f32) {
+        self.tone.set_gain(gain);
+    }
+
+    pub fn set_limiter(&mut self, limiter: Option<SoftLimiter>) {
+        self.tone.set_limiter(limiter);
+    }
 }
 
+// A failed step is yielded as the last item
 impl<'a, const N: usize> Iterator for StreamTransmitter<'a, N> {
-    type Item = Vec<f32>;
+    type Item = Result<Vec<f32>, WavetrxError>;
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.close {
             return None;
         }
 
+        match self.append_steps() {
+            Ok(()) => Some(Ok(self.tone.take_samples())),
+            Err(err) => {
+                self.close = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl<'a, const N: usize> StreamTransmitter<'a, N> {
+    fn append_steps(&mut self) -> Result<(), WavetrxError> {
         for _ in 0..N {
             match self.stage {
                 StreamTxStage::Start => {
-                    self.tx.append_silence(&mut self.tone).unwrap();
-                    self.tx.append_start(&mut self.tone, self.fade).unwrap();
-                    self.tx.append_next(&mut self.tone, self.fade).unwrap();
+                    self.tx.append_silence(&mut self.tone)?;
+                    self.tx.append_preamble(&mut self.tone, self.fade)?;
+                    self.tx.append_start(&mut self.tone, self.fade)?;
+                    self.tx.append_next(&mut self.tone, self.fade)?;
                     self.stage = StreamTxStage::Data;
                 }
                 StreamTxStage::Data => {
-                    if let Some(&byte) = self.data.next() {
+                    if self.symbol_idx < self.symbols.len() {
+                        let end: usize = (self.symbol_idx + 8).min(self.symbols.len());
+                        let range: Range<usize> = self.symbol_idx..end;
                         self.tx
-                            .append_byte(&mut self.tone, byte, self.fade)
-                            .unwrap();
+                            .append_symbols(&mut self.tone, &self.symbols, range, self.fade)?;
+                        self.symbol_idx = end;
                     } else {
                         self.stage = StreamTxStage::End;
                     }
                 }
                 StreamTxStage::End => {
-                    self.tx.append_end(&mut self.tone, self.fade).unwrap();
-                    self.tx.append_next(&mut self.tone, self.fade).unwrap();
-                    self.tx.append_silence(&mut self.tone).unwrap();
+                    self.tx.append_end(&mut self.tone, self.fade)?;
+                    self.tx.append_next(&mut self.tone, self.fade)?;
+                    self.tx.append_silence(&mut self.tone)?;
                     self.close = true;
                     break;
                 }
             };
         }
-
-        Some(self.tone.take_samples())
+        Ok(())
     }
 }
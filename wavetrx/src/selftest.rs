@@ -0,0 +1,165 @@
+use std::thread::sleep;
+use std::time::Duration;
+use std::time::Instant;
+
+use cpal::traits::DeviceTrait;
+use cpal::traits::HostTrait;
+use cpal::Device;
+use cpal::Host;
+use cpal::StreamConfig;
+
+use crate::audio::player::OutputPlayer;
+use crate::audio::recorder::InputRecorder;
+use crate::audio::types::AudioSpec;
+use crate::audio::types::NormSamples;
+use crate::audio::types::SampleEncoding;
+use crate::error::WavetrxError;
+use crate::protocol::profile::Profile;
+use crate::protocol::rx::DecodedMessage;
+use crate::protocol::rx::Receiver;
+use crate::protocol::tx::Transmitter;
+
+const SELFTEST_PAYLOAD: &[u8] = b"WaveTrx";
+const NOISE_WINDOW: Duration = Duration::from_millis(250);
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelfTestReport {
+    pub decoded: bool,
+    pub snr_db: Option<f32>,
+    pub latency: Option<Duration>,
+}
+
+struct Capture {
+    recorder: InputRecorder,
+    channels: usize,
+    samples: Vec<f32>,
+}
+
+impl Capture {
+    fn collect(&mut self) -> usize {
+        let frame: NormSamples = match self.recorder.take_frame() {
+            Some(frame) => frame,
+            None => return 0,
+        };
+
+        let start: usize = self.samples.len();
+        self.samples
+            .extend(frame.0.into_iter().step_by(self.channels));
+        self.samples.len() - start
+    }
+}
+
+pub fn selftest(profile: Profile, timeout: Duration) -> Result<SelfTestReport, WavetrxError> {
+    let host: Host = cpal::default_host();
+    let output_device: Device = host
+        .default_output_device()
+        .ok_or_else(|| WavetrxError::DeviceError("No output device available".to_string()))?;
+    let input_device: Device = host
+        .default_input_device()
+        .ok_or_else(|| WavetrxError::DeviceError("No input device available".to_string()))?;
+    let output_config: StreamConfig = output_device.default_output_config()?.into();
+    let input_config: StreamConfig = input_device.default_input_config()?.into();
+
+    let tx_spec: AudioSpec = get_mono_spec(&output_config);
+    let rx_spec: AudioSpec = get_mono_spec(&input_config);
+    let transmitter: Transmitter = Transmitter::new(&profile, &tx_spec);
+    let frame: Vec<f32> = transmitter.create(SELFTEST_PAYLOAD)?;
+
+    let mut player: OutputPlayer = OutputPlayer::new(output_device, output_config, tx_spec);
+    let channels: usize = (input_config.channels as usize).max(1);
+    let mut capture: Capture = Capture {
+        recorder: InputRecorder::new(input_device, input_config),
+        channels,
+        samples: Vec::new(),
+    };
+
+    player.play()?;
+    capture.recorder.record()?;
+
+    let started: Instant = Instant::now();
+    let noise_size: usize =
+        rx_spec.sample_rate() as usize * NOISE_WINDOW.as_millis() as usize / 1000;
+    while capture.samples.len() < noise_size && started.elapsed() < timeout {
+        capture.collect();
+        sleep(POLL_INTERVAL);
+    }
+
+    let send_idx: usize = capture.samples.len();
+    player.add_samples(NormSamples::from_vec(frame));
+
+    let mut receiver: Receiver = Receiver::new(profile, rx_spec);
+    let mut fed: usize = 0;
+    let mut message: Option<DecodedMessage> = None;
+
+    while message.is_none() && started.elapsed() < timeout {
+        if capture.collect() == 0 {
+            sleep(POLL_INTERVAL);
+            continue;
+        }
+
+        let mut samples: NormSamples = NormSamples::from_slice(&capture.samples[fed..]);
+        fed = capture.samples.len();
+        receiver.add_samples(&mut samples);
+        receiver.analyze_full_buffer();
+
+        message = receiver
+            .take_messages()
+            .into_iter()
+            .find(|message| message.data() == SELFTEST_PAYLOAD);
+    }
+
+    let report: SelfTestReport = match message {
+        Some(message) => SelfTestReport {
+            decoded: true,
+            snr_db: get_snr_db(&capture.samples, send_idx, &message),
+            latency: get_latency(&profile, &rx_spec, send_idx, &message),
+        },
+        None => SelfTestReport {
+            decoded: false,
+            snr_db: None,
+            latency: None,
+        },
+    };
+    Ok(report)
+}
+
+fn get_mono_spec(config: &StreamConfig) -> AudioSpec {
+    AudioSpec::new(config.sample_rate.0, 32, 1, SampleEncoding::F32)
+}
+
+fn get_rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    let sum: f32 = samples.iter().map(|sample| sample * sample).sum();
+    (sum / samples.len() as f32).sqrt()
+}
+
+fn get_snr_db(samples: &[f32], send_idx: usize, message: &DecodedMessage) -> Option<f32> {
+    let end: usize = message.end_sample().min(samples.len());
+    let start: usize = message.start_sample().min(end);
+
+    let noise: f32 = get_rms(&samples[..send_idx]);
+    let signal: f32 = get_rms(&samples[start..end]);
+    if noise <= 0.0 || signal <= 0.0 {
+        return None;
+    }
+    Some(20.0 * (signal / noise).log10())
+}
+
+fn get_latency(
+    profile: &Profile,
+    spec: &AudioSpec,
+    send_idx: usize,
+    message: &DecodedMessage,
+) -> Option<Duration> {
+    let offset: usize = message.start_sample().checked_sub(send_idx)?;
+    let arrival: Duration = spec.sample_timestamp(offset);
+
+    // The transmitted frame leads with four gaps of silence before the start marker
+    let leading_silence: Duration =
+        Duration::from_micros(profile.pulses.gap.as_micros::<u64>() * 4);
+    Some(arrival.saturating_sub(leading_silence))
+}
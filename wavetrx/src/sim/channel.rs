@@ -0,0 +1,260 @@
+#[cfg(feature = "wav")]
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rustfft::num_complex::Complex;
+use rustfft::Fft;
+use rustfft::FftPlanner;
+
+use super::signal_power;
+use super::NoiseSource;
+
+use crate::audio::resampler::resample;
+use crate::audio::types::AudioSpec;
+#[cfg(feature = "wav")]
+use crate::audio::types::ChannelMode;
+#[cfg(feature = "wav")]
+use crate::audio::types::NormSamples;
+#[cfg(feature = "wav")]
+use crate::error::WavetrxError;
+#[cfg(feature = "wav")]
+use crate::utils::read_wav_file;
+
+// An acoustic or electrical path between transmitter and receiver
+pub trait Channel {
+    fn apply(&mut self, samples: &[f32], spec: &AudioSpec) -> Vec<f32>;
+}
+
+// Additive white Gaussian noise at a fixed ratio to the signal power
+pub struct AwgnChannel {
+    snr_db: f32,
+    noise: NoiseSource,
+}
+
+impl AwgnChannel {
+    pub fn new(snr_db: f32, seed: u64) -> Self {
+        let noise: NoiseSource = NoiseSource::new(seed);
+        AwgnChannel { snr_db, noise }
+    }
+
+    pub fn snr_db(&self) -> f32 {
+        self.snr_db
+    }
+}
+
+impl Channel for AwgnChannel {
+    fn apply(&mut self, samples: &[f32], _spec: &AudioSpec) -> Vec<f32> {
+        let power: f32 = signal_power(samples);
+        let noise_rms: f32 = (power / 10f32.powf(self.snr_db / 10.0)).sqrt();
+        samples
+            .iter()
+            .map(|sample| sample + self.noise.gaussian() * noise_rms)
+            .collect()
+    }
+}
+
+// Convolution with a measured impulse response, e.g. a room recording
+pub struct ImpulseResponse {
+    taps: Vec<f32>,
+    sample_rate: u32,
+}
+
+impl ImpulseResponse {
+    pub fn new(taps: Vec<f32>, sample_rate: u32) -> Self {
+        ImpulseResponse { taps, sample_rate }
+    }
+
+    // Multi-channel responses are downmixed to one
+    #[cfg(feature = "wav")]
+    pub fn from_file<P>(filename: P) -> Result<Self, WavetrxError>
+    where
+        P: AsRef<Path>,
+    {
+        let (samples, spec): (NormSamples, AudioSpec) = read_wav_file(filename)?;
+        let samples: NormSamples =
+            samples.into_mono(spec.channels() as usize, ChannelMode::Downmix);
+        if samples.0.is_empty() {
+            let reason: String = "Impulse response has no samples".to_string();
+            return Err(WavetrxError::UnsupportedWav(reason));
+        }
+        Ok(ImpulseResponse::new(samples.0, spec.sample_rate()))
+    }
+
+    pub fn taps(&self) -> &[f32] {
+        &self.taps
+    }
+}
+
+impl Channel for ImpulseResponse {
+    fn apply(&mut self, samples: &[f32], spec: &AudioSpec) -> Vec<f32> {
+        if self.sample_rate != spec.sample_rate() {
+            self.taps = resample(&self.taps, self.sample_rate, spec.sample_rate());
+            self.sample_rate = spec.sample_rate();
+        }
+        convolve(samples, &self.taps)
+    }
+}
+
+// Direct path plus delayed, attenuated copies of the signal
+#[derive(Clone, Debug, PartialEq)]
+pub struct Multipath {
+    paths: Vec<(Duration, f32)>,
+}
+
+impl Multipath {
+    pub fn new() -> Self {
+        let paths: Vec<(Duration, f32)> = vec![(Duration::ZERO, 1.0)];
+        Multipath { paths }
+    }
+
+    pub fn with_echo(mut self, delay: Duration, gain: f32) -> Self {
+        self.paths.push((delay, gain));
+        self
+    }
+
+    pub fn paths(&self) -> &[(Duration, f32)] {
+        &self.paths
+    }
+}
+
+impl Default for Multipath {
+    fn default() -> Self {
+        Multipath::new()
+    }
+}
+
+impl Channel for Multipath {
+    fn apply(&mut self, samples: &[f32], spec: &AudioSpec) -> Vec<f32> {
+        let sample_rate: f64 = spec.sample_rate() as f64;
+        let delays: Vec<(usize, f32)> = self
+            .paths
+            .iter()
+            .map(|(delay, gain)| ((delay.as_secs_f64() * sample_rate).round() as usize, *gain))
+            .collect();
+        let max_delay: usize = delays.iter().map(|(delay, _)| *delay).max().unwrap_or(0);
+
+        let mut output: Vec<f32> = vec![0.0; samples.len() + max_delay];
+        for (delay, gain) in delays {
+            for (idx, sample) in samples.iter().enumerate() {
+                output[idx + delay] += sample * gain;
+            }
+        }
+        output
+    }
+}
+
+// Applies each channel in turn, so paths can be stacked like the real world
+#[derive(Default)]
+pub struct ChannelChain {
+    channels: Vec<Box<dyn Channel>>,
+}
+
+impl ChannelChain {
+    pub fn new() -> Self {
+        ChannelChain::default()
+    }
+
+    pub fn then<C>(mut self, channel: C) -> Self
+    where
+        C: Channel + 'static,
+    {
+        self.channels.push(Box::new(channel));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.channels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+    }
+}
+
+impl Channel for ChannelChain {
+    fn apply(&mut self, samples: &[f32], spec: &AudioSpec) -> Vec<f32> {
+        let mut output: Vec<f32> = samples.to_vec();
+        for channel in self.channels.iter_mut() {
+            output = channel.apply(&output, spec);
+        }
+        output
+    }
+}
+
+// Linear convolution through a single zero-padded FFT
+pub fn convolve(samples: &[f32], taps: &[f32]) -> Vec<f32> {
+    if samples.is_empty() || taps.is_empty() {
+        return samples.to_vec();
+    }
+
+    let output_len: usize = samples.len() + taps.len() - 1;
+    let fft_size: usize = output_len.next_power_of_two();
+    let mut planner: FftPlanner<f32> = FftPlanner::<f32>::new();
+    let forward: Arc<dyn Fft<f32>> = planner.plan_fft_forward(fft_size);
+    let inverse: Arc<dyn Fft<f32>> = planner.plan_fft_inverse(fft_size);
+
+    let mut signal: Vec<Complex<f32>> = zero_padded(samples, fft_size);
+    let mut kernel: Vec<Complex<f32>> = zero_padded(taps, fft_size);
+    forward.process(&mut signal);
+    forward.process(&mut kernel);
+
+    for (value, tap) in signal.iter_mut().zip(kernel.iter()) {
+        *value *= tap;
+    }
+    inverse.process(&mut signal);
+
+    let scale: f32 = 1.0 / fft_size as f32;
+    signal[..output_len]
+        .iter()
+        .map(|value| value.re * scale)
+        .collect()
+}
+
+fn zero_padded(samples: &[f32], size: usize) -> Vec<Complex<f32>> {
+    let mut buffer: Vec<Complex<f32>> = vec![Complex::new(0.0, 0.0); size];
+    for (slot, sample) in buffer.iter_mut().zip(samples.iter()) {
+        slot.re = *sample;
+    }
+    buffer
+}
+
+#[test]
+fn test_multipath_matches_convolution() {
+    use crate::audio::types::SampleEncoding;
+
+    let spec: AudioSpec = AudioSpec::new(1_000, 32, 1, SampleEncoding::F32);
+    let samples: Vec<f32> = vec![1.0, -0.5, 0.25, 0.0, 0.75];
+
+    let mut multipath: Multipath = Multipath::new().with_echo(Duration::from_millis(3), 0.5);
+    let echoed: Vec<f32> = multipath.apply(&samples, &spec);
+    assert_eq!(echoed, vec![1.0, -0.5, 0.25, 0.5, 0.5, 0.125, 0.0, 0.375]);
+
+    let mut response: ImpulseResponse = ImpulseResponse::new(vec![1.0, 0.0, 0.0, 0.5], 1_000);
+    let convolved: Vec<f32> = response.apply(&samples, &spec);
+    assert_eq!(convolved.len(), echoed.len());
+    for (a, b) in convolved.iter().zip(echoed.iter()) {
+        assert!((a - b).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn test_awgn_snr() {
+    use crate::audio::types::SampleEncoding;
+
+    let spec: AudioSpec = AudioSpec::new(48_000, 32, 1, SampleEncoding::F32);
+    let samples: Vec<f32> = (0..48_000)
+        .map(|idx| (idx as f32 * 0.1).sin() * 0.5)
+        .collect();
+
+    let mut chain: ChannelChain = ChannelChain::new().then(AwgnChannel::new(10.0, 7));
+    let noisy: Vec<f32> = chain.apply(&samples, &spec);
+    let noise: Vec<f32> = noisy
+        .iter()
+        .zip(samples.iter())
+        .map(|(a, b)| a - b)
+        .collect();
+
+    let snr_db: f32 = 10.0 * (signal_power(&samples) / signal_power(&noise)).log10();
+    assert!((snr_db - 10.0).abs() < 0.5);
+}
@@ -0,0 +1,347 @@
+mod channel;
+
+use std::f32::consts;
+use std::sync::mpsc;
+
+use crate::audio::filters::FrequencyPass;
+use crate::audio::types::AudioSpec;
+use crate::audio::types::NormSamples;
+use crate::audio::types::SampleEncoding;
+use crate::error::WavetrxError;
+use crate::protocol::bitvec::BitVec;
+use crate::protocol::profile::Profile;
+use crate::protocol::rx::DecodedMessage;
+use crate::protocol::rx::Receiver;
+use crate::protocol::rx::RxEvent;
+use crate::protocol::tx::Transmitter;
+
+pub use channel::convolve;
+pub use channel::AwgnChannel;
+pub use channel::Channel;
+pub use channel::ChannelChain;
+pub use channel::ImpulseResponse;
+pub use channel::Multipath;
+
+const SIM_SAMPLE_RATE: u32 = 48_000;
+const SIM_PAYLOAD_SIZE: usize = 8;
+const SIM_TRIALS: usize = 10;
+const SIM_SEED: u64 = 0x5EED_5EED;
+const FILTER_Q: f32 = 0.707;
+
+// Seeded xorshift generator so simulation runs are reproducible
+pub struct NoiseSource {
+    state: u64,
+}
+
+impl NoiseSource {
+    pub fn new(seed: u64) -> Self {
+        let state: u64 = seed.max(1);
+        NoiseSource { state }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    // Uniform in (0, 1]
+    pub fn uniform(&mut self) -> f32 {
+        ((self.next_u64() >> 40) as f32 + 1.0) / (1u64 << 24) as f32
+    }
+
+    pub fn gaussian(&mut self) -> f32 {
+        let u1: f32 = self.uniform();
+        let u2: f32 = self.uniform();
+        (-2.0 * u1.ln()).sqrt() * (2.0 * consts::PI * u2).cos()
+    }
+
+    pub fn bytes(&mut self, size: usize) -> Vec<u8> {
+        (0..size).map(|_| self.next_u64() as u8).collect()
+    }
+}
+
+// Channel impairments, applied in order: offset, band-limiting, noise, clipping
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Impairments {
+    pub snr_db: Option<f32>,
+    pub lowpass: Option<f32>,
+    pub highpass: Option<f32>,
+    pub clip: Option<f32>,
+    pub offset: usize,
+}
+
+impl Impairments {
+    pub fn new() -> Self {
+        Impairments::default()
+    }
+
+    // White Gaussian noise at this ratio to the transmitted signal power
+    pub fn with_noise(mut self, snr_db: f32) -> Self {
+        self.snr_db = Some(snr_db);
+        self
+    }
+
+    pub fn with_lowpass(mut self, frequency: f32) -> Self {
+        self.lowpass = Some(frequency);
+        self
+    }
+
+    pub fn with_highpass(mut self, frequency: f32) -> Self {
+        self.highpass = Some(frequency);
+        self
+    }
+
+    // Hard-clips at this fraction of full scale
+    pub fn with_clipping(mut self, level: f32) -> Self {
+        self.clip = Some(level.abs());
+        self
+    }
+
+    // Leading silence in samples, so symbols land off the sample grid of a clean capture
+    pub fn with_offset(mut self, samples: usize) -> Self {
+        self.offset = samples;
+        self
+    }
+
+    pub fn apply(&self, samples: &[f32], spec: &AudioSpec, noise: &mut NoiseSource) -> Vec<f32> {
+        let mut output: Vec<f32> = vec![0.0; self.offset];
+        output.extend_from_slice(samples);
+
+        if let Some(frequency) = self.highpass {
+            FrequencyPass::new(&mut output, spec).apply_highpass(frequency, FILTER_Q);
+        }
+        if let Some(frequency) = self.lowpass {
+            FrequencyPass::new(&mut output, spec).apply_lowpass(frequency, FILTER_Q);
+        }
+
+        if let Some(snr_db) = self.snr_db {
+            let power: f32 = signal_power(samples);
+            let noise_rms: f32 = (power / 10f32.powf(snr_db / 10.0)).sqrt();
+            for sample in output.iter_mut() {
+                *sample += noise.gaussian() * noise_rms;
+            }
+        }
+
+        if let Some(level) = self.clip {
+            for sample in output.iter_mut() {
+                *sample = sample.clamp(-level, level);
+            }
+        }
+        output
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SimReport {
+    pub trials: usize,
+    pub packets_ok: usize,
+    pub bits: usize,
+    pub bit_errors: usize,
+}
+
+impl SimReport {
+    // Raw channel bits before FEC; bits that never arrived count as errors
+    pub fn ber(&self) -> f32 {
+        if self.bits == 0 {
+            return 0.0;
+        }
+        self.bit_errors as f32 / self.bits as f32
+    }
+
+    pub fn per(&self) -> f32 {
+        if self.trials == 0 {
+            return 0.0;
+        }
+        (self.trials - self.packets_ok) as f32 / self.trials as f32
+    }
+}
+
+// Runs tx -> channel -> rx entirely in memory with random payloads
+pub struct Simulation {
+    profile: Profile,
+    spec: AudioSpec,
+    impairments: Impairments,
+    channel: ChannelChain,
+    trials: usize,
+    payload_size: usize,
+    seed: u64,
+}
+
+impl Simulation {
+    pub fn new(profile: Profile) -> Self {
+        let sample_rate: u32 = profile.sample_rate.unwrap_or(SIM_SAMPLE_RATE);
+        let spec: AudioSpec = AudioSpec::new(sample_rate, 32, 1, SampleEncoding::F32);
+
+        Simulation {
+            profile,
+            spec,
+            impairments: Impairments::default(),
+            channel: ChannelChain::new(),
+            trials: SIM_TRIALS,
+            payload_size: SIM_PAYLOAD_SIZE,
+            seed: SIM_SEED,
+        }
+    }
+
+    pub fn with_impairments(mut self, impairments: Impairments) -> Self {
+        self.impairments = impairments;
+        self
+    }
+
+    // Channels run after the impairments, in the order they were added
+    pub fn with_channel<C>(mut self, channel: C) -> Self
+    where
+        C: Channel + 'static,
+    {
+        self.channel = self.channel.then(channel);
+        self
+    }
+
+    pub fn with_trials(mut self, trials: usize) -> Self {
+        self.trials = trials;
+        self
+    }
+
+    pub fn with_payload_size(mut self, payload_size: usize) -> Self {
+        self.payload_size = payload_size.max(1);
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn run(&mut self) -> Result<SimReport, WavetrxError> {
+        let mut noise: NoiseSource = NoiseSource::new(self.seed);
+        let transmitter: Transmitter = Transmitter::new(&self.profile, &self.spec);
+        let mut report: SimReport = SimReport::default();
+
+        for _ in 0..self.trials {
+            let payload: Vec<u8> = noise.bytes(self.payload_size);
+            let expected: BitVec = transmitter.encode_frame(&payload)?;
+
+            let samples: Vec<f32> = transmitter.create_encoded(&expected)?;
+            let samples: Vec<f32> = self.impairments.apply(&samples, &self.spec, &mut noise);
+            let samples: Vec<f32> = self.channel.apply(&samples, &self.spec);
+
+            let (received, messages): (Vec<bool>, Vec<DecodedMessage>) = self.receive(samples);
+            report.trials += 1;
+            report.bits += expected.len();
+            report.bit_errors += count_bit_errors(&expected, &received);
+            if messages
+                .iter()
+                .any(|message| message.data() == payload.as_slice())
+            {
+                report.packets_ok += 1;
+            }
+        }
+        Ok(report)
+    }
+}
+
+impl Simulation {
+    // Bits are collected from the last Start up to the first completed frame,
+    // so a false start does not shift every later bit
+    fn receive(&self, samples: Vec<f32>) -> (Vec<bool>, Vec<DecodedMessage>) {
+        let mut receiver: Receiver = Receiver::new(self.profile, self.spec);
+        let events: mpsc::Receiver<RxEvent> = receiver.subscribe();
+        receiver.add_samples(&mut NormSamples::from_vec(samples));
+        receiver.analyze_full_buffer();
+
+        let mut received: Vec<bool> = Vec::new();
+        for event in events.try_iter() {
+            match event {
+                RxEvent::StartDetected { .. } => received.clear(),
+                RxEvent::BitReceived(bit) => received.push(bit),
+                RxEvent::MessageComplete { .. } | RxEvent::DecodeError(_) => break,
+                RxEvent::SymbolReceived { .. } | RxEvent::DeviceChanged { .. } => {}
+            }
+        }
+        (received, receiver.take_messages())
+    }
+}
+
+fn signal_power(samples: &[f32]) -> f32 {
+    let active: Vec<f32> = samples
+        .iter()
+        .copied()
+        .filter(|sample| *sample != 0.0)
+        .collect();
+    if active.is_empty() {
+        return 0.0;
+    }
+    active.iter().map(|sample| sample * sample).sum::<f32>() / active.len() as f32
+}
+
+fn count_bit_errors(expected: &BitVec, received: &[bool]) -> usize {
+    let mut errors: usize = 0;
+    for (idx, bit) in expected.iter_bits().enumerate() {
+        match received.get(idx) {
+            Some(received_bit) if *received_bit == bit => {}
+            _ => errors += 1,
+        }
+    }
+    errors
+}
+
+#[test]
+fn test_clean_channel() {
+    use crate::utils::get_fast_profile;
+
+    let impairments: Impairments = Impairments::new().with_offset(17).with_clipping(0.9);
+    let report: SimReport = Simulation::new(get_fast_profile())
+        .with_impairments(impairments)
+        .with_trials(4)
+        .run()
+        .unwrap();
+
+    assert_eq!(report.trials, 4);
+    assert_eq!(report.packets_ok, 4);
+    assert_eq!(report.bit_errors, 0);
+    assert_eq!(report.per(), 0.0);
+}
+
+#[test]
+fn test_noisy_channel() {
+    use crate::utils::get_fast_profile;
+
+    let profile: Profile = get_fast_profile();
+    let mild: SimReport = Simulation::new(profile)
+        .with_impairments(Impairments::new().with_noise(20.0))
+        .with_trials(4)
+        .run()
+        .unwrap();
+    let severe: SimReport = Simulation::new(profile)
+        .with_impairments(Impairments::new().with_noise(-10.0))
+        .with_trials(4)
+        .run()
+        .unwrap();
+
+    assert_eq!(mild.per(), 0.0);
+    assert!(severe.per() > mild.per());
+    assert!(severe.ber() > mild.ber());
+}
+
+#[test]
+fn test_multipath_channel() {
+    use std::time::Duration;
+
+    use crate::utils::get_default_profile;
+
+    let multipath: Multipath = Multipath::new()
+        .with_echo(Duration::from_micros(250), 0.3)
+        .with_echo(Duration::from_micros(700), 0.1);
+    let report: SimReport = Simulation::new(get_default_profile())
+        .with_channel(multipath)
+        .with_channel(AwgnChannel::new(20.0, 3))
+        .with_trials(2)
+        .run()
+        .unwrap();
+
+    assert_eq!(report.per(), 0.0);
+}
@@ -1,18 +1,36 @@
+use std::collections::hash_map::RandomState;
+#[cfg(feature = "wav")]
 use std::fs::File;
+use std::hash::BuildHasher;
+#[cfg(feature = "wav")]
 use std::io::BufReader;
+#[cfg(feature = "wav")]
 use std::path::Path;
 
+#[cfg(feature = "wav")]
 use hound::WavReader;
 
+#[cfg(feature = "symphonia")]
+use crate::audio::compressed::decode_file;
+#[cfg(feature = "wav")]
 use crate::audio::types::AudioSpec;
+#[cfg(feature = "wav")]
 use crate::audio::types::NormSamples;
+#[cfg(feature = "wav")]
+use crate::audio::types::SampleEncoding;
+use crate::protocol::bitvec::BitPadding;
+use crate::protocol::bitvec::BitVec;
+use crate::protocol::framing::BitOrder;
 use crate::protocol::profile::Bits;
 use crate::protocol::profile::Markers;
 use crate::protocol::profile::Profile;
 use crate::protocol::profile::Pulses;
 
+use crate::error::WavetrxError;
+
 use crate::consts::DefaultProfile;
 use crate::consts::FastProfile;
+use crate::consts::UltrasonicProfile;
 
 pub fn get_default_profile() -> Profile {
     let markers: Markers = Markers::new(
@@ -43,35 +61,95 @@ pub fn get_fast_profile() -> Profile {
     profile
 }
 
-fn bits_to_bytes(bits: &Vec<u8>) -> Vec<u8> {
-    let mut bytes: Vec<u8> = Vec::new();
-    for chunk in bits.chunks(8) {
-        let mut byte: u8 = 0u8;
-        for (index, &bit) in chunk.iter().enumerate() {
-            if bit == 1 {
-                byte |= 1 << (7 - index);
-            }
-        }
-        bytes.push(byte);
+pub fn get_ultrasonic_profile() -> Profile {
+    let markers: Markers = Markers::new(
+        UltrasonicProfile::MARKER_TONE_START,
+        UltrasonicProfile::MARKER_TONE_END,
+        UltrasonicProfile::MARKER_TONE_NEXT,
+    );
+    let bits: Bits = Bits::new(
+        UltrasonicProfile::BIT_TONE_HIGH,
+        UltrasonicProfile::BIT_TONE_LOW,
+    );
+    let pulses: Pulses = Pulses::new(
+        UltrasonicProfile::PULSE_LENGTH_US,
+        UltrasonicProfile::PULSE_GAP_US,
+    );
+
+    let profile: Profile =
+        Profile::new(markers, bits, pulses).with_threshold(UltrasonicProfile::DB_THRESHOLD);
+    profile
+}
+
+pub fn get_profile_by_name(name: &str) -> Result<Profile, WavetrxError> {
+    match name.to_ascii_lowercase().as_str() {
+        "default" => Ok(get_default_profile()),
+        "fast" => Ok(get_fast_profile()),
+        "ultrasonic" => Ok(get_ultrasonic_profile()),
+        _ => Err(WavetrxError::ProfileInvalid(format!(
+            "Unknown profile: {}",
+            name
+        ))),
     }
-    bytes
 }
 
-pub fn bits_to_string(bits: &Vec<u8>) -> String {
-    let bytes: Vec<u8> = bits_to_bytes(bits);
-    let string: String = String::from_utf8(bytes).expect("Failed to convert to string");
+// Differs between runs of the process and between calls; for ids and counters
+// that must not restart at the same value, not for anything secret
+pub fn random_seed() -> u64 {
+    RandomState::new().hash_one(0u8)
+}
+
+pub fn bits_to_bytes(bits: &BitVec, bit_order: BitOrder) -> Vec<u8> {
+    bits.to_bytes(bit_order, BitPadding::Zero)
+}
+
+pub fn bits_to_string(bits: &BitVec, bit_order: BitOrder) -> String {
+    let bytes: Vec<u8> = bits_to_bytes(bits, bit_order);
+    let string: String = String::from_utf8_lossy(&bytes).into_owned();
     string
 }
 
-pub fn read_wav_file<P>(filename: P) -> (NormSamples, AudioSpec)
+#[cfg(feature = "wav")]
+pub fn read_wav_file<P>(filename: P) -> Result<(NormSamples, AudioSpec), WavetrxError>
 where
     P: AsRef<Path>,
 {
-    let mut reader: WavReader<BufReader<File>> = hound::WavReader::open(filename).unwrap();
+    let mut reader: WavReader<BufReader<File>> = hound::WavReader::open(filename)?;
     let spec: AudioSpec = reader.spec().into();
 
-    let samples_i32: Vec<i32> = reader.samples::<i32>().map(Result::unwrap).collect();
-    let samples: NormSamples = NormSamples::from_i32(&samples_i32, &spec);
+    // Hound hands back 8-bit PCM already shifted to signed
+    spec.check_wav_support()?;
+
+    let samples: NormSamples = match spec.encoding() {
+        SampleEncoding::F32 => {
+            let samples: Vec<f32> = reader.samples::<f32>().collect::<Result<_, _>>()?;
+            NormSamples::from_vec(samples)
+        }
+        SampleEncoding::I32 => {
+            let samples_i32: Vec<i32> = reader.samples::<i32>().collect::<Result<_, _>>()?;
+            NormSamples::from_i32(&samples_i32, &spec)
+        }
+    };
+
+    Ok((samples, spec))
+}
 
-    (samples, spec)
+// WAV goes through hound; with the `symphonia` feature any other extension,
+// or none, is probed as a compressed container
+#[cfg(feature = "wav")]
+pub fn read_audio_file<P>(filename: P) -> Result<(NormSamples, AudioSpec), WavetrxError>
+where
+    P: AsRef<Path>,
+{
+    #[cfg(feature = "symphonia")]
+    {
+        let is_wav: bool = filename
+            .as_ref()
+            .extension()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("wav"));
+        if !is_wav {
+            return decode_file(filename);
+        }
+    }
+    read_wav_file(filename)
 }
@@ -0,0 +1,68 @@
+use js_sys::Function;
+use js_sys::Uint8Array;
+use wasm_bindgen::prelude::wasm_bindgen;
+use wasm_bindgen::JsValue;
+
+use crate::audio::spectrum::GoertzelMagnitude;
+use crate::audio::types::AudioSpec;
+use crate::audio::types::NormSamples;
+use crate::audio::types::SampleEncoding;
+use crate::protocol::profile::Profile;
+use crate::protocol::rx::DecodedMessage;
+use crate::protocol::rx::Receiver;
+use crate::utils::get_profile_by_name;
+
+// Receiver for Web Audio input; feed it the Float32Array blocks an
+// AudioWorklet or ScriptProcessor hands out for a getUserMedia stream
+#[wasm_bindgen]
+pub struct WasmReceiver {
+    receiver: Receiver<GoertzelMagnitude>,
+    on_message: Option<Function>,
+}
+
+#[wasm_bindgen]
+impl WasmReceiver {
+    // `sample_rate` is the AudioContext rate; input is mono
+    #[wasm_bindgen(constructor)]
+    pub fn new(profile_name: &str, sample_rate: u32) -> Result<WasmReceiver, JsValue> {
+        let profile: Profile =
+            get_profile_by_name(profile_name).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        let spec: AudioSpec = AudioSpec::new(sample_rate, 32, 1, SampleEncoding::F32);
+        let receiver: Receiver<GoertzelMagnitude> = Receiver::new(profile, spec);
+        let on_message: Option<Function> = None;
+        Ok(WasmReceiver {
+            receiver,
+            on_message,
+        })
+    }
+
+    // Called as `callback(data: Uint8Array, startSample: number)` per message
+    #[wasm_bindgen(js_name = setOnMessage)]
+    pub fn set_on_message(&mut self, callback: Option<Function>) {
+        self.on_message = callback;
+    }
+
+    #[wasm_bindgen(js_name = pushSamples)]
+    pub fn push_samples(&mut self, samples: &[f32]) -> Result<(), JsValue> {
+        self.receiver
+            .add_samples(&mut NormSamples::from_slice(samples));
+        // Drain the whole buffer so a block holding a full message decodes in this call
+        self.receiver.analyze_full_buffer();
+
+        let messages: Vec<DecodedMessage> = self.receiver.take_messages();
+        let callback: &Function = match &self.on_message {
+            Some(callback) => callback,
+            None => return Ok(()),
+        };
+        for message in messages {
+            let data: Uint8Array = Uint8Array::from(message.data());
+            let start: JsValue = JsValue::from_f64(message.start_sample() as f64);
+            callback.call2(&JsValue::NULL, &data, &start)?;
+        }
+        Ok(())
+    }
+
+    pub fn resync(&mut self) {
+        self.receiver.resync();
+    }
+}